@@ -0,0 +1,274 @@
+//! Сводка по истории распределений: доходы по источникам, наполнение
+//! конвертов и категорий, средний остаток и доля сбережений за месяц,
+//! квартал или год.
+
+use std::collections::BTreeMap;
+
+use ai_core::{
+    distribute::Budget,
+    finance::{Currency, ExchangeError, ExchangeRates, Money, Percentage},
+};
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Категории, деньги в которых считаются сбережениями, если не заданы другие
+pub const SAVINGS_CATEGORIES: [&str; 1] = ["Капитал"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    #[default]
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Granularity {
+    /// Первый день периода, в который попадает `date`
+    pub fn start(self, date: NaiveDate) -> NaiveDate {
+        let month = match self {
+            Granularity::Month => date.month(),
+            Granularity::Quarter => (date.month() - 1) / 3 * 3 + 1,
+            Granularity::Year => 1,
+        };
+        NaiveDate::from_ymd_opt(date.year(), month, 1)
+            .expect("первое число месяца всегда существует")
+    }
+
+    /// Подпись периода: `2026-03`, `2026-Q1` или `2026`
+    pub fn label(self, start: NaiveDate) -> String {
+        match self {
+            Granularity::Month => start.format("%Y-%m").to_string(),
+            Granularity::Quarter => {
+                format!("{}-Q{}", start.year(), (start.month() - 1) / 3 + 1)
+            }
+            Granularity::Year => start.year().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeriodStats {
+    pub period: String,
+    pub start: NaiveDate,
+    /// Сколько распределений попало в период
+    pub budgets: usize,
+    pub income: Money,
+    pub by_source: BTreeMap<String, Money>,
+    pub by_envelope: BTreeMap<String, Money>,
+    /// Конверты без категории сюда не входят
+    pub by_category: BTreeMap<String, Money>,
+    pub average_rest: Money,
+    /// Отложено в категории сбережений
+    pub savings: Money,
+    /// Доля дохода, отложенная в сбережения
+    pub savings_rate: Percentage,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Analytics {
+    pub granularity: Granularity,
+    /// Валюта всех сумм - базовая валюта плана
+    pub currency: Currency,
+    /// Периоды с распределениями, от старых к новым
+    pub periods: Vec<PeriodStats>,
+}
+
+/// Распределение с суммами в базовой валюте
+struct Converted {
+    income: Money,
+    rest: Money,
+    entries: Vec<(Option<String>, String, Money)>,
+}
+
+fn convert(budget: &Budget, rates: &ExchangeRates) -> Result<Converted, ExchangeError> {
+    let to_base = |m: Money| rates.convert(m, rates.base);
+    let entries = budget
+        .no_category
+        .iter()
+        .map(|e| (None, e))
+        .chain(budget.categories.iter().flat_map(|(category, entries)| {
+            entries.iter().map(move |e| (Some(category.clone()), e))
+        }))
+        .map(|(category, e)| Ok((category, e.expense.name.clone(), to_base(e.amount)?)))
+        .collect::<Result<_, _>>()?;
+    Ok(Converted {
+        income: to_base(budget.income.amount)?,
+        rest: to_base(budget.rest)?,
+        entries,
+    })
+}
+
+/// Сводит действующие распределения по периодам. Суммы пересчитываются в
+/// базовую валюту `rates`; распределение в валюте без курса пропускается
+pub fn aggregate<'a>(
+    budgets: impl IntoIterator<Item = &'a Budget>,
+    granularity: Granularity,
+    rates: &ExchangeRates,
+    savings: &[String],
+) -> Analytics {
+    let zero = Money::new(Decimal::ZERO, rates.base);
+    let mut periods: BTreeMap<NaiveDate, (PeriodStats, Money)> = BTreeMap::new();
+    for budget in budgets {
+        let converted = match convert(budget, rates) {
+            Ok(c) => c,
+            Err(e) => {
+                warn!(
+                    "Распределение {} не попало в сводку: {e}",
+                    budget.income.date
+                );
+                continue;
+            }
+        };
+        let start = granularity.start(budget.income.date);
+        let (stats, rest) = periods.entry(start).or_insert_with(|| {
+            let stats = PeriodStats {
+                period: granularity.label(start),
+                start,
+                budgets: 0,
+                income: zero,
+                by_source: BTreeMap::new(),
+                by_envelope: BTreeMap::new(),
+                by_category: BTreeMap::new(),
+                average_rest: zero,
+                savings: zero,
+                savings_rate: Percentage::from_int(0),
+            };
+            (stats, zero)
+        });
+        stats.budgets += 1;
+        stats.income += converted.income;
+        *stats
+            .by_source
+            .entry(budget.income.source.name.clone())
+            .or_insert(zero) += converted.income;
+        *rest += converted.rest;
+        for (category, name, amount) in converted.entries {
+            *stats.by_envelope.entry(name).or_insert(zero) += amount;
+            if let Some(category) = category {
+                if savings.contains(&category) {
+                    stats.savings += amount;
+                }
+                *stats.by_category.entry(category).or_insert(zero) += amount;
+            }
+        }
+    }
+
+    let periods = periods
+        .into_values()
+        .map(|(mut stats, rest)| {
+            stats.average_rest = rest / stats.budgets as i64;
+            if !stats.income.value.is_zero() {
+                stats.savings_rate =
+                    Percentage::of(stats.savings.value, stats.income.value);
+            }
+            stats
+        })
+        .collect();
+    Analytics {
+        granularity,
+        currency: rates.base,
+        periods,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ai_core::{
+        distribute::{BudgetEntry, Income},
+        planning::{Expense, ExpenseValue, IncomeKind, IncomeSource},
+    };
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn budget(
+        source: &str,
+        date: (i32, u32, u32),
+        income: Money,
+        entries: &[(&str, Option<&str>, Decimal)],
+    ) -> Budget {
+        let source =
+            IncomeSource::new(source.into(), IncomeKind::Other { expected: income });
+        let date = NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap();
+        let mut budget = Budget::new(Income::new(source, income, date));
+        for (name, category, amount) in entries {
+            let amount = Money::new(*amount, income.currency);
+            let expense = Expense::envelope(
+                (*name).into(),
+                ExpenseValue::MONEY { value: amount },
+                category.map(Into::into),
+            );
+            budget.push(category.map(Into::into), BudgetEntry::new(expense, amount));
+        }
+        budget
+    }
+
+    #[test]
+    fn periods_sum_incomes_envelopes_and_savings() {
+        let rub = |v| Money::new_rub(v);
+        let budgets = [
+            budget(
+                "Зарплата",
+                (2026, 1, 10),
+                rub(dec!(100000)),
+                &[
+                    ("Подушка", Some("Капитал"), dec!(20000)),
+                    ("Аренда", Some("Жильё"), dec!(30000)),
+                    ("Еда", None, dec!(40000)),
+                ],
+            ),
+            budget(
+                "Фриланс",
+                (2026, 2, 20),
+                rub(dec!(50000)),
+                &[("Подушка", Some("Капитал"), dec!(25000))],
+            ),
+            budget(
+                "Бонус",
+                (2026, 4, 1),
+                Money::new(dec!(1000), Currency::USD),
+                &[("Подушка", Some("Капитал"), dec!(1000))],
+            ),
+            budget(
+                "Бонус",
+                (2026, 5, 1),
+                Money::new(dec!(1), Currency::USD),
+                &[],
+            ),
+        ];
+        // Без курса доллара апрельский бонус в сводку не попадает
+        let rates = ExchangeRates::new(Currency::RUB);
+        let savings = vec!["Капитал".to_string()];
+
+        let by_quarter = aggregate(&budgets, Granularity::Quarter, &rates, &savings);
+
+        assert_eq!(by_quarter.periods.len(), 1);
+        let q1 = &by_quarter.periods[0];
+        assert_eq!(q1.period, "2026-Q1");
+        assert_eq!(q1.budgets, 2);
+        assert_eq!(q1.income, rub(dec!(150000)));
+        assert_eq!(q1.by_source["Фриланс"], rub(dec!(50000)));
+        assert_eq!(q1.by_envelope["Подушка"], rub(dec!(45000)));
+        assert_eq!(q1.by_category["Жильё"], rub(dec!(30000)));
+        assert!(!q1.by_category.contains_key("Еда"));
+        // Остатки: 10 000 и 25 000
+        assert_eq!(q1.average_rest, rub(dec!(17500)));
+        assert_eq!(q1.savings, rub(dec!(45000)));
+        assert_eq!(q1.savings_rate, Percentage::from_int(30));
+
+        let rates = rates.with_rate(Currency::USD, dec!(90));
+        let by_month = aggregate(&budgets, Granularity::Month, &rates, &savings);
+        let labels: Vec<_> =
+            by_month.periods.iter().map(|p| p.period.as_str()).collect();
+        assert_eq!(labels, vec!["2026-01", "2026-02", "2026-04", "2026-05"]);
+        assert_eq!(by_month.periods[2].income, rub(dec!(90000)));
+        assert_eq!(by_month.periods[2].savings_rate, Percentage::from_int(100));
+
+        let by_year = aggregate(&budgets, Granularity::Year, &rates, &savings);
+        assert_eq!(by_year.periods[0].period, "2026");
+        assert_eq!(by_year.periods[0].budgets, 4);
+    }
+}
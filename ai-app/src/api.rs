@@ -1,26 +1,90 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
 
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::instrument;
+use tracing::{error, instrument, warn};
 
 use ai_core::{
-    distribute::{Budget, Income, distribute as core_dist},
-    plan::Plan,
-    planning::DistributionWeights,
+    distribute::{
+        Budget,
+        EnvelopeChange,
+        Error as DistributeError,
+        Income,
+        distribute as core_dist,
+    },
+    finance::{Currency, ExchangeRates, Money},
+    plan::{Plan, PlanDiff},
+    planning::{
+        CreditPayoff,
+        DistributionWeights,
+        ExpectedIncome,
+        ExpenseKind,
+        GoalProgress,
+        RepaymentKind,
+        month_bounds,
+        track_incomes,
+    },
+    simulate::{self, Forecast, Scenario},
 };
 
-use crate::storage::{
-    BudgetId,
-    CoreRepo,
-    Cursor,
-    Page,
-    PlanDraft,
-    PlanId,
-    StorageBudget,
-    StoragePlan,
-    UserId,
+use crate::{
+    analytics::{self, Analytics, Granularity},
+    archive::{
+        ARCHIVE_VERSION,
+        Archive,
+        ArchivedPlan,
+        Conflict,
+        ConflictKind,
+        ImportMode,
+        ImportReport,
+    },
+    ledger::{
+        EnvelopeBalance,
+        Ledger,
+        LedgerEntry,
+        LedgerEntryId,
+        LedgerError,
+        LedgerOperation,
+    },
+    report::{self, PlanReport},
+    statement::{
+        Confirmation,
+        MatchRule,
+        Proposal,
+        Reconciliation,
+        SkipReason,
+        Skipped,
+        Transaction,
+        match_source,
+    },
+    storage::{
+        BudgetId,
+        BudgetVoid,
+        CoreRepo,
+        Cursor,
+        Page,
+        PlanDraft,
+        PlanEvent,
+        PlanId,
+        PlanRef,
+        PlanStatus,
+        StorageBudget,
+        StorageError,
+        StoragePlan,
+        UserId,
+        Version,
+        build_id,
+    },
 };
 
+/// Размер страницы при полном обходе хранилища
+const PAGE_SIZE: usize = 100;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("distribution error: {message}")]
@@ -37,8 +101,70 @@ pub enum Error {
     CantDeletePlan,
     #[error("cant update plan")]
     CantUpdatePlan,
+    #[error("plan was changed concurrently, current version is {current}")]
+    PlanVersionConflict { current: Version },
     #[error("plan not found")]
     PlanNotFound,
+    #[error("plan name must not be empty")]
+    EmptyPlanName,
+    #[error("cant rename plan")]
+    CantRenamePlan,
+    #[error("cant activate plan")]
+    CantActivatePlan,
+    #[error("invalid ledger operation: {message}")]
+    InvalidLedgerOperation { message: String },
+    #[error("cant save ledger entry")]
+    CantSaveLedgerEntry,
+    #[error("budget not found")]
+    BudgetNotFound,
+    #[error("budget already voided")]
+    BudgetAlreadyVoided,
+    #[error("budget is in use: {message}")]
+    BudgetInUse { message: String },
+    #[error("cant void budget")]
+    CantVoidBudget,
+    #[error("plan version not found")]
+    PlanVersionNotFound,
+    #[error("income source not found")]
+    SourceNotFound,
+    #[error("income source belongs to another member")]
+    ForeignSource,
+    #[error("archive version {version} is not supported")]
+    UnsupportedArchive { version: u32 },
+    #[error("cant import archive")]
+    CantImport,
+    #[error("invalid archive: {message}")]
+    InvalidArchive { message: String },
+    #[error("forecast is limited to {max} months")]
+    ForecastTooLong { max: u32 },
+}
+
+/// Распределение дохода и версия плана, по которой оно посчитано
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DistributedIncome {
+    pub budget: Budget,
+    pub plan: PlanRef,
+}
+
+/// Доход из истории, заново распределённый по выбранной версии плана
+#[derive(Debug, Clone, Serialize)]
+pub struct Redistribution {
+    pub budget_id: BudgetId,
+    /// Версия плана, по которой распределение было сохранено
+    pub original: Option<PlanRef>,
+    /// Версия плана, по которой доход распределён заново
+    pub target: PlanRef,
+    pub budget: Budget,
+    /// Разница с сохранённым распределением по конвертам
+    pub changes: Vec<EnvelopeChange>,
+}
+
+/// Сколько участник общего плана внёс в конверт; сумма в валюте конверта
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Contribution {
+    pub envelope: String,
+    pub member: UserId,
+    pub amount: Money,
 }
 
 #[derive(Clone)]
@@ -56,16 +182,48 @@ impl<R: CoreRepo> CoreApi<R> {
         self.repo.get_plan(user_id).await
     }
 
+    /// Все планы пользователя; активный среди них помечен `active`
+    #[instrument(skip(self))]
+    pub async fn list_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        self.repo.list_plans(user_id).await
+    }
+
+    /// Общие планы других пользователей, в которых пользователь участвует
+    #[instrument(skip(self))]
+    pub async fn shared_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        self.repo.shared_plans(user_id).await
+    }
+
+    /// Свой или общий план, в котором пользователь участвует
+    async fn household_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Option<StoragePlan> {
+        let own = self.repo.list_plans(user_id).await;
+        if let Some(sp) = own.into_iter().find(|sp| &sp.id == plan_id) {
+            return Some(sp);
+        }
+        self.repo
+            .shared_plans(user_id)
+            .await
+            .into_iter()
+            .find(|sp| &sp.id == plan_id)
+    }
+
+    /// Создаёт план; активным он становится, только если активного ещё нет
     #[instrument(skip(self, draft))]
     pub async fn create_plan(
         &self,
         user_id: &UserId,
         plan_id: PlanId,
+        name: String,
         draft: PlanDraft,
     ) -> Result<PlanId, Error> {
+        let name = Self::validate_name(name)?;
         let plan = Self::validate(draft)?;
         self.repo
-            .create_plan(user_id, plan_id, plan)
+            .create_plan(user_id, plan_id, name, plan)
             .await
             .map_err(|e| match e {
                 crate::storage::StorageError::PlanAlreadyExists => {
@@ -75,18 +233,88 @@ impl<R: CoreRepo> CoreApi<R> {
             })
     }
 
+    /// Обновляет план, если с версии `expected` его никто не менял
     #[instrument(skip(self, draft))]
     pub async fn update_plan(
         &self,
         user_id: &UserId,
         plan_id: PlanId,
+        expected: Version,
         draft: PlanDraft,
     ) -> Result<(), Error> {
         let plan = Self::validate(draft)?;
         self.repo
-            .update_plan(user_id, &plan_id, plan)
+            .update_plan(user_id, &plan_id, expected, plan)
+            .await
+            .map_err(|e| match e {
+                crate::storage::StorageError::VersionConflict { current } => {
+                    Error::PlanVersionConflict { current }
+                }
+                _ => Error::CantUpdatePlan,
+            })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn rename_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        name: String,
+    ) -> Result<(), Error> {
+        let name = Self::validate_name(name)?;
+        self.repo
+            .rename_plan(user_id, plan_id, name)
             .await
-            .map_err(|_| Error::CantUpdatePlan)
+            .map_err(|e| match e {
+                crate::storage::StorageError::PlanNotFound => Error::PlanNotFound,
+                crate::storage::StorageError::PlanAlreadyExists => {
+                    Error::PlanAlreadyExists
+                }
+                _ => Error::CantRenamePlan,
+            })
+    }
+
+    /// Копия плана под новым именем; копия создаётся неактивной
+    #[instrument(skip(self))]
+    pub async fn clone_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        new_id: PlanId,
+        name: String,
+    ) -> Result<PlanId, Error> {
+        let original = self
+            .repo
+            .list_plans(user_id)
+            .await
+            .into_iter()
+            .find(|sp| &sp.id == plan_id)
+            .ok_or(Error::PlanNotFound)?;
+        self.create_plan(user_id, new_id, name, original.plan).await
+    }
+
+    /// Переключает распределение доходов на указанный план
+    #[instrument(skip(self))]
+    pub async fn activate_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<(), Error> {
+        self.repo
+            .activate_plan(user_id, plan_id)
+            .await
+            .map_err(|e| match e {
+                crate::storage::StorageError::PlanNotFound => Error::PlanNotFound,
+                _ => Error::CantActivatePlan,
+            })
+    }
+
+    fn validate_name(name: String) -> Result<String, Error> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::EmptyPlanName);
+        }
+        Ok(name.to_string())
     }
 
     fn validate(draft: PlanDraft) -> Result<Plan, Error> {
@@ -110,6 +338,35 @@ impl<R: CoreRepo> CoreApi<R> {
             .map_err(|_| Error::CantDeletePlan)
     }
 
+    /// Журнал изменений плана, от новых событий к старым
+    #[instrument(skip(self))]
+    pub async fn plan_events(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<PlanEvent> {
+        self.repo.plan_events(user_id, plan_id, from, limit).await
+    }
+
+    /// Разница между версиями `from` и `to` плана
+    #[instrument(skip(self))]
+    pub async fn plan_diff(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        from: Version,
+        to: Version,
+    ) -> Result<PlanDiff, Error> {
+        let old = self.plan_version(user_id, plan_id, from).await;
+        let new = self.plan_version(user_id, plan_id, to).await;
+        match (old, new) {
+            (Some(old), Some(new)) => Ok(old.diff(&new)),
+            _ => Err(Error::PlanVersionNotFound),
+        }
+    }
+
     #[instrument(skip(plan, income, self))]
     pub fn distribute(
         &self,
@@ -121,122 +378,1107 @@ impl<R: CoreRepo> CoreApi<R> {
         })
     }
 
+    /// Распределяет доход по плану `sp` с учётом накоплений: доли целей
+    /// пересчитываются по балансам конвертов на дату дохода. Версия плана
+    /// возвращается вместе с распределением, чтобы сохранить именно её.
+    #[instrument(skip(sp, income, self))]
+    pub async fn distribute_income(
+        &self,
+        user_id: &UserId,
+        sp: &StoragePlan,
+        income: &Income,
+    ) -> Result<DistributedIncome, Error> {
+        let plan = DistributionWeights::try_from(sp.plan.clone()).map_err(|e| {
+            Error::InvalidPlan {
+                message: e.to_string(),
+            }
+        })?;
+        Ok(DistributedIncome {
+            budget: self
+                .distribute_with_goals(user_id, &plan, income, None)
+                .await?,
+            plan: sp.plan_ref(),
+        })
+    }
+
+    /// Распределение с долями целей по накоплениям на дату дохода.
+    /// Распределение `skip` в накоплениях не учитывается.
+    async fn distribute_with_goals(
+        &self,
+        user_id: &UserId,
+        plan: &DistributionWeights,
+        income: &Income,
+        skip: Option<&BudgetId>,
+    ) -> Result<Budget, Error> {
+        let saved = self
+            .saved_by_envelope(user_id, &plan.rates, income.date, skip)
+            .await;
+        let adjusted = plan.adjust_goals(&saved, income.date).map_err(|e| {
+            Error::CantDistribute {
+                message: e.to_string(),
+            }
+        })?;
+        self.distribute(&adjusted, income)
+    }
+
+    /// Прогресс по всем целям активного плана на дату `on`
+    #[instrument(skip(self))]
+    pub async fn goals(
+        &self,
+        user_id: &UserId,
+        on: NaiveDate,
+    ) -> Option<Vec<GoalProgress>> {
+        let sp = self.repo.get_plan(user_id).await?;
+        let saved = self
+            .saved_by_envelope(user_id, &sp.plan.rates, on, None)
+            .await;
+        let goals = sp
+            .plan
+            .expenses
+            .iter()
+            .filter_map(|expense| {
+                let ExpenseKind::Goal(goal) = &expense.kind else {
+                    return None;
+                };
+                let currency = goal.target.currency;
+                let saved = saved
+                    .get(&expense.name)
+                    .and_then(|m| sp.plan.rates.convert(*m, currency).ok())
+                    .unwrap_or(Money::new(Decimal::ZERO, currency));
+                expense.goal_progress(saved, on)
+            })
+            .collect();
+        Some(goals)
+    }
+
+    /// Графики погашения всех кредитов активного плана и остаток долга на дату `on`
+    #[instrument(skip(self))]
+    pub async fn credits(
+        &self,
+        user_id: &UserId,
+        kind: RepaymentKind,
+        on: NaiveDate,
+    ) -> Result<Vec<CreditPayoff>, Error> {
+        let sp = self
+            .repo
+            .get_plan(user_id)
+            .await
+            .ok_or(Error::PlanNotFound)?;
+        let mut credits = Vec::new();
+        for expense in &sp.plan.expenses {
+            let payoff =
+                expense
+                    .credit_payoff(kind, on)
+                    .map_err(|e| Error::InvalidPlan {
+                        message: e.to_string(),
+                    })?;
+            credits.extend(payoff);
+        }
+        Ok(credits)
+    }
+
+    /// Ожидаемые в месяце даты `on` поступления и что из них уже пришло
+    #[instrument(skip(self))]
+    pub async fn expected_incomes(
+        &self,
+        user_id: &UserId,
+        on: NaiveDate,
+    ) -> Option<Vec<ExpectedIncome>> {
+        let sp = self.repo.get_plan(user_id).await?;
+        let budgets = self.all_budgets(user_id).await;
+        let incomes = budgets
+            .iter()
+            .filter(|b| !b.is_voided())
+            .map(|b| &b.budget.income);
+        Some(track_incomes(&sp.plan.sources, incomes, on))
+    }
+
+    /// Накопления по конвертам на дату `on` в базовой валюте плана,
+    /// без распределения `skip`. Суммы в валютах без курса не учитываются.
+    async fn saved_by_envelope(
+        &self,
+        user_id: &UserId,
+        rates: &ExchangeRates,
+        on: NaiveDate,
+        skip: Option<&BudgetId>,
+    ) -> HashMap<String, Money> {
+        let mut saved: HashMap<String, Money> = HashMap::new();
+        for envelope in self.ledger_on(user_id, on, skip).await.balances() {
+            let Ok(balance) = rates.convert(envelope.balance, rates.base) else {
+                warn!("Нет курса для баланса конверта {}", envelope.name);
+                continue;
+            };
+            *saved
+                .entry(envelope.name)
+                .or_insert(Money::new(Decimal::ZERO, rates.base)) += balance;
+        }
+        saved
+    }
+
+    /// Сохраняет распределение вместе с версией плана, по которой оно
+    /// посчитано (см. [`Self::distribute_income`])
     #[instrument(skip(budget, self))]
     pub async fn save_budget(
         &self,
+        user_id: &UserId,
         budget_id: BudgetId,
         budget: Budget,
+        plan: Option<PlanRef>,
     ) -> Result<BudgetId, Error> {
         self.repo
-            .save_budget(budget_id, budget)
+            .save_budget(user_id, budget_id, budget, plan)
             .await
             .map_err(|_| Error::CantSaveBudget)
     }
 
+    /// Заново распределяет доход сохранённого распределения по версии `version`
+    /// его плана (без версии - по текущему плану) и считает разницу по конвертам.
+    /// Доли целей не пересчитываются по накоплениям: балансы на дату дохода
+    /// уже не восстановить.
+    #[instrument(skip(self))]
+    pub async fn redistribute(
+        &self,
+        user_id: &UserId,
+        budget_id: &BudgetId,
+        version: Option<Version>,
+    ) -> Result<Redistribution, Error> {
+        let stored = self
+            .repo
+            .budget_by_id(user_id, budget_id)
+            .await
+            .ok_or(Error::BudgetNotFound)?;
+        let current = self.repo.get_plan(user_id).await;
+        let (target, plan) = match version {
+            None => {
+                let sp = current.ok_or(Error::PlanNotFound)?;
+                let target = PlanRef {
+                    plan_id: sp.id,
+                    version: sp.version,
+                };
+                (target, sp.plan)
+            }
+            Some(version) => {
+                let plan_id = stored
+                    .plan
+                    .as_ref()
+                    .map(|p| p.plan_id.clone())
+                    .or(current.map(|sp| sp.id))
+                    .ok_or(Error::PlanNotFound)?;
+                let plan = self
+                    .plan_version(user_id, &plan_id, version)
+                    .await
+                    .ok_or(Error::PlanVersionNotFound)?;
+                (PlanRef { plan_id, version }, plan)
+            }
+        };
+        let weights =
+            DistributionWeights::try_from(plan).map_err(|e| Error::InvalidPlan {
+                message: e.to_string(),
+            })?;
+        // Цели пересчитываются так же, как при первом распределении
+        let budget = self
+            .distribute_with_goals(
+                user_id,
+                &weights,
+                &stored.budget.income,
+                Some(&stored.id),
+            )
+            .await?;
+        Ok(Redistribution {
+            budget_id: stored.id,
+            original: stored.plan,
+            target,
+            changes: stored.budget.compare(&budget),
+            budget,
+        })
+    }
+
+    /// Распределяет доход участника из источника `source` по его долям в плане
+    /// и сохраняет распределение в историю владельца плана: конверты у всех
+    /// участников общие. Распределять можно только доход из своих источников.
+    #[instrument(skip(self))]
+    pub async fn contribute(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        budget_id: BudgetId,
+        source: &str,
+        amount: Decimal,
+        date: NaiveDate,
+    ) -> Result<StorageBudget, Error> {
+        let sp = self
+            .household_plan(user_id, plan_id)
+            .await
+            .ok_or(Error::PlanNotFound)?;
+        let source = sp
+            .plan
+            .sources
+            .iter()
+            .find(|s| s.name == source)
+            .cloned()
+            .ok_or(Error::SourceNotFound)?;
+        let contributor = sp
+            .plan
+            .member_of(&source.name)
+            .map_or(&sp.user_id, |m| &m.id);
+        if contributor != user_id {
+            return Err(Error::ForeignSource);
+        }
+        // Доход приходит в валюте источника
+        let currency = source.net().currency;
+        let income = Income::new(source, Money::new(amount, currency), date);
+        let DistributedIncome { budget, plan } =
+            self.distribute_income(&sp.user_id, &sp, &income).await?;
+        self.repo
+            .save_budget(
+                &sp.user_id,
+                budget_id.clone(),
+                budget.clone(),
+                Some(plan.clone()),
+            )
+            .await
+            .map_err(|_| Error::CantSaveBudget)?;
+        Ok(StorageBudget {
+            plan: Some(plan),
+            ..StorageBudget::from((budget_id, budget))
+        })
+    }
+
+    /// Вклады участников в конверты плана за всю его историю, по конверту и
+    /// участнику. Участник определяется по источнику дохода; доход из
+    /// источника без участника записывается на владельца плана.
+    #[instrument(skip(self))]
+    pub async fn contributions(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<Vec<Contribution>, Error> {
+        let sp = self
+            .household_plan(user_id, plan_id)
+            .await
+            .ok_or(Error::PlanNotFound)?;
+        let mut totals: BTreeMap<(String, UserId, Currency), Money> = BTreeMap::new();
+        for stored in self.all_budgets(&sp.user_id).await {
+            let of_plan = stored.plan.as_ref().is_some_and(|p| p.plan_id == sp.id);
+            if stored.is_voided() || !of_plan {
+                continue;
+            }
+            let member = sp
+                .plan
+                .member_of(&stored.budget.income.source.name)
+                .map_or(&sp.user_id, |m| &m.id);
+            for ((envelope, currency), amount) in stored.budget.envelope_totals() {
+                *totals
+                    .entry((envelope, member.clone(), currency))
+                    .or_insert(Money::new(Decimal::ZERO, currency)) += amount;
+            }
+        }
+        Ok(totals
+            .into_iter()
+            .map(|((envelope, member, _), amount)| Contribution {
+                envelope,
+                member,
+                amount,
+            })
+            .collect())
+    }
+
+    /// Содержимое плана в версии `version` по журналу событий
+    async fn plan_version(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        version: Version,
+    ) -> Option<Plan> {
+        let mut cursor = None;
+        loop {
+            let page = self
+                .repo
+                .plan_events(user_id, plan_id, cursor, PAGE_SIZE)
+                .await;
+            let found = page
+                .items
+                .into_iter()
+                .find(|e| e.version == version && e.content.is_some());
+            if let Some(event) = found {
+                return event.content;
+            }
+            cursor = Some(page.next_cursor?);
+        }
+    }
+
     pub async fn budget_list(
         &self,
+        user_id: &UserId,
         from: Option<Cursor>,
         limit: usize,
     ) -> Page<StorageBudget> {
-        self.repo.budgets(from, limit).await
+        self.repo.budgets(user_id, from, limit).await
     }
 
+    /// Действующие распределения с доходом в периоде `[from, to]`
+    /// от старых к новым; границы необязательны
     #[instrument(skip(self))]
-    pub async fn budget_by_id(&self, id: &BudgetId) -> Option<StorageBudget> {
-        self.repo.budget_by_id(id).await
+    pub async fn budget_history(
+        &self,
+        user_id: &UserId,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Vec<StorageBudget> {
+        let mut budgets: Vec<_> = self
+            .all_budgets(user_id)
+            .await
+            .into_iter()
+            .filter(|sb| {
+                let date = sb.budget.income.date;
+                !sb.is_voided()
+                    && from.is_none_or(|from| date >= from)
+                    && to.is_none_or(|to| date <= to)
+            })
+            .collect();
+        budgets.sort_by(|a, b| {
+            a.budget
+                .income
+                .date
+                .cmp(&b.budget.income.date)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        budgets
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::{Arc, Mutex};
+    /// Сводка распределений периода `[from, to]` в базовой валюте активного плана.
+    /// `savings` - категории, которые считаются сбережениями
+    #[instrument(skip(self))]
+    pub async fn analytics(
+        &self,
+        user_id: &UserId,
+        granularity: Granularity,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        savings: &[String],
+    ) -> Analytics {
+        let rates = self
+            .repo
+            .get_plan(user_id)
+            .await
+            .map(|sp| sp.plan.rates)
+            .unwrap_or_default();
+        let budgets = self.budget_history(user_id, from, to).await;
+        analytics::aggregate(
+            budgets.iter().map(|sb| &sb.budget),
+            granularity,
+            &rates,
+            savings,
+        )
+    }
 
-    use ai_core::{
-        distribute::Budget,
-        finance::{Money, Percentage},
-        plan::Plan,
-        planning::{Expense, ExpenseValue, IncomeKind, IncomeSource},
-    };
-    use rust_decimal_macros::dec;
-    use serde::Serialize;
+    /// План/факт активного плана за месяц даты `month`
+    #[instrument(skip(self))]
+    pub async fn plan_report(
+        &self,
+        user_id: &UserId,
+        month: NaiveDate,
+    ) -> Result<PlanReport, Error> {
+        let sp = self
+            .repo
+            .get_plan(user_id)
+            .await
+            .ok_or(Error::PlanNotFound)?;
+        let (from, to) = month_bounds(month);
+        let budgets = self.budget_history(user_id, Some(from), Some(to)).await;
+        report::plan_vs_actual(&sp.plan, budgets.iter().map(|sb| &sb.budget), month)
+            .map_err(|e| Error::InvalidPlan {
+                message: e.to_string(),
+            })
+    }
 
-    use super::*;
-    use crate::storage::*;
+    /// Прогноз сценария по активному плану. Если накопления на начало
+    /// не указаны, прогноз начинается с текущих балансов конвертов
+    #[instrument(skip(self, scenario))]
+    pub async fn simulate(
+        &self,
+        user_id: &UserId,
+        mut scenario: Scenario,
+    ) -> Result<Forecast, Error> {
+        let sp = self
+            .repo
+            .get_plan(user_id)
+            .await
+            .ok_or(Error::PlanNotFound)?;
+        if scenario.opening.is_empty() {
+            scenario.opening = self
+                .saved_by_envelope(user_id, &sp.plan.rates, NaiveDate::MAX, None)
+                .await;
+        }
+        simulate::simulate(&sp.plan, &scenario).map_err(|e| match e {
+            DistributeError::ForecastTooLong(max) => Error::ForecastTooLong { max },
+            e => Error::CantDistribute {
+                message: e.to_string(),
+            },
+        })
+    }
 
-    fn other_source(name: &str, expected: Money) -> IncomeSource {
-        IncomeSource::new(name.to_string(), IncomeKind::Other { expected })
+    #[instrument(skip(self))]
+    pub async fn budget_by_id(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+    ) -> Option<StorageBudget> {
+        self.repo.budget_by_id(user_id, id).await
     }
 
-    struct InMemoryCoreRepo {
-        plan: Mutex<Option<StoragePlan>>,
-        events: Mutex<Vec<PlanEvent>>,
+    /// Аннулирует сохранённое распределение: оно остаётся в истории, но больше
+    /// не пополняет конверты. Если без его денег уже записанные снятия и
+    /// переводы не сходятся, распределение аннулировать нельзя.
+    #[instrument(skip(self))]
+    pub async fn void_budget(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+        date: NaiveDate,
+        reason: Option<String>,
+    ) -> Result<StorageBudget, Error> {
+        let mut budget = self
+            .repo
+            .budget_by_id(user_id, id)
+            .await
+            .ok_or(Error::BudgetNotFound)?;
+        if budget.is_voided() {
+            return Err(Error::BudgetAlreadyVoided);
+        }
+        let budgets = self.all_budgets(user_id).await;
+        let entries = self.all_ledger_entries(user_id).await;
+        let (_, before) = Self::replay(&budgets, &entries, None);
+        let (_, after) = Self::replay(&budgets, &entries, Some(id));
+        if let Some((_, e)) = after
+            .into_iter()
+            .find(|(entry, _)| !before.iter().any(|(failed, _)| failed == entry))
+        {
+            return Err(Error::BudgetInUse {
+                message: e.to_string(),
+            });
+        }
+        let void = BudgetVoid { date, reason };
+        self.repo
+            .void_budget(user_id, id, void.clone())
+            .await
+            .map_err(|_| Error::CantVoidBudget)?;
+        budget.voided = Some(void);
+        Ok(budget)
     }
 
-    impl InMemoryCoreRepo {
-        fn new() -> Self {
-            Self {
-                plan: Mutex::new(None),
-                events: Mutex::new(vec![]),
+    /// Текущие балансы всех конвертов
+    #[instrument(skip(self))]
+    pub async fn envelope_balances(&self, user_id: &UserId) -> Vec<EnvelopeBalance> {
+        self.ledger(user_id).await.balances()
+    }
+
+    /// Записывает снятие или перевод, если в конверте хватает денег
+    #[instrument(skip(self))]
+    pub async fn record_ledger_operation(
+        &self,
+        user_id: &UserId,
+        date: NaiveDate,
+        operation: LedgerOperation,
+        comment: Option<String>,
+    ) -> Result<LedgerEntry, Error> {
+        self.ledger(user_id).await.check(&operation).map_err(|e| {
+            Error::InvalidLedgerOperation {
+                message: e.to_string(),
             }
-        }
+        })?;
+        let entry = LedgerEntry {
+            id: build_id(),
+            date,
+            operation,
+            comment,
+        };
+        self.repo
+            .save_ledger_entry(user_id, entry.clone())
+            .await
+            .map_err(|_| Error::CantSaveLedgerEntry)?;
+        Ok(entry)
     }
 
-    impl CoreRepo for InMemoryCoreRepo {
-        async fn get_plan(&self, _user_id: &UserId) -> Option<StoragePlan> {
-            self.plan.lock().unwrap().clone()
-        }
+    /// Предлагает распределения для входящих платежей выписки по активному
+    /// плану. Платёж пропускается, если доход с той же датой и суммой уже
+    /// распределён или для него не нашёлся источник
+    #[instrument(skip(self, transactions, rules))]
+    pub async fn reconcile_statement(
+        &self,
+        user_id: &UserId,
+        transactions: Vec<Transaction>,
+        rules: &[MatchRule],
+    ) -> Result<Reconciliation, Error> {
+        let sp = self
+            .repo
+            .get_plan(user_id)
+            .await
+            .ok_or(Error::PlanNotFound)?;
+        let weights = DistributionWeights::try_from(sp.plan.clone()).map_err(|e| {
+            Error::InvalidPlan {
+                message: e.to_string(),
+            }
+        })?;
+        let mut distributed = self.distributed_incomes(user_id).await;
 
-        async fn create_plan(
-            &self,
-            user_id: &UserId,
-            plan_id: PlanId,
-            plan: Plan,
-        ) -> Result<PlanId, StorageError> {
-            if self.plan.lock().unwrap().is_some() {
-                return Err(StorageError::PlanAlreadyExists);
+        let mut reconciliation = Reconciliation::default();
+        for transaction in transactions.into_iter().filter(Transaction::is_incoming) {
+            if take_distributed(&mut distributed, transaction.date, transaction.amount)
+            {
+                reconciliation.skipped.push(Skipped {
+                    transaction,
+                    reason: SkipReason::AlreadyDistributed,
+                });
+                continue;
             }
-            let sp = StoragePlan {
-                user_id: user_id.clone(),
-                id: plan_id.clone(),
-                plan,
-                version: 1,
-                status: PlanStatus::Active,
+            let Some(source) = match_source(&weights.sources, rules, &transaction)
+            else {
+                reconciliation.skipped.push(Skipped {
+                    transaction,
+                    reason: SkipReason::NoSource,
+                });
+                continue;
             };
-            self.events.lock().unwrap().push(PlanEvent {
-                id: 1,
-                plan_id: plan_id.clone(),
-                version: 1,
-                action: PlanAction::Created,
-                content: Some(sp.plan.clone()),
-                created_at: "2026-03-10T00:00:00".into(),
+            let income =
+                Income::new(source.clone(), transaction.amount, transaction.date);
+            let DistributedIncome { budget, plan } =
+                self.distribute_income(user_id, &sp, &income).await?;
+            reconciliation.proposals.push(Proposal {
+                transaction,
+                budget,
+                plan,
             });
-            *self.plan.lock().unwrap() = Some(sp);
-            Ok(plan_id)
         }
+        Ok(reconciliation)
+    }
 
-        async fn update_plan(
-            &self,
-            _user_id: &UserId,
-            plan_id: &PlanId,
-            plan: Plan,
-        ) -> Result<(), StorageError> {
-            let mut current = self.plan.lock().unwrap();
-            let sp = current
-                .as_mut()
-                .filter(|sp| &sp.id == plan_id)
-                .ok_or(StorageError::UpdatePlan)?;
-            sp.version += 1;
-            sp.plan = plan.clone();
+    /// Сохраняет подтверждённые распределения из выписки. Доход пропускается,
+    /// если такой же по дате и сумме уже сохранён раньше; одинаковые платежи
+    /// внутри выписки сохраняются все
+    #[instrument(skip(self, budgets))]
+    pub async fn confirm_statement(
+        &self,
+        user_id: &UserId,
+        budgets: Vec<DistributedIncome>,
+    ) -> Result<Confirmation, Error> {
+        let mut distributed = self.distributed_incomes(user_id).await;
+        let mut confirmation = Confirmation::default();
+        for DistributedIncome { budget, plan } in budgets {
+            if take_distributed(
+                &mut distributed,
+                budget.income.date,
+                budget.income.amount,
+            ) {
+                confirmation.skipped.push(budget);
+                continue;
+            }
+            let id = self
+                .save_budget(user_id, build_id(), budget, Some(plan))
+                .await?;
+            confirmation.saved.push(id);
+        }
+        Ok(confirmation)
+    }
+
+    /// Сколько действующих распределений у каждой пары даты и суммы дохода
+    async fn distributed_incomes(
+        &self,
+        user_id: &UserId,
+    ) -> HashMap<(NaiveDate, Money), usize> {
+        let mut distributed = HashMap::new();
+        for sb in self.all_budgets(user_id).await {
+            if !sb.is_voided() {
+                *distributed
+                    .entry((sb.budget.income.date, sb.budget.income.amount))
+                    .or_insert(0) += 1;
+            }
+        }
+        distributed
+    }
+
+    /// Собирает планы с историей, распределения и операции пользователя в архив
+    #[instrument(skip(self))]
+    pub async fn export(&self, user_id: &UserId, now: DateTime<Utc>) -> Archive {
+        let mut plans = Vec::new();
+        for sp in self.repo.list_plans(user_id).await {
+            let events = self.all_plan_events(user_id, &sp.id).await;
+            plans.push(ArchivedPlan {
+                id: sp.id,
+                name: sp.name,
+                version: sp.version,
+                active: sp.active,
+                plan: sp.plan,
+                events,
+            });
+        }
+        Archive {
+            version: ARCHIVE_VERSION,
+            exported_at: now,
+            plans,
+            budgets: self.all_budgets(user_id).await,
+            ledger: self.all_ledger_entries(user_id).await,
+        }
+    }
+
+    /// Загружает архив. При [`ImportMode::Merge`] планы, распределения и
+    /// операции с уже занятыми идентификаторами пропускаются и попадают
+    /// в конфликты; распределения сопоставляются по [`BudgetId`].
+    /// При [`ImportMode::Replace`] данные пользователя сначала удаляются;
+    /// если архив не удалось загрузить, прежние данные возвращаются.
+    /// Архив проверяется целиком до любых изменений.
+    #[instrument(skip(self, archive))]
+    pub async fn import(
+        &self,
+        user_id: &UserId,
+        archive: Archive,
+        mode: ImportMode,
+    ) -> Result<ImportReport, Error> {
+        Self::check_archive(&archive)?;
+        if mode == ImportMode::Merge {
+            return self.load_archive(user_id, archive).await;
+        }
+
+        let backup = self.export(user_id, Utc::now()).await;
+        self.repo
+            .clear_user_data(user_id)
+            .await
+            .map_err(|_| Error::CantImport)?;
+        let e = match self.load_archive(user_id, archive).await {
+            Ok(report) => return Ok(report),
+            Err(e) => e,
+        };
+        warn!("Архив не загружен, возвращаем прежние данные: {e}");
+        let restored = match self.repo.clear_user_data(user_id).await {
+            Ok(()) => self.load_archive(user_id, backup).await.map(|_| ()),
+            Err(_) => Err(Error::CantImport),
+        };
+        if let Err(e) = restored {
+            error!("Не удалось вернуть данные пользователя {user_id}: {e}");
+        }
+        Err(e)
+    }
+
+    /// Проверяет версию архива, уникальность идентификаторов и планы
+    fn check_archive(archive: &Archive) -> Result<(), Error> {
+        if archive.version > ARCHIVE_VERSION {
+            return Err(Error::UnsupportedArchive {
+                version: archive.version,
+            });
+        }
+        let invalid = |message: String| Error::InvalidArchive { message };
+        let mut plans = HashSet::new();
+        for archived in &archive.plans {
+            if !plans.insert(&archived.id) {
+                return Err(invalid(format!("duplicate plan {}", archived.id)));
+            }
+            DistributionWeights::try_from(archived.plan.clone())
+                .map_err(|e| invalid(format!("plan {}: {e}", archived.id)))?;
+        }
+        let mut budgets = HashSet::new();
+        if let Some(sb) = archive.budgets.iter().find(|sb| !budgets.insert(&sb.id)) {
+            return Err(invalid(format!("duplicate budget {}", sb.id)));
+        }
+        let mut entries = HashSet::new();
+        if let Some(entry) = archive.ledger.iter().find(|e| !entries.insert(&e.id)) {
+            return Err(invalid(format!("duplicate ledger entry {}", entry.id)));
+        }
+        Ok(())
+    }
+
+    /// Записывает проверенный архив в хранилище поверх имеющихся данных
+    async fn load_archive(
+        &self,
+        user_id: &UserId,
+        archive: Archive,
+    ) -> Result<ImportReport, Error> {
+        let mut report = ImportReport::default();
+        let conflict = |report: &mut ImportReport, kind, id: &str| {
+            report.conflicts.push(Conflict {
+                kind,
+                id: id.to_string(),
+            });
+        };
+
+        let local: HashSet<PlanId> = self
+            .repo
+            .list_plans(user_id)
+            .await
+            .into_iter()
+            .map(|sp| sp.id)
+            .collect();
+        let mut has_active = self.repo.get_plan(user_id).await.is_some();
+        for archived in archive.plans {
+            if local.contains(&archived.id) {
+                conflict(&mut report, ConflictKind::Plan, &archived.id);
+                continue;
+            }
+            // Загруженный план не отбирает отметку у уже активного
+            let active = archived.active && !has_active;
+            let plan = StoragePlan {
+                user_id: user_id.clone(),
+                id: archived.id.clone(),
+                name: archived.name,
+                plan: archived.plan,
+                version: archived.version,
+                status: PlanStatus::Active,
+                active,
+            };
+            match self.repo.restore_plan(user_id, plan, archived.events).await {
+                Ok(()) => {
+                    report.plans += 1;
+                    has_active |= active;
+                }
+                Err(StorageError::PlanAlreadyExists) => {
+                    conflict(&mut report, ConflictKind::Plan, &archived.id);
+                }
+                Err(_) => return Err(Error::CantImport),
+            }
+        }
+
+        for budget in archive.budgets {
+            if self.repo.budget_by_id(user_id, &budget.id).await.is_some() {
+                conflict(&mut report, ConflictKind::Budget, &budget.id);
+                continue;
+            }
+            // Идентификатор может быть занят распределением другого пользователя
+            let id = match self
+                .repo
+                .save_budget(user_id, budget.id.clone(), budget.budget, budget.plan)
+                .await
+            {
+                Ok(id) => id,
+                Err(StorageError::BudgetAlreadyExists) => {
+                    conflict(&mut report, ConflictKind::Budget, &budget.id);
+                    continue;
+                }
+                Err(_) => return Err(Error::CantImport),
+            };
+            if let Some(void) = budget.voided {
+                self.repo
+                    .void_budget(user_id, &id, void)
+                    .await
+                    .map_err(|_| Error::CantImport)?;
+            }
+            report.budgets += 1;
+        }
+
+        let local: HashSet<LedgerEntryId> = self
+            .all_ledger_entries(user_id)
+            .await
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        for entry in archive.ledger {
+            if local.contains(&entry.id) {
+                conflict(&mut report, ConflictKind::LedgerEntry, &entry.id);
+                continue;
+            }
+            let id = entry.id.clone();
+            match self.repo.save_ledger_entry(user_id, entry).await {
+                Ok(_) => report.ledger += 1,
+                Err(StorageError::LedgerEntryAlreadyExists) => {
+                    conflict(&mut report, ConflictKind::LedgerEntry, &id);
+                }
+                Err(_) => return Err(Error::CantImport),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn ledger(&self, user_id: &UserId) -> Ledger {
+        let budgets = self.all_budgets(user_id).await;
+        let entries = self.all_ledger_entries(user_id).await;
+        let (ledger, failed) = Self::replay(&budgets, &entries, None);
+        for (id, e) in failed {
+            warn!("Операция {id} не применена к балансам: {e}");
+        }
+        ledger
+    }
+
+    /// Балансы по распределениям и операциям, датированным не позже `on`
+    async fn ledger_on(
+        &self,
+        user_id: &UserId,
+        on: NaiveDate,
+        skip: Option<&BudgetId>,
+    ) -> Ledger {
+        let budgets: Vec<_> = self
+            .all_budgets(user_id)
+            .await
+            .into_iter()
+            .filter(|b| *b.budget.income_date() <= on)
+            .collect();
+        let entries: Vec<_> = self
+            .all_ledger_entries(user_id)
+            .await
+            .into_iter()
+            .filter(|e| e.date <= on)
+            .collect();
+        let (ledger, failed) = Self::replay(&budgets, &entries, skip);
+        for (id, e) in failed {
+            warn!("Операция {id} не применена к балансам на {on}: {e}");
+        }
+        ledger
+    }
+
+    /// Сворачивает действующие распределения и операции с конвертами в балансы.
+    /// Распределение `skip` не учитывается. Вторым значением возвращаются
+    /// операции, которые не удалось применить.
+    fn replay(
+        budgets: &[StorageBudget],
+        entries: &[LedgerEntry],
+        skip: Option<&BudgetId>,
+    ) -> (Ledger, Vec<(LedgerEntryId, LedgerError)>) {
+        let mut ledger = Ledger::new();
+        for budget in budgets {
+            if budget.is_voided() || Some(&budget.id) == skip {
+                continue;
+            }
+            ledger.apply_budget(&budget.budget);
+        }
+        // Операции хранятся от новых к старым, а применять их нужно по порядку
+        let failed = entries
+            .iter()
+            .rev()
+            .filter_map(|entry| {
+                ledger
+                    .apply(&entry.operation)
+                    .err()
+                    .map(|e| (entry.id.clone(), e))
+            })
+            .collect();
+        (ledger, failed)
+    }
+
+    async fn all_budgets(&self, user_id: &UserId) -> Vec<StorageBudget> {
+        let mut all = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self.repo.budgets(user_id, cursor, PAGE_SIZE).await;
+            all.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return all,
+            }
+        }
+    }
+
+    /// Все события плана от старых к новым
+    async fn all_plan_events(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Vec<PlanEvent> {
+        let mut all = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self
+                .repo
+                .plan_events(user_id, plan_id, cursor, PAGE_SIZE)
+                .await;
+            all.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        all.sort_by_key(|e| e.id);
+        all
+    }
+
+    async fn all_ledger_entries(&self, user_id: &UserId) -> Vec<LedgerEntry> {
+        let mut all = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = self.repo.ledger_entries(user_id, cursor, PAGE_SIZE).await;
+            all.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return all,
+            }
+        }
+    }
+}
+
+/// Засчитывает доход за одно из уже сохранённых распределений с той же
+/// датой и суммой, если такое ещё осталось
+fn take_distributed(
+    distributed: &mut HashMap<(NaiveDate, Money), usize>,
+    date: NaiveDate,
+    amount: Money,
+) -> bool {
+    match distributed.get_mut(&(date, amount)) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use ai_core::{
+        distribute::Budget,
+        finance::{Money, Percentage},
+        plan::Plan,
+        planning::{Expense, ExpenseValue, IncomeKind, IncomeSource, Member},
+    };
+    use rust_decimal_macros::dec;
+    use serde::Serialize;
+
+    use super::*;
+    use crate::{ledger::LedgerEntryId, storage::*};
+
+    fn other_source(name: &str, expected: Money) -> IncomeSource {
+        IncomeSource::new(name.to_string(), IncomeKind::Other { expected })
+    }
+
+    struct InMemoryCoreRepo {
+        plans: Mutex<Vec<StoragePlan>>,
+        events: Mutex<Vec<PlanEvent>>,
+        budgets: Mutex<Vec<(UserId, StorageBudget)>>,
+        ledger: Mutex<Vec<(UserId, LedgerEntry)>>,
+    }
+
+    impl InMemoryCoreRepo {
+        fn new() -> Self {
+            Self {
+                plans: Mutex::new(vec![]),
+                events: Mutex::new(vec![]),
+                budgets: Mutex::new(vec![]),
+                ledger: Mutex::new(vec![]),
+            }
+        }
+
+        fn push_event(
+            &self,
+            plan_id: &PlanId,
+            version: Version,
+            action: PlanAction,
+            content: Option<Plan>,
+        ) {
             let mut events = self.events.lock().unwrap();
             let next_id = events.len() as i64 + 1;
             events.push(PlanEvent {
                 id: next_id,
                 plan_id: plan_id.clone(),
-                version: sp.version,
-                action: PlanAction::Updated,
-                content: Some(plan),
+                version,
+                action,
+                content,
                 created_at: "2026-03-10T00:00:00".into(),
             });
+        }
+    }
+
+    impl CoreRepo for InMemoryCoreRepo {
+        async fn get_plan(&self, _user_id: &UserId) -> Option<StoragePlan> {
+            self.plans
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|sp| sp.active)
+                .cloned()
+        }
+
+        async fn list_plans(&self, _user_id: &UserId) -> Vec<StoragePlan> {
+            self.plans.lock().unwrap().clone()
+        }
+
+        async fn shared_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+            self.plans
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|sp| {
+                    &sp.user_id != user_id
+                        && sp.plan.members.iter().any(|m| &m.id == user_id)
+                })
+                .cloned()
+                .collect()
+        }
+
+        async fn create_plan(
+            &self,
+            user_id: &UserId,
+            plan_id: PlanId,
+            name: String,
+            plan: Plan,
+        ) -> Result<PlanId, StorageError> {
+            let mut plans = self.plans.lock().unwrap();
+            if plans.iter().any(|sp| sp.id == plan_id || sp.name == name) {
+                return Err(StorageError::PlanAlreadyExists);
+            }
+            let active = !plans.iter().any(|sp| sp.active);
+            self.push_event(&plan_id, 1, PlanAction::Created, Some(plan.clone()));
+            plans.push(StoragePlan {
+                user_id: user_id.clone(),
+                id: plan_id.clone(),
+                name,
+                plan,
+                version: 1,
+                status: PlanStatus::Active,
+                active,
+            });
+            Ok(plan_id)
+        }
+
+        async fn update_plan(
+            &self,
+            _user_id: &UserId,
+            plan_id: &PlanId,
+            expected: Version,
+            plan: Plan,
+        ) -> Result<(), StorageError> {
+            let mut plans = self.plans.lock().unwrap();
+            let sp = plans
+                .iter_mut()
+                .find(|sp| &sp.id == plan_id)
+                .ok_or(StorageError::UpdatePlan)?;
+            if sp.version != expected {
+                return Err(StorageError::VersionConflict {
+                    current: sp.version,
+                });
+            }
+            sp.version += 1;
+            sp.plan = plan.clone();
+            self.push_event(plan_id, sp.version, PlanAction::Updated, Some(plan));
+            Ok(())
+        }
+
+        async fn rename_plan(
+            &self,
+            _user_id: &UserId,
+            plan_id: &PlanId,
+            name: String,
+        ) -> Result<(), StorageError> {
+            let mut plans = self.plans.lock().unwrap();
+            if plans.iter().any(|sp| sp.name == name && &sp.id != plan_id) {
+                return Err(StorageError::PlanAlreadyExists);
+            }
+            let sp = plans
+                .iter_mut()
+                .find(|sp| &sp.id == plan_id)
+                .ok_or(StorageError::PlanNotFound)?;
+            sp.name = name;
+            Ok(())
+        }
+
+        async fn activate_plan(
+            &self,
+            _user_id: &UserId,
+            plan_id: &PlanId,
+        ) -> Result<(), StorageError> {
+            let mut plans = self.plans.lock().unwrap();
+            if !plans.iter().any(|sp| &sp.id == plan_id) {
+                return Err(StorageError::PlanNotFound);
+            }
+            for sp in plans.iter_mut() {
+                sp.active = &sp.id == plan_id;
+            }
             Ok(())
         }
 
@@ -245,25 +1487,13 @@ mod tests {
             _user_id: &UserId,
             plan_id: &PlanId,
         ) -> Result<(), StorageError> {
-            let mut current = self.plan.lock().unwrap();
-            let sp = current
-                .as_mut()
-                .filter(|sp| &sp.id == plan_id)
+            let mut plans = self.plans.lock().unwrap();
+            let index = plans
+                .iter()
+                .position(|sp| &sp.id == plan_id)
                 .ok_or(StorageError::DeletePlan)?;
-            sp.version += 1;
-            sp.status = PlanStatus::Deleted;
-            let version = sp.version;
-            let mut events = self.events.lock().unwrap();
-            let next_id = events.len() as i64 + 1;
-            events.push(PlanEvent {
-                id: next_id,
-                plan_id: plan_id.clone(),
-                version,
-                action: PlanAction::Deleted,
-                content: None,
-                created_at: "2026-03-10T00:00:00".into(),
-            });
-            *current = None;
+            let sp = plans.remove(index);
+            self.push_event(plan_id, sp.version + 1, PlanAction::Deleted, None);
             Ok(())
         }
 
@@ -285,214 +1515,1436 @@ mod tests {
             Page::new(items, None)
         }
 
-        async fn save_budget(
-            &self,
-            budget_id: BudgetId,
-            _budget: Budget,
-        ) -> Result<BudgetId, StorageError> {
-            Ok(budget_id)
+        async fn save_budget(
+            &self,
+            user_id: &UserId,
+            budget_id: BudgetId,
+            budget: Budget,
+            plan: Option<PlanRef>,
+        ) -> Result<BudgetId, StorageError> {
+            let mut budgets = self.budgets.lock().unwrap();
+            if budgets.iter().any(|(_, sb)| sb.id == budget_id) {
+                return Err(StorageError::SaveBudget);
+            }
+            budgets.push((
+                user_id.clone(),
+                StorageBudget {
+                    plan,
+                    ..StorageBudget::from((budget_id.clone(), budget))
+                },
+            ));
+            Ok(budget_id)
+        }
+
+        async fn budget_by_id(
+            &self,
+            user_id: &UserId,
+            id: &BudgetId,
+        ) -> Option<StorageBudget> {
+            self.budgets
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(owner, b)| owner == user_id && &b.id == id)
+                .map(|(_, b)| b.clone())
+        }
+
+        async fn budgets(
+            &self,
+            user_id: &UserId,
+            _from: Option<Cursor>,
+            _limit: usize,
+        ) -> Page<StorageBudget> {
+            let items = self
+                .budgets
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(owner, _)| owner == user_id)
+                .map(|(_, b)| b.clone())
+                .collect();
+            Page::new(items, None)
+        }
+
+        async fn void_budget(
+            &self,
+            user_id: &UserId,
+            id: &BudgetId,
+            void: BudgetVoid,
+        ) -> Result<(), StorageError> {
+            let mut budgets = self.budgets.lock().unwrap();
+            let (_, budget) = budgets
+                .iter_mut()
+                .find(|(owner, b)| owner == user_id && &b.id == id)
+                .ok_or(StorageError::VoidBudget)?;
+            budget.voided = Some(void);
+            Ok(())
+        }
+
+        async fn save_ledger_entry(
+            &self,
+            user_id: &UserId,
+            entry: LedgerEntry,
+        ) -> Result<LedgerEntryId, StorageError> {
+            let id = entry.id.clone();
+            self.ledger
+                .lock()
+                .unwrap()
+                .insert(0, (user_id.clone(), entry));
+            Ok(id)
+        }
+
+        async fn ledger_entries(
+            &self,
+            user_id: &UserId,
+            _from: Option<Cursor>,
+            _limit: usize,
+        ) -> Page<LedgerEntry> {
+            let items = self
+                .ledger
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(owner, _)| owner == user_id)
+                .map(|(_, e)| e.clone())
+                .collect();
+            Page::new(items, None)
+        }
+
+        async fn restore_plan(
+            &self,
+            _user_id: &UserId,
+            plan: StoragePlan,
+            events: Vec<PlanEvent>,
+        ) -> Result<(), StorageError> {
+            let mut plans = self.plans.lock().unwrap();
+            if plans
+                .iter()
+                .any(|sp| sp.id == plan.id || sp.name == plan.name)
+            {
+                return Err(StorageError::PlanAlreadyExists);
+            }
+            if plan.active {
+                plans.iter_mut().for_each(|sp| sp.active = false);
+            }
+            for e in events {
+                self.push_event(&plan.id, e.version, e.action, e.content);
+            }
+            plans.push(plan);
+            Ok(())
+        }
+
+        async fn clear_user_data(&self, user_id: &UserId) -> Result<(), StorageError> {
+            self.plans.lock().unwrap().clear();
+            self.events.lock().unwrap().clear();
+            self.budgets
+                .lock()
+                .unwrap()
+                .retain(|(owner, _)| owner != user_id);
+            self.ledger
+                .lock()
+                .unwrap()
+                .retain(|(owner, _)| owner != user_id);
+            Ok(())
+        }
+    }
+
+    fn valid_plan() -> Plan {
+        Plan::build(
+            &[other_source("Зарплата", Money::new_rub(dec!(100000)))],
+            &[
+                Expense::envelope(
+                    "Аренда".into(),
+                    ExpenseValue::MONEY {
+                        value: Money::new_rub(dec!(30000)),
+                    },
+                    Some("Жильё".into()),
+                ),
+                Expense::envelope(
+                    "Накопления".into(),
+                    ExpenseValue::RATE {
+                        value: Percentage::from_int(20),
+                    },
+                    None,
+                ),
+            ],
+        )
+    }
+
+    #[derive(Serialize)]
+    #[serde(tag = "status")]
+    enum TestResult {
+        Ok { draft: Plan, stored: Plan },
+        Err { draft: Plan, error: String },
+    }
+
+    const TEST_PLAN_ID: &str = "test-plan-id";
+    const TEST_USER_ID: &str = "default";
+
+    fn make_api() -> CoreApi<InMemoryCoreRepo> {
+        CoreApi::new(Arc::new(InMemoryCoreRepo::new()))
+    }
+
+    #[tokio::test]
+    async fn create_plan_ok() {
+        let api = make_api();
+        let draft = valid_plan();
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            draft.clone(),
+        )
+        .await
+        .unwrap();
+        insta::assert_json_snapshot!(TestResult::Ok {
+            draft,
+            stored: api.get_plan(&TEST_USER_ID.into()).await.unwrap().plan
+        });
+    }
+
+    #[tokio::test]
+    async fn create_plan_already_exists() {
+        let api = make_api();
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        let err = api
+            .create_plan(
+                &TEST_USER_ID.into(),
+                TEST_PLAN_ID.into(),
+                DEFAULT_PLAN_NAME.into(),
+                valid_plan(),
+            )
+            .await
+            .unwrap_err();
+        insta::assert_debug_snapshot!(err);
+    }
+
+    #[tokio::test]
+    async fn create_plan_invalid_empty() {
+        let api = make_api();
+        let draft = Plan::default();
+        let err = api
+            .create_plan(
+                &TEST_USER_ID.into(),
+                TEST_PLAN_ID.into(),
+                DEFAULT_PLAN_NAME.into(),
+                draft.clone(),
+            )
+            .await
+            .unwrap_err();
+        insta::assert_json_snapshot!(TestResult::Err {
+            draft,
+            error: err.to_string()
+        });
+    }
+
+    #[tokio::test]
+    async fn create_plan_invalid_too_big_expenses() {
+        let api = make_api();
+        let draft = Plan::build(
+            &[other_source("Зарплата", Money::new_rub(dec!(100000)))],
+            &[Expense::envelope(
+                "Всё".into(),
+                ExpenseValue::RATE {
+                    value: Percentage::from_int(101),
+                },
+                None,
+            )],
+        );
+        let err = api
+            .create_plan(
+                &TEST_USER_ID.into(),
+                TEST_PLAN_ID.into(),
+                DEFAULT_PLAN_NAME.into(),
+                draft.clone(),
+            )
+            .await
+            .unwrap_err();
+        insta::assert_json_snapshot!(TestResult::Err {
+            draft,
+            error: err.to_string()
+        });
+    }
+
+    #[tokio::test]
+    async fn update_plan_ok() {
+        let api = make_api();
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        let updated = Plan::build(
+            &[other_source("Фриланс", Money::new_rub(dec!(200000)))],
+            &[Expense::envelope(
+                "Ипотека".into(),
+                ExpenseValue::MONEY {
+                    value: Money::new_rub(dec!(80000)),
+                },
+                Some("Жильё".into()),
+            )],
+        );
+        api.update_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            1,
+            updated.clone(),
+        )
+        .await
+        .unwrap();
+        insta::assert_json_snapshot!(TestResult::Ok {
+            draft: updated,
+            stored: api.get_plan(&TEST_USER_ID.into()).await.unwrap().plan
+        });
+    }
+
+    #[tokio::test]
+    async fn update_plan_no_existing() {
+        let api = make_api();
+        let err = api
+            .update_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), 1, valid_plan())
+            .await
+            .unwrap_err();
+        insta::assert_debug_snapshot!(err);
+    }
+
+    #[tokio::test]
+    async fn update_plan_invalid() {
+        let api = make_api();
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        let draft = Plan::default();
+        let err = api
+            .update_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), 1, draft.clone())
+            .await
+            .unwrap_err();
+        insta::assert_json_snapshot!(TestResult::Err {
+            draft,
+            error: err.to_string()
+        });
+    }
+
+    #[tokio::test]
+    async fn update_plan_increments_version() {
+        let api = make_api();
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(api.get_plan(&TEST_USER_ID.into()).await.unwrap().version, 1);
+
+        let updated = Plan::build(
+            &[other_source("Фриланс", Money::new_rub(dec!(200000)))],
+            &[Expense::envelope(
+                "Ипотека".into(),
+                ExpenseValue::MONEY {
+                    value: Money::new_rub(dec!(80000)),
+                },
+                Some("Жильё".into()),
+            )],
+        );
+        api.update_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), 1, updated)
+            .await
+            .unwrap();
+        assert_eq!(api.get_plan(&TEST_USER_ID.into()).await.unwrap().version, 2);
+    }
+
+    #[tokio::test]
+    async fn several_named_plans_with_one_active() {
+        let api = make_api();
+        let user: UserId = TEST_USER_ID.into();
+        api.create_plan(&user, "usual".into(), "Обычный месяц".into(), valid_plan())
+            .await
+            .unwrap();
+        api.clone_plan(&user, &"usual".into(), "vacation".into(), "Отпуск".into())
+            .await
+            .unwrap();
+
+        // Копия неактивна, доходы по-прежнему распределяются по первому плану
+        let plans = api.list_plans(&user).await;
+        let names: Vec<_> = plans.iter().map(|p| (p.name.as_str(), p.active)).collect();
+        assert_eq!(names, vec![("Обычный месяц", true), ("Отпуск", false)]);
+        assert_eq!(plans[1].plan, valid_plan());
+
+        assert!(matches!(
+            api.rename_plan(&user, &"vacation".into(), "Обычный месяц".into())
+                .await,
+            Err(Error::PlanAlreadyExists)
+        ));
+        assert!(matches!(
+            api.rename_plan(&user, &"vacation".into(), "  ".into())
+                .await,
+            Err(Error::EmptyPlanName)
+        ));
+        api.rename_plan(&user, &"vacation".into(), "Декрет".into())
+            .await
+            .unwrap();
+
+        api.activate_plan(&user, &"vacation".into()).await.unwrap();
+        let active = api.get_plan(&user).await.unwrap();
+        assert_eq!(
+            (active.id.as_str(), active.name.as_str()),
+            ("vacation", "Декрет")
+        );
+        assert_eq!(
+            api.list_plans(&user)
+                .await
+                .iter()
+                .filter(|p| p.active)
+                .count(),
+            1
+        );
+        assert!(matches!(
+            api.activate_plan(&user, &"missing".into()).await,
+            Err(Error::PlanNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn update_plan_rejects_stale_version() {
+        let api = make_api();
+        let user: UserId = TEST_USER_ID.into();
+        api.create_plan(
+            &user,
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        let mut first = valid_plan();
+        first.expenses.remove(1);
+        api.update_plan(&user, TEST_PLAN_ID.into(), 1, first.clone())
+            .await
+            .unwrap();
+
+        // Вторая вкладка всё ещё редактирует версию 1
+        let err = api
+            .update_plan(&user, TEST_PLAN_ID.into(), 1, valid_plan())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::PlanVersionConflict { current: 2 }));
+        let stored = api.get_plan(&user).await.unwrap();
+        assert_eq!(stored.version, 2);
+        assert_eq!(stored.plan, first);
+    }
+
+    #[tokio::test]
+    async fn budget_keeps_plan_version_it_was_distributed_with() {
+        let api = make_api();
+        let user: UserId = TEST_USER_ID.into();
+        api.create_plan(&user, TEST_PLAN_ID.into(), "Основной".into(), valid_plan())
+            .await
+            .unwrap();
+        let sp = api.get_plan(&user).await.unwrap();
+        let income = Income::new(
+            sp.plan.sources[0].clone(),
+            Money::new_rub(dec!(100000)),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+        );
+        let distributed = api.distribute_income(&user, &sp, &income).await.unwrap();
+
+        // План поменяли, пока распределение ждало подтверждения
+        api.update_plan(&user, TEST_PLAN_ID.into(), 1, valid_plan())
+            .await
+            .unwrap();
+        api.save_budget(
+            &user,
+            "b1".into(),
+            distributed.budget,
+            Some(distributed.plan),
+        )
+        .await
+        .unwrap();
+        let stored = api.budget_by_id(&user, &"b1".into()).await.unwrap();
+        assert_eq!(
+            stored.plan,
+            Some(PlanRef {
+                plan_id: TEST_PLAN_ID.into(),
+                version: 1,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn redistribute_against_plan_versions() {
+        let api = make_api();
+        let user: UserId = TEST_USER_ID.into();
+        api.create_plan(
+            &user,
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        let sp = api.get_plan(&user).await.unwrap();
+        let income = Income::new(
+            sp.plan.sources[0].clone(),
+            Money::new_rub(dec!(100000)),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+        );
+        let distributed = api.distribute_income(&user, &sp, &income).await.unwrap();
+        api.save_budget(
+            &user,
+            "b1".into(),
+            distributed.budget,
+            Some(distributed.plan),
+        )
+        .await
+        .unwrap();
+
+        let mut updated = valid_plan();
+        updated.expenses[1] = Expense::envelope(
+            "Накопления".into(),
+            ExpenseValue::RATE {
+                value: Percentage::from_int(30),
+            },
+            None,
+        );
+        api.update_plan(&user, TEST_PLAN_ID.into(), 1, updated)
+            .await
+            .unwrap();
+
+        let current = api.redistribute(&user, &"b1".into(), None).await.unwrap();
+        assert_eq!(
+            current.original,
+            Some(PlanRef {
+                plan_id: TEST_PLAN_ID.into(),
+                version: 1,
+            })
+        );
+        assert_eq!(current.target.version, 2);
+        let differences: Vec<_> = current
+            .changes
+            .iter()
+            .map(|c| (c.name.as_str(), c.difference))
+            .collect();
+        assert_eq!(
+            differences,
+            vec![
+                ("Аренда", Money::new_rub(dec!(0))),
+                ("Накопления", Money::new_rub(dec!(10000))),
+            ]
+        );
+
+        let same = api
+            .redistribute(&user, &"b1".into(), Some(1))
+            .await
+            .unwrap();
+        assert!(same.changes.iter().all(|c| c.difference.value.is_zero()));
+
+        assert!(matches!(
+            api.redistribute(&user, &"b1".into(), Some(5)).await,
+            Err(Error::PlanVersionNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn redistribute_keeps_goal_contributions() {
+        let api = make_api();
+        let user: UserId = TEST_USER_ID.into();
+        let goal = ai_core::planning::GoalExpense::new(
+            Money::new_rub(dec!(120000)),
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 12, 31).unwrap(),
+        )
+        .unwrap();
+        let plan = Plan::build(
+            &[other_source("Зарплата", Money::new_rub(dec!(100000)))],
+            &[Expense::goal("Отпуск".into(), goal, None)],
+        );
+        api.create_plan(&user, TEST_PLAN_ID.into(), DEFAULT_PLAN_NAME.into(), plan)
+            .await
+            .unwrap();
+        let sp = api.get_plan(&user).await.unwrap();
+        for (id, month) in [("b1", 1), ("b2", 3)] {
+            let income = Income::new(
+                sp.plan.sources[0].clone(),
+                Money::new_rub(dec!(100000)),
+                NaiveDate::from_ymd_opt(2026, month, 10).unwrap(),
+            );
+            let distributed = api.distribute_income(&user, &sp, &income).await.unwrap();
+            api.save_budget(
+                &user,
+                id.into(),
+                distributed.budget,
+                Some(distributed.plan),
+            )
+            .await
+            .unwrap();
+        }
+
+        for id in ["b1", "b2"] {
+            let same = api.redistribute(&user, &id.into(), None).await.unwrap();
+            assert!(!same.changes.is_empty());
+            assert!(same.changes.iter().all(|c| c.difference.value.is_zero()));
+        }
+    }
+
+    #[tokio::test]
+    async fn plan_events_and_diff() {
+        let api = make_api();
+        let user: UserId = TEST_USER_ID.into();
+        let plan_id: PlanId = TEST_PLAN_ID.into();
+        api.create_plan(
+            &user,
+            plan_id.clone(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        let mut updated = valid_plan();
+        updated.expenses.remove(1);
+        api.update_plan(&user, plan_id.clone(), 1, updated)
+            .await
+            .unwrap();
+
+        let events = api.plan_events(&user, &plan_id, None, 10).await;
+        let versions: Vec<_> = events.iter().map(|e| (e.version, &e.action)).collect();
+        assert_eq!(
+            versions,
+            vec![(1, &PlanAction::Created), (2, &PlanAction::Updated)]
+        );
+
+        let diff = api.plan_diff(&user, &plan_id, 1, 2).await.unwrap();
+        assert!(diff.sources.is_empty());
+        assert!(matches!(
+            diff.expenses.as_slice(),
+            [ai_core::plan::Change::Removed { old }] if old.name == "Накопления"
+        ));
+        assert!(matches!(
+            api.plan_diff(&user, &plan_id, 1, 3).await,
+            Err(Error::PlanVersionNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn delete_plan_ok() {
+        let api = make_api();
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        assert!(api.get_plan(&TEST_USER_ID.into()).await.is_some());
+        let result = api
+            .delete_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into())
+            .await;
+        assert!(result.is_ok());
+        assert!(api.get_plan(&TEST_USER_ID.into()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn envelope_balances_follow_budgets_and_operations() {
+        let api = make_api();
+        let plan: DistributionWeights = valid_plan().try_into().unwrap();
+        let income = Income::new(
+            plan.sources[0].clone(),
+            Money::new_rub(dec!(100000)),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+        );
+        for id in ["b1", "b2"] {
+            let budget = api.distribute(&plan, &income).unwrap();
+            api.save_budget(&TEST_USER_ID.into(), id.into(), budget, None)
+                .await
+                .unwrap();
         }
+        let date = NaiveDate::from_ymd_opt(2026, 3, 15).unwrap();
+        api.record_ledger_operation(
+            &TEST_USER_ID.into(),
+            date,
+            LedgerOperation::Withdrawal {
+                envelope: "Аренда".into(),
+                amount: Money::new_rub(dec!(30000)),
+            },
+            Some("Оплата за март".into()),
+        )
+        .await
+        .unwrap();
+        api.record_ledger_operation(
+            &TEST_USER_ID.into(),
+            date,
+            LedgerOperation::Transfer {
+                from: "Накопления".into(),
+                to: "Аренда".into(),
+                amount: Money::new_rub(dec!(5000)),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let err = api
+            .record_ledger_operation(
+                &TEST_USER_ID.into(),
+                date,
+                LedgerOperation::Withdrawal {
+                    envelope: "Аренда".into(),
+                    amount: Money::new_rub(dec!(1000000)),
+                },
+                None,
+            )
+            .await
+            .unwrap_err();
 
-        async fn budget_by_id(&self, _id: &BudgetId) -> Option<StorageBudget> {
-            None
-        }
+        insta::assert_debug_snapshot!((
+            api.envelope_balances(&TEST_USER_ID.into()).await,
+            err
+        ));
+    }
 
-        async fn budgets(
-            &self,
-            _from: Option<Cursor>,
-            _limit: usize,
-        ) -> Page<StorageBudget> {
-            Page::new(vec![], None)
+    #[tokio::test]
+    async fn voided_budget_drops_out_of_balances() {
+        let api = make_api();
+        let plan: DistributionWeights = valid_plan().try_into().unwrap();
+        let income = Income::new(
+            plan.sources[0].clone(),
+            Money::new_rub(dec!(100000)),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+        );
+        for id in ["b1", "b2"] {
+            let budget = api.distribute(&plan, &income).unwrap();
+            api.save_budget(&TEST_USER_ID.into(), id.into(), budget, None)
+                .await
+                .unwrap();
         }
+        let date = NaiveDate::from_ymd_opt(2026, 3, 15).unwrap();
+        let withdraw = |amount| LedgerOperation::Withdrawal {
+            envelope: "Аренда".into(),
+            amount: Money::new_rub(amount),
+        };
+        api.record_ledger_operation(
+            &TEST_USER_ID.into(),
+            date,
+            withdraw(dec!(10000)),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let voided = api
+            .void_budget(
+                &TEST_USER_ID.into(),
+                &"b2".into(),
+                date,
+                Some("Опечатка в сумме".into()),
+            )
+            .await
+            .unwrap();
+        assert!(voided.is_voided());
+        let balances: Vec<_> = api
+            .envelope_balances(&TEST_USER_ID.into())
+            .await
+            .into_iter()
+            .map(|b| (b.name, b.balance))
+            .collect();
+        assert_eq!(
+            balances,
+            vec![
+                ("Аренда".to_string(), Money::new_rub(dec!(20000))),
+                ("Накопления".to_string(), Money::new_rub(dec!(20000))),
+            ]
+        );
+        // Распределение осталось в истории
+        assert_eq!(
+            api.budget_list(&TEST_USER_ID.into(), None, 10).await.len(),
+            2
+        );
+        // Чужие распределения и балансы не видны
+        assert!(api.budget_list(&"ivan".into(), None, 10).await.is_empty());
+        assert!(api.envelope_balances(&"ivan".into()).await.is_empty());
+
+        assert!(matches!(
+            api.void_budget(&TEST_USER_ID.into(), &"b2".into(), date, None)
+                .await,
+            Err(Error::BudgetAlreadyVoided)
+        ));
+        assert!(matches!(
+            api.void_budget(&TEST_USER_ID.into(), &"missing".into(), date, None)
+                .await,
+            Err(Error::BudgetNotFound)
+        ));
+        // Без денег b1 снятие из «Аренды» не сходится
+        assert!(matches!(
+            api.void_budget(&TEST_USER_ID.into(), &"b1".into(), date, None)
+                .await,
+            Err(Error::BudgetInUse { .. })
+        ));
     }
 
-    fn valid_plan() -> Plan {
-        Plan::build(
+    #[tokio::test]
+    async fn goals_track_saved_amounts() {
+        let api = make_api();
+        let goal = ai_core::planning::GoalExpense::new(
+            Money::new_rub(dec!(120000)),
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 12, 31).unwrap(),
+        )
+        .unwrap();
+        let plan = Plan::build(
             &[other_source("Зарплата", Money::new_rub(dec!(100000)))],
-            &[
-                Expense::envelope(
-                    "Аренда".into(),
-                    ExpenseValue::MONEY {
-                        value: Money::new_rub(dec!(30000)),
-                    },
-                    Some("Жильё".into()),
-                ),
-                Expense::envelope(
-                    "Накопления".into(),
-                    ExpenseValue::RATE {
-                        value: Percentage::from_int(20),
-                    },
-                    None,
-                ),
-            ],
+            &[Expense::goal("Отпуск".into(), goal, None)],
+        );
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            plan.clone(),
         )
-    }
-
-    #[derive(Serialize)]
-    #[serde(tag = "status")]
-    enum TestResult {
-        Ok { draft: Plan, stored: Plan },
-        Err { draft: Plan, error: String },
-    }
+        .await
+        .unwrap();
+        let sp = api.get_plan(&TEST_USER_ID.into()).await.unwrap();
+        let income = Income::new(
+            sp.plan.sources[0].clone(),
+            Money::new_rub(dec!(100000)),
+            NaiveDate::from_ymd_opt(2026, 1, 10).unwrap(),
+        );
+        let distributed = api
+            .distribute_income(&TEST_USER_ID.into(), &sp, &income)
+            .await
+            .unwrap();
+        api.save_budget(
+            &TEST_USER_ID.into(),
+            "b1".into(),
+            distributed.budget,
+            Some(distributed.plan),
+        )
+        .await
+        .unwrap();
 
-    const TEST_PLAN_ID: &str = "test-plan-id";
-    const TEST_USER_ID: &str = "default";
+        // В феврале уже накоплено 10000, до срока 11 месяцев
+        let income = Income::new(
+            income.source,
+            Money::new_rub(dec!(100000)),
+            NaiveDate::from_ymd_opt(2026, 2, 10).unwrap(),
+        );
+        let budget = api
+            .distribute_income(&TEST_USER_ID.into(), &sp, &income)
+            .await
+            .unwrap()
+            .budget;
+        assert_eq!(budget.no_category[0].amount, Money::new_rub(dec!(10000)));
 
-    fn make_api() -> CoreApi<InMemoryCoreRepo> {
-        CoreApi::new(Arc::new(InMemoryCoreRepo::new()))
+        let goals = api
+            .goals(
+                &TEST_USER_ID.into(),
+                NaiveDate::from_ymd_opt(2026, 2, 10).unwrap(),
+            )
+            .await
+            .unwrap();
+        insta::assert_json_snapshot!(goals);
     }
 
     #[tokio::test]
-    async fn create_plan_ok() {
+    async fn backdated_income_ignores_later_savings() {
         let api = make_api();
-        let draft = valid_plan();
-        api.create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), draft.clone())
+        let goal = ai_core::planning::GoalExpense::new(
+            Money::new_rub(dec!(120000)),
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 12, 31).unwrap(),
+        )
+        .unwrap();
+        let plan = Plan::build(
+            &[other_source("Зарплата", Money::new_rub(dec!(100000)))],
+            &[Expense::goal("Отпуск".into(), goal, None)],
+        );
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            plan,
+        )
+        .await
+        .unwrap();
+        let sp = api.get_plan(&TEST_USER_ID.into()).await.unwrap();
+        let income = |day| {
+            Income::new(
+                sp.plan.sources[0].clone(),
+                Money::new_rub(dec!(100000)),
+                NaiveDate::from_ymd_opt(2026, 1, day).unwrap(),
+            )
+        };
+        let distributed = api
+            .distribute_income(&TEST_USER_ID.into(), &sp, &income(10))
             .await
             .unwrap();
-        insta::assert_json_snapshot!(TestResult::Ok {
-            draft,
-            stored: api.get_plan(&TEST_USER_ID.into()).await.unwrap().plan
-        });
+        api.save_budget(
+            &TEST_USER_ID.into(),
+            "b1".into(),
+            distributed.budget,
+            Some(distributed.plan),
+        )
+        .await
+        .unwrap();
+
+        // К 5 января ещё ничего не накоплено: взнос на полный год
+        let budget = api
+            .distribute_income(&TEST_USER_ID.into(), &sp, &income(5))
+            .await
+            .unwrap()
+            .budget;
+        assert_eq!(budget.no_category[0].amount, Money::new_rub(dec!(10000)));
     }
 
     #[tokio::test]
-    async fn create_plan_already_exists() {
+    async fn credits_show_remaining_principal() {
         let api = make_api();
-        api.create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), valid_plan())
+        let credit = ai_core::planning::CreditExpense::new(
+            Money::new_rub(dec!(30000)),
+            Money::new_rub(dec!(90000)),
+            ai_core::finance::Percentage::ZERO,
+            3,
+            NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
+        )
+        .unwrap();
+        let plan = Plan::build(
+            &[other_source("Зарплата", Money::new_rub(dec!(100000)))],
+            &[Expense::credit("Ноутбук".into(), credit, None)],
+        );
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            plan,
+        )
+        .await
+        .unwrap();
+
+        let credits = api
+            .credits(
+                &TEST_USER_ID.into(),
+                RepaymentKind::Annuity,
+                NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            )
             .await
             .unwrap();
-        let err = api
-            .create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), valid_plan())
-            .await
-            .unwrap_err();
-        insta::assert_debug_snapshot!(err);
+        assert_eq!(credits.len(), 1);
+        assert!(credits[0].payment_matches_terms);
+        assert_eq!(credits[0].remaining_principal, Money::new_rub(dec!(60000)));
+        assert_eq!(credits[0].schedule.installments.len(), 3);
     }
 
     #[tokio::test]
-    async fn create_plan_invalid_empty() {
+    async fn simulate_starts_from_envelope_balances() {
         let api = make_api();
-        let draft = Plan::default();
-        let err = api
-            .create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), draft.clone())
+        let user_id: UserId = TEST_USER_ID.into();
+        api.create_plan(
+            &user_id,
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            valid_plan(),
+        )
+        .await
+        .unwrap();
+        let plan: DistributionWeights = valid_plan().try_into().unwrap();
+        let march = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let income =
+            Income::new(plan.sources[0].clone(), Money::new_rub(dec!(100000)), march);
+        let budget = api.distribute(&plan, &income).unwrap();
+        api.save_budget(&user_id, "b1".into(), budget, None)
             .await
-            .unwrap_err();
-        insta::assert_json_snapshot!(TestResult::Err {
-            draft,
-            error: err.to_string()
-        });
+            .unwrap();
+
+        let april = NaiveDate::from_ymd_opt(2026, 4, 1).unwrap();
+        let scenario = Scenario {
+            start: april,
+            months: 2,
+            incomes: ai_core::simulate::planned_incomes(
+                &valid_plan().sources,
+                april,
+                2,
+            ),
+            changes: vec![],
+            opening: HashMap::new(),
+            repayment: RepaymentKind::default(),
+        };
+        let forecast = api.simulate(&user_id, scenario).await.unwrap();
+
+        let may = &forecast.months[1];
+        assert_eq!(may.envelopes["Аренда"], Money::new_rub(dec!(90000)));
+        assert_eq!(may.envelopes["Накопления"], Money::new_rub(dec!(60000)));
+
+        let endless = Scenario {
+            start: april,
+            months: u32::MAX,
+            incomes: vec![],
+            changes: vec![],
+            opening: HashMap::new(),
+            repayment: RepaymentKind::default(),
+        };
+        assert!(matches!(
+            api.simulate(&user_id, endless).await,
+            Err(Error::ForecastTooLong { .. })
+        ));
     }
 
     #[tokio::test]
-    async fn create_plan_invalid_too_big_expenses() {
+    async fn expected_incomes_compare_actual_with_plan() {
         let api = make_api();
-        let draft = Plan::build(
-            &[other_source("Зарплата", Money::new_rub(dec!(100000)))],
+        let schedule =
+            ai_core::planning::IncomeSchedule::monthly(vec![10, 25]).unwrap();
+        let source = other_source("Зарплата", Money::new_rub(dec!(50000)))
+            .with_schedule(schedule);
+        let plan = Plan::build(
+            std::slice::from_ref(&source),
             &[Expense::envelope(
-                "Всё".into(),
+                "Еда".into(),
                 ExpenseValue::RATE {
-                    value: Percentage::from_int(101),
+                    value: Percentage::from_int(50),
                 },
                 None,
             )],
         );
-        let err = api
-            .create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), draft.clone())
+        api.create_plan(
+            &TEST_USER_ID.into(),
+            TEST_PLAN_ID.into(),
+            DEFAULT_PLAN_NAME.into(),
+            plan.clone(),
+        )
+        .await
+        .unwrap();
+        let weights: DistributionWeights = plan.try_into().unwrap();
+        let income = Income::new(
+            source,
+            Money::new_rub(dec!(52000)),
+            NaiveDate::from_ymd_opt(2026, 3, 10).unwrap(),
+        );
+        let budget = api.distribute(&weights, &income).unwrap();
+        api.save_budget(&TEST_USER_ID.into(), "b1".into(), budget, None)
             .await
-            .unwrap_err();
-        insta::assert_json_snapshot!(TestResult::Err {
-            draft,
-            error: err.to_string()
-        });
+            .unwrap();
+
+        let expected = api
+            .expected_incomes(
+                &TEST_USER_ID.into(),
+                NaiveDate::from_ymd_opt(2026, 3, 20).unwrap(),
+            )
+            .await
+            .unwrap();
+        insta::assert_json_snapshot!(expected);
     }
 
     #[tokio::test]
-    async fn update_plan_ok() {
+    async fn members_contribute_to_shared_envelopes() {
         let api = make_api();
-        api.create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), valid_plan())
+        let owner: UserId = TEST_USER_ID.into();
+        let ivan: UserId = "ivan".into();
+        let plan = Plan::build(
+            &[
+                other_source("Зарплата Анны", Money::new_rub(dec!(60000))),
+                other_source("Зарплата Ивана", Money::new_rub(dec!(40000))),
+            ],
+            &[
+                Expense::envelope(
+                    "Аренда".into(),
+                    ExpenseValue::MONEY {
+                        value: Money::new_rub(dec!(30000)),
+                    },
+                    None,
+                ),
+                Expense::envelope(
+                    "Еда".into(),
+                    ExpenseValue::MONEY {
+                        value: Money::new_rub(dec!(20000)),
+                    },
+                    None,
+                ),
+            ],
+        )
+        .with_members(vec![
+            Member::new(owner.clone(), vec!["Зарплата Анны".into()])
+                .with_share("Аренда", Percentage::HALF),
+            Member::new(ivan.clone(), vec!["Зарплата Ивана".into()])
+                .with_share("Аренда", Percentage::HALF),
+        ]);
+        api.create_plan(&owner, TEST_PLAN_ID.into(), "Семья".into(), plan)
             .await
             .unwrap();
-        let updated = Plan::build(
-            &[other_source("Фриланс", Money::new_rub(dec!(200000)))],
-            &[Expense::envelope(
-                "Ипотека".into(),
-                ExpenseValue::MONEY {
-                    value: Money::new_rub(dec!(80000)),
-                },
-                Some("Жильё".into()),
-            )],
-        );
-        api.update_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), updated.clone())
+        let date = NaiveDate::from_ymd_opt(2026, 5, 5).unwrap();
+
+        assert_eq!(api.shared_plans(&ivan).await.len(), 1);
+        assert!(api.shared_plans(&owner).await.is_empty());
+        assert!(matches!(
+            api.contribute(
+                &ivan,
+                &TEST_PLAN_ID.into(),
+                "b0".into(),
+                "Зарплата Анны",
+                dec!(60000),
+                date
+            )
+            .await,
+            Err(Error::ForeignSource)
+        ));
+        api.contribute(
+            &ivan,
+            &TEST_PLAN_ID.into(),
+            "b1".into(),
+            "Зарплата Ивана",
+            dec!(40000),
+            date,
+        )
+        .await
+        .unwrap();
+        api.contribute(
+            &owner,
+            &TEST_PLAN_ID.into(),
+            "b2".into(),
+            "Зарплата Анны",
+            dec!(60000),
+            date,
+        )
+        .await
+        .unwrap();
+
+        // Распределения участников ложатся в общие конверты владельца
+        assert_eq!(api.budget_list(&owner, None, 10).await.len(), 2);
+        assert!(api.budget_list(&ivan, None, 10).await.is_empty());
+        let contributions: Vec<_> = api
+            .contributions(&ivan, &TEST_PLAN_ID.into())
             .await
-            .unwrap();
-        insta::assert_json_snapshot!(TestResult::Ok {
-            draft: updated,
-            stored: api.get_plan(&TEST_USER_ID.into()).await.unwrap().plan
-        });
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.envelope, c.member, c.amount))
+            .collect();
+        assert_eq!(
+            contributions,
+            vec![
+                ("Аренда".into(), owner.clone(), Money::new_rub(dec!(15000))),
+                ("Аренда".into(), ivan.clone(), Money::new_rub(dec!(15000))),
+                ("Еда".into(), owner.clone(), Money::new_rub(dec!(12000))),
+                ("Еда".into(), ivan.clone(), Money::new_rub(dec!(8000))),
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn update_plan_no_existing() {
+    async fn budget_history_skips_voided_and_keeps_period() {
         let api = make_api();
-        let err = api
-            .update_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), valid_plan())
+        let user: UserId = TEST_USER_ID.into();
+        let plan: DistributionWeights = valid_plan().try_into().unwrap();
+        for (id, day) in [("b3", 20), ("b1", 1), ("b2", 10), ("b4", 31)] {
+            let date = NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+            let income =
+                Income::new(plan.sources[0].clone(), Money::new_rub(dec!(1000)), date);
+            let budget = api.distribute(&plan, &income).unwrap();
+            api.save_budget(&user, id.into(), budget, None)
+                .await
+                .unwrap();
+        }
+        api.void_budget(&user, &"b2".into(), NaiveDate::default(), None)
             .await
-            .unwrap_err();
-        insta::assert_debug_snapshot!(err);
+            .unwrap();
+
+        let ids = |budgets: Vec<StorageBudget>| -> Vec<BudgetId> {
+            budgets.into_iter().map(|sb| sb.id).collect()
+        };
+        assert_eq!(
+            ids(api.budget_history(&user, None, None).await),
+            vec!["b1", "b3", "b4"]
+        );
+        assert_eq!(
+            ids(api
+                .budget_history(
+                    &user,
+                    NaiveDate::from_ymd_opt(2026, 3, 2),
+                    NaiveDate::from_ymd_opt(2026, 3, 20),
+                )
+                .await),
+            vec!["b3"]
+        );
     }
 
     #[tokio::test]
-    async fn update_plan_invalid() {
+    async fn statement_proposes_budgets_for_new_incomes() {
         let api = make_api();
-        api.create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), valid_plan())
+        let user: UserId = TEST_USER_ID.into();
+        let plan = Plan::build(
+            &[
+                other_source("Зарплата", Money::new_rub(dec!(100000))),
+                other_source("Фриланс", Money::new_rub(dec!(20000))),
+            ],
+            &valid_plan().expenses,
+        );
+        api.create_plan(&user, TEST_PLAN_ID.into(), "Основной".into(), plan)
             .await
             .unwrap();
-        let draft = Plan::default();
-        let err = api
-            .update_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), draft.clone())
+        let tx = |day, amount, counterparty: &str| Transaction {
+            date: NaiveDate::from_ymd_opt(2026, 3, day).unwrap(),
+            amount: Money::new_rub(amount),
+            counterparty: Some(counterparty.into()),
+            description: None,
+        };
+        let transactions = vec![
+            tx(5, dec!(95000), "ООО Ромашка"),
+            tx(10, dec!(20000), "Иванов И.И."),
+            tx(11, dec!(5000), "Петров П.П."),
+            tx(12, dec!(-3000), "Магазин"),
+        ];
+        let rules = [MatchRule {
+            source: "Зарплата".into(),
+            counterparty: Some("ромашка".into()),
+            min: None,
+            max: None,
+        }];
+
+        let reconciliation = api
+            .reconcile_statement(&user, transactions.clone(), &rules)
             .await
-            .unwrap_err();
-        insta::assert_json_snapshot!(TestResult::Err {
-            draft,
-            error: err.to_string()
-        });
+            .unwrap();
+        let sources: Vec<_> = reconciliation
+            .proposals
+            .iter()
+            .map(|p| p.budget.income.source.name.as_str())
+            .collect();
+        assert_eq!(sources, vec!["Зарплата", "Фриланс"]);
+        let skipped: Vec<_> = reconciliation.skipped.iter().map(|s| s.reason).collect();
+        assert_eq!(skipped, vec![SkipReason::NoSource]);
+
+        let budgets: Vec<_> = reconciliation
+            .proposals
+            .into_iter()
+            .map(|p| DistributedIncome {
+                budget: p.budget,
+                plan: p.plan,
+            })
+            .collect();
+        let confirmation = api.confirm_statement(&user, budgets.clone()).await.unwrap();
+        assert_eq!(confirmation.saved.len(), 2);
+        assert!(confirmation.skipped.is_empty());
+        let confirmation = api.confirm_statement(&user, budgets).await.unwrap();
+        assert!(confirmation.saved.is_empty());
+        assert_eq!(confirmation.skipped.len(), 2);
+
+        let again = api
+            .reconcile_statement(&user, transactions, &rules)
+            .await
+            .unwrap();
+        assert!(again.proposals.is_empty());
+        let skipped: Vec<_> = again.skipped.iter().map(|s| s.reason).collect();
+        assert_eq!(
+            skipped,
+            vec![
+                SkipReason::AlreadyDistributed,
+                SkipReason::AlreadyDistributed,
+                SkipReason::NoSource,
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn update_plan_increments_version() {
+    async fn statement_keeps_repeated_payments() {
         let api = make_api();
-        api.create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), valid_plan())
+        let user: UserId = TEST_USER_ID.into();
+        api.create_plan(&user, TEST_PLAN_ID.into(), "Основной".into(), valid_plan())
             .await
             .unwrap();
-        assert_eq!(api.get_plan(&TEST_USER_ID.into()).await.unwrap().version, 1);
+        // Два одинаковых перевода в один день - разные платежи
+        let payment = Transaction {
+            date: NaiveDate::from_ymd_opt(2026, 3, 5).unwrap(),
+            amount: Money::new_rub(dec!(100000)),
+            counterparty: Some("ООО Ромашка".into()),
+            description: None,
+        };
+        let transactions = vec![payment.clone(), payment];
 
-        let updated = Plan::build(
-            &[other_source("Фриланс", Money::new_rub(dec!(200000)))],
-            &[Expense::envelope(
-                "Ипотека".into(),
-                ExpenseValue::MONEY {
-                    value: Money::new_rub(dec!(80000)),
+        let reconciliation = api
+            .reconcile_statement(&user, transactions.clone(), &[])
+            .await
+            .unwrap();
+        let budgets: Vec<_> = reconciliation
+            .proposals
+            .into_iter()
+            .map(|p| DistributedIncome {
+                budget: p.budget,
+                plan: p.plan,
+            })
+            .collect();
+        assert_eq!(budgets.len(), 2);
+        let confirmation = api.confirm_statement(&user, budgets.clone()).await.unwrap();
+        assert_eq!(confirmation.saved.len(), 2);
+
+        let again = api
+            .reconcile_statement(&user, transactions, &[])
+            .await
+            .unwrap();
+        assert!(again.proposals.is_empty());
+        assert_eq!(again.skipped.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn export_and_import_archive() {
+        let source = make_api();
+        let user: UserId = TEST_USER_ID.into();
+        source
+            .create_plan(&user, TEST_PLAN_ID.into(), "Основной".into(), valid_plan())
+            .await
+            .unwrap();
+        source
+            .update_plan(&user, TEST_PLAN_ID.into(), 1, valid_plan())
+            .await
+            .unwrap();
+        let plan: DistributionWeights = valid_plan().try_into().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let income =
+            Income::new(plan.sources[0].clone(), Money::new_rub(dec!(100000)), date);
+        for id in ["b1", "b2"] {
+            let budget = source.distribute(&plan, &income).unwrap();
+            source
+                .save_budget(&user, id.into(), budget, None)
+                .await
+                .unwrap();
+        }
+        source
+            .void_budget(&user, &"b2".into(), date, None)
+            .await
+            .unwrap();
+        source
+            .record_ledger_operation(
+                &user,
+                date,
+                LedgerOperation::Withdrawal {
+                    envelope: "Аренда".into(),
+                    amount: Money::new_rub(dec!(1000)),
                 },
-                Some("Жильё".into()),
-            )],
+                None,
+            )
+            .await
+            .unwrap();
+        let now = DateTime::parse_from_rfc3339("2026-05-01T10:00:00Z")
+            .unwrap()
+            .to_utc();
+        let archive = source.export(&user, now).await;
+        assert_eq!(archive.version, ARCHIVE_VERSION);
+        let versions: Vec<_> =
+            archive.plans[0].events.iter().map(|e| e.version).collect();
+        assert_eq!(versions, vec![1, 2]);
+
+        let target = make_api();
+        let report = target
+            .import(&user, archive.clone(), ImportMode::Merge)
+            .await
+            .unwrap();
+        assert_eq!((report.plans, report.budgets, report.ledger), (1, 2, 1));
+        assert!(report.conflicts.is_empty());
+        let restored = target.get_plan(&user).await.unwrap();
+        assert_eq!(restored.version, 2);
+        assert!(
+            target
+                .budget_by_id(&user, &"b2".into())
+                .await
+                .unwrap()
+                .is_voided()
         );
-        api.update_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), updated)
+        assert_eq!(
+            target.envelope_balances(&user).await,
+            source.envelope_balances(&user).await
+        );
+
+        // Повторная загрузка ничего не дублирует
+        let report = target
+            .import(&user, archive.clone(), ImportMode::Merge)
             .await
             .unwrap();
-        assert_eq!(api.get_plan(&TEST_USER_ID.into()).await.unwrap().version, 2);
+        assert_eq!((report.plans, report.budgets, report.ledger), (0, 0, 0));
+        let kinds: Vec<_> = report.conflicts.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ConflictKind::Plan,
+                ConflictKind::Budget,
+                ConflictKind::Budget,
+                ConflictKind::LedgerEntry,
+            ]
+        );
+
+        let report = target
+            .import(&user, archive.clone(), ImportMode::Replace)
+            .await
+            .unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(target.budget_list(&user, None, 10).await.len(), 2);
+
+        let future = Archive {
+            version: ARCHIVE_VERSION + 1,
+            ..archive
+        };
+        assert!(matches!(
+            target.import(&user, future, ImportMode::Merge).await,
+            Err(Error::UnsupportedArchive { .. })
+        ));
     }
 
     #[tokio::test]
-    async fn delete_plan_ok() {
+    async fn replace_import_keeps_data_on_failure() {
         let api = make_api();
-        api.create_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into(), valid_plan())
+        let user: UserId = TEST_USER_ID.into();
+        let ivan: UserId = "ivan".into();
+        api.create_plan(&user, TEST_PLAN_ID.into(), "Основной".into(), valid_plan())
             .await
             .unwrap();
-        assert!(api.get_plan(&TEST_USER_ID.into()).await.is_some());
-        let result = api
-            .delete_plan(&TEST_USER_ID.into(), TEST_PLAN_ID.into())
-            .await;
-        assert!(result.is_ok());
-        assert!(api.get_plan(&TEST_USER_ID.into()).await.is_none());
+        let plan: DistributionWeights = valid_plan().try_into().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let income =
+            Income::new(plan.sources[0].clone(), Money::new_rub(dec!(100000)), date);
+        let budget = api.distribute(&plan, &income).unwrap();
+        api.save_budget(&user, "b1".into(), budget.clone(), None)
+            .await
+            .unwrap();
+        api.save_budget(&ivan, "b2".into(), budget, None)
+            .await
+            .unwrap();
+        let before = api.export(&user, Utc::now()).await;
+
+        // "b2" занят другим пользователем, хранилище откажет на середине
+        let mut archive = before.clone();
+        archive.budgets[0].id = "b2".into();
+        assert!(matches!(
+            api.import(&user, archive, ImportMode::Replace).await,
+            Err(Error::CantImport)
+        ));
+        let after = api.export(&user, before.exported_at).await;
+        assert_eq!(after.plans.len(), 1);
+        let ids =
+            |a: &Archive| a.budgets.iter().map(|sb| sb.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&after), ids(&before));
+
+        let mut duplicates = before.clone();
+        duplicates.budgets.push(before.budgets[0].clone());
+        let mut broken = before.clone();
+        broken.plans[0].plan = Plan::default();
+        for archive in [duplicates, broken] {
+            assert!(matches!(
+                api.import(&user, archive, ImportMode::Replace).await,
+                Err(Error::InvalidArchive { .. })
+            ));
+        }
+        assert!(api.get_plan(&user).await.is_some());
+        assert_eq!(api.budget_list(&user, None, 10).await.len(), 1);
     }
 
     // events are still stored in the repository for history,
@@ -0,0 +1,78 @@
+//! Архив с данными пользователя: резервная копия и перенос между
+//! десктоп-приложением и сервером.
+//!
+//! Архив не зависит от хранилища: он собирается и разворачивается через
+//! [`CoreRepo`](crate::storage::CoreRepo), поэтому данные из SQLite можно
+//! загрузить в PostgreSQL или в файлы и обратно.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use ai_core::plan::Plan;
+
+use crate::{
+    ledger::LedgerEntry,
+    storage::{PlanEvent, PlanId, StorageBudget, Version},
+};
+
+/// Версия формата архива. Растёт при несовместимых изменениях;
+/// архив более новой версии не загружается
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Archive {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub plans: Vec<ArchivedPlan>,
+    pub budgets: Vec<StorageBudget>,
+    #[serde(default)]
+    pub ledger: Vec<LedgerEntry>,
+}
+
+/// План вместе с историей изменений от старых событий к новым
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedPlan {
+    pub id: PlanId,
+    pub name: String,
+    pub version: Version,
+    pub active: bool,
+    pub plan: Plan,
+    #[serde(default)]
+    pub events: Vec<PlanEvent>,
+}
+
+/// Что делать с данными, которые уже есть в хранилище
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Добавить недостающее. Записи с теми же идентификаторами остаются
+    /// прежними и попадают в конфликты
+    #[default]
+    Merge,
+    /// Удалить данные пользователя и загрузить архив целиком
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    Plan,
+    Budget,
+    LedgerEntry,
+}
+
+/// Запись архива, которая не загружена, потому что уже есть в хранилище
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub id: String,
+}
+
+/// Итог загрузки архива: сколько записей загружено и что пропущено
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub plans: usize,
+    pub budgets: usize,
+    pub ledger: usize,
+    pub conflicts: Vec<Conflict>,
+}
@@ -0,0 +1,314 @@
+//! Учётные записи и сессии веб-интерфейса.
+//!
+//! Пароли хранятся как argon2-хэши, токены сессий - как sha256 от токена:
+//! утечка базы не даёт ни паролей, ни действующих токенов.
+
+use std::sync::Arc;
+
+use argon2::{
+    Argon2,
+    PasswordHash,
+    PasswordHasher,
+    PasswordVerifier,
+    password_hash::SaltString,
+};
+use chrono::{DateTime, Duration, Utc};
+use rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::{instrument, warn};
+
+use crate::storage::{
+    Account,
+    AccountRepo,
+    DEFAULT_USER_ID,
+    Session,
+    StorageError,
+    UserId,
+    build_id,
+};
+
+/// Сколько живёт сессия после входа
+const SESSION_TTL_DAYS: i64 = 30;
+const MIN_PASSWORD_LEN: usize = 8;
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("login must not be empty")]
+    EmptyLogin,
+    #[error("password must be at least {MIN_PASSWORD_LEN} characters")]
+    WeakPassword,
+    #[error("login already taken")]
+    LoginTaken,
+    #[error("invalid login or password")]
+    InvalidCredentials,
+    #[error("cant create account")]
+    CantCreateAccount,
+    #[error("cant create session")]
+    CantCreateSession,
+    #[error("cant close session")]
+    CantCloseSession,
+}
+
+/// Выданная при входе сессия. Токен показывается клиенту один раз
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedSession {
+    pub token: String,
+    pub user_id: UserId,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct AuthApi<R: AccountRepo> {
+    repo: Arc<R>,
+}
+
+impl<R: AccountRepo> AuthApi<R> {
+    pub fn new(repo: Arc<R>) -> Self {
+        Self { repo }
+    }
+
+    /// Заводит учётную запись. Первая учётная запись получает данные,
+    /// сохранённые без входа, остальные начинают с чистого листа.
+    #[instrument(skip(self, password))]
+    pub async fn register(
+        &self,
+        login: &str,
+        password: &str,
+    ) -> Result<Account, Error> {
+        let login = Self::normalize_login(login)?;
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(Error::WeakPassword);
+        }
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|_| Error::CantCreateAccount)?
+            .to_string();
+        let id = if self.repo.accounts_count().await == 0 {
+            DEFAULT_USER_ID.to_string()
+        } else {
+            build_id()
+        };
+        let account = Account { id, login };
+        self.repo
+            .create_account(account.clone(), password_hash)
+            .await
+            .map_err(|e| match e {
+                StorageError::AccountAlreadyExists => Error::LoginTaken,
+                _ => Error::CantCreateAccount,
+            })?;
+        Ok(account)
+    }
+
+    /// Проверяет пароль и открывает сессию, действующую [`SESSION_TTL_DAYS`] дней
+    #[instrument(skip(self, password))]
+    pub async fn login(
+        &self,
+        login: &str,
+        password: &str,
+        now: DateTime<Utc>,
+    ) -> Result<IssuedSession, Error> {
+        let login = Self::normalize_login(login)?;
+        let stored = self
+            .repo
+            .account_by_login(&login)
+            .await
+            .ok_or(Error::InvalidCredentials)?;
+        let hash = PasswordHash::new(&stored.password_hash).map_err(|e| {
+            warn!("Повреждён хэш пароля {login}: {e}");
+            Error::InvalidCredentials
+        })?;
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .map_err(|_| Error::InvalidCredentials)?;
+
+        let mut bytes = [0u8; TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        let session = Session {
+            token_hash: Self::token_hash(&token),
+            user_id: stored.account.id,
+            expires_at: now + Duration::days(SESSION_TTL_DAYS),
+        };
+        self.repo
+            .create_session(session.clone())
+            .await
+            .map_err(|_| Error::CantCreateSession)?;
+        Ok(IssuedSession {
+            token,
+            user_id: session.user_id,
+            expires_at: session.expires_at,
+        })
+    }
+
+    /// Владелец действующей сессии с токеном `token`
+    #[instrument(skip_all)]
+    pub async fn authenticate(
+        &self,
+        token: &str,
+        now: DateTime<Utc>,
+    ) -> Option<Account> {
+        self.repo
+            .session_account(&Self::token_hash(token), now)
+            .await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn logout(&self, token: &str) -> Result<(), Error> {
+        self.repo
+            .delete_session(&Self::token_hash(token))
+            .await
+            .map_err(|_| Error::CantCloseSession)
+    }
+
+    fn normalize_login(login: &str) -> Result<String, Error> {
+        let login = login.trim().to_lowercase();
+        if login.is_empty() {
+            return Err(Error::EmptyLogin);
+        }
+        Ok(login)
+    }
+
+    fn token_hash(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::storage::StoredAccount;
+
+    #[derive(Default)]
+    struct InMemoryAccountRepo {
+        accounts: Mutex<Vec<StoredAccount>>,
+        sessions: Mutex<Vec<Session>>,
+    }
+
+    impl AccountRepo for InMemoryAccountRepo {
+        async fn accounts_count(&self) -> usize {
+            self.accounts.lock().unwrap().len()
+        }
+
+        async fn create_account(
+            &self,
+            account: Account,
+            password_hash: String,
+        ) -> Result<(), StorageError> {
+            let mut accounts = self.accounts.lock().unwrap();
+            if accounts.iter().any(|a| a.account.login == account.login) {
+                return Err(StorageError::AccountAlreadyExists);
+            }
+            accounts.push(StoredAccount {
+                account,
+                password_hash,
+            });
+            Ok(())
+        }
+
+        async fn account_by_login(&self, login: &str) -> Option<StoredAccount> {
+            self.accounts
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|a| a.account.login == login)
+                .cloned()
+        }
+
+        async fn create_session(&self, session: Session) -> Result<(), StorageError> {
+            self.sessions.lock().unwrap().push(session);
+            Ok(())
+        }
+
+        async fn session_account(
+            &self,
+            token_hash: &str,
+            now: DateTime<Utc>,
+        ) -> Option<Account> {
+            let user_id = self
+                .sessions
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|s| s.token_hash == token_hash && s.expires_at > now)
+                .map(|s| s.user_id.clone())?;
+            self.accounts
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|a| a.account.id == user_id)
+                .map(|a| a.account.clone())
+        }
+
+        async fn delete_session(&self, token_hash: &str) -> Result<(), StorageError> {
+            self.sessions
+                .lock()
+                .unwrap()
+                .retain(|s| s.token_hash != token_hash);
+            Ok(())
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-05-01T10:00:00Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    #[tokio::test]
+    async fn register_login_and_logout() {
+        let repo = Arc::new(InMemoryAccountRepo::default());
+        let auth = AuthApi::new(repo.clone());
+
+        let anna = auth.register(" Anna ", "correct horse").await.unwrap();
+        assert_eq!(anna.id, DEFAULT_USER_ID);
+        assert_eq!(anna.login, "anna");
+        let ivan = auth.register("ivan", "battery staple").await.unwrap();
+        assert_ne!(ivan.id, DEFAULT_USER_ID);
+        // Пароль не хранится в открытом виде
+        assert!(
+            repo.accounts
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|a| !a.password_hash.contains("correct horse"))
+        );
+
+        assert!(matches!(
+            auth.register("ANNA", "another password").await,
+            Err(Error::LoginTaken)
+        ));
+        assert!(matches!(
+            auth.register("maria", "short").await,
+            Err(Error::WeakPassword)
+        ));
+        assert!(matches!(
+            auth.login("anna", "wrong password", now()).await,
+            Err(Error::InvalidCredentials)
+        ));
+        assert!(matches!(
+            auth.login("nobody", "correct horse", now()).await,
+            Err(Error::InvalidCredentials)
+        ));
+
+        let session = auth.login("anna", "correct horse", now()).await.unwrap();
+        assert_eq!(
+            auth.authenticate(&session.token, now()).await,
+            Some(anna.clone())
+        );
+        assert!(
+            auth.authenticate(&session.token, session.expires_at)
+                .await
+                .is_none()
+        );
+        assert!(auth.authenticate("forged", now()).await.is_none());
+
+        auth.logout(&session.token).await.unwrap();
+        assert!(auth.authenticate(&session.token, now()).await.is_none());
+    }
+}
@@ -0,0 +1,304 @@
+//! Учёт накоплений по конвертам.
+//!
+//! Каждое сохранённое распределение пополняет конверты, а ручные операции
+//! (снятие и перевод) их расходуют. Баланс конверта - свёртка всех этих событий.
+
+use std::collections::BTreeMap;
+
+use ai_core::{
+    distribute::Budget,
+    finance::{Currency, Money},
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub type LedgerEntryId = String;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LedgerError {
+    #[error("сумма операции должна быть положительной")]
+    NonPositiveAmount,
+    #[error("конверт «{0}» не найден")]
+    UnknownEnvelope(String),
+    #[error("нельзя перевести деньги в тот же конверт")]
+    SameEnvelope,
+    #[error("в конверте «{name}» недостаточно средств: {balance}")]
+    InsufficientFunds { name: String, balance: Money },
+}
+
+/// Ручная операция с конвертами
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LedgerOperation {
+    /// Деньги потрачены из конверта
+    Withdrawal { envelope: String, amount: Money },
+    /// Деньги переложены из одного конверта в другой
+    Transfer {
+        from: String,
+        to: String,
+        amount: Money,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub id: LedgerEntryId,
+    pub date: NaiveDate,
+    pub operation: LedgerOperation,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvelopeBalance {
+    pub name: String,
+    pub category: Option<String>,
+    pub balance: Money,
+}
+
+/// Балансы конвертов. Конверт с суммами в разных валютах
+/// учитывается отдельно по каждой валюте - без курса их не сложить.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    balances: BTreeMap<(String, Currency), EnvelopeBalance>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Зачисляет в конверты все суммы распределения
+    pub fn apply_budget(&mut self, budget: &Budget) {
+        let entries = budget
+            .no_category
+            .iter()
+            .chain(budget.categories.values().flatten());
+        for entry in entries {
+            let balance = self.envelope(&entry.expense.name, entry.amount.currency);
+            balance.category.clone_from(&entry.expense.category);
+            balance.balance += entry.amount;
+        }
+    }
+
+    /// Проверяет операцию и применяет её к балансам
+    pub fn apply(&mut self, operation: &LedgerOperation) -> Result<(), LedgerError> {
+        self.check(operation)?;
+        match operation {
+            LedgerOperation::Withdrawal { envelope, amount } => {
+                self.envelope(envelope, amount.currency).balance -= *amount;
+            }
+            LedgerOperation::Transfer { from, to, amount } => {
+                self.envelope(from, amount.currency).balance -= *amount;
+                let category = self.category_of(to);
+                let target = self.envelope(to, amount.currency);
+                if target.category.is_none() {
+                    target.category = category;
+                }
+                target.balance += *amount;
+            }
+        }
+        Ok(())
+    }
+
+    /// Операция допустима, если конверты известны и в источнике хватает денег
+    pub fn check(&self, operation: &LedgerOperation) -> Result<(), LedgerError> {
+        let (from, amount) = match operation {
+            LedgerOperation::Withdrawal { envelope, amount } => (envelope, amount),
+            LedgerOperation::Transfer { from, to, amount } => {
+                if from == to {
+                    return Err(LedgerError::SameEnvelope);
+                }
+                if !self.contains(to) {
+                    return Err(LedgerError::UnknownEnvelope(to.clone()));
+                }
+                (from, amount)
+            }
+        };
+        if amount.value <= Decimal::ZERO {
+            return Err(LedgerError::NonPositiveAmount);
+        }
+        if !self.contains(from) {
+            return Err(LedgerError::UnknownEnvelope(from.clone()));
+        }
+        let balance = self
+            .balances
+            .get(&(from.clone(), amount.currency))
+            .map_or(Money::new(Decimal::ZERO, amount.currency), |b| b.balance);
+        if balance.value < amount.value {
+            return Err(LedgerError::InsufficientFunds {
+                name: from.clone(),
+                balance,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.balances.keys().any(|(n, _)| n == name)
+    }
+
+    /// Балансы всех конвертов, отсортированные по имени
+    pub fn balances(&self) -> Vec<EnvelopeBalance> {
+        self.balances.values().cloned().collect()
+    }
+
+    fn category_of(&self, name: &str) -> Option<String> {
+        self.balances
+            .iter()
+            .find(|((n, _), _)| n == name)
+            .and_then(|(_, b)| b.category.clone())
+    }
+
+    fn envelope(&mut self, name: &str, currency: Currency) -> &mut EnvelopeBalance {
+        self.balances
+            .entry((name.to_string(), currency))
+            .or_insert_with(|| EnvelopeBalance {
+                name: name.to_string(),
+                category: None,
+                balance: Money::new(Decimal::ZERO, currency),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ai_core::{
+        distribute::{BudgetEntry, Income},
+        planning::{Expense, ExpenseValue, IncomeKind, IncomeSource},
+    };
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn envelope(name: &str, category: Option<&str>) -> Expense {
+        Expense::envelope(
+            name.into(),
+            ExpenseValue::MONEY {
+                value: Money::new_rub(dec!(1)),
+            },
+            category.map(Into::into),
+        )
+    }
+
+    fn budget(entries: &[(&str, Option<&str>, Money)]) -> Budget {
+        let source = IncomeSource::new(
+            "Зарплата".into(),
+            IncomeKind::Other {
+                expected: Money::new_rub(dec!(100000)),
+            },
+        );
+        let mut budget = Budget::new(Income::new(
+            source,
+            Money::new_rub(dec!(100000)),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+        ));
+        for (name, category, amount) in entries {
+            budget.push(
+                category.map(Into::into),
+                BudgetEntry::new(envelope(name, *category), *amount),
+            );
+        }
+        budget
+    }
+
+    fn rub(v: Decimal) -> Money {
+        Money::new_rub(v)
+    }
+
+    #[test]
+    fn budgets_accumulate() {
+        let mut ledger = Ledger::new();
+        ledger.apply_budget(&budget(&[
+            ("Подушка", None, rub(dec!(10000))),
+            ("Аренда", Some("Жильё"), rub(dec!(30000))),
+        ]));
+        ledger.apply_budget(&budget(&[("Подушка", None, rub(dec!(5000)))]));
+
+        assert_eq!(
+            ledger.balances(),
+            vec![
+                EnvelopeBalance {
+                    name: "Аренда".into(),
+                    category: Some("Жильё".into()),
+                    balance: rub(dec!(30000)),
+                },
+                EnvelopeBalance {
+                    name: "Подушка".into(),
+                    category: None,
+                    balance: rub(dec!(15000)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn withdrawal_and_transfer() {
+        let mut ledger = Ledger::new();
+        ledger.apply_budget(&budget(&[
+            ("Подушка", None, rub(dec!(10000))),
+            ("Отпуск", Some("Отдых"), rub(dec!(2000))),
+        ]));
+        ledger
+            .apply(&LedgerOperation::Withdrawal {
+                envelope: "Подушка".into(),
+                amount: rub(dec!(1000)),
+            })
+            .unwrap();
+        ledger
+            .apply(&LedgerOperation::Transfer {
+                from: "Подушка".into(),
+                to: "Отпуск".into(),
+                amount: rub(dec!(4000)),
+            })
+            .unwrap();
+
+        let balances = ledger.balances();
+        assert_eq!(balances[0].name, "Отпуск");
+        assert_eq!(balances[0].balance, rub(dec!(6000)));
+        assert_eq!(balances[1].name, "Подушка");
+        assert_eq!(balances[1].balance, rub(dec!(5000)));
+    }
+
+    #[test]
+    fn rejects_invalid_operations() {
+        let mut ledger = Ledger::new();
+        ledger.apply_budget(&budget(&[("Подушка", None, rub(dec!(100)))]));
+
+        let withdraw = |envelope: &str, amount| LedgerOperation::Withdrawal {
+            envelope: envelope.into(),
+            amount,
+        };
+        assert_eq!(
+            ledger.apply(&withdraw("Подушка", rub(dec!(101)))),
+            Err(LedgerError::InsufficientFunds {
+                name: "Подушка".into(),
+                balance: rub(dec!(100)),
+            })
+        );
+        assert_eq!(
+            ledger.apply(&withdraw("Подушка", rub(dec!(0)))),
+            Err(LedgerError::NonPositiveAmount)
+        );
+        assert_eq!(
+            ledger.apply(&withdraw("Машина", rub(dec!(1)))),
+            Err(LedgerError::UnknownEnvelope("Машина".into()))
+        );
+        assert_eq!(
+            ledger.apply(&LedgerOperation::Transfer {
+                from: "Подушка".into(),
+                to: "Подушка".into(),
+                amount: rub(dec!(1)),
+            }),
+            Err(LedgerError::SameEnvelope)
+        );
+        assert_eq!(
+            ledger.apply(&withdraw("Подушка", Money::new(dec!(1), Currency::USD))),
+            Err(LedgerError::InsufficientFunds {
+                name: "Подушка".into(),
+                balance: Money::new(dec!(0), Currency::USD),
+            })
+        );
+    }
+}
@@ -3,6 +3,12 @@
 //! Отделён от `ai_core`, чтобы домен оставался WASM-совместимым и не знал о персистентности.
 //! Отделён от конкретных бинарей, чтобы переиспользоваться между ними.
 
+pub mod analytics;
 pub mod api;
+pub mod archive;
+pub mod auth;
 pub mod config;
+pub mod ledger;
+pub mod report;
+pub mod statement;
 pub mod storage;
@@ -0,0 +1,281 @@
+//! Отчёт план/факт за месяц: сколько каждый конверт должен был получить
+//! по плану и сколько ему на самом деле досталось из распределений месяца.
+
+use ai_core::{
+    distribute::Budget,
+    finance::{Currency, Money, Percentage},
+    plan::Plan,
+    planning::{Error, ExpenseValue, month_bounds},
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tracing::warn;
+
+/// Суммы в базовой валюте плана
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fulfillment {
+    pub planned: Money,
+    pub actual: Money,
+    /// Факт минус план: отрицательная разница - конверт недополучил
+    pub difference: Money,
+    /// Доля плана, которую конверт получил; без плановой суммы не считается
+    pub fulfilled: Option<Percentage>,
+}
+
+impl Fulfillment {
+    fn new(planned: Money, actual: Money) -> Self {
+        let fulfilled = (!planned.value.is_zero())
+            .then(|| Percentage::of(actual.value, planned.value));
+        Self {
+            planned,
+            actual,
+            difference: actual - planned,
+            fulfilled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpenseLine {
+    pub name: String,
+    #[serde(flatten)]
+    pub amounts: Fulfillment,
+}
+
+/// Расходы категории с итогом; `None` - расходы без категории
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategoryLine {
+    pub category: Option<String>,
+    #[serde(flatten)]
+    pub amounts: Fulfillment,
+    pub expenses: Vec<ExpenseLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanReport {
+    /// Первый день месяца отчёта
+    pub month: NaiveDate,
+    pub currency: Currency,
+    /// Сколько распределений попало в месяц
+    pub budgets: usize,
+    #[serde(flatten)]
+    pub total: Fulfillment,
+    /// Категории в порядке плана; конверты, которых уже нет в плане, - в конце
+    pub categories: Vec<CategoryLine>,
+}
+
+/// Сравнивает месячный план с распределениями месяца даты `month`.
+/// Суммы конвертов в валюте без курса в факт не попадают
+pub fn plan_vs_actual<'a>(
+    plan: &Plan,
+    budgets: impl IntoIterator<Item = &'a Budget>,
+    month: NaiveDate,
+) -> Result<PlanReport, Error> {
+    let (first, last) = month_bounds(month);
+    let rates = &plan.rates;
+    let currency = plan.currency();
+    let zero = Money::new(Decimal::ZERO, currency);
+    let total_income = plan.total_incomes()?;
+
+    // (категория, конверт, план, факт)
+    let mut lines: Vec<(Option<String>, String, Money, Money)> = Vec::new();
+    for expense in &plan.expenses {
+        let planned = match expense.value() {
+            ExpenseValue::MONEY { value } => rates.convert(value, currency)?,
+            ExpenseValue::RATE { value } => {
+                Money::new(value.apply_to(total_income.value), currency)
+            }
+        };
+        lines.push((
+            expense.category.clone(),
+            expense.name.clone(),
+            planned,
+            zero,
+        ));
+    }
+
+    let mut count = 0;
+    for budget in budgets {
+        let date = budget.income.date;
+        if date < first || date > last {
+            continue;
+        }
+        count += 1;
+        let entries = budget.no_category.iter().map(|e| (None, e)).chain(
+            budget
+                .categories
+                .iter()
+                .flat_map(|(c, entries)| entries.iter().map(move |e| (Some(c), e))),
+        );
+        for (category, entry) in entries {
+            let Ok(amount) = rates.convert(entry.amount, currency) else {
+                warn!(
+                    "Конверт {} от {date} не попал в отчёт: нет курса {}",
+                    entry.expense.name, entry.amount.currency
+                );
+                continue;
+            };
+            match lines.iter_mut().find(|l| l.1 == entry.expense.name) {
+                Some(line) => line.3 += amount,
+                None => lines.push((
+                    category.cloned(),
+                    entry.expense.name.clone(),
+                    zero,
+                    amount,
+                )),
+            }
+        }
+    }
+
+    let mut categories: Vec<CategoryLine> = Vec::new();
+    let (mut planned, mut actual) = (zero, zero);
+    for (category, name, line_planned, line_actual) in lines {
+        planned += line_planned;
+        actual += line_actual;
+        let line = ExpenseLine {
+            name,
+            amounts: Fulfillment::new(line_planned, line_actual),
+        };
+        match categories.iter_mut().find(|c| c.category == category) {
+            Some(group) => group.expenses.push(line),
+            None => categories.push(CategoryLine {
+                category,
+                amounts: Fulfillment::new(zero, zero),
+                expenses: vec![line],
+            }),
+        }
+    }
+    for group in &mut categories {
+        let sum = |f: fn(&Fulfillment) -> Money| {
+            group
+                .expenses
+                .iter()
+                .fold(zero, |acc, e| acc + f(&e.amounts))
+        };
+        group.amounts = Fulfillment::new(sum(|a| a.planned), sum(|a| a.actual));
+    }
+
+    Ok(PlanReport {
+        month: first,
+        currency,
+        budgets: count,
+        total: Fulfillment::new(planned, actual),
+        categories,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ai_core::{
+        distribute::{BudgetEntry, Income},
+        planning::{Expense, IncomeKind, IncomeSource},
+    };
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn envelope(name: &str, value: ExpenseValue, category: Option<&str>) -> Expense {
+        Expense::envelope(name.into(), value, category.map(Into::into))
+    }
+
+    #[test]
+    fn report_compares_plan_with_month_budgets() {
+        let rub = Money::new_rub;
+        let salary = IncomeSource::new(
+            "Зарплата".into(),
+            IncomeKind::Other {
+                expected: rub(dec!(100000)),
+            },
+        );
+        let rent = envelope(
+            "Аренда",
+            ExpenseValue::MONEY {
+                value: rub(dec!(30000)),
+            },
+            Some("Жильё"),
+        );
+        let cushion = envelope(
+            "Подушка",
+            ExpenseValue::RATE {
+                value: Percentage::from_int(10),
+            },
+            Some("Капитал"),
+        );
+        let food = envelope(
+            "Еда",
+            ExpenseValue::MONEY {
+                value: rub(dec!(20000)),
+            },
+            None,
+        );
+        let plan = Plan::build(
+            std::slice::from_ref(&salary),
+            &[rent.clone(), cushion.clone(), food],
+        );
+
+        let budget = |date: NaiveDate, entries: Vec<(&Expense, Decimal)>| {
+            let mut budget =
+                Budget::new(Income::new(salary.clone(), rub(dec!(50000)), date));
+            for (expense, amount) in entries {
+                budget.push(
+                    expense.category.clone(),
+                    BudgetEntry::new(expense.clone(), rub(amount)),
+                );
+            }
+            budget
+        };
+        let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let old_envelope = envelope(
+            "Отпуск",
+            ExpenseValue::MONEY {
+                value: rub(dec!(1000)),
+            },
+            Some("Капитал"),
+        );
+        let budgets = [
+            budget(day(5), vec![(&rent, dec!(15000)), (&cushion, dec!(5000))]),
+            budget(
+                day(20),
+                vec![(&rent, dec!(15000)), (&old_envelope, dec!(1000))],
+            ),
+            // Апрельский доход в мартовский отчёт не попадает
+            budget(
+                NaiveDate::from_ymd_opt(2026, 4, 1).unwrap(),
+                vec![(&rent, dec!(30000))],
+            ),
+        ];
+
+        let report = plan_vs_actual(&plan, &budgets, day(15)).unwrap();
+
+        assert_eq!(report.month, day(1));
+        assert_eq!(report.budgets, 2);
+        assert_eq!(report.total.planned, rub(dec!(60000)));
+        assert_eq!(report.total.actual, rub(dec!(36000)));
+        assert_eq!(report.total.difference, rub(dec!(-24000)));
+        let names: Vec<_> = report
+            .categories
+            .iter()
+            .map(|c| c.category.as_deref())
+            .collect();
+        assert_eq!(names, vec![Some("Жильё"), Some("Капитал"), None]);
+
+        let housing = &report.categories[0];
+        assert_eq!(housing.amounts.fulfilled, Some(Percentage::from_int(100)));
+
+        let capital = &report.categories[1];
+        assert_eq!(capital.amounts.planned, rub(dec!(10000)));
+        assert_eq!(capital.amounts.actual, rub(dec!(6000)));
+        assert_eq!(
+            capital.expenses[0].amounts.fulfilled,
+            Some(Percentage::from_int(50))
+        );
+        // Конверта уже нет в плане: факт есть, процент выполнения не считается
+        assert_eq!(capital.expenses[1].name, "Отпуск");
+        assert_eq!(capital.expenses[1].amounts.fulfilled, None);
+
+        let no_category = &report.categories[2];
+        assert_eq!(no_category.amounts.actual, rub(dec!(0)));
+        assert_eq!(no_category.amounts.fulfilled, Some(Percentage::from_int(0)));
+    }
+}
@@ -0,0 +1,121 @@
+//! Сверка банковской выписки с планом.
+//!
+//! Входящие платежи выписки сопоставляются с источниками дохода плана по
+//! правилам и превращаются в предложенные распределения. Пользователь
+//! подтверждает их пачкой; платежи, которые уже распределены, пропускаются.
+
+use ai_core::{distribute::Budget, finance::Money, planning::IncomeSource};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{BudgetId, PlanRef};
+
+/// Операция из выписки. Поступления положительные, списания отрицательные
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub date: NaiveDate,
+    pub amount: Money,
+    /// Плательщик или получатель
+    #[serde(default)]
+    pub counterparty: Option<String>,
+    /// Назначение платежа
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl Transaction {
+    pub fn is_incoming(&self) -> bool {
+        self.amount.value > Decimal::ZERO
+    }
+}
+
+/// Правило сопоставления платежа с источником дохода.
+/// Платёж подходит, если выполнены все заданные условия
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchRule {
+    /// Имя источника дохода в плане
+    pub source: String,
+    /// Подстрока плательщика или назначения платежа, без учёта регистра
+    #[serde(default)]
+    pub counterparty: Option<String>,
+    #[serde(default)]
+    pub min: Option<Decimal>,
+    #[serde(default)]
+    pub max: Option<Decimal>,
+}
+
+impl MatchRule {
+    fn matches(&self, tx: &Transaction) -> bool {
+        let amount = tx.amount.value;
+        let counterparty = self.counterparty.as_ref().is_none_or(|needle| {
+            let needle = needle.to_lowercase();
+            [&tx.counterparty, &tx.description]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&needle))
+        });
+        counterparty
+            && self.min.is_none_or(|min| amount >= min)
+            && self.max.is_none_or(|max| amount <= max)
+    }
+}
+
+/// Источник дохода для платежа: первое подошедшее правило, а без него -
+/// единственный источник, ожидаемая сумма которого совпадает с платежом
+pub fn match_source<'a>(
+    sources: &'a [IncomeSource],
+    rules: &[MatchRule],
+    tx: &Transaction,
+) -> Option<&'a IncomeSource> {
+    let by_rule = rules
+        .iter()
+        .filter(|rule| rule.matches(tx))
+        .find_map(|rule| sources.iter().find(|s| s.name == rule.source));
+    by_rule.or_else(|| {
+        let mut by_amount = sources.iter().filter(|s| s.net() == tx.amount);
+        match (by_amount.next(), by_amount.next()) {
+            (Some(source), None) => Some(source),
+            _ => None,
+        }
+    })
+}
+
+/// Распределение, которое предлагается сохранить для платежа
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    pub transaction: Transaction,
+    pub budget: Budget,
+    /// Версия плана, по которой посчитано распределение
+    pub plan: PlanRef,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Доход с той же датой и суммой уже распределён
+    AlreadyDistributed,
+    /// Ни одно правило не подошло
+    NoSource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Skipped {
+    pub transaction: Transaction,
+    pub reason: SkipReason,
+}
+
+/// Итог сверки: списания выписки в него не попадают
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Reconciliation {
+    pub proposals: Vec<Proposal>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// Итог подтверждения распределений из выписки
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Confirmation {
+    pub saved: Vec<BudgetId>,
+    /// Доходы, распределённые ещё до подтверждения
+    pub skipped: Vec<Budget>,
+}
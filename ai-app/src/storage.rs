@@ -1,9 +1,12 @@
 use std::{ops::Deref, str::FromStr};
 
 use ai_core::{distribute::Budget, plan::Plan};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::ledger::{LedgerEntry, LedgerEntryId};
+
 pub type PlanDraft = Plan;
 pub type PlanId = String;
 pub type UserId = String;
@@ -11,6 +14,13 @@ pub type BudgetId = String;
 pub type Cursor = String;
 pub type Version = i64;
 
+/// Пользователь без учётной записи: ему принадлежат данные локальной установки
+/// и всё, что было сохранено до появления учётных записей
+pub const DEFAULT_USER_ID: &str = "default";
+
+/// Имя плана, если пользователь его не указал
+pub const DEFAULT_PLAN_NAME: &str = "Основной";
+
 #[must_use]
 pub fn build_id() -> String {
     uuid::Uuid::now_v7().to_string()
@@ -22,12 +32,41 @@ pub enum StorageError {
     CreatePlan,
     #[error("plan already exists")]
     PlanAlreadyExists,
+    #[error("plan not found")]
+    PlanNotFound,
     #[error("failed to update plan")]
     UpdatePlan,
+    #[error("plan was changed concurrently, current version is {current}")]
+    VersionConflict { current: Version },
     #[error("failed to delete plan")]
     DeletePlan,
+    #[error("failed to rename plan")]
+    RenamePlan,
+    #[error("failed to activate plan")]
+    ActivatePlan,
+    #[error("failed to restore plan")]
+    RestorePlan,
+    #[error("failed to clear user data")]
+    ClearUserData,
     #[error("failed to save budget")]
     SaveBudget,
+    /// Идентификатор уже занят распределением другого пользователя
+    #[error("budget already exists")]
+    BudgetAlreadyExists,
+    #[error("failed to save ledger entry")]
+    SaveLedgerEntry,
+    #[error("ledger entry already exists")]
+    LedgerEntryAlreadyExists,
+    #[error("failed to void budget")]
+    VoidBudget,
+    #[error("failed to create account")]
+    CreateAccount,
+    #[error("account already exists")]
+    AccountAlreadyExists,
+    #[error("failed to create session")]
+    CreateSession,
+    #[error("failed to delete session")]
+    DeleteSession,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -93,9 +132,12 @@ impl FromStr for PlanAction {
 pub struct StoragePlan {
     pub user_id: UserId,
     pub id: PlanId,
+    pub name: String,
     pub plan: Plan,
     pub version: Version,
     pub status: PlanStatus,
+    /// По этому плану распределяются доходы; у пользователя такой план один
+    pub active: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,10 +150,47 @@ pub struct PlanEvent {
     pub created_at: String,
 }
 
+/// Версия плана, по которой посчитано распределение
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanRef {
+    pub plan_id: PlanId,
+    pub version: Version,
+}
+
+/// Отметка об аннулировании распределения
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetVoid {
+    pub date: NaiveDate,
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageBudget {
     pub id: BudgetId,
     pub budget: Budget,
+    /// Распределения, сохранённые до появления этого поля, версию плана не знают
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<PlanRef>,
+    /// Аннулированное распределение остаётся в истории,
+    /// но не участвует в балансах конвертов и сводках
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voided: Option<BudgetVoid>,
+}
+
+impl StorageBudget {
+    pub fn is_voided(&self) -> bool {
+        self.voided.is_some()
+    }
+}
+
+impl StoragePlan {
+    /// Ссылка на текущую версию плана
+    pub fn plan_ref(&self) -> PlanRef {
+        PlanRef {
+            plan_id: self.id.clone(),
+            version: self.version,
+        }
+    }
 }
 
 impl From<(BudgetId, Budget)> for StorageBudget {
@@ -119,10 +198,34 @@ impl From<(BudgetId, Budget)> for StorageBudget {
         Self {
             id: value.0,
             budget: value.1,
+            plan: None,
+            voided: None,
         }
     }
 }
 
+/// Учётная запись пользователя веб-интерфейса
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub id: UserId,
+    pub login: String,
+}
+
+/// Учётная запись вместе с хэшем пароля; наружу не отдаётся
+#[derive(Debug, Clone)]
+pub struct StoredAccount {
+    pub account: Account,
+    pub password_hash: String,
+}
+
+/// Сессия входа. Сам токен не хранится, только его хэш
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub token_hash: String,
+    pub user_id: UserId,
+    pub expires_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Page<T> {
     pub items: Vec<T>,
@@ -147,19 +250,46 @@ pub trait LocalCoreRepo: Sync {
     /// Возвращает активный план пользователя
     async fn get_plan(&self, user_id: &UserId) -> Option<StoragePlan>;
 
+    /// Все неудалённые планы пользователя в порядке создания
+    async fn list_plans(&self, user_id: &UserId) -> Vec<StoragePlan>;
+
+    /// Неудалённые планы других пользователей, в участниках которых есть `user_id`
+    async fn shared_plans(&self, user_id: &UserId) -> Vec<StoragePlan>;
+
     /// Создаёт новый план для пользователя с указанным внешним идентификатором.
+    /// План становится активным, только если активного ещё нет; имя должно быть
+    /// уникальным среди планов пользователя.
     async fn create_plan(
         &self,
         user_id: &UserId,
         plan_id: PlanId,
+        name: String,
         plan: Plan,
     ) -> Result<PlanId, StorageError>;
 
+    /// Переименовывает план пользователя
+    async fn rename_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        name: String,
+    ) -> Result<(), StorageError>;
+
+    /// Делает план активным, снимая отметку с прежнего активного плана
+    async fn activate_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<(), StorageError>;
+
     /// Обновляет указанный план пользователя и добавляет событие об изменении.
+    /// Если версия плана уже не `expected`, возвращает
+    /// [`StorageError::VersionConflict`] и ничего не меняет.
     async fn update_plan(
         &self,
         user_id: &UserId,
         plan_id: &PlanId,
+        expected: Version,
         plan: Plan,
     ) -> Result<(), StorageError>;
 
@@ -179,13 +309,91 @@ pub trait LocalCoreRepo: Sync {
         limit: usize,
     ) -> Page<PlanEvent>;
 
+    /// Сохраняет распределение пользователя вместе с версией плана,
+    /// по которой оно посчитано.
     async fn save_budget(
         &self,
+        user_id: &UserId,
         budget_id: BudgetId,
         budget: Budget,
+        plan: Option<PlanRef>,
     ) -> Result<BudgetId, StorageError>;
 
-    async fn budget_by_id(&self, id: &BudgetId) -> Option<StorageBudget>;
+    /// Распределение пользователя; чужие распределения не находятся
+    async fn budget_by_id(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+    ) -> Option<StorageBudget>;
+
+    async fn budgets(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<StorageBudget>;
+
+    /// Помечает распределение аннулированным. Само распределение не удаляется.
+    async fn void_budget(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+        void: BudgetVoid,
+    ) -> Result<(), StorageError>;
+
+    /// Сохраняет ручную операцию с конвертами (снятие или перевод).
+    async fn save_ledger_entry(
+        &self,
+        user_id: &UserId,
+        entry: LedgerEntry,
+    ) -> Result<LedgerEntryId, StorageError>;
+
+    /// Возвращает страницу операций с конвертами, от новых к старым.
+    async fn ledger_entries(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<LedgerEntry>;
+
+    /// Восстанавливает план из архива: версия, статус и события сохраняются
+    /// как есть, события передаются от старых к новым. Если идентификатор
+    /// или имя плана заняты, возвращает [`StorageError::PlanAlreadyExists`].
+    /// Активный план снимает отметку с прежнего активного.
+    async fn restore_plan(
+        &self,
+        user_id: &UserId,
+        plan: StoragePlan,
+        events: Vec<PlanEvent>,
+    ) -> Result<(), StorageError>;
+
+    /// Безвозвратно удаляет планы пользователя с их историей,
+    /// распределения и операции с конвертами
+    async fn clear_user_data(&self, user_id: &UserId) -> Result<(), StorageError>;
+}
+
+#[trait_variant::make(AccountRepo: Send)]
+pub trait LocalAccountRepo: Sync {
+    /// Сколько учётных записей заведено
+    async fn accounts_count(&self) -> usize;
+
+    /// Создаёт учётную запись; логин должен быть уникальным.
+    async fn create_account(
+        &self,
+        account: Account,
+        password_hash: String,
+    ) -> Result<(), StorageError>;
+
+    async fn account_by_login(&self, login: &str) -> Option<StoredAccount>;
+
+    async fn create_session(&self, session: Session) -> Result<(), StorageError>;
+
+    /// Владелец сессии, если она ещё не истекла к моменту `now`
+    async fn session_account(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Option<Account>;
 
-    async fn budgets(&self, from: Option<Cursor>, limit: usize) -> Page<StorageBudget>;
+    async fn delete_session(&self, token_hash: &str) -> Result<(), StorageError>;
 }
@@ -1,11 +1,20 @@
 use crate::{
-    finance::{Money, Percentage},
-    planning::{DistributionWeights, Expense, IncomeSource},
+    finance::{Currency, ExchangeError, ExchangeRates, Money, Percentage},
+    planning::{
+        self,
+        DistributionMode,
+        DistributionWeights,
+        Expense,
+        ExpenseValue,
+        IncomeSource,
+        SurplusRule,
+    },
 };
 use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::{Debug, Formatter},
 };
 use thiserror::Error;
@@ -16,6 +25,35 @@ pub enum Error {
     EmptyPlan,
     #[error("unknown source")]
     UnknownSource,
+    #[error("missing exchange rate for {0:?}")]
+    MissingExchangeRate(Currency),
+    #[error("surplus rule targets unknown envelope {0}")]
+    UnknownSurplusEnvelope(String),
+    #[error("invalid plan: {0}")]
+    InvalidPlan(String),
+    #[error("forecast is limited to {0} months")]
+    ForecastTooLong(u32),
+}
+
+impl From<planning::Error> for Error {
+    fn from(e: planning::Error) -> Self {
+        match e {
+            planning::Error::MissingExchangeRate(currency) => {
+                Error::MissingExchangeRate(currency)
+            }
+            e => Error::InvalidPlan(e.to_string()),
+        }
+    }
+}
+
+impl From<ExchangeError> for Error {
+    fn from(e: ExchangeError) -> Self {
+        match e {
+            ExchangeError::MissingRate(currency) => {
+                Error::MissingExchangeRate(currency)
+            }
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -48,11 +86,26 @@ impl Income {
 pub struct BudgetEntry {
     pub expense: Expense,
     pub amount: Money,
+    /// Сумма пришла из остатка по правилу [`SurplusRule`], а не по плану
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub surplus: bool,
 }
 
 impl BudgetEntry {
     pub fn new(expense: Expense, amount: Money) -> Self {
-        Self { expense, amount }
+        Self {
+            expense,
+            amount,
+            surplus: false,
+        }
+    }
+
+    pub fn surplus(expense: Expense, amount: Money) -> Self {
+        Self {
+            expense,
+            amount,
+            surplus: true,
+        }
     }
 
     pub fn name(&self) -> &str {
@@ -60,12 +113,34 @@ impl BudgetEntry {
     }
 }
 
+/// Конверт, которому не хватило дохода. Суммы - в валюте конверта.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Underfunded {
+    pub name: String,
+    pub planned: Money,
+    pub received: Money,
+    pub shortfall: Money,
+}
+
 #[derive(PartialEq, Clone, Serialize, Deserialize)]
 pub struct Budget {
     pub income: Income,
     pub rest: Money,
     pub no_category: Vec<BudgetEntry>,
     pub categories: HashMap<String, Vec<BudgetEntry>>,
+    /// Недополученное конвертами при распределении по приоритетам
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub underfunded: Vec<Underfunded>,
+}
+
+/// Как изменилась сумма конверта при пересчёте распределения.
+/// Суммы в валюте конверта; конверта нет в распределении - сумма нулевая.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct EnvelopeChange {
+    pub name: String,
+    pub before: Money,
+    pub after: Money,
+    pub difference: Money,
 }
 
 impl Budget {
@@ -76,6 +151,44 @@ impl Budget {
     pub fn rest(&self) -> &Money {
         &self.rest
     }
+
+    /// Суммы по конвертам; записи из остатка складываются с плановыми
+    pub fn envelope_totals(&self) -> BTreeMap<(String, Currency), Money> {
+        let mut totals = BTreeMap::new();
+        for entry in self
+            .no_category
+            .iter()
+            .chain(self.categories.values().flatten())
+        {
+            *totals
+                .entry((entry.expense.name.clone(), entry.amount.currency))
+                .or_insert(Money::new(Decimal::ZERO, entry.amount.currency)) +=
+                entry.amount;
+        }
+        totals
+    }
+
+    /// Сравнивает по конвертам с другим распределением, например того же
+    /// дохода по другой версии плана. Конверты отсортированы по имени.
+    pub fn compare(&self, after: &Budget) -> Vec<EnvelopeChange> {
+        let before = self.envelope_totals();
+        let after = after.envelope_totals();
+        let keys: std::collections::BTreeSet<_> =
+            before.keys().chain(after.keys()).cloned().collect();
+        keys.into_iter()
+            .map(|key| {
+                let zero = Money::new(Decimal::ZERO, key.1);
+                let was = before.get(&key).copied().unwrap_or(zero);
+                let now = after.get(&key).copied().unwrap_or(zero);
+                EnvelopeChange {
+                    name: key.0,
+                    before: was,
+                    after: now,
+                    difference: now - was,
+                }
+            })
+            .collect()
+    }
 }
 
 impl Debug for Budget {
@@ -99,11 +212,16 @@ impl Budget {
             income,
             no_category: Vec::new(),
             categories: HashMap::new(),
+            underfunded: Vec::new(),
         }
     }
 
     pub fn push(&mut self, category: Option<String>, entry: BudgetEntry) {
         self.rest -= entry.amount;
+        self.insert(category, entry);
+    }
+
+    fn insert(&mut self, category: Option<String>, entry: BudgetEntry) {
         if let Some(category) = category {
             self.categories.entry(category).or_default().push(entry);
         } else {
@@ -111,9 +229,106 @@ impl Budget {
         }
     }
 
-    fn calculate(&mut self, expense: Expense, rate: &Percentage) {
-        let money = Money::new_rub(rate.apply_to(self.income.amount.value));
-        self.push(expense.category.clone(), BudgetEntry::new(expense, money));
+    /// Доля считается в валюте дохода и списывается из остатка,
+    /// а в конверт попадает уже в его собственной валюте:
+    /// для фиксированной суммы - в валюте суммы, для процента - в базовой валюте плана.
+    fn calculate(
+        &mut self,
+        expense: Expense,
+        rate: &Percentage,
+        rates: &ExchangeRates,
+    ) -> Result<(), Error> {
+        let share = Money::new(
+            rate.apply_to(self.income.amount.value),
+            self.income.amount.currency,
+        );
+        self.allocate(expense, share, rates)?;
+        Ok(())
+    }
+
+    /// Списывает `share` (в валюте дохода) из остатка в конверт и возвращает,
+    /// сколько конверт получил в своей валюте
+    fn allocate(
+        &mut self,
+        expense: Expense,
+        share: Money,
+        rates: &ExchangeRates,
+    ) -> Result<Money, Error> {
+        let amount = envelope_amount(&expense, share, rates)?;
+        self.rest -= share;
+        self.insert(expense.category.clone(), BudgetEntry::new(expense, amount));
+        Ok(amount)
+    }
+
+    /// Раскладывает положительный остаток по правилам плана.
+    /// Доли считаются от остатка после основного распределения.
+    fn route_surplus(&mut self, plan: &DistributionWeights) -> Result<(), Error> {
+        let surplus = self.rest;
+        if surplus.value <= Decimal::ZERO {
+            return Ok(());
+        }
+        for SurplusRule { envelope, share } in &plan.surplus {
+            let Some(expense) = plan.keys().find(|e| &e.name == envelope) else {
+                return Err(Error::UnknownSurplusEnvelope(envelope.clone()));
+            };
+            let share = Money::new(share.apply_to(surplus.value), surplus.currency);
+            let amount = envelope_amount(expense, share, &plan.rates)?;
+            self.rest -= share;
+            self.insert(
+                expense.category.clone(),
+                BudgetEntry::surplus(expense.clone(), amount),
+            );
+        }
+        Ok(())
+    }
+
+    /// Наполняет конверты по очереди приоритетов, пока не кончится доход.
+    /// Каждый конверт претендует на свою долю от планового дохода источника;
+    /// если на уровень приоритета не хватает, его конверты урезаются пропорционально.
+    fn fill_by_priority(&mut self, plan: &DistributionWeights) -> Result<(), Error> {
+        let income = self.income.amount;
+        let planned = planned_income(plan, &self.income)?;
+
+        let mut levels: BTreeMap<u32, Vec<(&Expense, Decimal)>> = BTreeMap::new();
+        for (expense, rate) in plan {
+            levels
+                .entry(expense.effective_priority())
+                .or_default()
+                .push((expense, rate.apply_to(planned.value)));
+        }
+
+        let mut available = income.value;
+        for mut expenses in levels.into_values() {
+            expenses.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+            let demand: Decimal = expenses.iter().map(|(_, d)| *d).sum();
+            let ratio = if demand <= available || demand.is_zero() {
+                Decimal::ONE
+            } else {
+                available / demand
+            };
+            for (expense, demand) in expenses {
+                let share = (demand * ratio).round_dp(2).min(available);
+                available -= share;
+                let received = self.allocate(
+                    expense.clone(),
+                    Money::new(share, income.currency),
+                    &plan.rates,
+                )?;
+                if share < demand {
+                    let planned = plan.rates.convert(
+                        Money::new(demand, income.currency),
+                        received.currency,
+                    )?;
+                    self.underfunded.push(Underfunded {
+                        name: expense.name.clone(),
+                        planned,
+                        received,
+                        shortfall: planned - received,
+                    });
+                }
+            }
+        }
+        Ok(())
     }
 }
 
@@ -126,6 +341,8 @@ impl Budget {
 ///
 /// # Errors
 /// `UnknownSource` - план не содержит Источника полученного Дохода
+/// `MissingExchangeRate` - нет курса для пересчёта Дохода в валюту конверта
+/// `InvalidPlan` - привязанные к источнику конверты не помещаются в его доход
 ///
 /// returns: Result<Distribute, `DistributeError`>
 ///
@@ -139,8 +356,19 @@ pub fn distribute(
 
     let mut d = Budget::new(income.clone());
 
-    plan.into_iter()
-        .for_each(|(e, r)| d.calculate(e.clone(), r));
+    // С привязками к источникам у каждого источника свои веса
+    let source_weights = plan.for_source(&income.source)?;
+    let weights = source_weights.as_ref().unwrap_or(plan);
+
+    match weights.mode {
+        DistributionMode::Priority if income_below_plan(weights, income)? => {
+            d.fill_by_priority(weights)?;
+        }
+        _ => weights
+            .into_iter()
+            .try_for_each(|(e, r)| d.calculate(e.clone(), r, &weights.rates))?,
+    }
+    d.route_surplus(plan)?;
 
     for entries in d.categories.values_mut() {
         entries.sort_by_key(|entry| entry.expense.name.clone());
@@ -149,21 +377,59 @@ pub fn distribute(
     Ok(d)
 }
 
+/// Сумма в валюте конверта: для фиксированной суммы - в валюте суммы,
+/// для процента - в базовой валюте плана
+fn envelope_amount(
+    expense: &Expense,
+    share: Money,
+    rates: &ExchangeRates,
+) -> Result<Money, Error> {
+    let currency = match expense.value() {
+        ExpenseValue::MONEY { value } => value.currency,
+        ExpenseValue::RATE { .. } => rates.base,
+    };
+    Ok(rates.convert(share, currency)?)
+}
+
+/// Сколько по плану приносит источник дохода, в валюте дохода.
+/// Доли весов - от любого дохода, поэтому с несколькими источниками
+/// каждый доход сравнивается только со своим источником
+fn planned_income(plan: &DistributionWeights, income: &Income) -> Result<Money, Error> {
+    Ok(plan
+        .rates
+        .convert(income.source.net(), income.amount.currency)?)
+}
+
+fn income_below_plan(
+    plan: &DistributionWeights,
+    income: &Income,
+) -> Result<bool, Error> {
+    let planned = planned_income(plan, income)?;
+    Ok(income.amount.value < planned.value)
+}
+
 #[cfg(test)]
 mod test_distribute {
     use chrono::Utc;
     use rust_decimal::{Decimal, prelude::FromPrimitive};
 
+    use chrono::NaiveDate;
+
     use crate::{
-        distribute::{Budget, Error, Income, distribute},
-        finance::{Money, Percentage},
+        distribute::{Budget, Error, Income, Underfunded, distribute},
+        finance::{Currency, ExchangeRates, Money, Percentage},
         plan::Plan,
         planning::{
+            self,
+            CreditExpense,
+            DistributionMode,
             DistributionWeights,
             Expense,
             ExpenseValue,
             IncomeKind,
             IncomeSource,
+            Member,
+            SurplusRule,
         },
     };
 
@@ -205,7 +471,13 @@ mod test_distribute {
         let plan = DistributionWeights::try_from(draft).unwrap();
         let income = Income::new_today(source, rub(1.0));
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense.clone(), &Percentage::from_int(50));
+        expected
+            .calculate(
+                expense.clone(),
+                &Percentage::from_int(50),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(0.5);
         assert_eq!(distribute(&plan, &income), Ok(expected));
     }
@@ -225,7 +497,13 @@ mod test_distribute {
         let income = Income::new_today(source, rub(0.5));
         let plan = DistributionWeights::try_from(draft).unwrap();
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense.clone(), &Percentage::from_int(100));
+        expected
+            .calculate(
+                expense.clone(),
+                &Percentage::from_int(100),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(0.0);
         assert_eq!(distribute(&plan, &income), Ok(expected));
     }
@@ -247,7 +525,13 @@ mod test_distribute {
         let income = Income::new_today(source, rub(1.0));
         let plan = DistributionWeights::try_from(draft).unwrap();
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense.clone(), &Percentage::from_int(100));
+        expected
+            .calculate(
+                expense.clone(),
+                &Percentage::from_int(100),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(0.0);
         assert_eq!(distribute(&plan, &income), Ok(expected));
     }
@@ -269,7 +553,13 @@ mod test_distribute {
         let income = Income::new_today(source, rub(1.0));
         let plan = DistributionWeights::try_from(draft).unwrap();
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense.clone(), &Percentage::from_int(50));
+        expected
+            .calculate(
+                expense.clone(),
+                &Percentage::from_int(50),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(0.5);
         assert_eq!(distribute(&plan, &income), Ok(expected));
     }
@@ -291,7 +581,13 @@ mod test_distribute {
         let income = Income::new_today(source, rub(1.0));
         let plan = DistributionWeights::try_from(draft).unwrap();
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense.clone(), &Percentage::from_int(0));
+        expected
+            .calculate(
+                expense.clone(),
+                &Percentage::from_int(0),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(1.0);
         assert_eq!(distribute(&plan, &income), Ok(expected));
     }
@@ -313,7 +609,13 @@ mod test_distribute {
         let income = Income::new_today(source, rub(1.0));
         let plan = DistributionWeights::try_from(draft).unwrap();
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense.clone(), &Percentage::from_int(1));
+        expected
+            .calculate(
+                expense.clone(),
+                &Percentage::from_int(1),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(0.99);
         assert_eq!(distribute(&plan, &income), Ok(expected));
     }
@@ -342,8 +644,20 @@ mod test_distribute {
         let plan = DistributionWeights::try_from(draft).unwrap();
 
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense_no_category.clone(), &Percentage::from_int(30));
-        expected.calculate(expense_with_category.clone(), &Percentage::from_int(20));
+        expected
+            .calculate(
+                expense_no_category.clone(),
+                &Percentage::from_int(30),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
+        expected
+            .calculate(
+                expense_with_category.clone(),
+                &Percentage::from_int(20),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(0.5);
 
         assert_eq!(distribute(&plan, &income), Ok(expected));
@@ -367,9 +681,558 @@ mod test_distribute {
         let plan = DistributionWeights::try_from(draft).unwrap();
 
         let mut expected = Budget::new(income.clone());
-        expected.calculate(expense.clone(), &Percentage::from_int(40));
+        expected
+            .calculate(
+                expense.clone(),
+                &Percentage::from_int(40),
+                &ExchangeRates::default(),
+            )
+            .unwrap();
         expected.rest = rub(0.6);
 
         assert_eq!(distribute(&plan, &income), Ok(expected));
     }
+
+    #[test]
+    fn entries_in_envelope_currency() {
+        let salary = other_source("Зарплата", rub(90000.0));
+        let contract =
+            other_source("Контракт", Money::new(Decimal::from(1000), Currency::USD));
+        let rent = Expense::envelope(
+            "Аренда".to_string(),
+            ExpenseValue::MONEY {
+                value: Money::new(Decimal::from(500), Currency::USD),
+            },
+            None,
+        );
+        let food = Expense::envelope(
+            "Продукты".to_string(),
+            ExpenseValue::RATE {
+                value: Percentage::QUARTER,
+            },
+            None,
+        );
+        let draft =
+            Plan::build(&[salary, contract.clone()], &[rent.clone(), food.clone()])
+                .with_rates(
+                    ExchangeRates::default()
+                        .with_rate(Currency::USD, Decimal::from(90)),
+                );
+        let plan = DistributionWeights::try_from(draft).unwrap();
+        let income =
+            Income::new_today(contract, Money::new(Decimal::from(1000), Currency::USD));
+
+        let budget = distribute(&plan, &income).unwrap();
+        let amount_of = |name: &str| {
+            budget
+                .no_category
+                .iter()
+                .find(|e| e.name() == name)
+                .map(|e| e.amount)
+        };
+        assert_eq!(
+            amount_of("Аренда"),
+            Some(Money::new(Decimal::from(250), Currency::USD))
+        );
+        assert_eq!(amount_of("Продукты"), Some(rub(22500.0)));
+        assert_eq!(budget.rest, Money::new(Decimal::from(500), Currency::USD));
+    }
+
+    #[test]
+    fn missing_rate_for_envelope_currency() {
+        let source = other_source("Зарплата", rub(1000.0));
+        let expense = Expense::envelope(
+            "Отпуск".to_string(),
+            ExpenseValue::RATE {
+                value: Percentage::HALF,
+            },
+            None,
+        );
+        let draft = Plan::build(std::slice::from_ref(&source), &[expense])
+            .with_rates(ExchangeRates::new(Currency::USD));
+        assert_eq!(
+            DistributionWeights::try_from(draft),
+            Err(crate::planning::Error::MissingExchangeRate(Currency::RUB))
+        );
+    }
+
+    fn priority_plan(mode: DistributionMode) -> (IncomeSource, DistributionWeights) {
+        let salary = other_source("Зарплата", rub(100000.0));
+        let money = |name: &str, v: f64| {
+            Expense::envelope(
+                name.to_string(),
+                ExpenseValue::MONEY { value: rub(v) },
+                None,
+            )
+        };
+        let credit = Expense::credit(
+            "Кредит".to_string(),
+            CreditExpense::new(
+                rub(15000.0),
+                rub(150000.0),
+                Percentage::ZERO,
+                10,
+                NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            )
+            .unwrap(),
+            None,
+        );
+        let fun = Expense::envelope(
+            "Развлечения".to_string(),
+            ExpenseValue::RATE {
+                value: Percentage::from_int(10),
+            },
+            None,
+        );
+        let draft = Plan::build(
+            std::slice::from_ref(&salary),
+            &[
+                money("Аренда", 30000.0).with_priority(1),
+                money("Продукты", 20000.0).with_priority(2),
+                credit,
+                fun,
+            ],
+        )
+        .with_mode(mode);
+        (salary, DistributionWeights::try_from(draft).unwrap())
+    }
+
+    fn amount_of(budget: &Budget, name: &str) -> Option<Money> {
+        budget
+            .no_category
+            .iter()
+            .find(|e| e.name() == name)
+            .map(|e| e.amount)
+    }
+
+    #[test]
+    fn priority_fills_important_envelopes_first() {
+        let (salary, plan) = priority_plan(DistributionMode::Priority);
+        let income = Income::new_today(salary, rub(50000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        assert_eq!(amount_of(&budget, "Кредит"), Some(rub(15000.0)));
+        assert_eq!(amount_of(&budget, "Аренда"), Some(rub(30000.0)));
+        assert_eq!(amount_of(&budget, "Продукты"), Some(rub(5000.0)));
+        assert_eq!(amount_of(&budget, "Развлечения"), Some(rub(0.0)));
+        assert_eq!(budget.rest, rub(0.0));
+        assert_eq!(
+            budget.underfunded,
+            vec![
+                Underfunded {
+                    name: "Продукты".to_string(),
+                    planned: rub(20000.0),
+                    received: rub(5000.0),
+                    shortfall: rub(15000.0),
+                },
+                Underfunded {
+                    name: "Развлечения".to_string(),
+                    planned: rub(10000.0),
+                    received: rub(0.0),
+                    shortfall: rub(10000.0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn priority_splits_level_proportionally() {
+        let salary = other_source("Зарплата", rub(100000.0));
+        let envelope = |name: &str, v: f64| {
+            Expense::envelope(
+                name.to_string(),
+                ExpenseValue::MONEY { value: rub(v) },
+                None,
+            )
+            .with_priority(1)
+        };
+        let draft = Plan::build(
+            std::slice::from_ref(&salary),
+            &[envelope("Аренда", 60000.0), envelope("Продукты", 20000.0)],
+        )
+        .with_mode(DistributionMode::Priority);
+        let plan = DistributionWeights::try_from(draft).unwrap();
+        let income = Income::new_today(salary, rub(40000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        assert_eq!(amount_of(&budget, "Аренда"), Some(rub(30000.0)));
+        assert_eq!(amount_of(&budget, "Продукты"), Some(rub(10000.0)));
+        assert_eq!(budget.underfunded.len(), 2);
+    }
+
+    #[test]
+    fn priority_compares_income_with_its_source() {
+        let advance = other_source("Аванс", rub(40000.0));
+        let salary = other_source("Зарплата", rub(60000.0));
+        let envelope = |name: &str, v: f64, priority: u32| {
+            Expense::envelope(
+                name.to_string(),
+                ExpenseValue::MONEY { value: rub(v) },
+                None,
+            )
+            .with_priority(priority)
+        };
+        let draft = Plan::build(
+            &[advance.clone(), salary],
+            &[
+                envelope("Аренда", 30000.0, 1),
+                envelope("Продукты", 20000.0, 2),
+            ],
+        )
+        .with_mode(DistributionMode::Priority);
+        let plan = DistributionWeights::try_from(draft).unwrap();
+
+        // Аванс пришёл полностью: это не нехватка, а его обычная доля
+        let full = distribute(&plan, &Income::new_today(advance.clone(), rub(40000.0)))
+            .unwrap();
+        assert_eq!(amount_of(&full, "Аренда"), Some(rub(12000.0)));
+        assert_eq!(amount_of(&full, "Продукты"), Some(rub(8000.0)));
+        assert!(full.underfunded.is_empty());
+
+        // Аренда претендует только на долю аванса, а не на всю месячную сумму
+        let short =
+            distribute(&plan, &Income::new_today(advance, rub(16000.0))).unwrap();
+        assert_eq!(amount_of(&short, "Аренда"), Some(rub(12000.0)));
+        assert_eq!(amount_of(&short, "Продукты"), Some(rub(4000.0)));
+        assert_eq!(
+            short.underfunded,
+            vec![Underfunded {
+                name: "Продукты".to_string(),
+                planned: rub(8000.0),
+                received: rub(4000.0),
+                shortfall: rub(4000.0),
+            }]
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::proportional_mode(DistributionMode::Proportional, 50000.0)]
+    #[case::income_covers_plan(DistributionMode::Priority, 100000.0)]
+    fn priority_not_applied(#[case] mode: DistributionMode, #[case] amount: f64) {
+        let (salary, plan) = priority_plan(mode);
+        let income = Income::new_today(salary, rub(amount));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        let share = amount / 100000.0;
+        assert_eq!(amount_of(&budget, "Аренда"), Some(rub(30000.0 * share)));
+        assert_eq!(
+            amount_of(&budget, "Развлечения"),
+            Some(rub(10000.0 * share))
+        );
+        assert!(budget.underfunded.is_empty());
+    }
+
+    fn surplus_plan(rules: Vec<SurplusRule>) -> (IncomeSource, DistributionWeights) {
+        let salary = other_source("Зарплата", rub(100000.0));
+        let envelope = |name: &str, v: f64| {
+            Expense::envelope(
+                name.to_string(),
+                ExpenseValue::MONEY { value: rub(v) },
+                None,
+            )
+        };
+        let draft = Plan::build(
+            std::slice::from_ref(&salary),
+            &[
+                envelope("Аренда", 50000.0),
+                envelope("Подушка", 10000.0),
+                envelope("Отпуск", 10000.0),
+            ],
+        )
+        .with_surplus(rules);
+        (salary, DistributionWeights::try_from(draft).unwrap())
+    }
+
+    #[test]
+    fn surplus_split_between_envelopes() {
+        let (salary, plan) = surplus_plan(vec![
+            SurplusRule::new("Подушка", Percentage::HALF),
+            SurplusRule::new("Отпуск", Percentage::HALF),
+        ]);
+        let income = Income::new_today(salary, rub(200000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        // План забирает 70%, остаток 60000 делится пополам
+        let surplus: Vec<_> = budget
+            .no_category
+            .iter()
+            .filter(|e| e.surplus)
+            .map(|e| (e.name().to_string(), e.amount))
+            .collect();
+        assert_eq!(
+            surplus.len(),
+            2,
+            "каждое правило даёт отдельную запись: {surplus:?}"
+        );
+        assert!(surplus.contains(&("Подушка".to_string(), rub(30000.0))));
+        assert!(surplus.contains(&("Отпуск".to_string(), rub(30000.0))));
+        assert_eq!(budget.rest, rub(0.0));
+    }
+
+    #[test]
+    fn surplus_partial_share_keeps_rest() {
+        let (salary, plan) =
+            surplus_plan(vec![SurplusRule::new("Подушка", Percentage::QUARTER)]);
+        let income = Income::new_today(salary, rub(100000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        let entries: Vec<_> = budget
+            .no_category
+            .iter()
+            .filter(|e| e.name() == "Подушка")
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert!(
+            entries
+                .iter()
+                .any(|e| !e.surplus && e.amount == rub(10000.0))
+        );
+        assert!(entries.iter().any(|e| e.surplus && e.amount == rub(7500.0)));
+        assert_eq!(budget.rest, rub(22500.0));
+    }
+
+    #[test]
+    fn surplus_skipped_without_rest() {
+        let (salary, plan) =
+            surplus_plan(vec![SurplusRule::new("Подушка", Percentage::ONE_HUNDRED)])
+                .clone();
+        let plan = DistributionWeights {
+            mode: DistributionMode::Priority,
+            ..plan
+        };
+        let income = Income::new_today(salary, rub(50000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        assert!(budget.no_category.iter().all(|e| !e.surplus));
+    }
+
+    /// Аванс оплачивает аренду и коммуналку, зарплата - всё остальное
+    fn bound_plan() -> (IncomeSource, IncomeSource, DistributionWeights) {
+        let advance = other_source("Аванс", rub(40000.0));
+        let salary = other_source("Зарплата", rub(60000.0));
+        let bound = vec!["Аванс".to_string()];
+        let expenses = [
+            Expense::envelope(
+                "Аренда".to_string(),
+                ExpenseValue::MONEY {
+                    value: rub(30000.0),
+                },
+                None,
+            )
+            .with_sources(bound.clone()),
+            Expense::envelope(
+                "Коммуналка".to_string(),
+                ExpenseValue::MONEY { value: rub(6000.0) },
+                None,
+            )
+            .with_sources(bound),
+            Expense::envelope(
+                "Еда".to_string(),
+                ExpenseValue::MONEY {
+                    value: rub(30000.0),
+                },
+                None,
+            ),
+            Expense::envelope(
+                "Подушка".to_string(),
+                ExpenseValue::RATE {
+                    value: Percentage::from_int(12),
+                },
+                None,
+            ),
+        ];
+        let draft = Plan::build(&[advance.clone(), salary.clone()], &expenses);
+        (
+            advance,
+            salary,
+            DistributionWeights::try_from(draft).unwrap(),
+        )
+    }
+
+    fn amounts(budget: &Budget) -> Vec<(String, Money)> {
+        let mut amounts: Vec<_> = budget
+            .no_category
+            .iter()
+            .map(|e| (e.name().to_string(), e.amount))
+            .collect();
+        amounts.sort_by(|a, b| a.0.cmp(&b.0));
+        amounts
+    }
+
+    #[test]
+    fn bound_expenses_funded_only_by_their_source() {
+        let (advance, _, plan) = bound_plan();
+        let income = Income::new_today(advance, rub(40000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        assert_eq!(
+            amounts(&budget),
+            vec![
+                ("Аренда".to_string(), rub(30000.0)),
+                ("Коммуналка".to_string(), rub(6000.0)),
+            ]
+        );
+        assert_eq!(budget.rest, rub(4000.0));
+    }
+
+    #[test]
+    fn unbound_expenses_funded_by_free_sources() {
+        let (_, salary, plan) = bound_plan();
+        let income = Income::new_today(salary, rub(60000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        // 12% от общего дохода 100000 целиком ложатся на зарплату
+        assert_eq!(
+            amounts(&budget),
+            vec![
+                ("Еда".to_string(), rub(30000.0)),
+                ("Подушка".to_string(), rub(12000.0)),
+            ]
+        );
+        assert_eq!(budget.rest, rub(18000.0));
+    }
+
+    #[test]
+    fn bound_weights_scale_with_income() {
+        let (advance, _, plan) = bound_plan();
+        let income = Income::new_today(advance, rub(20000.0));
+
+        let budget = distribute(&plan, &income).unwrap();
+
+        assert_eq!(
+            amounts(&budget),
+            vec![
+                ("Аренда".to_string(), rub(15000.0)),
+                ("Коммуналка".to_string(), rub(3000.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_budgets_by_envelope() {
+        let (advance, salary, plan) = bound_plan();
+        let before =
+            distribute(&plan, &Income::new_today(advance, rub(20000.0))).unwrap();
+        let after =
+            distribute(&plan, &Income::new_today(salary, rub(80000.0))).unwrap();
+
+        let changes: Vec<_> = before
+            .compare(&after)
+            .into_iter()
+            .map(|c| (c.name, c.before, c.after, c.difference))
+            .collect();
+        let mut expected: Vec<_> = amounts(&before)
+            .into_iter()
+            .map(|(name, was)| (name, was, rub(0.0), rub(0.0) - was))
+            .chain(
+                amounts(&after)
+                    .into_iter()
+                    .map(|(name, now)| (name, rub(0.0), now, now)),
+            )
+            .collect();
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(changes, expected);
+    }
+
+    /// Аренду делят пополам, подушку копит Анна, еду - по доходу
+    fn household_plan(anna: Member) -> Result<DistributionWeights, planning::Error> {
+        let expenses = [
+            Expense::envelope(
+                "Аренда".to_string(),
+                ExpenseValue::MONEY {
+                    value: rub(30000.0),
+                },
+                None,
+            ),
+            Expense::envelope(
+                "Еда".to_string(),
+                ExpenseValue::MONEY {
+                    value: rub(20000.0),
+                },
+                None,
+            ),
+            Expense::envelope(
+                "Подушка".to_string(),
+                ExpenseValue::RATE {
+                    value: Percentage::from_int(12),
+                },
+                None,
+            ),
+        ];
+        let ivan = Member::new("ivan", vec!["Зарплата Ивана".to_string()])
+            .with_share("Аренда", Percentage::HALF);
+        let draft = Plan::build(
+            &[
+                other_source("Зарплата Анны", rub(60000.0)),
+                other_source("Зарплата Ивана", rub(40000.0)),
+            ],
+            &expenses,
+        )
+        .with_members(vec![anna, ivan]);
+        DistributionWeights::try_from(draft)
+    }
+
+    fn anna() -> Member {
+        Member::new("anna", vec!["Зарплата Анны".to_string()])
+            .with_share("Аренда", Percentage::HALF)
+            .with_share("Подушка", Percentage::ONE_HUNDRED)
+    }
+
+    #[test]
+    fn member_income_fills_agreed_shares() {
+        let plan = household_plan(anna()).unwrap();
+        let anna_income = Income::new_today(plan.sources[0].clone(), rub(60000.0));
+        let ivan_income = Income::new_today(plan.sources[1].clone(), rub(40000.0));
+
+        let anna_budget = distribute(&plan, &anna_income).unwrap();
+        let ivan_budget = distribute(&plan, &ivan_income).unwrap();
+
+        assert_eq!(
+            amounts(&anna_budget),
+            vec![
+                ("Аренда".to_string(), rub(15000.0)),
+                ("Еда".to_string(), rub(12000.0)),
+                ("Подушка".to_string(), rub(12000.0)),
+            ]
+        );
+        assert_eq!(anna_budget.rest, rub(21000.0));
+        assert_eq!(
+            amounts(&ivan_budget),
+            vec![
+                ("Аренда".to_string(), rub(15000.0)),
+                ("Еда".to_string(), rub(8000.0)),
+            ]
+        );
+        assert_eq!(ivan_budget.rest, rub(17000.0));
+    }
+
+    #[test]
+    fn member_shares_must_cover_envelope() {
+        let greedy = anna().with_share("Аренда", Percentage::from_int(70));
+        assert_eq!(
+            household_plan(greedy).unwrap_err(),
+            planning::Error::InvalidShares("Аренда".to_string())
+        );
+
+        let stranger = Member::new("anna", vec!["Премия".to_string()]);
+        assert_eq!(
+            household_plan(stranger).unwrap_err(),
+            planning::Error::UnknownMemberSource("Премия".to_string())
+        );
+
+        let without_sources = Member::new("anna", vec![]);
+        assert_eq!(
+            household_plan(without_sources).unwrap_err(),
+            planning::Error::SourceWithoutMember("Зарплата Анны".to_string())
+        );
+    }
 }
@@ -2,11 +2,12 @@ use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fmt::{Display, Formatter},
-    iter::Sum,
     ops::{Add, AddAssign, Div, Sub, SubAssign},
     str::FromStr,
 };
+use thiserror::Error;
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, PartialOrd, Serialize, Deserialize)]
 pub struct Percentage(Decimal);
@@ -38,6 +39,14 @@ impl SubAssign for Percentage {
     }
 }
 
+impl Add for Percentage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::from(self.0 + rhs.0)
+    }
+}
+
 impl Sub for Percentage {
     type Output = Self;
 
@@ -197,22 +206,26 @@ impl Display for Money {
 impl FromStr for Money {
     type Err = rust_decimal::Error;
 
+    /// Разбирает сумму с необязательным знаком валюты в начале.
+    /// Без знака сумма считается рублёвой.
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use rust_decimal_macros::dec;
+    /// use ai_core::finance::{Currency, Money};
+    ///
+    /// assert_eq!(Money::from_str("₽100").unwrap(), Money::new_rub(dec!(100)));
+    /// assert_eq!(Money::from_str("100").unwrap(), Money::new_rub(dec!(100)));
+    /// assert_eq!(Money::from_str("$12.5").unwrap(), Money::new(dec!(12.5), Currency::USD));
+    /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let trimmed = s.trim_start_matches('₽');
-        trimmed.parse::<Decimal>().map(Money::new_rub)
-    }
-}
-
-impl Sum for Money {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        let mut total_rub = Money::new_rub(Decimal::ZERO);
-        for m in iter {
-            match m.currency {
-                Currency::RUB => total_rub += m,
-                Currency::USD => panic!("Нельзя складывать разные валюты"),
-            }
+        match s.strip_prefix('$') {
+            Some(usd) => usd.parse::<Decimal>().map(|v| Money::new(v, Currency::USD)),
+            None => s
+                .trim_start_matches('₽')
+                .parse::<Decimal>()
+                .map(Money::new_rub),
         }
-        total_rub
     }
 }
 
@@ -324,8 +337,21 @@ impl Money {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[derive(
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Copy,
+    Clone,
+    Serialize,
+    Deserialize,
+)]
 pub enum Currency {
+    #[default]
     RUB,
     USD,
 }
@@ -342,3 +368,124 @@ impl Display for Currency {
         }
     }
 }
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ExchangeError {
+    #[error("Не задан курс для валюты {0:?}")]
+    MissingRate(Currency),
+}
+
+/// Таблица курсов, которую пользователь хранит вместе с планом.
+///
+/// Все курсы задаются относительно базовой валюты: запись `USD -> 90`
+/// означает, что 1 USD стоит 90 единиц базовой валюты.
+/// Пересчёт между двумя небазовыми валютами идёт через базовую.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    pub base: Currency,
+    #[serde(default)]
+    pub rates: BTreeMap<Currency, Decimal>,
+}
+
+impl Default for ExchangeRates {
+    fn default() -> Self {
+        Self::new(Currency::RUB)
+    }
+}
+
+impl ExchangeRates {
+    #[must_use]
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            rates: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_rate(mut self, currency: Currency, rate: Decimal) -> Self {
+        self.rates.insert(currency, rate);
+        self
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Стоимость единицы валюты в базовой валюте.
+    /// Неположительный курс считается незаданным.
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use rust_decimal_macros::dec;
+    /// use ai_core::finance::{Currency, ExchangeRates};
+    ///
+    /// let rates = ExchangeRates::default().with_rate(Currency::USD, dec!(90));
+    /// assert_eq!(rates.rate(Currency::RUB), Some(Decimal::ONE));
+    /// assert_eq!(rates.rate(Currency::USD), Some(dec!(90)));
+    ///
+    /// let broken = ExchangeRates::default().with_rate(Currency::USD, Decimal::ZERO);
+    /// assert_eq!(broken.rate(Currency::USD), None);
+    /// ```
+    pub fn rate(&self, currency: Currency) -> Option<Decimal> {
+        if currency == self.base {
+            return Some(Decimal::ONE);
+        }
+        self.rates
+            .get(&currency)
+            .copied()
+            .filter(|rate| *rate > Decimal::ZERO)
+    }
+
+    /// Пересчитывает сумму в валюту `to`. Результат округляется до копеек.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use ai_core::finance::{Currency, ExchangeError, ExchangeRates, Money};
+    ///
+    /// let rates = ExchangeRates::default().with_rate(Currency::USD, dec!(90));
+    /// let usd = Money::new(dec!(10), Currency::USD);
+    ///
+    /// assert_eq!(rates.convert(usd, Currency::RUB), Ok(Money::new_rub(dec!(900))));
+    /// assert_eq!(
+    ///     rates.convert(Money::new_rub(dec!(100)), Currency::USD),
+    ///     Ok(Money::new(dec!(1.11), Currency::USD))
+    /// );
+    /// assert_eq!(rates.convert(usd, Currency::USD), Ok(usd));
+    ///
+    /// let no_usd = ExchangeRates::default();
+    /// assert_eq!(no_usd.convert(usd, Currency::RUB), Err(ExchangeError::MissingRate(Currency::USD)));
+    /// ```
+    pub fn convert(&self, money: Money, to: Currency) -> Result<Money, ExchangeError> {
+        if money.currency == to {
+            return Ok(money);
+        }
+        let from_rate = self
+            .rate(money.currency)
+            .ok_or(ExchangeError::MissingRate(money.currency))?;
+        let to_rate = self.rate(to).ok_or(ExchangeError::MissingRate(to))?;
+        Ok(Money::new(money.value * from_rate / to_rate, to))
+    }
+
+    /// Складывает суммы в разных валютах, пересчитывая каждую в `to`.
+    ///
+    /// ```
+    /// use rust_decimal_macros::dec;
+    /// use ai_core::finance::{Currency, ExchangeRates, Money};
+    ///
+    /// let rates = ExchangeRates::default().with_rate(Currency::USD, dec!(90));
+    /// let mixed = vec![Money::new_rub(dec!(100)), Money::new(dec!(1), Currency::USD)];
+    /// assert_eq!(rates.sum(mixed, Currency::RUB), Ok(Money::new_rub(dec!(190))));
+    /// ```
+    pub fn sum(
+        &self,
+        items: impl IntoIterator<Item = Money>,
+        to: Currency,
+    ) -> Result<Money, ExchangeError> {
+        items
+            .into_iter()
+            .try_fold(Money::new(Decimal::ZERO, to), |acc, m| {
+                Ok(acc + self.convert(m, to)?)
+            })
+    }
+}
@@ -2,4 +2,5 @@ pub mod distribute;
 pub mod finance;
 pub mod plan;
 pub mod planning;
+pub mod simulate;
 pub mod templates;
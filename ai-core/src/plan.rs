@@ -1,8 +1,16 @@
 use crate::{
-    finance::{Money, Percentage},
-    planning::{DistributionWeights, Error, Expense, ExpenseValue, IncomeSource},
+    finance::{Currency, ExchangeRates, Money, Percentage},
+    planning::{
+        DistributionMode,
+        DistributionWeights,
+        Error,
+        Expense,
+        ExpenseValue,
+        IncomeSource,
+        Member,
+        SurplusRule,
+    },
 };
-use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt::Debug};
 
@@ -14,12 +22,13 @@ impl TryFrom<Plan> for DistributionWeights {
             return Err(Error::EmptyPlan);
         }
 
-        let plan_total = draft.total_incomes();
+        let plan_total = draft.total_incomes()?;
         let mut rate_plan = HashMap::with_capacity(draft.expenses.len());
         let mut total = Percentage::ZERO;
         for e in draft.expenses {
             let current = match e.value() {
                 ExpenseValue::MONEY { value } => {
+                    let value = draft.rates.convert(value, plan_total.currency)?;
                     Percentage::of(value.value, plan_total.value)
                 }
                 ExpenseValue::RATE { value } => value,
@@ -30,11 +39,41 @@ impl TryFrom<Plan> for DistributionWeights {
             }
             rate_plan.insert(e.clone(), current);
         }
-        Ok(Self {
+
+        for expense in rate_plan.keys() {
+            for name in &expense.sources {
+                if !draft.sources.iter().any(|s| &s.name == name) {
+                    return Err(Error::UnknownBindingSource(name.clone()));
+                }
+            }
+        }
+
+        let mut surplus_total = Percentage::ZERO;
+        for rule in &draft.surplus {
+            if !rate_plan.keys().any(|e| e.name == rule.envelope) {
+                return Err(Error::UnknownSurplusEnvelope(rule.envelope.clone()));
+            }
+            surplus_total += rule.share.clone();
+        }
+        if surplus_total > Percentage::ONE_HUNDRED {
+            return Err(Error::TooBigSurplus);
+        }
+
+        let weights = Self {
             sources: draft.sources.clone(),
             budget: rate_plan,
             rest: Percentage::ONE_HUNDRED - total,
-        })
+            rates: draft.rates,
+            mode: draft.mode,
+            surplus: draft.surplus,
+            members: draft.members,
+        };
+        weights.check_members()?;
+        // Каждый источник должен покрывать привязанные к нему конверты
+        for source in &weights.sources {
+            weights.for_source(source)?;
+        }
+        Ok(weights)
     }
 }
 
@@ -42,6 +81,18 @@ impl TryFrom<Plan> for DistributionWeights {
 pub struct Plan {
     pub sources: Vec<IncomeSource>,
     pub expenses: Vec<Expense>,
+    /// Курсы для пересчёта сумм в базовую валюту плана
+    #[serde(default, skip_serializing_if = "ExchangeRates::is_default")]
+    pub rates: ExchangeRates,
+    /// Режим распределения дохода, которого не хватает на весь план
+    #[serde(default, skip_serializing_if = "DistributionMode::is_default")]
+    pub mode: DistributionMode,
+    /// Куда направлять остаток, который не разошёлся по конвертам
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub surplus: Vec<SurplusRule>,
+    /// Участники общего плана; пусто - план одного пользователя
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<Member>,
 }
 
 impl Default for Plan {
@@ -63,6 +114,10 @@ impl Plan {
         Self {
             sources: vec![],
             expenses: vec![],
+            rates: ExchangeRates::default(),
+            mode: DistributionMode::default(),
+            surplus: vec![],
+            members: vec![],
         }
     }
 
@@ -73,33 +128,165 @@ impl Plan {
         draft
     }
 
-    pub fn total_incomes(&self) -> Money {
-        self.sources
-            .iter()
-            .map(|s| s.net())
-            .fold(Money::new_rub(Decimal::ZERO), |acc, income| acc + income)
+    #[must_use]
+    pub fn with_rates(mut self, rates: ExchangeRates) -> Self {
+        self.rates = rates;
+        self
     }
 
-    pub fn total_expenses(&self) -> Money {
-        let total_income = self.total_incomes();
-        self.expenses
-            .iter()
-            .map(|expense| match expense.value() {
-                ExpenseValue::MONEY { value } => value,
-                ExpenseValue::RATE { value } => {
-                    Money::new_rub(value.apply_to(total_income.value))
-                }
-            })
-            .fold(Money::new_rub(Decimal::ZERO), |acc, expense| acc + expense)
+    #[must_use]
+    pub fn with_mode(mut self, mode: DistributionMode) -> Self {
+        self.mode = mode;
+        self
     }
 
-    pub fn balance(&self) -> Money {
-        self.total_incomes() - self.total_expenses()
+    #[must_use]
+    pub fn with_surplus(mut self, rules: Vec<SurplusRule>) -> Self {
+        self.surplus = rules;
+        self
+    }
+
+    #[must_use]
+    pub fn with_members(mut self, members: Vec<Member>) -> Self {
+        self.members = members;
+        self
+    }
+
+    /// Участник общего плана, который приносит источник `source`
+    pub fn member_of(&self, source: &str) -> Option<&Member> {
+        self.members.iter().find(|m| m.owns(source))
+    }
+
+    /// Базовая валюта плана: в ней считаются итоги и доли конвертов
+    pub fn currency(&self) -> Currency {
+        self.rates.base
+    }
+
+    pub fn total_incomes(&self) -> Result<Money, Error> {
+        let total = self
+            .rates
+            .sum(self.sources.iter().map(|s| s.net()), self.currency())?;
+        Ok(total)
+    }
+
+    pub fn total_expenses(&self) -> Result<Money, Error> {
+        let total_income = self.total_incomes()?;
+        let expenses = self.expenses.iter().map(|expense| match expense.value() {
+            ExpenseValue::MONEY { value } => value,
+            ExpenseValue::RATE { value } => {
+                Money::new(value.apply_to(total_income.value), total_income.currency)
+            }
+        });
+        Ok(self.rates.sum(expenses, self.currency())?)
+    }
+
+    pub fn balance(&self) -> Result<Money, Error> {
+        Ok(self.total_incomes()? - self.total_expenses()?)
     }
 
     pub fn is_empty(&self) -> bool {
         self.sources.is_empty() || self.expenses.is_empty()
     }
+
+    /// Что изменилось в плане `new` по сравнению с этим
+    pub fn diff(&self, new: &Plan) -> PlanDiff {
+        PlanDiff {
+            sources: diff_by_name(&self.sources, &new.sources, |s| &s.name),
+            expenses: diff_by_name(&self.expenses, &new.expenses, |e| &e.name),
+            settings_changed: self.rates != new.rates
+                || self.mode != new.mode
+                || self.surplus != new.surplus
+                || self.members != new.members,
+        }
+    }
+
+    /// Переносит изменения, сделанные в этом плане относительно `base`, на
+    /// более свежую версию `theirs`. При совпадении имён побеждает этот план;
+    /// настройки берутся отсюда, только если они здесь менялись.
+    pub fn merge(&self, base: &Plan, theirs: &Plan) -> Plan {
+        let mine = base.diff(self);
+        let mut merged = theirs.clone();
+        apply_by_name(&mut merged.sources, mine.sources, |s| &s.name);
+        apply_by_name(&mut merged.expenses, mine.expenses, |e| &e.name);
+        if mine.settings_changed {
+            merged.rates = self.rates.clone();
+            merged.mode = self.mode;
+            merged.surplus = self.surplus.clone();
+            merged.members = self.members.clone();
+        }
+        merged
+    }
+}
+
+/// Изменение одного источника или расхода между версиями плана
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change<T> {
+    Added { new: T },
+    Removed { old: T },
+    Changed { old: T, new: T },
+}
+
+/// Структурная разница двух версий плана.
+/// Источники и расходы сопоставляются по имени, так что переименование -
+/// это удаление и добавление.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PlanDiff {
+    pub sources: Vec<Change<IncomeSource>>,
+    pub expenses: Vec<Change<Expense>>,
+    /// Изменились курсы валют, режим распределения, правила остатка
+    /// или участники общего плана
+    pub settings_changed: bool,
+}
+
+impl PlanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty() && self.expenses.is_empty() && !self.settings_changed
+    }
+}
+
+fn apply_by_name<T>(
+    items: &mut Vec<T>,
+    changes: Vec<Change<T>>,
+    name: impl Fn(&T) -> &str,
+) {
+    for change in changes {
+        match change {
+            Change::Removed { old } => items.retain(|i| name(i) != name(&old)),
+            Change::Added { new } | Change::Changed { new, .. } => {
+                match items.iter_mut().find(|i| name(i) == name(&new)) {
+                    Some(item) => *item = new,
+                    None => items.push(new),
+                }
+            }
+        }
+    }
+}
+
+/// Изменённые и удалённые - в порядке старой версии, добавленные - новой
+fn diff_by_name<T: Clone + PartialEq>(
+    old: &[T],
+    new: &[T],
+    name: impl Fn(&T) -> &str,
+) -> Vec<Change<T>> {
+    let find = |items: &[T], key: &str| items.iter().find(|i| name(i) == key).cloned();
+    let mut changes: Vec<Change<T>> = old
+        .iter()
+        .filter_map(|o| match find(new, name(o)) {
+            None => Some(Change::Removed { old: o.clone() }),
+            Some(n) if &n != o => Some(Change::Changed {
+                old: o.clone(),
+                new: n,
+            }),
+            Some(_) => None,
+        })
+        .collect();
+    changes.extend(
+        new.iter()
+            .filter(|n| find(old, name(n)).is_none())
+            .map(|n| Change::Added { new: n.clone() }),
+    );
+    changes
 }
 
 #[cfg(test)]
@@ -205,6 +392,10 @@ mod test_planning {
                 sources: vec![source.clone()],
                 budget: expected,
                 rest: Percentage::from_int(0),
+                rates: ExchangeRates::default(),
+                mode: DistributionMode::default(),
+                surplus: vec![],
+                members: vec![],
             }
         );
     }
@@ -278,6 +469,10 @@ mod test_planning {
                 sources: vec![source.clone()],
                 budget: expected,
                 rest: Percentage::HALF,
+                rates: ExchangeRates::default(),
+                mode: DistributionMode::default(),
+                surplus: vec![],
+                members: vec![],
             }
         );
     }
@@ -319,10 +514,79 @@ mod test_planning {
                 sources: vec![source.clone()],
                 budget: expected,
                 rest: Percentage::ZERO,
+                rates: ExchangeRates::default(),
+                mode: DistributionMode::default(),
+                surplus: vec![],
+                members: vec![],
             }
         );
     }
 
+    fn usd(v: Decimal) -> Money {
+        Money::new(v, Currency::USD)
+    }
+
+    #[test]
+    fn mixed_currency_plan_without_rate() {
+        let draft = Plan::build(
+            &[
+                other_source("Зарплата", rub(90000.0)),
+                other_source("Контракт", usd(dec!(1000))),
+            ],
+            &[Expense::envelope(
+                "Аренда".to_string(),
+                ExpenseValue::MONEY {
+                    value: rub(45000.0),
+                },
+                None,
+            )],
+        );
+        assert_eq!(
+            draft.total_incomes(),
+            Err(Error::MissingExchangeRate(Currency::USD))
+        );
+        assert_eq!(
+            DistributionWeights::try_from(draft),
+            Err(Error::MissingExchangeRate(Currency::USD))
+        );
+    }
+
+    #[test]
+    fn mixed_currency_plan_totals() {
+        let rent = Expense::envelope(
+            "Аренда".to_string(),
+            ExpenseValue::MONEY {
+                value: usd(dec!(500)),
+            },
+            None,
+        );
+        let food = Expense::envelope(
+            "Продукты".to_string(),
+            ExpenseValue::RATE {
+                value: Percentage::QUARTER,
+            },
+            None,
+        );
+        let draft = Plan::build(
+            &[
+                other_source("Зарплата", rub(90000.0)),
+                other_source("Контракт", usd(dec!(1000))),
+            ],
+            &[rent.clone(), food.clone()],
+        )
+        .with_rates(ExchangeRates::default().with_rate(Currency::USD, dec!(90)));
+
+        assert_eq!(draft.total_incomes(), Ok(rub(180000.0)));
+        assert_eq!(draft.total_expenses(), Ok(rub(90000.0)));
+        assert_eq!(draft.balance(), Ok(rub(90000.0)));
+
+        let weights = DistributionWeights::try_from(draft).unwrap();
+        assert_eq!(weights.get(&rent), Some(&Percentage::QUARTER));
+        assert_eq!(weights.get(&food), Some(&Percentage::QUARTER));
+        assert_eq!(weights.rest, Percentage::HALF);
+        assert_eq!(weights.total_income(), Ok(rub(180000.0)));
+    }
+
     #[test]
     fn test_plan_display() {
         let source = other_source("Зарплата", rub(100000.0));
@@ -354,4 +618,173 @@ mod test_planning {
         );
         let _plan = DistributionWeights::try_from(draft).unwrap();
     }
+
+    #[test]
+    fn surplus_rules_validated() {
+        let source = IncomeSource::new(
+            "Зарплата".to_string(),
+            IncomeKind::Other {
+                expected: Money::new_rub(dec!(100000)),
+            },
+        );
+        let expense = Expense::envelope(
+            "Подушка".to_string(),
+            ExpenseValue::RATE {
+                value: Percentage::from_int(10),
+            },
+            None,
+        );
+        let draft = Plan::build(&[source], &[expense]);
+
+        let unknown = draft
+            .clone()
+            .with_surplus(vec![SurplusRule::new("Отпуск", Percentage::HALF)]);
+        assert_eq!(
+            DistributionWeights::try_from(unknown),
+            Err(Error::UnknownSurplusEnvelope("Отпуск".to_string()))
+        );
+
+        let too_big = draft.with_surplus(vec![
+            SurplusRule::new("Подушка", Percentage::HALF),
+            SurplusRule::new("Подушка", Percentage::from_int(51)),
+        ]);
+        assert_eq!(
+            DistributionWeights::try_from(too_big),
+            Err(Error::TooBigSurplus)
+        );
+    }
+
+    #[test]
+    fn source_bindings_validated() {
+        let advance = other_source("Аванс", Money::new_rub(dec!(40000)));
+        let salary = other_source("Зарплата", Money::new_rub(dec!(60000)));
+        let rent = |amount| {
+            Expense::envelope(
+                "Аренда".to_string(),
+                ExpenseValue::MONEY {
+                    value: Money::new_rub(amount),
+                },
+                None,
+            )
+        };
+
+        let unknown = Plan::build(
+            &[advance.clone(), salary.clone()],
+            &[rent(dec!(30000)).with_sources(vec!["Премия".to_string()])],
+        );
+        assert_eq!(
+            DistributionWeights::try_from(unknown),
+            Err(Error::UnknownBindingSource("Премия".to_string()))
+        );
+
+        // В общий план аренда помещается, но аванса на неё не хватает
+        let too_big = Plan::build(
+            &[advance.clone(), salary.clone()],
+            &[rent(dec!(50000)).with_sources(vec!["Аванс".to_string()])],
+        );
+        assert_eq!(
+            DistributionWeights::try_from(too_big),
+            Err(Error::TooBigSourceExpenses("Аванс".to_string()))
+        );
+
+        let fits = Plan::build(
+            &[advance, salary],
+            &[rent(dec!(30000)).with_sources(vec!["Аванс".to_string()])],
+        );
+        assert!(DistributionWeights::try_from(fits).is_ok());
+    }
+
+    #[test]
+    fn diff_between_versions() {
+        let salary = other_source("Зарплата", Money::new_rub(dec!(100000)));
+        let rent = Expense::envelope(
+            "Аренда".into(),
+            ExpenseValue::MONEY {
+                value: Money::new_rub(dec!(30000)),
+            },
+            None,
+        );
+        let food = Expense::envelope(
+            "Еда".into(),
+            ExpenseValue::MONEY {
+                value: Money::new_rub(dec!(20000)),
+            },
+            None,
+        );
+        let old =
+            Plan::build(std::slice::from_ref(&salary), &[rent.clone(), food.clone()]);
+
+        let raised = other_source("Зарплата", Money::new_rub(dec!(120000)));
+        let fun = Expense::envelope(
+            "Развлечения".into(),
+            ExpenseValue::RATE {
+                value: Percentage::from_int(5),
+            },
+            None,
+        );
+        let new = Plan::build(std::slice::from_ref(&raised), &[rent, fun.clone()]);
+
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.sources,
+            vec![Change::Changed {
+                old: salary,
+                new: raised,
+            }]
+        );
+        assert_eq!(
+            diff.expenses,
+            vec![Change::Removed { old: food }, Change::Added { new: fun }]
+        );
+        assert!(!diff.settings_changed);
+        assert!(old.diff(&old).is_empty());
+        assert!(
+            old.diff(&old.clone().with_mode(DistributionMode::Priority))
+                .settings_changed
+        );
+    }
+
+    #[test]
+    fn merge_keeps_both_sides() {
+        let salary = other_source("Зарплата", Money::new_rub(dec!(100000)));
+        let rent = Expense::envelope(
+            "Аренда".into(),
+            ExpenseValue::MONEY {
+                value: Money::new_rub(dec!(30000)),
+            },
+            None,
+        );
+        let food = Expense::envelope(
+            "Еда".into(),
+            ExpenseValue::MONEY {
+                value: Money::new_rub(dec!(20000)),
+            },
+            None,
+        );
+        let base =
+            Plan::build(std::slice::from_ref(&salary), &[rent.clone(), food.clone()]);
+
+        // Другая вкладка подняла зарплату
+        let raised = other_source("Зарплата", Money::new_rub(dec!(120000)));
+        let theirs =
+            Plan::build(std::slice::from_ref(&raised), &[rent.clone(), food.clone()]);
+
+        // А здесь удалили еду и добавили развлечения
+        let fun = Expense::envelope(
+            "Развлечения".into(),
+            ExpenseValue::RATE {
+                value: Percentage::from_int(5),
+            },
+            None,
+        );
+        let mine =
+            Plan::build(std::slice::from_ref(&salary), &[rent.clone(), fun.clone()])
+                .with_mode(DistributionMode::Priority);
+
+        let merged = mine.merge(&base, &theirs);
+        assert_eq!(merged.sources, vec![raised]);
+        assert_eq!(merged.expenses, vec![rent, fun]);
+        assert_eq!(merged.mode, DistributionMode::Priority);
+        assert_eq!(base.merge(&base, &theirs), theirs);
+    }
 }
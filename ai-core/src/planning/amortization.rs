@@ -0,0 +1,236 @@
+//! График погашения кредита.
+//!
+//! Строится по сумме, ставке и сроку [`CreditExpense`]: платежи идут раз в месяц,
+//! первый - через месяц после даты оформления. Проценты начисляются на остаток
+//! долга по ставке `годовая / 12`, каждая строка округляется до копеек, а
+//! последний платёж закрывает остаток целиком. Если суммы не помещаются
+//! в Decimal, вместо графика возвращается ошибка.
+
+use chrono::{Months, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    finance::Money,
+    planning::{CreditExpense, CreditValidationError, Error, Expense, ExpenseKind},
+};
+
+/// Допустимое расхождение указанного платежа с расчётным, в процентах
+const PAYMENT_TOLERANCE: Decimal = dec!(1);
+
+/// Способ погашения
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepaymentKind {
+    /// Равные платежи весь срок
+    #[default]
+    Annuity,
+    /// Равные доли основного долга, проценты на остаток
+    Differentiated,
+}
+
+/// Строка графика
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Installment {
+    pub number: u32,
+    pub date: NaiveDate,
+    pub payment: Money,
+    pub interest: Money,
+    pub principal: Money,
+    /// Остаток основного долга после платежа
+    pub remaining: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AmortizationSchedule {
+    pub kind: RepaymentKind,
+    pub installments: Vec<Installment>,
+}
+
+/// Состояние кредита на дату
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreditPayoff {
+    pub name: String,
+    pub monthly_payment: Money,
+    /// Аннуитетный платёж по ставке и сроку
+    pub expected_payment: Money,
+    pub payment_matches_terms: bool,
+    pub remaining_principal: Money,
+    pub schedule: AmortizationSchedule,
+}
+
+impl AmortizationSchedule {
+    pub fn total_interest(&self) -> Money {
+        self.total(|i| i.interest)
+    }
+
+    pub fn total_paid(&self) -> Money {
+        self.total(|i| i.payment)
+    }
+
+    /// Все строки графика в валюте кредита, поэтому складываются значения
+    fn total(&self, amount: impl Fn(&Installment) -> Money) -> Money {
+        let currency = self
+            .installments
+            .first()
+            .map(|i| amount(i).currency)
+            .unwrap_or_default();
+        let value = self.installments.iter().map(|i| amount(i).value).sum();
+        Money::new(value, currency)
+    }
+
+    /// Остаток основного долга после всех платежей, наступивших к дате `on`
+    pub fn remaining_on(&self, credit: &CreditExpense, on: NaiveDate) -> Money {
+        self.installments
+            .iter()
+            .take_while(|i| i.date <= on)
+            .last()
+            .map_or(credit.total_amount, |i| i.remaining)
+    }
+}
+
+impl CreditExpense {
+    /// Месячная ставка в долях единицы
+    fn monthly_rate(&self) -> Decimal {
+        self.interest_rate.apply_to(Decimal::ONE) / dec!(12)
+    }
+
+    /// Аннуитетный платёж, который следует из суммы, ставки и срока
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use rust_decimal_macros::dec;
+    /// use ai_core::{finance::{Money, Percentage}, planning::CreditExpense};
+    ///
+    /// let credit = CreditExpense::new(
+    ///     Money::new_rub(dec!(8885)),
+    ///     Money::new_rub(dec!(100000)),
+    ///     Percentage::from_int(12),
+    ///     12,
+    ///     NaiveDate::from_ymd_opt(2026, 1, 15).unwrap(),
+    /// )
+    /// .unwrap();
+    /// assert_eq!(credit.annuity_payment(), Ok(Money::new_rub(dec!(8884.88))));
+    /// ```
+    ///
+    /// # Errors
+    /// `InvalidCredit(TermsOutOfRange)` - платёж не помещается в Decimal
+    pub fn annuity_payment(&self) -> Result<Money, Error> {
+        let principal = self.total_amount.value;
+        let n = self.term_months;
+        let r = self.monthly_rate();
+        let payment = if n == 0 {
+            None
+        } else if r.is_zero() {
+            principal.checked_div(Decimal::from(n))
+        } else {
+            (0..n)
+                .try_fold(Decimal::ONE, |acc, _| acc.checked_mul(Decimal::ONE + r))
+                .and_then(|growth| {
+                    principal
+                        .checked_mul(r)?
+                        .checked_mul(growth)?
+                        .checked_div(growth - Decimal::ONE)
+                })
+        };
+        let payment = payment.ok_or_else(out_of_range)?;
+        Ok(Money::new(payment.round_dp(2), self.total_amount.currency))
+    }
+
+    /// Совпадает ли указанный платёж с аннуитетным с точностью до 1%
+    ///
+    /// # Errors
+    /// Как у [`CreditExpense::annuity_payment`]
+    pub fn payment_matches_terms(&self) -> Result<bool, Error> {
+        let expected = self.annuity_payment()?.value;
+        let diff = (self.monthly_payment.value - expected).abs();
+        Ok(diff * dec!(100) <= expected * PAYMENT_TOLERANCE)
+    }
+
+    /// # Errors
+    /// Как у [`CreditExpense::annuity_payment`]
+    pub fn schedule(&self, kind: RepaymentKind) -> Result<AmortizationSchedule, Error> {
+        let currency = self.total_amount.currency;
+        let money = |v: Decimal| Money::new(v, currency);
+        let r = self.monthly_rate();
+        let annuity = self.annuity_payment()?.value;
+        let principal_share =
+            (self.total_amount.value / Decimal::from(self.term_months)).round_dp(2);
+
+        let mut remaining = self.total_amount.value;
+        let mut installments = Vec::new();
+        for number in 1..=self.term_months {
+            let interest = remaining
+                .checked_mul(r)
+                .ok_or_else(out_of_range)?
+                .round_dp(2);
+            let principal = if number == self.term_months {
+                remaining
+            } else {
+                match kind {
+                    RepaymentKind::Annuity => (annuity - interest).min(remaining),
+                    RepaymentKind::Differentiated => principal_share.min(remaining),
+                }
+            };
+            remaining -= principal;
+            let payment = principal.checked_add(interest).ok_or_else(out_of_range)?;
+            installments.push(Installment {
+                number,
+                date: self.payment_date(number),
+                payment: money(payment),
+                interest: money(interest),
+                principal: money(principal),
+                remaining: money(remaining),
+            });
+        }
+        Ok(AmortizationSchedule { kind, installments })
+    }
+
+    /// Остаток основного долга на дату
+    ///
+    /// # Errors
+    /// Как у [`CreditExpense::annuity_payment`]
+    pub fn remaining_principal(
+        &self,
+        kind: RepaymentKind,
+        on: NaiveDate,
+    ) -> Result<Money, Error> {
+        Ok(self.schedule(kind)?.remaining_on(self, on))
+    }
+
+    fn payment_date(&self, number: u32) -> NaiveDate {
+        self.start_date
+            .checked_add_months(Months::new(number))
+            .unwrap_or(NaiveDate::MAX)
+    }
+}
+
+impl Expense {
+    /// График и остаток долга, если расход - кредит
+    ///
+    /// # Errors
+    /// Как у [`CreditExpense::annuity_payment`]
+    pub fn credit_payoff(
+        &self,
+        kind: RepaymentKind,
+        on: NaiveDate,
+    ) -> Result<Option<CreditPayoff>, Error> {
+        let ExpenseKind::Credit(credit) = &self.kind else {
+            return Ok(None);
+        };
+        let schedule = credit.schedule(kind)?;
+        Ok(Some(CreditPayoff {
+            name: self.name.clone(),
+            monthly_payment: credit.monthly_payment,
+            expected_payment: credit.annuity_payment()?,
+            payment_matches_terms: credit.payment_matches_terms()?,
+            remaining_principal: schedule.remaining_on(credit, on),
+            schedule,
+        }))
+    }
+}
+
+fn out_of_range() -> Error {
+    Error::InvalidCredit(CreditValidationError::TermsOutOfRange)
+}
@@ -3,8 +3,9 @@ use std::{
     str::FromStr,
 };
 
-use chrono::NaiveDate;
+use chrono::{Datelike, Months, NaiveDate};
 use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::{
@@ -12,11 +13,20 @@ use crate::{
     planning::Error,
 };
 
+/// Самый длинный срок кредита: 50 лет
+pub const MAX_TERM_MONTHS: u32 = 600;
+/// Самая высокая годовая ставка, в процентах
+const MAX_INTEREST_RATE: Decimal = dec!(1000);
+
 #[derive(Debug, PartialEq)]
 pub enum CreditValidationError {
     ZeroTermMonths,
+    TooLongTerm,
+    InterestRateOutOfRange,
     NonPositivePayment,
     NonPositiveAmount,
+    /// Платёж по таким сумме, ставке и сроку не помещается в Decimal
+    TermsOutOfRange,
 }
 
 impl Display for CreditValidationError {
@@ -25,12 +35,43 @@ impl Display for CreditValidationError {
             CreditValidationError::ZeroTermMonths => {
                 write!(f, "term months must be > 0")
             }
+            CreditValidationError::TooLongTerm => {
+                write!(f, "term months must be <= {MAX_TERM_MONTHS}")
+            }
+            CreditValidationError::InterestRateOutOfRange => {
+                write!(
+                    f,
+                    "interest rate must be between 0% and {MAX_INTEREST_RATE}%"
+                )
+            }
             CreditValidationError::NonPositivePayment => {
                 write!(f, "monthly payment must be > 0")
             }
             CreditValidationError::NonPositiveAmount => {
                 write!(f, "total amount must be > 0")
             }
+            CreditValidationError::TermsOutOfRange => {
+                write!(f, "payment is too large for these terms")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GoalValidationError {
+    NonPositiveTarget,
+    DeadlineBeforeStart,
+}
+
+impl Display for GoalValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoalValidationError::NonPositiveTarget => {
+                write!(f, "target amount must be > 0")
+            }
+            GoalValidationError::DeadlineBeforeStart => {
+                write!(f, "deadline must not be before start date")
+            }
         }
     }
 }
@@ -116,6 +157,16 @@ impl CreditExpense {
         if term_months == 0 {
             return Err(Error::InvalidCredit(CreditValidationError::ZeroTermMonths));
         }
+        if term_months > MAX_TERM_MONTHS {
+            return Err(Error::InvalidCredit(CreditValidationError::TooLongTerm));
+        }
+        if interest_rate < Percentage::ZERO
+            || interest_rate > Percentage::from(MAX_INTEREST_RATE)
+        {
+            return Err(Error::InvalidCredit(
+                CreditValidationError::InterestRateOutOfRange,
+            ));
+        }
         if monthly_payment.value <= Decimal::ZERO {
             return Err(Error::InvalidCredit(
                 CreditValidationError::NonPositivePayment,
@@ -126,13 +177,15 @@ impl CreditExpense {
                 CreditValidationError::NonPositiveAmount,
             ));
         }
-        Ok(Self {
+        let credit = Self {
             monthly_payment,
             total_amount,
             interest_rate,
             term_months,
             start_date,
-        })
+        };
+        credit.annuity_payment()?;
+        Ok(credit)
     }
 
     pub fn value(&self) -> ExpenseValue {
@@ -142,11 +195,132 @@ impl CreditExpense {
     }
 }
 
+/// Накопление к сроку: конверт, который нужно наполнить до `target` к `deadline`.
+#[non_exhaustive]
+#[derive(PartialEq, Debug, Clone, Eq, Hash, Serialize)]
+pub struct GoalExpense {
+    pub target: Money,
+    pub start_date: NaiveDate,
+    pub deadline: NaiveDate,
+}
+
+impl<'de> Deserialize<'de> for GoalExpense {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            target: Money,
+            start_date: NaiveDate,
+            deadline: NaiveDate,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        GoalExpense::new(raw.target, raw.start_date, raw.deadline)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Прогресс накопления на дату
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub name: String,
+    pub target: Money,
+    pub saved: Money,
+    pub percent: Percentage,
+    pub deadline: NaiveDate,
+    /// Сколько нужно откладывать в месяц, чтобы успеть к сроку
+    pub monthly_contribution: Money,
+    /// Когда цель будет достигнута при текущем темпе накопления.
+    /// `None`, если накоплений ещё не было.
+    pub projected_date: Option<NaiveDate>,
+}
+
+impl GoalExpense {
+    pub fn new(
+        target: Money,
+        start_date: NaiveDate,
+        deadline: NaiveDate,
+    ) -> Result<Self, Error> {
+        if target.value <= Decimal::ZERO {
+            return Err(Error::InvalidGoal(GoalValidationError::NonPositiveTarget));
+        }
+        if deadline < start_date {
+            return Err(Error::InvalidGoal(GoalValidationError::DeadlineBeforeStart));
+        }
+        Ok(Self {
+            target,
+            start_date,
+            deadline,
+        })
+    }
+
+    /// Плановый ежемесячный взнос: цель, поделённая на все месяцы накопления
+    pub fn value(&self) -> ExpenseValue {
+        ExpenseValue::MONEY {
+            value: self.target / i64::from(months(self.start_date, self.deadline)),
+        }
+    }
+
+    /// Взнос на месяц даты `on`, чтобы с учётом уже накопленного успеть к сроку.
+    /// После срока весь остаток нужен сразу.
+    pub fn monthly_contribution(&self, saved: Money, on: NaiveDate) -> Money {
+        let remaining = self.remaining(saved);
+        let months_left = months(on, self.deadline).max(1);
+        remaining / i64::from(months_left)
+    }
+
+    pub fn progress(&self, name: &str, saved: Money, on: NaiveDate) -> GoalProgress {
+        let remaining = self.remaining(saved);
+        let projected_date = if remaining.value.is_zero() {
+            Some(on)
+        } else if saved.value > Decimal::ZERO {
+            // Средний темп за полные месяцы до `on`; взнос текущего месяца
+            // ещё впереди и считается первым из нужных
+            let elapsed = Decimal::from(months_between(self.start_date, on).max(1));
+            let months_needed = (remaining.value * elapsed / saved.value).ceil();
+            u32::try_from(months_needed)
+                .ok()
+                .and_then(|m| on.checked_add_months(Months::new(m.saturating_sub(1))))
+        } else {
+            None
+        };
+        GoalProgress {
+            name: name.to_string(),
+            target: self.target,
+            saved,
+            percent: Percentage::of(saved.value, self.target.value),
+            deadline: self.deadline,
+            monthly_contribution: self.monthly_contribution(saved, on),
+            projected_date,
+        }
+    }
+
+    fn remaining(&self, saved: Money) -> Money {
+        let remaining = self.target.value - saved.value;
+        Money::new(remaining.max(Decimal::ZERO), self.target.currency)
+    }
+}
+
+/// Число месяцев накопления с месяца `from` по месяц `to` включительно,
+/// не меньше одного
+fn months(from: NaiveDate, to: NaiveDate) -> u32 {
+    months_between(from, to) + 1
+}
+
+/// Сколько месяцев прошло с месяца `from` до месяца `to`, не считая `to`
+fn months_between(from: NaiveDate, to: NaiveDate) -> u32 {
+    let diff = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    u32::try_from(diff).unwrap_or(0)
+}
+
 #[derive(PartialEq, Debug, Clone, Eq, Hash, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ExpenseKind {
     Envelope { value: ExpenseValue },
     Credit(CreditExpense),
+    Goal(GoalExpense),
 }
 
 impl ExpenseKind {
@@ -154,6 +328,7 @@ impl ExpenseKind {
         match self {
             ExpenseKind::Envelope { value } => value.clone(),
             ExpenseKind::Credit(credit) => credit.value(),
+            ExpenseKind::Goal(goal) => goal.value(),
         }
     }
 }
@@ -163,6 +338,16 @@ pub struct Expense {
     pub name: String,
     pub kind: ExpenseKind,
     pub category: Option<String>,
+    /// Приоритет при нехватке дохода: чем меньше число, тем раньше конверт
+    /// наполняется. Используется только в режиме [`DistributionMode::Priority`].
+    ///
+    /// [`DistributionMode::Priority`]: crate::planning::DistributionMode::Priority
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u32>,
+    /// Имена источников дохода, из которых наполняется конверт.
+    /// Пусто - конверт наполняют источники без привязанных расходов.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
 }
 
 impl<'de> Deserialize<'de> for Expense {
@@ -176,6 +361,10 @@ impl<'de> Deserialize<'de> for Expense {
             kind: Option<ExpenseKind>,
             value: Option<ExpenseValue>,
             category: Option<String>,
+            #[serde(default)]
+            priority: Option<u32>,
+            #[serde(default)]
+            sources: Vec<String>,
         }
 
         let raw = Raw::deserialize(deserializer)?;
@@ -194,6 +383,8 @@ impl<'de> Deserialize<'de> for Expense {
             name: raw.name,
             kind,
             category: raw.category,
+            priority: raw.priority,
+            sources: raw.sources,
         })
     }
 }
@@ -208,6 +399,8 @@ impl Expense {
             name,
             kind: ExpenseKind::Envelope { value },
             category,
+            priority: None,
+            sources: vec![],
         }
     }
 
@@ -220,10 +413,57 @@ impl Expense {
             name,
             kind: ExpenseKind::Credit(credit),
             category,
+            priority: None,
+            sources: vec![],
+        }
+    }
+
+    pub fn goal(name: String, goal: GoalExpense, category: Option<String>) -> Self {
+        Self {
+            name,
+            kind: ExpenseKind::Goal(goal),
+            category,
+            priority: None,
+            sources: vec![],
+        }
+    }
+
+    #[must_use]
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    #[must_use]
+    pub fn with_sources(mut self, sources: Vec<String>) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Конверт наполняется только из указанных источников
+    pub fn is_bound(&self) -> bool {
+        !self.sources.is_empty()
+    }
+
+    /// Очередь наполнения в режиме приоритетов. Платёж по кредиту без явного
+    /// приоритета обязателен и идёт первым, прочие конверты без приоритета - последними.
+    pub fn effective_priority(&self) -> u32 {
+        match (self.priority, &self.kind) {
+            (Some(priority), _) => priority,
+            (None, ExpenseKind::Credit(_)) => 0,
+            (None, _) => u32::MAX,
         }
     }
 
     pub fn value(&self) -> ExpenseValue {
         self.kind.value()
     }
+
+    /// Прогресс накопления, если расход - цель
+    pub fn goal_progress(&self, saved: Money, on: NaiveDate) -> Option<GoalProgress> {
+        match &self.kind {
+            ExpenseKind::Goal(goal) => Some(goal.progress(&self.name, saved, on)),
+            _ => None,
+        }
+    }
 }
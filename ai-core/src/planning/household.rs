@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::finance::Percentage;
+
+/// Участник общего плана: пользователь со своими источниками дохода
+/// и согласованными долями в конвертах
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Member {
+    /// Идентификатор пользователя
+    pub id: String,
+    /// Имена источников плана, которые приносит участник
+    pub sources: Vec<String>,
+    /// Доля участника в конверте по его имени. В конверте без согласованной
+    /// доли участники делят остаток пропорционально своему доходу
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shares: BTreeMap<String, Percentage>,
+}
+
+impl Member {
+    pub fn new(id: impl Into<String>, sources: Vec<String>) -> Self {
+        Self {
+            id: id.into(),
+            sources,
+            shares: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_share(
+        mut self,
+        envelope: impl Into<String>,
+        share: Percentage,
+    ) -> Self {
+        self.shares.insert(envelope.into(), share);
+        self
+    }
+
+    pub fn owns(&self, source: &str) -> bool {
+        self.sources.iter().any(|s| s == source)
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::finance::{Money, Percentage};
+use crate::{
+    finance::{Money, Percentage},
+    planning::IncomeSchedule,
+};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -13,11 +16,24 @@ pub enum IncomeKind {
 pub struct IncomeSource {
     pub name: String,
     pub kind: IncomeKind,
+    /// Когда ожидать поступления; без графика источник выбирается вручную
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<IncomeSchedule>,
 }
 
 impl IncomeSource {
     pub fn new(name: String, kind: IncomeKind) -> Self {
-        Self { name, kind }
+        Self {
+            name,
+            kind,
+            schedule: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_schedule(mut self, schedule: IncomeSchedule) -> Self {
+        self.schedule = Some(schedule);
+        self
     }
 
     pub fn net(&self) -> Money {
@@ -40,6 +56,8 @@ impl<'de> Deserialize<'de> for IncomeSource {
             name: String,
             kind: Option<IncomeKind>,
             expected: Option<Money>,
+            #[serde(default)]
+            schedule: Option<IncomeSchedule>,
         }
 
         let raw = Raw::deserialize(deserializer)?;
@@ -57,6 +75,7 @@ impl<'de> Deserialize<'de> for IncomeSource {
         Ok(Self {
             name: raw.name,
             kind,
+            schedule: raw.schedule,
         })
     }
 }
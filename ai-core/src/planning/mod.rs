@@ -1,5 +1,8 @@
+mod amortization;
 mod expense;
+mod household;
 mod income;
+mod schedule;
 
 use std::{
     collections::{BTreeMap, HashMap},
@@ -7,22 +10,72 @@ use std::{
     ops::Deref,
 };
 
-use crate::finance::Percentage;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
 
+use crate::finance::{Currency, ExchangeError, ExchangeRates, Money, Percentage};
+
+pub use amortization::{
+    AmortizationSchedule,
+    CreditPayoff,
+    Installment,
+    RepaymentKind,
+};
 pub use expense::{
     CreditExpense,
     CreditValidationError,
     Expense,
     ExpenseKind,
     ExpenseValue,
+    GoalExpense,
+    GoalProgress,
+    GoalValidationError,
+    MAX_TERM_MONTHS,
 };
+pub use household::Member;
 pub use income::{IncomeKind, IncomeSource};
+pub use schedule::{
+    ExpectedIncome,
+    IncomeSchedule,
+    IncomeStatus,
+    MATCH_WINDOW_DAYS,
+    ScheduleValidationError,
+    month_bounds,
+    source_for_date,
+    track_incomes,
+};
+
+/// Допустимое расхождение суммы долей участников со 100%, в процентных пунктах
+const SHARES_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 1);
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
     EmptyPlan,
     TooBigExpenses,
     InvalidCredit(CreditValidationError),
+    InvalidGoal(GoalValidationError),
+    InvalidSchedule(ScheduleValidationError),
+    MissingExchangeRate(Currency),
+    UnknownSurplusEnvelope(String),
+    TooBigSurplus,
+    UnknownBindingSource(String),
+    TooBigSourceExpenses(String),
+    DuplicateMember(String),
+    UnknownMemberSource(String),
+    SourceWithoutMember(String),
+    SourceOfSeveralMembers(String),
+    UnknownShareEnvelope(String),
+    InvalidShares(String),
+}
+
+impl From<ExchangeError> for Error {
+    fn from(e: ExchangeError) -> Self {
+        match e {
+            ExchangeError::MissingRate(currency) => {
+                Error::MissingExchangeRate(currency)
+            }
+        }
+    }
 }
 
 impl Display for Error {
@@ -31,6 +84,73 @@ impl Display for Error {
             Error::EmptyPlan => write!(f, "empty plan"),
             Error::TooBigExpenses => write!(f, "expenses exceed income"),
             Error::InvalidCredit(e) => write!(f, "invalid credit: {e}"),
+            Error::InvalidGoal(e) => write!(f, "invalid goal: {e}"),
+            Error::InvalidSchedule(e) => write!(f, "invalid income schedule: {e}"),
+            Error::MissingExchangeRate(c) => {
+                write!(f, "missing exchange rate for {c:?}")
+            }
+            Error::UnknownSurplusEnvelope(name) => {
+                write!(f, "surplus rule targets unknown envelope {name}")
+            }
+            Error::TooBigSurplus => write!(f, "surplus shares exceed 100%"),
+            Error::UnknownBindingSource(name) => {
+                write!(f, "expense is bound to unknown source {name}")
+            }
+            Error::TooBigSourceExpenses(name) => {
+                write!(f, "expenses bound to {name} exceed its income")
+            }
+            Error::DuplicateMember(id) => write!(f, "member {id} is listed twice"),
+            Error::UnknownMemberSource(name) => {
+                write!(f, "member brings unknown source {name}")
+            }
+            Error::SourceWithoutMember(name) => {
+                write!(f, "source {name} does not belong to any member")
+            }
+            Error::SourceOfSeveralMembers(name) => {
+                write!(f, "source {name} belongs to several members")
+            }
+            Error::UnknownShareEnvelope(name) => {
+                write!(f, "member share targets unknown envelope {name}")
+            }
+            Error::InvalidShares(name) => {
+                write!(f, "member shares of {name} must add up to 100%")
+            }
+        }
+    }
+}
+
+/// Как делить доход, которого меньше, чем запланировано
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributionMode {
+    /// Все конверты урезаются в одной пропорции
+    #[default]
+    Proportional,
+    /// Конверты наполняются по очереди приоритетов, нехватка достаётся последним
+    Priority,
+}
+
+impl DistributionMode {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Правило для нераспределённого остатка: доля `share` остатка уходит в конверт
+/// `envelope`. Правила применяются после основного распределения.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct SurplusRule {
+    pub envelope: String,
+    pub share: Percentage,
+}
+
+impl SurplusRule {
+    pub fn new(envelope: impl Into<String>, share: Percentage) -> Self {
+        Self {
+            envelope: envelope.into(),
+            share,
         }
     }
 }
@@ -40,6 +160,14 @@ pub struct DistributionWeights {
     pub sources: Vec<IncomeSource>,
     pub budget: HashMap<Expense, Percentage>,
     pub rest: Percentage,
+    #[serde(default)]
+    pub rates: ExchangeRates,
+    #[serde(default)]
+    pub mode: DistributionMode,
+    #[serde(default)]
+    pub surplus: Vec<SurplusRule>,
+    #[serde(default)]
+    pub members: Vec<Member>,
 }
 
 impl Debug for DistributionWeights {
@@ -60,6 +188,10 @@ impl Clone for DistributionWeights {
             sources: self.sources.clone(),
             budget: self.budget.clone(),
             rest: self.rest.clone(),
+            rates: self.rates.clone(),
+            mode: self.mode,
+            surplus: self.surplus.clone(),
+            members: self.members.clone(),
         }
     }
 }
@@ -86,6 +218,238 @@ impl DistributionWeights {
         self.sources.contains(source)
     }
 
+    /// Плановый доход в базовой валюте плана
+    pub fn total_income(&self) -> Result<Money, Error> {
+        let total = self
+            .rates
+            .sum(self.sources.iter().map(IncomeSource::net), self.rates.base)?;
+        Ok(total)
+    }
+
+    /// Источники, из которых наполняется конверт: привязанные к нему явно,
+    /// а для непривязанного - все источники без привязок.
+    /// Если свободных источников нет, конверт наполняют все.
+    fn funders(&self, expense: &Expense) -> Vec<&IncomeSource> {
+        if expense.is_bound() {
+            return self
+                .sources
+                .iter()
+                .filter(|s| expense.sources.contains(&s.name))
+                .collect();
+        }
+        let free: Vec<_> = self
+            .sources
+            .iter()
+            .filter(|s| !self.keys().any(|e| e.sources.contains(&s.name)))
+            .collect();
+        if free.is_empty() {
+            self.sources.iter().collect()
+        } else {
+            free
+        }
+    }
+
+    /// Участник общего плана, который приносит источник `source`
+    pub fn member_of(&self, source: &str) -> Option<&Member> {
+        self.members.iter().find(|m| m.owns(source))
+    }
+
+    /// Доля участника `member` в конверте `expense`: согласованная, а без неё -
+    /// остаток после согласованных долей, поделённый между остальными
+    /// участниками пропорционально их доходу в источниках `funders`.
+    fn member_share(
+        &self,
+        member: &Member,
+        expense: &Expense,
+        funders: &[&IncomeSource],
+    ) -> Result<Percentage, Error> {
+        if let Some(share) = member.shares.get(&expense.name) {
+            return Ok(share.clone());
+        }
+        let income_of = |m: &Member| {
+            self.rates.sum(
+                funders.iter().filter(|s| m.owns(&s.name)).map(|s| s.net()),
+                self.rates.base,
+            )
+        };
+        let mut agreed = Percentage::ZERO;
+        let mut free_income = Decimal::ZERO;
+        for m in &self.members {
+            match m.shares.get(&expense.name) {
+                Some(share) => agreed += share.clone(),
+                None => free_income += income_of(m)?.value,
+            }
+        }
+        if free_income.is_zero() {
+            return Ok(Percentage::ZERO);
+        }
+        let own = income_of(member)?;
+        let rest = Percentage::ONE_HUNDRED - agreed;
+        Ok(Percentage::of(rest.apply_to(own.value), free_income))
+    }
+
+    /// Проверяет участников общего плана: каждый источник принадлежит ровно
+    /// одному участнику, а доли каждого конверта в сумме дают 100%.
+    pub(crate) fn check_members(&self) -> Result<(), Error> {
+        if self.members.is_empty() {
+            return Ok(());
+        }
+        for (i, member) in self.members.iter().enumerate() {
+            if self.members[..i].iter().any(|m| m.id == member.id) {
+                return Err(Error::DuplicateMember(member.id.clone()));
+            }
+            for name in &member.sources {
+                if !self.sources.iter().any(|s| &s.name == name) {
+                    return Err(Error::UnknownMemberSource(name.clone()));
+                }
+            }
+            for envelope in member.shares.keys() {
+                if !self.keys().any(|e| &e.name == envelope) {
+                    return Err(Error::UnknownShareEnvelope(envelope.clone()));
+                }
+            }
+        }
+        for source in &self.sources {
+            match self.members.iter().filter(|m| m.owns(&source.name)).count() {
+                0 => return Err(Error::SourceWithoutMember(source.name.clone())),
+                1 => {}
+                _ => return Err(Error::SourceOfSeveralMembers(source.name.clone())),
+            }
+        }
+        for expense in self.keys() {
+            let funders = self.funders(expense);
+            let mut total = Percentage::ZERO;
+            for member in &self.members {
+                let share = self.member_share(member, expense, &funders)?;
+                let has_funders = funders.iter().any(|s| member.owns(&s.name));
+                if !has_funders && share > Percentage::ZERO {
+                    return Err(Error::InvalidShares(expense.name.clone()));
+                }
+                total += share;
+            }
+            // Доли по доходу округляются, так что допускаем погрешность
+            let gap = (Percentage::ONE_HUNDRED - total).apply_to(Decimal::ONE_HUNDRED);
+            if gap.abs() > SHARES_TOLERANCE {
+                return Err(Error::InvalidShares(expense.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Веса для дохода из источника `source`.
+    ///
+    /// Плановая сумма конверта делится между его источниками пропорционально
+    /// их доходу, поэтому доля конверта одинакова для всех его источников.
+    /// В общем плане сумма сначала делится между участниками по их долям,
+    /// а затем - между источниками участника.
+    /// `None` - привязок и участников нет, и веса плана общие для всех источников.
+    pub fn for_source(&self, source: &IncomeSource) -> Result<Option<Self>, Error> {
+        if self.members.is_empty() && !self.keys().any(Expense::is_bound) {
+            return Ok(None);
+        }
+        let total = self.total_income()?;
+        let member = self.member_of(&source.name);
+        let mut budget = HashMap::new();
+        let mut spent = Percentage::ZERO;
+        for (expense, weight) in self {
+            let mut funders = self.funders(expense);
+            if !funders.iter().any(|s| s.name == source.name) {
+                continue;
+            }
+            let mut planned = weight.apply_to(total.value);
+            if let Some(member) = member {
+                planned = self
+                    .member_share(member, expense, &funders)?
+                    .apply_to(planned);
+                if planned.is_zero() {
+                    continue;
+                }
+                funders.retain(|s| member.owns(&s.name));
+            }
+            let funders_income = self
+                .rates
+                .sum(funders.iter().map(|s| s.net()), total.currency)?;
+            let share = if funders_income.value.is_zero() {
+                Percentage::ZERO
+            } else {
+                Percentage::of(planned, funders_income.value)
+            };
+            spent += share.clone();
+            budget.insert(expense.clone(), share);
+        }
+        if spent > Percentage::ONE_HUNDRED {
+            return Err(Error::TooBigSourceExpenses(source.name.clone()));
+        }
+        Ok(Some(Self {
+            sources: vec![source.clone()],
+            budget,
+            rest: Percentage::ONE_HUNDRED - spent,
+            rates: self.rates.clone(),
+            mode: self.mode,
+            surplus: self.surplus.clone(),
+            members: Vec::new(),
+        }))
+    }
+
+    /// Пересчитывает доли целей под уже накопленное к дате `on`.
+    ///
+    /// `saved` - накопления по имени конверта в любой валюте плана.
+    /// Доля цели не может превысить то, что остаётся нераспределённым,
+    /// а освободившаяся доля достигнутой цели уходит в остаток.
+    /// Цели с более ранним сроком получают остаток первыми.
+    pub fn adjust_goals(
+        &self,
+        saved: &HashMap<String, Money>,
+        on: NaiveDate,
+    ) -> Result<Self, Error> {
+        let total = self.total_income()?;
+        let mut budget = self.budget.clone();
+        let mut rest = self.rest.clone();
+        let mut goals: Vec<_> = self
+            .budget
+            .keys()
+            .filter_map(|expense| match &expense.kind {
+                ExpenseKind::Goal(goal) => Some((expense, goal)),
+                _ => None,
+            })
+            .collect();
+        goals.sort_by_key(|(expense, goal)| (goal.deadline, &expense.name));
+        for (expense, goal) in goals {
+            let Some(weight) = budget.get_mut(expense) else {
+                continue;
+            };
+            let saved = match saved.get(&expense.name) {
+                Some(money) => self.rates.convert(*money, goal.target.currency)?,
+                None => Money::new(Decimal::ZERO, goal.target.currency),
+            };
+            let contribution = self
+                .rates
+                .convert(goal.monthly_contribution(saved, on), total.currency)?;
+            let available = rest.clone() + weight.clone();
+            let needed = if total.value.is_zero() {
+                Percentage::ZERO
+            } else {
+                Percentage::of(contribution.value, total.value)
+            };
+            let adjusted = if needed > available {
+                available.clone()
+            } else {
+                needed
+            };
+            rest = available - adjusted.clone();
+            *weight = adjusted;
+        }
+        Ok(Self {
+            sources: self.sources.clone(),
+            budget,
+            rest,
+            rates: self.rates.clone(),
+            mode: self.mode,
+            surplus: self.surplus.clone(),
+            members: self.members.clone(),
+        })
+    }
+
     /// Группирует расходы по категориям
     pub fn categories(&self) -> impl Iterator<Item = (String, Vec<&Expense>)> {
         let mut sorted_expenses: Vec<_> = self.budget.keys().collect();
@@ -0,0 +1,265 @@
+//! График поступлений по источнику дохода.
+//!
+//! Ожидаемые даты считаются внутри календарного месяца: по числам месяца
+//! (число больше длины месяца сдвигается на последний день) или с шагом в
+//! несколько недель от опорной даты. Фактические доходы сопоставляются с
+//! ожидаемыми по ближайшей дате.
+
+use std::fmt::{Display, Formatter};
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    distribute::Income,
+    finance::Money,
+    planning::{Error, IncomeSource},
+};
+
+/// Насколько фактическая дата может отстоять от ожидаемой, чтобы источник
+/// определился по дате дохода
+pub const MATCH_WINDOW_DAYS: u64 = 5;
+
+#[derive(Debug, PartialEq)]
+pub enum ScheduleValidationError {
+    NoDays,
+    DayOutOfRange(u32),
+    ZeroWeeks,
+}
+
+impl Display for ScheduleValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleValidationError::NoDays => write!(f, "no days of month"),
+            ScheduleValidationError::DayOutOfRange(day) => {
+                write!(f, "day {day} is out of 1..=31")
+            }
+            ScheduleValidationError::ZeroWeeks => write!(f, "period must be > 0 weeks"),
+        }
+    }
+}
+
+/// Когда ожидать поступление
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IncomeSchedule {
+    /// По числам месяца, например 10 и 25
+    #[non_exhaustive]
+    MonthlyDays { days: Vec<u32> },
+    /// Каждые `weeks` недель, начиная с `start`
+    #[non_exhaustive]
+    EveryWeeks { weeks: u32, start: NaiveDate },
+}
+
+impl<'de> Deserialize<'de> for IncomeSchedule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(tag = "type", rename_all = "snake_case")]
+        enum Raw {
+            MonthlyDays { days: Vec<u32> },
+            EveryWeeks { weeks: u32, start: NaiveDate },
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::MonthlyDays { days } => IncomeSchedule::monthly(days),
+            Raw::EveryWeeks { weeks, start } => {
+                IncomeSchedule::every_weeks(weeks, start)
+            }
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+impl IncomeSchedule {
+    pub fn monthly(mut days: Vec<u32>) -> Result<Self, Error> {
+        if days.is_empty() {
+            return Err(Error::InvalidSchedule(ScheduleValidationError::NoDays));
+        }
+        if let Some(day) = days.iter().find(|d| !(1..=31).contains(*d)) {
+            return Err(Error::InvalidSchedule(
+                ScheduleValidationError::DayOutOfRange(*day),
+            ));
+        }
+        days.sort_unstable();
+        days.dedup();
+        Ok(Self::MonthlyDays { days })
+    }
+
+    pub fn every_weeks(weeks: u32, start: NaiveDate) -> Result<Self, Error> {
+        if weeks == 0 {
+            return Err(Error::InvalidSchedule(ScheduleValidationError::ZeroWeeks));
+        }
+        Ok(Self::EveryWeeks { weeks, start })
+    }
+
+    /// Ожидаемые даты в месяце, которому принадлежит `on`, по возрастанию
+    pub fn dates_in_month(&self, on: NaiveDate) -> Vec<NaiveDate> {
+        let first = on.with_day(1).unwrap_or(on);
+        let last = last_day_of_month(first);
+        match self {
+            IncomeSchedule::MonthlyDays { days } => {
+                let mut dates: Vec<_> = days
+                    .iter()
+                    .filter_map(|d| first.with_day((*d).min(last.day())))
+                    .collect();
+                dates.dedup();
+                dates
+            }
+            IncomeSchedule::EveryWeeks { weeks, start } => {
+                let step = u64::from(*weeks) * 7;
+                // Первая дата графика не раньше начала месяца
+                let mut date = *start;
+                if date < first {
+                    let behind = u64::try_from((first - date).num_days()).unwrap_or(0);
+                    let periods = behind.div_ceil(step);
+                    date = date + Days::new(periods * step);
+                }
+                let mut dates = Vec::new();
+                while date <= last {
+                    dates.push(date);
+                    date = date + Days::new(step);
+                }
+                dates
+            }
+        }
+    }
+
+    /// Ближайшая к `date` ожидаемая дата в соседних месяцах
+    fn nearest(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let months = [
+            date.checked_sub_months(Months::new(1)),
+            Some(date),
+            date.checked_add_months(Months::new(1)),
+        ];
+        months
+            .into_iter()
+            .flatten()
+            .flat_map(|m| self.dates_in_month(m))
+            .min_by_key(|d| (*d - date).num_days().abs())
+    }
+}
+
+fn last_day_of_month(first: NaiveDate) -> NaiveDate {
+    first
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(first)
+}
+
+/// Первый и последний день месяца даты `date`
+pub fn month_bounds(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first = date.with_day(1).unwrap_or(date);
+    (first, last_day_of_month(first))
+}
+
+/// Источник, чья ожидаемая дата ближе всего к `date`.
+/// `None`, если подходящих источников нет или их несколько на одном расстоянии.
+pub fn source_for_date(
+    sources: &[IncomeSource],
+    date: NaiveDate,
+) -> Option<&IncomeSource> {
+    let mut candidates: Vec<(i64, &IncomeSource)> = sources
+        .iter()
+        .filter_map(|source| {
+            let nearest = source.schedule.as_ref()?.nearest(date)?;
+            let distance = (nearest - date).num_days().abs();
+            (distance <= MATCH_WINDOW_DAYS as i64).then_some((distance, source))
+        })
+        .collect();
+    candidates.sort_by_key(|(distance, _)| *distance);
+    match candidates.as_slice() {
+        [(best, source), rest @ ..] if rest.first().is_none_or(|(d, _)| d > best) => {
+            Some(source)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncomeStatus {
+    /// Доход получен
+    Arrived,
+    /// Дата прошла, а дохода нет
+    Late,
+    /// Дата ещё не наступила
+    Pending,
+}
+
+/// Ожидаемое поступление и что пришло по факту
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedIncome {
+    pub source: String,
+    pub date: NaiveDate,
+    /// Плановая сумма - `net()` источника
+    pub expected: Money,
+    pub status: IncomeStatus,
+    pub actual: Option<Money>,
+    pub actual_date: Option<NaiveDate>,
+    /// Факт минус план; только если валюты совпадают
+    pub difference: Option<Money>,
+}
+
+/// Ожидаемые в месяце даты `on` поступления по всем источникам с графиком.
+///
+/// Каждый фактический доход закрывает ближайшую ещё не закрытую дату своего
+/// источника; доходы сверх графика не учитываются.
+pub fn track_incomes<'a>(
+    sources: &[IncomeSource],
+    incomes: impl IntoIterator<Item = &'a Income>,
+    on: NaiveDate,
+) -> Vec<ExpectedIncome> {
+    let incomes: Vec<&Income> = incomes
+        .into_iter()
+        .filter(|i| i.date.year() == on.year() && i.date.month() == on.month())
+        .collect();
+    let mut tracked = Vec::new();
+    for source in sources {
+        let Some(schedule) = &source.schedule else {
+            continue;
+        };
+        let expected = source.net();
+        let mut slots: Vec<ExpectedIncome> = schedule
+            .dates_in_month(on)
+            .into_iter()
+            .map(|date| ExpectedIncome {
+                source: source.name.clone(),
+                date,
+                expected,
+                status: if date < on {
+                    IncomeStatus::Late
+                } else {
+                    IncomeStatus::Pending
+                },
+                actual: None,
+                actual_date: None,
+                difference: None,
+            })
+            .collect();
+        let mut actual: Vec<_> = incomes
+            .iter()
+            .filter(|i| i.source.name == source.name)
+            .collect();
+        actual.sort_by_key(|i| i.date);
+        for income in actual {
+            let Some(slot) = slots
+                .iter_mut()
+                .filter(|s| s.actual.is_none())
+                .min_by_key(|s| (s.date - income.date).num_days().abs())
+            else {
+                break;
+            };
+            slot.status = IncomeStatus::Arrived;
+            slot.actual = Some(income.amount);
+            slot.actual_date = Some(income.date);
+            slot.difference = (income.amount.currency == expected.currency)
+                .then(|| income.amount - expected);
+        }
+        tracked.extend(slots);
+    }
+    tracked.sort_by(|a, b| (a.date, &a.source).cmp(&(b.date, &b.source)));
+    tracked
+}
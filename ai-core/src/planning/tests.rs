@@ -2,16 +2,31 @@ use chrono::NaiveDate;
 use rstest::rstest;
 use rust_decimal_macros::dec;
 
-use crate::finance::{Currency, Money, Percentage};
-
-use crate::planning::{
-    CreditExpense,
-    CreditValidationError,
-    Error,
-    Expense,
-    ExpenseValue,
-    IncomeKind,
-    IncomeSource,
+use std::collections::HashMap;
+
+use crate::finance::{Currency, ExchangeRates, Money, Percentage};
+
+use crate::{
+    distribute::Income,
+    plan::Plan,
+    planning::{
+        CreditExpense,
+        CreditValidationError,
+        DistributionWeights,
+        Error,
+        Expense,
+        ExpenseValue,
+        GoalExpense,
+        GoalValidationError,
+        IncomeKind,
+        IncomeSchedule,
+        IncomeSource,
+        IncomeStatus,
+        RepaymentKind,
+        ScheduleValidationError,
+        source_for_date,
+        track_incomes,
+    },
 };
 
 fn make_source(name: &str, kind: IncomeKind) -> IncomeSource {
@@ -127,6 +142,10 @@ fn credit_expense_valid_creation() {
     Money::new_rub(dec!(15000)), Money::new_rub(dec!(0)), 36,
     CreditValidationError::NonPositiveAmount
 )]
+#[case::too_long_term(
+    Money::new_rub(dec!(15000)), Money::new_rub(dec!(500000)), 10000,
+    CreditValidationError::TooLongTerm
+)]
 fn credit_expense_rejects_invalid(
     #[case] payment: Money,
     #[case] total: Money,
@@ -143,6 +162,25 @@ fn credit_expense_rejects_invalid(
     assert_eq!(result, Err(Error::InvalidCredit(expected)));
 }
 
+#[rstest]
+#[case::negative_rate(dec!(-1), 36, CreditValidationError::InterestRateOutOfRange)]
+#[case::too_high_rate(dec!(1001), 36, CreditValidationError::InterestRateOutOfRange)]
+#[case::payment_overflow(dec!(1000), 600, CreditValidationError::TermsOutOfRange)]
+fn credit_expense_rejects_invalid_terms(
+    #[case] rate: rust_decimal::Decimal,
+    #[case] term: u32,
+    #[case] expected: CreditValidationError,
+) {
+    let result = CreditExpense::new(
+        Money::new_rub(dec!(15000)),
+        Money::new_rub(dec!(500000)),
+        Percentage::from(rate),
+        term,
+        default_date(),
+    );
+    assert_eq!(result, Err(Error::InvalidCredit(expected)));
+}
+
 #[test]
 fn credit_expense_value_returns_monthly_payment() {
     let monthly = Money::new_rub(dec!(15000));
@@ -181,6 +219,99 @@ fn expense_value_for_credit_returns_monthly_payment() {
     assert_eq!(expense.value(), ExpenseValue::MONEY { value: monthly });
 }
 
+fn loan(monthly_payment: rust_decimal::Decimal, rate: i64, term: u32) -> CreditExpense {
+    CreditExpense::new(
+        Money::new_rub(monthly_payment),
+        Money::new_rub(dec!(100000)),
+        Percentage::from_int(rate),
+        term,
+        date(2026, 1, 15),
+    )
+    .unwrap()
+}
+
+#[rstest]
+#[case::annuity(RepaymentKind::Annuity)]
+#[case::differentiated(RepaymentKind::Differentiated)]
+fn credit_schedule_repays_principal(#[case] kind: RepaymentKind) {
+    let schedule = loan(dec!(8885), 12, 12).schedule(kind).unwrap();
+    assert_eq!(schedule.installments.len(), 12);
+    let principal = ExchangeRates::default()
+        .sum(
+            schedule.installments.iter().map(|i| i.principal),
+            Currency::RUB,
+        )
+        .unwrap();
+    assert_eq!(principal, Money::new_rub(dec!(100000)));
+    let last = schedule.installments.last().unwrap();
+    assert_eq!(last.remaining, Money::new_rub(dec!(0)));
+    assert_eq!(last.date, date(2027, 1, 15));
+    assert_eq!(
+        schedule.total_paid(),
+        Money::new_rub(dec!(100000)) + schedule.total_interest()
+    );
+}
+
+#[test]
+fn credit_schedule_annuity() {
+    let schedule = loan(dec!(8885), 12, 3)
+        .schedule(RepaymentKind::Annuity)
+        .unwrap();
+    insta::assert_json_snapshot!(schedule);
+}
+
+#[test]
+fn credit_schedule_differentiated() {
+    let schedule = loan(dec!(34000), 12, 3)
+        .schedule(RepaymentKind::Differentiated)
+        .unwrap();
+    insta::assert_json_snapshot!(schedule);
+}
+
+#[test]
+fn credit_schedule_without_interest() {
+    let schedule = loan(dec!(25000), 0, 4)
+        .schedule(RepaymentKind::Annuity)
+        .unwrap();
+    assert!(
+        schedule
+            .installments
+            .iter()
+            .all(|i| i.payment == Money::new_rub(dec!(25000)))
+    );
+    assert_eq!(schedule.total_interest(), Money::new_rub(dec!(0)));
+}
+
+#[rstest]
+#[case::before_first_payment(date(2026, 2, 14), dec!(100000))]
+#[case::on_payment_date(date(2026, 2, 15), dec!(75000))]
+#[case::between_payments(date(2026, 4, 1), dec!(50000))]
+#[case::after_payoff(date(2027, 1, 1), dec!(0))]
+fn credit_remaining_principal(
+    #[case] on: NaiveDate,
+    #[case] expected: rust_decimal::Decimal,
+) {
+    let credit = loan(dec!(25000), 0, 4);
+    assert_eq!(
+        credit
+            .remaining_principal(RepaymentKind::Differentiated, on)
+            .unwrap(),
+        Money::new_rub(expected)
+    );
+}
+
+#[rstest]
+#[case::exact(dec!(8884.88), true)]
+#[case::rounded(dec!(8900), true)]
+#[case::too_low(dec!(8000), false)]
+#[case::too_high(dec!(10000), false)]
+fn credit_payment_matches_terms(
+    #[case] payment: rust_decimal::Decimal,
+    #[case] expected: bool,
+) {
+    assert_eq!(loan(payment, 12, 12).payment_matches_terms(), Ok(expected));
+}
+
 #[test]
 fn serde_envelope_expense_roundtrip() {
     let expense = Expense::envelope(
@@ -224,3 +355,269 @@ fn serde_expense_backward_compat_no_kind() {
         serde_json::from_value(serde_json::to_value(&expense).unwrap()).unwrap();
     assert_eq!(roundtrip, expense);
 }
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn car_goal() -> GoalExpense {
+    GoalExpense::new(
+        Money::new_rub(dec!(1200000)),
+        date(2026, 1, 1),
+        date(2026, 12, 31),
+    )
+    .unwrap()
+}
+
+#[rstest]
+#[case::zero_target(
+    Money::new_rub(dec!(0)), date(2026, 12, 31),
+    GoalValidationError::NonPositiveTarget
+)]
+#[case::deadline_before_start(
+    Money::new_rub(dec!(100)), date(2025, 12, 31),
+    GoalValidationError::DeadlineBeforeStart
+)]
+fn goal_expense_rejects_invalid(
+    #[case] target: Money,
+    #[case] deadline: NaiveDate,
+    #[case] expected: GoalValidationError,
+) {
+    let result = GoalExpense::new(target, date(2026, 1, 1), deadline);
+    assert_eq!(result, Err(Error::InvalidGoal(expected)));
+}
+
+#[test]
+fn goal_value_spreads_target_over_months() {
+    assert_eq!(
+        car_goal().value(),
+        ExpenseValue::MONEY {
+            value: Money::new_rub(dec!(100000)),
+        }
+    );
+}
+
+#[rstest]
+#[case::on_track(dec!(300000), date(2026, 4, 10), dec!(100000))]
+#[case::behind(dec!(100000), date(2026, 4, 10), dec!(122222.22))]
+#[case::last_month(dec!(1100000), date(2026, 12, 1), dec!(100000))]
+#[case::overdue(dec!(1000000), date(2027, 2, 1), dec!(200000))]
+#[case::reached(dec!(1300000), date(2026, 6, 1), dec!(0))]
+fn goal_monthly_contribution(
+    #[case] saved: rust_decimal::Decimal,
+    #[case] on: NaiveDate,
+    #[case] expected: rust_decimal::Decimal,
+) {
+    assert_eq!(
+        car_goal().monthly_contribution(Money::new_rub(saved), on),
+        Money::new_rub(expected)
+    );
+}
+
+#[test]
+fn goal_progress_projects_by_pace() {
+    let expense = Expense::goal("Авто".to_string(), car_goal(), None);
+    let progress = expense
+        .goal_progress(Money::new_rub(dec!(200000)), date(2026, 4, 10))
+        .unwrap();
+    insta::assert_json_snapshot!(progress);
+}
+
+#[test]
+fn goal_on_track_projects_by_deadline() {
+    let goal = GoalExpense::new(
+        Money::new_rub(dec!(120000)),
+        date(2026, 1, 1),
+        date(2026, 12, 31),
+    )
+    .unwrap();
+    let progress =
+        goal.progress("Отпуск", Money::new_rub(dec!(10000)), date(2026, 2, 10));
+    assert_eq!(progress.monthly_contribution, Money::new_rub(dec!(10000)));
+    assert_eq!(progress.projected_date, Some(date(2026, 12, 10)));
+}
+
+#[test]
+fn serde_goal_expense_roundtrip() {
+    let expense = Expense::goal("Авто".to_string(), car_goal(), Some("Цели".into()));
+    let json = serde_json::to_value(&expense).unwrap();
+    insta::assert_json_snapshot!(json);
+    let deserialized: Expense = serde_json::from_value(json).unwrap();
+    assert_eq!(deserialized, expense);
+}
+
+#[test]
+fn adjust_goals_uses_saved_amounts() {
+    let goal = Expense::goal("Авто".to_string(), car_goal(), None);
+    let rent = Expense::envelope(
+        "Аренда".to_string(),
+        ExpenseValue::MONEY {
+            value: Money::new_rub(dec!(50000)),
+        },
+        None,
+    );
+    let plan = Plan::build(
+        &[make_source(
+            "Зарплата",
+            IncomeKind::Other {
+                expected: Money::new_rub(dec!(200000)),
+            },
+        )],
+        &[goal.clone(), rent.clone()],
+    );
+    let weights = DistributionWeights::try_from(plan).unwrap();
+    assert_eq!(weights.get(&goal), Some(&Percentage::HALF));
+    assert_eq!(weights.rest, Percentage::QUARTER);
+
+    // Отстаём от графика: нужно больше, но не больше нераспределённого
+    let behind = HashMap::from([("Авто".to_string(), Money::new_rub(dec!(0)))]);
+    let adjusted = weights.adjust_goals(&behind, date(2026, 10, 1)).unwrap();
+    assert_eq!(adjusted.get(&goal), Some(&Percentage::from_int(75)));
+    assert_eq!(adjusted.get(&rent), Some(&Percentage::QUARTER));
+    assert_eq!(adjusted.rest, Percentage::ZERO);
+
+    // Цель достигнута: её доля уходит в остаток
+    let reached = HashMap::from([("Авто".to_string(), Money::new_rub(dec!(1200000)))]);
+    let adjusted = weights.adjust_goals(&reached, date(2026, 10, 1)).unwrap();
+    assert_eq!(adjusted.get(&goal), Some(&Percentage::ZERO));
+    assert_eq!(adjusted.rest, Percentage::from_int(75));
+}
+
+#[test]
+fn adjust_goals_prefers_earlier_deadline() {
+    let car = Expense::goal("Авто".to_string(), car_goal(), None);
+    let gym = Expense::goal(
+        "Абонемент".to_string(),
+        GoalExpense::new(
+            Money::new_rub(dec!(240000)),
+            date(2026, 1, 1),
+            date(2027, 12, 31),
+        )
+        .unwrap(),
+        None,
+    );
+    let plan = Plan::build(
+        &[make_source(
+            "Зарплата",
+            IncomeKind::Other {
+                expected: Money::new_rub(dec!(200000)),
+            },
+        )],
+        &[gym.clone(), car.clone()],
+    );
+    let weights = DistributionWeights::try_from(plan).unwrap();
+    let adjusted = weights
+        .adjust_goals(&HashMap::new(), date(2026, 10, 1))
+        .unwrap();
+    assert_eq!(adjusted.get(&car), Some(&Percentage::from_int(95)));
+    assert_eq!(adjusted.get(&gym), Some(&Percentage::from_int(5)));
+    assert_eq!(adjusted.rest, Percentage::ZERO);
+}
+
+#[rstest]
+#[case::empty(vec![], ScheduleValidationError::NoDays)]
+#[case::out_of_range(vec![10, 32], ScheduleValidationError::DayOutOfRange(32))]
+fn monthly_schedule_rejects_invalid(
+    #[case] days: Vec<u32>,
+    #[case] expected: ScheduleValidationError,
+) {
+    assert_eq!(
+        IncomeSchedule::monthly(days),
+        Err(Error::InvalidSchedule(expected))
+    );
+}
+
+#[rstest]
+#[case::regular(date(2026, 3, 15), vec![date(2026, 3, 10), date(2026, 3, 31)])]
+#[case::short_month(date(2026, 2, 1), vec![date(2026, 2, 10), date(2026, 2, 28)])]
+fn monthly_schedule_dates(#[case] on: NaiveDate, #[case] expected: Vec<NaiveDate>) {
+    let schedule = IncomeSchedule::monthly(vec![31, 10]).unwrap();
+    assert_eq!(schedule.dates_in_month(on), expected);
+}
+
+#[test]
+fn biweekly_schedule_dates() {
+    let schedule = IncomeSchedule::every_weeks(2, date(2026, 1, 2)).unwrap();
+    assert_eq!(
+        schedule.dates_in_month(date(2026, 2, 10)),
+        vec![date(2026, 2, 13), date(2026, 2, 27)]
+    );
+    assert_eq!(
+        IncomeSchedule::every_weeks(0, date(2026, 1, 2)),
+        Err(Error::InvalidSchedule(ScheduleValidationError::ZeroWeeks))
+    );
+}
+
+fn scheduled_sources() -> Vec<IncomeSource> {
+    let other = |name: &str, amount| {
+        make_source(
+            name,
+            IncomeKind::Other {
+                expected: Money::new_rub(amount),
+            },
+        )
+    };
+    vec![
+        other("Аванс", dec!(40000))
+            .with_schedule(IncomeSchedule::monthly(vec![25]).unwrap()),
+        other("Зарплата", dec!(60000))
+            .with_schedule(IncomeSchedule::monthly(vec![10]).unwrap()),
+        other("Фриланс", dec!(10000)),
+    ]
+}
+
+#[rstest]
+#[case::exact(date(2026, 3, 10), Some("Зарплата"))]
+#[case::early(date(2026, 3, 23), Some("Аванс"))]
+#[case::next_month(date(2026, 3, 29), Some("Аванс"))]
+#[case::too_far(date(2026, 3, 17), None)]
+fn source_picked_by_date(#[case] on: NaiveDate, #[case] expected: Option<&str>) {
+    let sources = scheduled_sources();
+    assert_eq!(
+        source_for_date(&sources, on).map(|s| s.name.as_str()),
+        expected
+    );
+}
+
+#[test]
+fn track_incomes_marks_arrived_and_late() {
+    let sources = scheduled_sources();
+    let salary = Income::new(
+        sources[1].clone(),
+        Money::new_rub(dec!(58000)),
+        date(2026, 3, 11),
+    );
+    let previous = Income::new(
+        sources[0].clone(),
+        Money::new_rub(dec!(40000)),
+        date(2026, 2, 25),
+    );
+
+    let tracked = track_incomes(&sources, [&salary, &previous], date(2026, 3, 20));
+
+    assert_eq!(tracked.len(), 2, "фриланс без графика не ожидается");
+    let salary = &tracked[0];
+    assert_eq!(salary.source, "Зарплата");
+    assert_eq!(salary.status, IncomeStatus::Arrived);
+    assert_eq!(salary.actual_date, Some(date(2026, 3, 11)));
+    assert_eq!(salary.difference, Some(Money::new_rub(dec!(-2000))));
+    let advance = &tracked[1];
+    assert_eq!(advance.source, "Аванс");
+    assert_eq!(advance.status, IncomeStatus::Pending);
+
+    let later = track_incomes(&sources, [], date(2026, 3, 26));
+    assert!(later.iter().all(|e| e.status == IncomeStatus::Late));
+}
+
+#[test]
+fn serde_income_schedule_roundtrip() {
+    let source = scheduled_sources().remove(0);
+    let json = serde_json::to_string(&source).unwrap();
+    assert!(json.contains("monthly_days"));
+    let deserialized: IncomeSource = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, source);
+
+    let invalid = r#"{"name": "x", "expected": {"value": "1", "currency": "RUB"},
+        "schedule": {"type": "every_weeks", "weeks": 0, "start": "2026-01-01"}}"#;
+    assert!(serde_json::from_str::<IncomeSource>(invalid).is_err());
+}
@@ -0,0 +1,369 @@
+//! Прогноз плана на несколько месяцев вперёд.
+//!
+//! Гипотетические доходы распределяются по плану, который действует на их дату,
+//! суммы копятся в конвертах, а платежи по графикам кредитов из конвертов
+//! списываются. Для каждого месяца получаются накопления конвертов и остаток
+//! основного долга по кредитам на его конец.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Months, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    distribute::{Error, Income, distribute},
+    finance::{ExchangeRates, Money},
+    plan::Plan,
+    planning::{
+        DistributionWeights,
+        ExpenseKind,
+        IncomeSource,
+        MAX_TERM_MONTHS,
+        RepaymentKind,
+        month_bounds,
+    },
+};
+
+/// Самый длинный прогноз: в него помещается график самого долгого кредита
+pub const MAX_MONTHS: u32 = MAX_TERM_MONTHS;
+
+/// С даты `date` доходы распределяются по плану `plan`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanChange {
+    pub date: NaiveDate,
+    pub plan: Plan,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Любая дата первого месяца прогноза
+    pub start: NaiveDate,
+    /// Не больше [`MAX_MONTHS`]
+    pub months: u32,
+    /// Источник дохода ищется по имени в плане, действующем на дату дохода
+    #[serde(default)]
+    pub incomes: Vec<Income>,
+    #[serde(default)]
+    pub changes: Vec<PlanChange>,
+    /// Накопления конвертов к началу прогноза
+    #[serde(default)]
+    pub opening: HashMap<String, Money>,
+    #[serde(default)]
+    pub repayment: RepaymentKind,
+}
+
+/// Состояние на конец месяца
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthForecast {
+    /// Первый день месяца
+    pub month: NaiveDate,
+    /// Доходы месяца в базовой валюте плана, действующего на конец месяца
+    pub income: Money,
+    /// Нераспределённый за месяц остаток в той же валюте
+    pub rest: Money,
+    /// Накопления конвертов в валюте конверта
+    pub envelopes: BTreeMap<String, Money>,
+    /// Остаток основного долга по кредитам
+    pub credits: BTreeMap<String, Money>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Forecast {
+    pub months: Vec<MonthForecast>,
+}
+
+/// Доходы, которые ждём по плану: каждый источник приносит `net()` в даты
+/// своего графика, а источник без графика - первого числа месяца.
+/// Считается не больше [`MAX_MONTHS`] месяцев.
+pub fn planned_incomes(
+    sources: &[IncomeSource],
+    start: NaiveDate,
+    months: u32,
+) -> Vec<Income> {
+    let (first, _) = month_bounds(start);
+    let mut incomes = Vec::new();
+    for month in (0..months.min(MAX_MONTHS))
+        .filter_map(|m| first.checked_add_months(Months::new(m)))
+    {
+        for source in sources {
+            let dates = match &source.schedule {
+                Some(schedule) => schedule.dates_in_month(month),
+                None => vec![month],
+            };
+            incomes.extend(
+                dates
+                    .into_iter()
+                    .map(|date| Income::new(source.clone(), source.net(), date)),
+            );
+        }
+    }
+    incomes.sort_by_key(|i| i.date);
+    incomes
+}
+
+/// Прогоняет сценарий по плану `plan` и изменениям из сценария.
+///
+/// # Errors
+/// `UnknownSource` - в действующем на дату дохода плане нет источника с таким именем
+/// `MissingExchangeRate` - не хватает курса для пересчёта дохода или накоплений
+/// `InvalidPlan` - план или одно из изменений нельзя распределить
+/// `ForecastTooLong` - прогноз длиннее [`MAX_MONTHS`]
+pub fn simulate(plan: &Plan, scenario: &Scenario) -> Result<Forecast, Error> {
+    if scenario.months > MAX_MONTHS {
+        return Err(Error::ForecastTooLong(MAX_MONTHS));
+    }
+    let mut changes: Vec<&PlanChange> = scenario.changes.iter().collect();
+    changes.sort_by_key(|c| c.date);
+    let mut plans =
+        vec![(NaiveDate::MIN, DistributionWeights::try_from(plan.clone())?)];
+    for change in changes {
+        plans.push((
+            change.date,
+            DistributionWeights::try_from(change.plan.clone())?,
+        ));
+    }
+    let active = |date: NaiveDate| {
+        plans
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= date)
+            .map(|(_, weights)| weights)
+            .expect("исходный план действует с самого начала")
+    };
+
+    let mut incomes: Vec<&Income> = scenario.incomes.iter().collect();
+    incomes.sort_by_key(|i| i.date);
+    let mut balances = scenario.opening.clone();
+    let (start, _) = month_bounds(scenario.start);
+    let mut forecast = Vec::new();
+
+    for month in
+        (0..scenario.months).filter_map(|m| start.checked_add_months(Months::new(m)))
+    {
+        let (first, last) = month_bounds(month);
+        let at_end = active(last);
+        let currency = at_end.rates.base;
+        let mut income = Money::new(Decimal::ZERO, currency);
+        let mut rest = Money::new(Decimal::ZERO, currency);
+
+        for hypothetical in incomes.iter().filter(|i| i.date >= first && i.date <= last)
+        {
+            let weights = active(hypothetical.date);
+            let source = weights
+                .sources
+                .iter()
+                .find(|s| s.name == hypothetical.source.name)
+                .ok_or(Error::UnknownSource)?;
+            let hypothetical =
+                Income::new(source.clone(), hypothetical.amount, hypothetical.date);
+            let adjusted = weights.adjust_goals(&balances, hypothetical.date)?;
+            let budget = distribute(&adjusted, &hypothetical)?;
+            for entry in budget
+                .no_category
+                .iter()
+                .chain(budget.categories.values().flatten())
+            {
+                deposit(
+                    &mut balances,
+                    &entry.expense.name,
+                    entry.amount,
+                    &weights.rates,
+                )?;
+            }
+            income += at_end.rates.convert(hypothetical.amount, currency)?;
+            rest += at_end.rates.convert(budget.rest, currency)?;
+        }
+
+        let mut credits = BTreeMap::new();
+        for expense in at_end.keys() {
+            let ExpenseKind::Credit(credit) = &expense.kind else {
+                continue;
+            };
+            let schedule = credit.schedule(scenario.repayment)?;
+            for installment in schedule
+                .installments
+                .iter()
+                .filter(|i| i.date >= first && i.date <= last)
+            {
+                let payment = installment.payment;
+                let withdrawal = Money::new(-payment.value, payment.currency);
+                deposit(&mut balances, &expense.name, withdrawal, &at_end.rates)?;
+            }
+            credits.insert(expense.name.clone(), schedule.remaining_on(credit, last));
+        }
+
+        forecast.push(MonthForecast {
+            month: first,
+            income,
+            rest,
+            envelopes: balances.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+            credits,
+        });
+    }
+    Ok(Forecast { months: forecast })
+}
+
+/// Кладёт сумму в конверт в валюте его накоплений
+fn deposit(
+    balances: &mut HashMap<String, Money>,
+    name: &str,
+    amount: Money,
+    rates: &ExchangeRates,
+) -> Result<(), Error> {
+    match balances.get_mut(name) {
+        Some(balance) => *balance += rates.convert(amount, balance.currency)?,
+        None => {
+            balances.insert(name.to_string(), amount);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::{
+        finance::Percentage,
+        planning::{CreditExpense, Expense, ExpenseValue, IncomeKind},
+    };
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn salary() -> IncomeSource {
+        IncomeSource::new(
+            "Зарплата".into(),
+            IncomeKind::Other {
+                expected: Money::new_rub(dec!(100000)),
+            },
+        )
+    }
+
+    fn food(value: Decimal) -> Expense {
+        Expense::envelope(
+            "Еда".into(),
+            ExpenseValue::MONEY {
+                value: Money::new_rub(value),
+            },
+            None,
+        )
+    }
+
+    fn credit() -> CreditExpense {
+        CreditExpense::new(
+            Money::new_rub(dec!(10000)),
+            Money::new_rub(dec!(120000)),
+            Percentage::ZERO,
+            12,
+            date(2026, 1, 15),
+        )
+        .unwrap()
+    }
+
+    fn plan(food_value: Decimal) -> Plan {
+        Plan::build(
+            &[salary()],
+            &[
+                food(food_value),
+                Expense::credit("Ипотека".into(), credit(), Some("Долги".into())),
+            ],
+        )
+    }
+
+    #[test]
+    fn forecast_accumulates_envelopes_and_pays_credits() {
+        let base = plan(dec!(20000));
+        let mut incomes = planned_incomes(&base.sources, date(2026, 2, 10), 2);
+        incomes.push(Income::new(
+            salary(),
+            Money::new_rub(dec!(50000)),
+            date(2026, 3, 20),
+        ));
+        let scenario = Scenario {
+            start: date(2026, 2, 10),
+            months: 2,
+            incomes,
+            // С середины марта на еду закладываем больше
+            changes: vec![PlanChange {
+                date: date(2026, 3, 10),
+                plan: plan(dec!(30000)),
+            }],
+            opening: HashMap::from([("Еда".to_string(), Money::new_rub(dec!(1000)))]),
+            repayment: RepaymentKind::Annuity,
+        };
+
+        let forecast = simulate(&base, &scenario).unwrap();
+
+        let [february, march] = forecast.months.as_slice() else {
+            panic!("ожидали два месяца: {forecast:?}");
+        };
+        assert_eq!(february.month, date(2026, 2, 1));
+        assert_eq!(february.income, Money::new_rub(dec!(100000)));
+        assert_eq!(february.rest, Money::new_rub(dec!(70000)));
+        assert_eq!(february.envelopes["Еда"], Money::new_rub(dec!(21000)));
+        // Взнос ушёл на февральский платёж по графику
+        assert_eq!(february.envelopes["Ипотека"], Money::new_rub(dec!(0)));
+        assert_eq!(
+            february.credits["Ипотека"],
+            credit()
+                .remaining_principal(RepaymentKind::Annuity, date(2026, 2, 28))
+                .unwrap()
+        );
+
+        // Доход 1 марта - по старому плану, премия 20 марта - по новому
+        assert_eq!(march.income, Money::new_rub(dec!(150000)));
+        assert_eq!(march.envelopes["Еда"], Money::new_rub(dec!(56000)));
+        assert_eq!(march.rest, Money::new_rub(dec!(100000)));
+        assert_eq!(march.envelopes["Ипотека"], Money::new_rub(dec!(5000)));
+        assert_eq!(march.credits["Ипотека"], Money::new_rub(dec!(100000)));
+    }
+
+    #[test]
+    fn income_from_unknown_source_fails() {
+        let base = plan(dec!(20000));
+        let freelance = IncomeSource::new(
+            "Фриланс".into(),
+            IncomeKind::Other {
+                expected: Money::new_rub(dec!(10000)),
+            },
+        );
+        let scenario = Scenario {
+            start: date(2026, 2, 1),
+            months: 1,
+            incomes: vec![Income::new(
+                freelance,
+                Money::new_rub(dec!(10000)),
+                date(2026, 2, 5),
+            )],
+            changes: vec![],
+            opening: HashMap::new(),
+            repayment: RepaymentKind::default(),
+        };
+
+        assert_eq!(simulate(&base, &scenario), Err(Error::UnknownSource));
+    }
+
+    #[test]
+    fn forecast_length_is_limited() {
+        let base = plan(dec!(20000));
+        let scenario = Scenario {
+            start: date(2026, 2, 1),
+            months: u32::MAX,
+            incomes: vec![],
+            changes: vec![],
+            opening: HashMap::new(),
+            repayment: RepaymentKind::default(),
+        };
+
+        assert_eq!(
+            simulate(&base, &scenario),
+            Err(Error::ForecastTooLong(MAX_MONTHS))
+        );
+        let planned = planned_incomes(&base.sources, date(2026, 2, 1), u32::MAX);
+        assert_eq!(planned.len(), MAX_MONTHS as usize);
+    }
+}
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS ledger_entries (
+id TEXT PRIMARY KEY,
+kind TEXT NOT NULL,
+entry_date TEXT NOT NULL,
+content TEXT NOT NULL,
+created_at TEXT NOT NULL DEFAULT (datetime ('now'))
+) ;
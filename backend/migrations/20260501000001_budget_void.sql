@@ -0,0 +1,2 @@
+ALTER TABLE budgets ADD COLUMN voided_at TEXT ;
+ALTER TABLE budgets ADD COLUMN void_reason TEXT ;
@@ -0,0 +1,2 @@
+ALTER TABLE budgets ADD COLUMN plan_id TEXT ;
+ALTER TABLE budgets ADD COLUMN plan_version INTEGER ;
@@ -0,0 +1,6 @@
+ALTER TABLE plans ADD COLUMN is_active INTEGER NOT NULL DEFAULT 0 ;
+UPDATE plans SET name = 'Основной' WHERE name IS NULL ;
+UPDATE plans SET is_active = 1 WHERE rowid IN (
+SELECT max(rowid) FROM plans WHERE status = 'active' GROUP BY user_id
+) ;
+CREATE UNIQUE INDEX IF NOT EXISTS plans_one_active ON plans (user_id) WHERE is_active = 1 ;
@@ -0,0 +1,18 @@
+ALTER TABLE budgets ADD COLUMN user_id TEXT NOT NULL DEFAULT 'default' ;
+ALTER TABLE ledger_entries ADD COLUMN user_id TEXT NOT NULL DEFAULT 'default' ;
+CREATE INDEX IF NOT EXISTS budgets_user ON budgets (user_id, id) ;
+CREATE INDEX IF NOT EXISTS ledger_entries_user ON ledger_entries (user_id, id) ;
+
+CREATE TABLE IF NOT EXISTS accounts (
+id TEXT PRIMARY KEY,
+login TEXT NOT NULL UNIQUE,
+password_hash TEXT NOT NULL,
+created_at TEXT NOT NULL DEFAULT (datetime ('now'))
+) ;
+
+CREATE TABLE IF NOT EXISTS sessions (
+token_hash TEXT PRIMARY KEY,
+user_id TEXT NOT NULL REFERENCES accounts (id),
+expires_at TEXT NOT NULL,
+created_at TEXT NOT NULL DEFAULT (datetime ('now'))
+) ;
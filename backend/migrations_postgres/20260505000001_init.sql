@@ -0,0 +1,61 @@
+CREATE TABLE IF NOT EXISTS plans (
+id TEXT PRIMARY KEY,
+user_id TEXT NOT NULL DEFAULT 'default',
+name TEXT NOT NULL,
+status TEXT NOT NULL DEFAULT 'active',
+version BIGINT NOT NULL DEFAULT 1,
+content TEXT NOT NULL,
+is_active BOOLEAN NOT NULL DEFAULT FALSE,
+created_at TIMESTAMPTZ NOT NULL DEFAULT now (),
+updated_at TIMESTAMPTZ NOT NULL DEFAULT now (),
+deleted_at TIMESTAMPTZ
+) ;
+CREATE UNIQUE INDEX IF NOT EXISTS plans_one_active ON plans (user_id) WHERE is_active ;
+
+CREATE TABLE IF NOT EXISTS plan_events (
+id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+plan_id TEXT NOT NULL REFERENCES plans (id),
+version BIGINT NOT NULL,
+action TEXT NOT NULL,
+content TEXT,
+created_at TIMESTAMPTZ NOT NULL DEFAULT now ()
+) ;
+
+-- Идентификаторы сравниваются побайтово: по ним идёт пагинация
+CREATE TABLE IF NOT EXISTS budgets (
+id TEXT COLLATE "C" PRIMARY KEY,
+user_id TEXT NOT NULL DEFAULT 'default',
+source TEXT NOT NULL,
+income_date DATE NOT NULL,
+content TEXT NOT NULL,
+plan_id TEXT,
+plan_version BIGINT,
+voided_at DATE,
+void_reason TEXT,
+created_at TIMESTAMPTZ NOT NULL DEFAULT now ()
+) ;
+CREATE INDEX IF NOT EXISTS budgets_user ON budgets (user_id, id) ;
+
+CREATE TABLE IF NOT EXISTS ledger_entries (
+id TEXT COLLATE "C" PRIMARY KEY,
+user_id TEXT NOT NULL DEFAULT 'default',
+kind TEXT NOT NULL,
+entry_date DATE NOT NULL,
+content TEXT NOT NULL,
+created_at TIMESTAMPTZ NOT NULL DEFAULT now ()
+) ;
+CREATE INDEX IF NOT EXISTS ledger_entries_user ON ledger_entries (user_id, id) ;
+
+CREATE TABLE IF NOT EXISTS accounts (
+id TEXT PRIMARY KEY,
+login TEXT NOT NULL UNIQUE,
+password_hash TEXT NOT NULL,
+created_at TIMESTAMPTZ NOT NULL DEFAULT now ()
+) ;
+
+CREATE TABLE IF NOT EXISTS sessions (
+token_hash TEXT PRIMARY KEY,
+user_id TEXT NOT NULL REFERENCES accounts (id),
+expires_at TIMESTAMPTZ NOT NULL,
+created_at TIMESTAMPTZ NOT NULL DEFAULT now ()
+) ;
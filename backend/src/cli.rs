@@ -1,19 +1,41 @@
 use crate::interfaces::{
-    presentation::{budget_to_tree, plan_to_tree},
+    bank_statement::{StatementFormat, parse_statement},
+    csv_import::CsvProfile,
+    history_export::{HistoryFormat, HistoryTable},
+    presentation::{
+        budget_to_tree,
+        credits_to_tree,
+        expected_incomes_to_tree,
+        forecast_to_tree,
+        plan_report_to_tree,
+        plan_to_tree,
+        redistribution_to_tree,
+    },
     tree::to_text,
+    tui,
 };
 use ai_app::{
-    api::CoreApi,
-    storage::{BudgetId, CoreRepo},
+    api::{CoreApi, DistributedIncome, Error as AppError},
+    archive::{ConflictKind, ImportMode},
+    auth::AuthApi,
+    statement::{MatchRule, SkipReason},
+    storage::{AccountRepo, BudgetId, CoreRepo, DEFAULT_USER_ID, UserId},
 };
 use ai_core::{
     distribute::Income,
     finance::Money,
-    planning::{DistributionWeights, IncomeSource},
+    planning::{DistributionWeights, IncomeSource, RepaymentKind, source_for_date},
+    simulate::{PlanChange, Scenario, planned_incomes},
 };
-use clap::{Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
 use rust_decimal::Decimal;
-use std::{io, io::Write, path::PathBuf};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
 use thiserror::Error;
 
 #[derive(Parser, Debug)]
@@ -37,6 +59,17 @@ pub enum Commands {
     #[command(flatten)]
     Budget(BudgetCommand),
 
+    #[command(flatten)]
+    Archive(ArchiveCommand),
+
+    /// Управление планами: список, переименование, копирование, активация
+    #[clap(subcommand)]
+    Plans(PlansCommand),
+
+    /// Учётные записи для входа в web-интерфейс
+    #[clap(subcommand)]
+    Users(UsersCommand),
+
     /// Запустить web-интерфейс
     Web {
         /// Host (overrides config.json)
@@ -53,6 +86,17 @@ pub enum Commands {
         #[clap(long)]
         file: PathBuf,
     },
+
+    /// Импорт истории распределений из CSV по профилю колонок
+    ImportCsv {
+        file: PathBuf,
+        /// JSON-профиль сопоставления колонок; по умолчанию раскладка Excel
+        #[clap(long)]
+        profile: Option<PathBuf>,
+        /// Только показать, что будет загружено, и строки с ошибками
+        #[clap(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -61,6 +105,9 @@ pub enum BudgetCommand {
     #[clap(alias = "income")]
     AddIncome {
         amount: Decimal,
+        /// Дата поступления (по умолчанию сегодня); по ней выбирается источник
+        #[clap(long)]
+        date: Option<NaiveDate>,
         #[clap(long)]
         dry_run: bool,
     },
@@ -71,6 +118,178 @@ pub enum BudgetCommand {
     /// Показать бюджет по id
     #[clap(alias = "show")]
     ShowBudget { id: String },
+
+    /// Аннулировать сохранённый бюджет: он останется в истории,
+    /// но перестанет пополнять конверты
+    #[clap(alias = "void")]
+    VoidBudget {
+        id: String,
+        /// Причина аннулирования
+        #[clap(long)]
+        reason: Option<String>,
+    },
+
+    /// Заново распределить доход бюджета по версии плана и показать разницу
+    Redistribute {
+        id: String,
+        /// Версия плана (по умолчанию текущий план)
+        #[clap(long)]
+        version: Option<i64>,
+    },
+
+    /// Показать ожидаемые в этом месяце доходы и что из них пришло
+    Incomes {
+        /// Любая дата нужного месяца (по умолчанию сегодня)
+        #[clap(long)]
+        date: Option<NaiveDate>,
+    },
+
+    /// Показать графики погашения кредитов и остаток долга
+    Credits {
+        /// Способ погашения
+        #[clap(long, value_enum, default_value_t = Repayment::Annuity)]
+        kind: Repayment,
+        /// Дата, на которую считается остаток (по умолчанию сегодня)
+        #[clap(long)]
+        date: Option<NaiveDate>,
+    },
+
+    /// Сравнить план с тем, что конверты получили за месяц
+    Report {
+        /// Любая дата нужного месяца (по умолчанию сегодня)
+        #[clap(long)]
+        date: Option<NaiveDate>,
+    },
+
+    /// Спрогнозировать накопления конвертов и остаток долгов по плану
+    Simulate {
+        /// Сколько месяцев прогнозировать
+        #[clap(long, default_value_t = 12)]
+        months: u32,
+        /// Любая дата первого месяца (по умолчанию сегодня)
+        #[clap(long)]
+        date: Option<NaiveDate>,
+        /// JSON-файл сценария: дополнительные доходы `incomes` и изменения
+        /// плана `changes` с датой, с которой они действуют
+        #[clap(long)]
+        scenario: Option<PathBuf>,
+        /// Не добавлять доходы по плану - только доходы из сценария
+        #[clap(long)]
+        no_planned: bool,
+        /// Способ погашения кредитов
+        #[clap(long, value_enum, default_value_t = Repayment::Annuity)]
+        kind: Repayment,
+    },
+
+    /// Полноэкранный интерфейс: план, история распределений и ввод дохода
+    Tui,
+
+    /// Найти поступления в банковской выписке и распределить их
+    /// после подтверждения
+    ImportStatement {
+        file: PathBuf,
+        /// Формат выписки (по умолчанию по расширению файла)
+        #[clap(long, value_enum)]
+        format: Option<StatementFormat>,
+        /// JSON-профиль колонок CSV-выписки; колонка `source` - плательщик
+        #[clap(long)]
+        profile: Option<PathBuf>,
+        /// JSON-файл с правилами сопоставления платежей с источниками дохода
+        #[clap(long)]
+        rules: Option<PathBuf>,
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ArchiveCommand {
+    /// Выгрузить планы с историей, распределения и операции в JSON-архив
+    Export {
+        /// Файл архива (по умолчанию архив выводится в stdout)
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Загрузить архив, выгруженный командой export
+    Import {
+        file: PathBuf,
+        /// Что делать с уже сохранёнными данными
+        #[clap(long, value_enum, default_value_t = ImportStrategy::Merge)]
+        mode: ImportStrategy,
+    },
+
+    /// Выгрузить историю распределений таблицей: строка на доход,
+    /// колонка на конверт
+    ExportHistory {
+        /// Первый день периода, например 2026-01-01
+        #[clap(long)]
+        from: Option<NaiveDate>,
+        /// Последний день периода
+        #[clap(long)]
+        to: Option<NaiveDate>,
+        #[clap(long, value_enum, default_value_t = HistoryFormat::Csv)]
+        format: HistoryFormat,
+        /// Файл таблицы (по умолчанию CSV выводится в stdout)
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PlansCommand {
+    /// Показать все планы; активный отмечен звёздочкой
+    List,
+
+    /// Переименовать план
+    Rename { id: String, name: String },
+
+    /// Скопировать план под новым именем; копия создаётся неактивной
+    Clone { id: String, name: String },
+
+    /// Распределять доходы по указанному плану
+    Activate { id: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UsersCommand {
+    /// Завести учётную запись; пароль запрашивается в терминале.
+    /// Первая учётная запись получает уже сохранённые данные
+    Add { login: String },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Repayment {
+    /// Аннуитетный
+    Annuity,
+    /// Дифференцированный
+    Differentiated,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ImportStrategy {
+    /// Добавить недостающее, совпадающие записи оставить прежними
+    Merge,
+    /// Удалить текущие данные и загрузить архив целиком
+    Replace,
+}
+
+impl From<ImportStrategy> for ImportMode {
+    fn from(value: ImportStrategy) -> Self {
+        match value {
+            ImportStrategy::Merge => ImportMode::Merge,
+            ImportStrategy::Replace => ImportMode::Replace,
+        }
+    }
+}
+
+impl From<Repayment> for RepaymentKind {
+    fn from(value: Repayment) -> Self {
+        match value {
+            Repayment::Annuity => RepaymentKind::Annuity,
+            Repayment::Differentiated => RepaymentKind::Differentiated,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -79,17 +298,56 @@ pub enum Error {
     NoPlan,
     #[error("Не удалось записать результат")]
     CantWriteResult,
+    #[error("Укажите файл для выгрузки: --output")]
+    NoOutputFile,
     #[error("Неверный ввод")]
     InvalidInput,
+    #[error("Отменено")]
+    Cancelled,
     #[error("Не удалось распределить бюджет")]
     CantDistribute,
     #[error("Не удалось построить план распределения бюджета")]
     InvalidPlan,
+    #[error("Не удалось аннулировать бюджет: {0}")]
+    CantVoidBudget(String),
+    #[error("Не удалось перераспределить бюджет: {0}")]
+    CantRedistribute(String),
+    #[error("Не удалось изменить план: {0}")]
+    CantChangePlan(String),
+    #[error("Пароли не совпадают")]
+    PasswordMismatch,
+    #[error("Не удалось создать учётную запись: {0}")]
+    CantCreateAccount(String),
+    #[error("Не удалось прочитать архив: {0}")]
+    CantReadArchive(String),
+    #[error("Не удалось загрузить архив: {0}")]
+    CantImport(String),
+    #[error("Не удалось прочитать выписку: {0}")]
+    CantReadStatement(String),
+    #[error("Не удалось построить график погашения: {0}")]
+    CantBuildSchedule(String),
+    #[error("Не удалось построить отчёт: {0}")]
+    CantBuildReport(String),
+    #[error("Не удалось построить прогноз: {0}")]
+    CantSimulate(String),
+    #[error("Прогноз строится не больше чем на {0} месяцев")]
+    ForecastTooLong(u32),
+    #[error("Ошибка терминального интерфейса: {0}")]
+    CantRunTui(String),
+}
+
+/// Сценарий прогноза из файла: доходы сверх плановых и изменения плана
+#[derive(Deserialize, Default)]
+struct ScenarioFile {
+    #[serde(default)]
+    incomes: Vec<Income>,
+    #[serde(default)]
+    changes: Vec<PlanChange>,
 }
 
 fn user_input() -> Result<usize, Error> {
     let mut source_num = String::new();
-    io::stdout().flush().unwrap();
+    io::stdout().flush().map_err(|_| Error::InvalidInput)?;
     io::stdin()
         .read_line(&mut source_num)
         .map_err(|_| Error::InvalidInput)?;
@@ -100,17 +358,192 @@ fn user_input() -> Result<usize, Error> {
 }
 
 #[tracing::instrument(skip(plan))]
-fn choose_source(plan: &DistributionWeights) -> Result<&IncomeSource, Error> {
+fn choose_source(
+    plan: &DistributionWeights,
+    date: NaiveDate,
+) -> Result<&IncomeSource, Error> {
     if plan.sources.len() == 1 {
         return plan.sources.first().ok_or(Error::NoPlan);
     }
+    if let Some(source) = source_for_date(&plan.sources, date) {
+        println!("Источник по графику: {} [{}]", source.name, source.net());
+        return Ok(source);
+    }
     println!("В бюджете указано несколько источников дохода:");
-    for (n, i) in plan.sources.iter().enumerate() {
-        println!("  {n}: {} [{}]", i.name, i.net());
+    if !io::stdin().is_terminal() {
+        // Ввод из скрипта или канала: номер источника читаем строкой
+        for (n, i) in plan.sources.iter().enumerate() {
+            println!("  {n}: {} [{}]", i.name, i.net());
+        }
+        print!("Введите номер источника: ");
+        let input = user_input()?;
+        return plan.sources.get(input).ok_or(Error::InvalidInput);
+    }
+    let picked = tui::pick_source(&plan.sources).map_err(|_| Error::InvalidInput)?;
+    picked
+        .and_then(|n| plan.sources.get(n))
+        .ok_or(Error::Cancelled)
+}
+
+#[tracing::instrument(skip(api, cmd))]
+pub async fn run_plans<R>(api: CoreApi<R>, cmd: PlansCommand) -> Result<(), Error>
+where
+    R: CoreRepo + Clone + Send + Sync + 'static,
+{
+    let user_id: UserId = DEFAULT_USER_ID.to_string();
+    match cmd {
+        PlansCommand::List => {
+            let plans = api.list_plans(&user_id).await;
+            if plans.is_empty() {
+                return Err(Error::NoPlan);
+            }
+            for sp in plans {
+                let mark = if sp.active { "*" } else { " " };
+                println!("{mark} {} {} (v{})", sp.id, sp.name, sp.version);
+            }
+        }
+        PlansCommand::Rename { id, name } => {
+            api.rename_plan(&user_id, &id, name)
+                .await
+                .map_err(|e| Error::CantChangePlan(e.to_string()))?;
+            println!("✏️ План {id} переименован");
+        }
+        PlansCommand::Clone { id, name } => {
+            let new_id = api
+                .clone_plan(&user_id, &id, ai_app::storage::build_id(), name)
+                .await
+                .map_err(|e| Error::CantChangePlan(e.to_string()))?;
+            println!("📄 Копия плана {id} создана с ID: {new_id}");
+        }
+        PlansCommand::Activate { id } => {
+            api.activate_plan(&user_id, &id)
+                .await
+                .map_err(|e| Error::CantChangePlan(e.to_string()))?;
+            println!("✅ План {id} теперь активный");
+        }
     }
-    print!("Введите номер источника: ");
-    let input = user_input()?;
-    plan.sources.get(input).ok_or(Error::InvalidInput)
+    Ok(())
+}
+
+#[tracing::instrument(skip(api, cmd))]
+pub async fn run_archive<R>(api: CoreApi<R>, cmd: ArchiveCommand) -> Result<(), Error>
+where
+    R: CoreRepo + Clone + Send + Sync + 'static,
+{
+    let user_id: UserId = DEFAULT_USER_ID.to_string();
+    match cmd {
+        ArchiveCommand::Export { output } => {
+            let archive = api.export(&user_id, chrono::Utc::now()).await;
+            let json = serde_json::to_string_pretty(&archive)
+                .map_err(|_| Error::CantWriteResult)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json).map_err(|_| Error::CantWriteResult)?;
+                    println!(
+                        "📦 Архив сохранён в {}: планов {}, распределений {}, операций {}",
+                        path.display(),
+                        archive.plans.len(),
+                        archive.budgets.len(),
+                        archive.ledger.len()
+                    );
+                }
+                None => println!("{json}"),
+            }
+        }
+        ArchiveCommand::ExportHistory {
+            from,
+            to,
+            format,
+            output,
+        } => {
+            // XLSX в терминал не выводим
+            if output.is_none() && format == HistoryFormat::Xlsx {
+                return Err(Error::NoOutputFile);
+            }
+            let budgets = api.budget_history(&user_id, from, to).await;
+            let table = HistoryTable::new(&budgets);
+            let content = table.write(format).map_err(|_| Error::CantWriteResult)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, content)
+                        .map_err(|_| Error::CantWriteResult)?;
+                    println!(
+                        "📊 История сохранена в {}: распределений {}, конвертов {}",
+                        path.display(),
+                        table.rows.len(),
+                        table.envelopes.len()
+                    );
+                }
+                None => io::stdout()
+                    .write_all(&content)
+                    .map_err(|_| Error::CantWriteResult)?,
+            }
+        }
+        ArchiveCommand::Import { file, mode } => {
+            let content = std::fs::read_to_string(&file)
+                .map_err(|e| Error::CantReadArchive(e.to_string()))?;
+            let archive = serde_json::from_str(&content)
+                .map_err(|e| Error::CantReadArchive(e.to_string()))?;
+            if let ImportStrategy::Replace = mode {
+                print!(
+                    "Текущие планы, распределения и операции будут удалены. Продолжить? [y/N] "
+                );
+                io::stdout().flush().map_err(|_| Error::InvalidInput)?;
+                let mut input = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .map_err(|_| Error::InvalidInput)?;
+                if input.trim().to_lowercase() != "y" {
+                    println!("Отменено");
+                    return Ok(());
+                }
+            }
+            let report = api
+                .import(&user_id, archive, mode.into())
+                .await
+                .map_err(|e| Error::CantImport(e.to_string()))?;
+            println!(
+                "📥 Загружено: планов {}, распределений {}, операций {}",
+                report.plans, report.budgets, report.ledger
+            );
+            for conflict in &report.conflicts {
+                let kind = match conflict.kind {
+                    ConflictKind::Plan => "план",
+                    ConflictKind::Budget => "распределение",
+                    ConflictKind::LedgerEntry => "операция",
+                };
+                println!("⚠️ Уже есть, пропущено: {kind} {}", conflict.id);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip(auth, cmd))]
+pub async fn run_users<R>(auth: AuthApi<R>, cmd: UsersCommand) -> Result<(), Error>
+where
+    R: AccountRepo + Send + Sync + 'static,
+{
+    match cmd {
+        UsersCommand::Add { login } => {
+            let password = rpassword::prompt_password("Пароль: ")
+                .map_err(|_| Error::InvalidInput)?;
+            let repeated = rpassword::prompt_password("Повторите пароль: ")
+                .map_err(|_| Error::InvalidInput)?;
+            if password != repeated {
+                return Err(Error::PasswordMismatch);
+            }
+            let account = auth
+                .register(&login, &password)
+                .await
+                .map_err(|e| Error::CantCreateAccount(e.to_string()))?;
+            println!(
+                "👤 Учётная запись {} создана с ID: {}",
+                account.login, account.id
+            );
+        }
+    }
+    Ok(())
 }
 
 #[tracing::instrument(skip(api, cmd))]
@@ -118,16 +551,24 @@ pub async fn run<R>(api: CoreApi<R>, cmd: BudgetCommand) -> Result<(), Error>
 where
     R: CoreRepo + Clone + Send + Sync + 'static,
 {
-    let user_id: ai_app::storage::UserId = "default".to_string();
+    let user_id: UserId = DEFAULT_USER_ID.to_string();
     let sp = api.get_plan(&user_id).await.ok_or(Error::NoPlan)?;
-    let weights = sp.plan.try_into().map_err(|_| Error::InvalidPlan)?;
+    let weights = sp.plan.clone().try_into().map_err(|_| Error::InvalidPlan)?;
     let start = std::time::Instant::now();
     match cmd {
-        BudgetCommand::AddIncome { amount, dry_run } => {
-            let source = choose_source(&weights)?;
-            let income = Income::new_today(source.clone(), Money::new_rub(amount));
-            let budget = api
-                .distribute(&weights, &income)
+        BudgetCommand::AddIncome {
+            amount,
+            date,
+            dry_run,
+        } => {
+            let date = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+            let source = choose_source(&weights, date)?;
+            let currency = source.net().currency;
+            let income =
+                Income::new(source.clone(), Money::new(amount, currency), date);
+            let DistributedIncome { budget, plan } = api
+                .distribute_income(&user_id, &sp, &income)
+                .await
                 .map_err(|_| Error::CantDistribute)?;
 
             let tree = budget_to_tree(&budget);
@@ -137,7 +578,7 @@ where
                 println!("🔍 DRY-RUN: Результат НЕ сохранён");
             } else {
                 let id = api
-                    .save_budget(id, budget)
+                    .save_budget(&user_id, id, budget, Some(plan))
                     .await
                     .map_err(|_| Error::CantWriteResult)?;
                 println!("💾 Бюджет сохранён с ID: {id}");
@@ -147,13 +588,212 @@ where
             let tree = plan_to_tree(&weights);
             println!("{}", to_text(&tree));
         }
-        BudgetCommand::ShowBudget { id } => match api.budget_by_id(&id).await {
+        BudgetCommand::ShowBudget { id } => match api.budget_by_id(&user_id, &id).await
+        {
             Some(budget) => {
                 let tree = budget_to_tree(&budget.budget);
                 println!("{}", to_text(&tree));
+                if let Some(void) = &budget.voided {
+                    println!(
+                        "🚫 Аннулирован {}{}",
+                        void.date,
+                        void.reason
+                            .as_ref()
+                            .map(|r| format!(": {r}"))
+                            .unwrap_or_default()
+                    );
+                }
             }
             None => eprintln!("❌ Ошибка: не удалось загрузить бюджет с ID {id}"),
         },
+        BudgetCommand::VoidBudget { id, reason } => {
+            let today = chrono::Local::now().date_naive();
+            api.void_budget(&user_id, &id, today, reason)
+                .await
+                .map_err(|e| Error::CantVoidBudget(e.to_string()))?;
+            println!("🚫 Бюджет {id} аннулирован");
+        }
+        BudgetCommand::Redistribute { id, version } => {
+            let redistribution = api
+                .redistribute(&user_id, &id, version)
+                .await
+                .map_err(|e| Error::CantRedistribute(e.to_string()))?;
+            println!("{}", to_text(&redistribution_to_tree(&redistribution)));
+        }
+        BudgetCommand::Incomes { date } => {
+            let on = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+            let expected = api
+                .expected_incomes(&user_id, on)
+                .await
+                .ok_or(Error::NoPlan)?;
+            let tree = expected_incomes_to_tree(&expected);
+            println!("{}", to_text(&tree));
+        }
+        BudgetCommand::Credits { kind, date } => {
+            let on = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+            let credits =
+                api.credits(&user_id, kind.into(), on)
+                    .await
+                    .map_err(|e| match e {
+                        AppError::PlanNotFound => Error::NoPlan,
+                        e => Error::CantBuildSchedule(e.to_string()),
+                    })?;
+            let tree = credits_to_tree(&credits);
+            println!("{}", to_text(&tree));
+        }
+        BudgetCommand::Report { date } => {
+            let month = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+            let report = api
+                .plan_report(&user_id, month)
+                .await
+                .map_err(|e| Error::CantBuildReport(e.to_string()))?;
+            println!("{}", to_text(&plan_report_to_tree(&report)));
+        }
+        BudgetCommand::Simulate {
+            months,
+            date,
+            scenario,
+            no_planned,
+            kind,
+        } => {
+            let start = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+            let file: ScenarioFile = match scenario {
+                Some(path) => {
+                    let content = std::fs::read_to_string(path)
+                        .map_err(|e| Error::CantSimulate(e.to_string()))?;
+                    serde_json::from_str(&content)
+                        .map_err(|e| Error::CantSimulate(e.to_string()))?
+                }
+                None => ScenarioFile::default(),
+            };
+            let mut incomes = if no_planned {
+                Vec::new()
+            } else {
+                planned_incomes(&weights.sources, start, months)
+            };
+            incomes.extend(file.incomes);
+            let scenario = Scenario {
+                start,
+                months,
+                incomes,
+                changes: file.changes,
+                opening: HashMap::new(),
+                repayment: kind.into(),
+            };
+            let forecast =
+                api.simulate(&user_id, scenario)
+                    .await
+                    .map_err(|e| match e {
+                        AppError::ForecastTooLong { max } => {
+                            Error::ForecastTooLong(max)
+                        }
+                        e => Error::CantSimulate(e.to_string()),
+                    })?;
+            println!("{}", to_text(&forecast_to_tree(&forecast)));
+        }
+        BudgetCommand::Tui => {
+            tui::run(&api, &user_id, sp, weights)
+                .await
+                .map_err(|e| Error::CantRunTui(e.to_string()))?;
+        }
+        BudgetCommand::ImportStatement {
+            file,
+            format,
+            profile,
+            rules,
+            dry_run,
+        } => {
+            let read_error = |e: io::Error| Error::CantReadStatement(e.to_string());
+            let format = format
+                .or_else(|| StatementFormat::detect(&file))
+                .ok_or_else(|| {
+                    Error::CantReadStatement("укажите формат: --format".into())
+                })?;
+            let profile = profile
+                .map(|path| CsvProfile::from_file(&path))
+                .transpose()
+                .map_err(read_error)?;
+            let rules: Vec<MatchRule> = match rules {
+                Some(path) => {
+                    let content = std::fs::read_to_string(path).map_err(read_error)?;
+                    serde_json::from_str(&content)
+                        .map_err(|e| Error::CantReadStatement(e.to_string()))?
+                }
+                None => Vec::new(),
+            };
+            let content = std::fs::read_to_string(&file).map_err(read_error)?;
+            let transactions = parse_statement(&content, format, profile.as_ref())
+                .map_err(read_error)?;
+            let reconciliation = api
+                .reconcile_statement(&user_id, transactions, &rules)
+                .await
+                .map_err(|_| Error::CantDistribute)?;
+
+            for proposal in &reconciliation.proposals {
+                let tx = &proposal.transaction;
+                println!(
+                    "📥 {} {} {} ← {}",
+                    tx.date,
+                    proposal.budget.income.source.name,
+                    tx.amount,
+                    tx.counterparty.as_deref().unwrap_or("?")
+                );
+            }
+            for skipped in &reconciliation.skipped {
+                let tx = &skipped.transaction;
+                let reason = match skipped.reason {
+                    SkipReason::AlreadyDistributed => "уже распределено",
+                    SkipReason::NoSource => "источник не найден",
+                };
+                println!(
+                    "⏭️ {} {} ← {}: {reason}",
+                    tx.date,
+                    tx.amount,
+                    tx.counterparty.as_deref().unwrap_or("?")
+                );
+            }
+            if reconciliation.proposals.is_empty() {
+                println!("Новых поступлений нет");
+            } else if dry_run {
+                println!("🔍 DRY-RUN: Результат НЕ сохранён");
+            } else {
+                print!(
+                    "Распределить и сохранить поступления ({})? [y/N] ",
+                    reconciliation.proposals.len()
+                );
+                io::stdout().flush().map_err(|_| Error::InvalidInput)?;
+                let mut input = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .map_err(|_| Error::InvalidInput)?;
+                if input.trim().to_lowercase() != "y" {
+                    println!("Отменено");
+                } else {
+                    let budgets = reconciliation
+                        .proposals
+                        .into_iter()
+                        .map(|p| DistributedIncome {
+                            budget: p.budget,
+                            plan: p.plan,
+                        })
+                        .collect();
+                    let confirmation = api
+                        .confirm_statement(&user_id, budgets)
+                        .await
+                        .map_err(|_| Error::CantWriteResult)?;
+                    println!(
+                        "💾 Сохранено распределений: {}",
+                        confirmation.saved.len()
+                    );
+                    for budget in &confirmation.skipped {
+                        println!(
+                            "⏭️ {} {}: уже распределено",
+                            budget.income.date, budget.income.amount
+                        );
+                    }
+                }
+            }
+        }
     }
     let elapsed = start.elapsed();
     println!("⏱️ Время выполнения: {elapsed:.2?}");
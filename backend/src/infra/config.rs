@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use ai_app::config;
 use tracing::info;
@@ -10,6 +10,8 @@ const LOG_FILE: &str = "anna_ivanovna.log";
 pub struct ConfigOverrides {
     pub host: Option<String>,
     pub port: Option<u16>,
+    /// Логи только в файл: терминал занят полноэкранным интерфейсом
+    pub quiet: bool,
 }
 
 /// Resolve buh_home, init logging, load config with overrides.
@@ -19,7 +21,7 @@ pub fn init(
 ) -> Result<config::Config, Box<dyn std::error::Error>> {
     let home = resolve_home(buh_home)?;
 
-    crate::infra::logging::init(&home, LOG_FILE)?;
+    crate::infra::logging::init(&home, LOG_FILE, overrides.quiet)?;
 
     let config_path = home.join("config.json");
     let mut cfg: config::Config = config::read_json(&config_path)?;
@@ -30,6 +32,11 @@ pub fn init(
     if let Some(port) = overrides.port {
         cfg.server.port = port;
     }
+    if let config::Database::Files { path, .. } = &mut cfg.database
+        && Path::new(path).is_relative()
+    {
+        *path = home.join(&path).to_string_lossy().into_owned();
+    }
 
     Ok(cfg)
 }
@@ -1,9 +1,14 @@
 use std::{fs::create_dir_all, path::Path};
 
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
 
-pub fn init(dir: &Path, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// `quiet` - писать логи только в файл, когда терминал занят интерфейсом
+pub fn init(
+    dir: &Path,
+    filename: &str,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let logdir = dir.join("logs");
     if let Err(e) = create_dir_all(&logdir) {
         eprintln!("Не удалось создать директорию для логов: {e}");
@@ -16,7 +21,11 @@ pub fn init(dir: &Path, filename: &str) -> Result<(), Box<dyn std::error::Error>
         .max_log_files(7)
         .build(&logdir)?;
 
-    let writer = std::io::stderr.and(file_appender);
+    let writer = if quiet {
+        BoxMakeWriter::new(file_appender)
+    } else {
+        BoxMakeWriter::new(std::io::stderr.and(file_appender))
+    };
     tracing_subscriber::fmt()
         .with_writer(writer)
         .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
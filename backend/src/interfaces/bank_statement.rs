@@ -0,0 +1,394 @@
+//! Чтение банковских выписок: CSV по профилю колонок, OFX и camt.053.
+//!
+//! Из выписки берутся дата, сумма со знаком, плательщик и назначение
+//! платежа; сопоставлением с источниками дохода занимается
+//! [`ai_app::statement`].
+
+use std::{io, path::Path, str::FromStr};
+
+use chrono::NaiveDate;
+use quick_xml::{Reader, events::Event};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use ai_app::statement::Transaction;
+use ai_core::finance::{Currency, Money};
+
+use crate::interfaces::csv_import::{CsvProfile, parse_currency};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    /// Колонки задаются профилем; колонка `source` профиля - плательщик
+    Csv,
+    Ofx,
+    /// ISO 20022 BankToCustomerStatement
+    Camt053,
+}
+
+impl StatementFormat {
+    /// Формат по расширению файла
+    pub fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Self::Csv),
+            "ofx" | "qfx" => Some(Self::Ofx),
+            "xml" => Some(Self::Camt053),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_statement(
+    content: &str,
+    format: StatementFormat,
+    profile: Option<&CsvProfile>,
+) -> io::Result<Vec<Transaction>> {
+    match format {
+        StatementFormat::Csv => {
+            let profile = profile.ok_or_else(|| {
+                io::Error::other("Для CSV-выписки нужен профиль колонок")
+            })?;
+            parse_csv(content, profile)
+        }
+        StatementFormat::Ofx => parse_ofx(content),
+        StatementFormat::Camt053 => parse_camt053(content),
+    }
+}
+
+fn parse_csv(content: &str, profile: &CsvProfile) -> io::Result<Vec<Transaction>> {
+    let delimiter = u8::try_from(profile.delimiter).map_err(|_| {
+        io::Error::other("Разделитель полей должен быть ASCII-символом")
+    })?;
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = rdr
+        .headers()
+        .map_err(|e| io::Error::other(format!("Ошибка чтения заголовков: {e}")))?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+    let find = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| io::Error::other(format!("В файле нет колонки '{name}'")))
+    };
+    let date = find(&profile.date)?;
+    let amount = find(&profile.amount)?;
+    let counterparty = profile.source.as_deref().map(find).transpose()?;
+    let currency = profile.currency.as_deref().map(find).transpose()?;
+
+    let mut transactions = Vec::new();
+    for record in rdr.records() {
+        let record = record.map_err(io::Error::other)?;
+        let line = record.position().map_or(0, |p| p.line());
+        let field = |i: usize| record.get(i).unwrap_or_default().trim();
+        let error =
+            |message: String| io::Error::other(format!("Строка {line}: {message}"));
+
+        let date = profile.parse_date(field(date)).ok_or_else(|| {
+            error(format!("не удалось разобрать дату '{}'", field(date)))
+        })?;
+        let value = profile.parse_amount(field(amount)).ok_or_else(|| {
+            error(format!("не удалось разобрать сумму '{}'", field(amount)))
+        })?;
+        let currency = match currency.map(field).filter(|c| !c.is_empty()) {
+            Some(raw) => parse_currency(raw)
+                .ok_or_else(|| error(format!("неизвестная валюта '{raw}'")))?,
+            None => profile.default_currency,
+        };
+        transactions.push(Transaction {
+            date,
+            amount: Money::new(value, currency),
+            counterparty: counterparty
+                .map(field)
+                .filter(|c| !c.is_empty())
+                .map(String::from),
+            description: None,
+        });
+    }
+    Ok(transactions)
+}
+
+/// OFX 1.x (SGML) и 2.x (XML): у SGML закрывающие теги полей необязательны,
+/// поэтому значение поля читается до следующего тега
+fn parse_ofx(content: &str) -> io::Result<Vec<Transaction>> {
+    let currency = ofx_field(content, "CURDEF")
+        .map(|c| {
+            parse_currency(c)
+                .ok_or_else(|| io::Error::other(format!("Неизвестная валюта '{c}'")))
+        })
+        .transpose()?
+        .unwrap_or_default();
+    content
+        .split("<STMTTRN>")
+        .skip(1)
+        .map(|block| {
+            let block = block.split("</STMTTRN>").next().unwrap_or(block);
+            let field = |tag| {
+                ofx_field(block, tag).ok_or_else(|| {
+                    io::Error::other(format!("В операции нет поля {tag}"))
+                })
+            };
+            let posted = field("DTPOSTED")?;
+            let date = posted
+                .get(..8)
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok())
+                .ok_or_else(|| {
+                    io::Error::other(format!("Не удалось разобрать дату '{posted}'"))
+                })?;
+            let amount = field("TRNAMT")?;
+            let value = Decimal::from_str(&amount.replace(',', ".")).map_err(|_| {
+                io::Error::other(format!("Не удалось разобрать сумму '{amount}'"))
+            })?;
+            Ok(Transaction {
+                date,
+                amount: Money::new(value, currency),
+                counterparty: ofx_field(block, "NAME").map(unescape),
+                description: ofx_field(block, "MEMO").map(unescape),
+            })
+        })
+        .collect()
+}
+
+fn ofx_field<'a>(block: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}>");
+    let start = block.find(&open)? + open.len();
+    let rest = &block[start..];
+    let value = rest[..rest.find('<').unwrap_or(rest.len())].trim();
+    (!value.is_empty()).then_some(value)
+}
+
+fn unescape(value: &str) -> String {
+    quick_xml::escape::unescape(value)
+        .map_or_else(|_| value.to_string(), |v| v.into_owned())
+}
+
+/// Операция camt.053, которая собирается по мере чтения `<Ntry>`
+#[derive(Default)]
+struct CamtEntry {
+    amount: Option<(Decimal, Currency)>,
+    debit: bool,
+    date: Option<NaiveDate>,
+    counterparty: Option<String>,
+    description: Vec<String>,
+}
+
+fn parse_camt053(content: &str) -> io::Result<Vec<Transaction>> {
+    let xml_error =
+        |e: &dyn std::fmt::Display| io::Error::other(format!("Ошибка чтения XML: {e}"));
+    // Текст не обрезается читателем: иначе пропадут пробелы вокруг `&amp;`
+    let mut reader = Reader::from_str(content);
+
+    let mut path: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut currency = None;
+    let mut entry: Option<CamtEntry> = None;
+    let mut transactions = Vec::new();
+    loop {
+        match reader.read_event().map_err(|e| xml_error(&e))? {
+            Event::Start(e) => {
+                let name =
+                    String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                if name == "Ntry" {
+                    entry = Some(CamtEntry::default());
+                }
+                if name == "Amt" {
+                    currency = e
+                        .try_get_attribute("Ccy")
+                        .map_err(|e| xml_error(&e))?
+                        .map(|a| a.unescape_value().map(|v| v.into_owned()))
+                        .transpose()
+                        .map_err(|e| xml_error(&e))?;
+                }
+                path.push(name);
+                text.clear();
+            }
+            Event::Text(e) => text.push_str(&e.decode().map_err(|e| xml_error(&e))?),
+            Event::GeneralRef(e) => {
+                let name = e.decode().map_err(|e| xml_error(&e))?;
+                text.push_str(&unescape(&format!("&{name};")));
+            }
+            Event::End(_) => {
+                if let Some(entry) = entry.as_mut() {
+                    camt_field(entry, &path, text.trim(), currency.as_deref())?;
+                }
+                if path.pop().as_deref() == Some("Ntry")
+                    && let Some(done) = entry.take()
+                {
+                    transactions.push(camt_transaction(done)?);
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(transactions)
+}
+
+fn camt_field(
+    entry: &mut CamtEntry,
+    path: &[String],
+    text: &str,
+    currency: Option<&str>,
+) -> io::Result<()> {
+    let ntry = path.iter().rposition(|p| p == "Ntry").unwrap_or_default();
+    let inner: Vec<&str> = path[ntry + 1..].iter().map(String::as_str).collect();
+    match inner.as_slice() {
+        ["Amt"] => {
+            let value = Decimal::from_str(text).map_err(|_| {
+                io::Error::other(format!("Не удалось разобрать сумму '{text}'"))
+            })?;
+            let currency = match currency {
+                Some(c) => parse_currency(c).ok_or_else(|| {
+                    io::Error::other(format!("Неизвестная валюта '{c}'"))
+                })?,
+                None => Currency::default(),
+            };
+            entry.amount = Some((value, currency));
+        }
+        ["CdtDbtInd"] => entry.debit = text == "DBIT",
+        ["BookgDt", "Dt" | "DtTm"] => {
+            entry.date = text.get(..10).and_then(|d| d.parse().ok());
+        }
+        ["ValDt", "Dt" | "DtTm"] if entry.date.is_none() => {
+            entry.date = text.get(..10).and_then(|d| d.parse().ok());
+        }
+        [.., "Nm"] if entry.counterparty.is_none() => {
+            // Для поступления контрагент - плательщик, для списания - получатель
+            let party = if entry.debit { "Cdtr" } else { "Dbtr" };
+            let related = inner.iter().position(|p| *p == "RltdPties");
+            if related.is_some_and(|i| inner.get(i + 1) == Some(&party)) {
+                entry.counterparty = Some(text.to_string());
+            }
+        }
+        [.., "RmtInf", "Ustrd"] => entry.description.push(text.to_string()),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn camt_transaction(entry: CamtEntry) -> io::Result<Transaction> {
+    let (value, currency) = entry
+        .amount
+        .ok_or_else(|| io::Error::other("В операции нет суммы"))?;
+    let date = entry
+        .date
+        .ok_or_else(|| io::Error::other("В операции нет даты"))?;
+    let value = if entry.debit { -value } else { value };
+    Ok(Transaction {
+        date,
+        amount: Money::new(value, currency),
+        counterparty: entry.counterparty,
+        description: (!entry.description.is_empty())
+            .then(|| entry.description.join(" ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn summary(transactions: &[Transaction]) -> Vec<(String, Money, Option<&str>)> {
+        transactions
+            .iter()
+            .map(|t| (t.date.to_string(), t.amount, t.counterparty.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_ofx_sgml() {
+        let ofx = "OFXHEADER:100\nDATA:OFXSGML\n\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>\n\
+                   <CURDEF>RUB\n<BANKTRANLIST>\n\
+                   <STMTTRN>\n<TRNTYPE>CREDIT\n<DTPOSTED>20260305120000[+3:MSK]\n\
+                   <TRNAMT>95000.00\n<NAME>ООО Ромашка &amp; Ко\n<MEMO>Зарплата за февраль\n</STMTTRN>\n\
+                   <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20260306\n<TRNAMT>-1500,50\n</STMTTRN>\n\
+                   </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+        let transactions = parse_statement(ofx, StatementFormat::Ofx, None).unwrap();
+
+        assert_eq!(
+            summary(&transactions),
+            vec![
+                (
+                    "2026-03-05".into(),
+                    Money::new_rub(dec!(95000)),
+                    Some("ООО Ромашка & Ко")
+                ),
+                ("2026-03-06".into(), Money::new_rub(dec!(-1500.50)), None),
+            ]
+        );
+        assert_eq!(
+            transactions[0].description.as_deref(),
+            Some("Зарплата за февраль")
+        );
+    }
+
+    #[test]
+    fn reads_camt053() {
+        let camt = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02"><BkToCstmrStmt><Stmt>
+  <Ntry>
+    <Amt Ccy="USD">1200.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+    <BookgDt><Dt>2026-03-10</Dt></BookgDt>
+    <NtryDtls><TxDtls>
+      <RltdPties><Dbtr><Nm>Acme &amp; Sons</Nm></Dbtr><Cdtr><Nm>Me</Nm></Cdtr></RltdPties>
+      <RmtInf><Ustrd>Invoice 42</Ustrd></RmtInf>
+    </TxDtls></NtryDtls>
+  </Ntry>
+  <Ntry>
+    <Amt Ccy="RUB">300</Amt><CdtDbtInd>DBIT</CdtDbtInd>
+    <BookgDt><DtTm>2026-03-11T10:00:00</DtTm></BookgDt>
+    <NtryDtls><TxDtls><RltdPties><Cdtr><Pty><Nm>Кафе</Nm></Pty></Cdtr></RltdPties></TxDtls></NtryDtls>
+  </Ntry>
+</Stmt></BkToCstmrStmt></Document>"#;
+
+        let transactions =
+            parse_statement(camt, StatementFormat::Camt053, None).unwrap();
+
+        assert_eq!(
+            summary(&transactions),
+            vec![
+                (
+                    "2026-03-10".into(),
+                    Money::new(dec!(1200), Currency::USD),
+                    Some("Acme & Sons")
+                ),
+                (
+                    "2026-03-11".into(),
+                    Money::new_rub(dec!(-300)),
+                    Some("Кафе")
+                ),
+            ]
+        );
+        assert_eq!(transactions[0].description.as_deref(), Some("Invoice 42"));
+    }
+
+    #[test]
+    fn csv_needs_profile() {
+        let csv = "Дата;Плательщик;Сумма\n05.03.2026;ООО Ромашка;95 000,00\n";
+        assert!(parse_statement(csv, StatementFormat::Csv, None).is_err());
+
+        let profile: CsvProfile = serde_json::from_str(
+            r#"{"delimiter": ";", "date": "Дата", "date_formats": ["%d.%m.%Y"],
+                "source": "Плательщик", "amount": "Сумма", "decimal_separator": ","}"#,
+        )
+        .unwrap();
+        let transactions =
+            parse_statement(csv, StatementFormat::Csv, Some(&profile)).unwrap();
+        assert_eq!(
+            summary(&transactions),
+            vec![(
+                "2026-03-05".into(),
+                Money::new_rub(dec!(95000)),
+                Some("ООО Ромашка")
+            )]
+        );
+    }
+}
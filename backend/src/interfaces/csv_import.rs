@@ -0,0 +1,478 @@
+//! Импорт истории распределений из CSV по профилю сопоставления колонок.
+//!
+//! Каждая строка файла - один доход: дата, источник и сумма, а в колонках
+//! конвертов - сколько из него отложено. Какая колонка что означает, как
+//! записаны даты и суммы, задаёт [`CsvProfile`]. Строки, которые не удалось
+//! разобрать, не прерывают импорт, а собираются в [`CsvImport::errors`].
+//!
+//! Суммы конвертов записаны в валюте дохода, если в заголовке колонки
+//! не указана своя: `Подушка (USD)`.
+
+use std::{collections::BTreeMap, fs::File, io, path::Path};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use ai_core::{
+    distribute::{Budget, BudgetEntry, Income},
+    finance::{Currency, Money},
+    planning::{Expense, ExpenseValue, IncomeKind, IncomeSource},
+};
+
+/// Какие колонки CSV что означают и как в них записаны значения
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CsvProfile {
+    /// Разделитель полей
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Колонка с датой дохода
+    pub date: String,
+    /// Форматы даты в синтаксисе chrono; пробуются по порядку
+    #[serde(default = "default_date_formats")]
+    pub date_formats: Vec<String>,
+    /// Колонка с источником дохода
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Источник для строк без источника
+    #[serde(default = "default_source")]
+    pub default_source: String,
+    /// Чем заменить пробелы в имени источника. Старая таблица Excel
+    /// сохраняла источники через `-`, и новые записи должны совпадать с ними
+    #[serde(default)]
+    pub source_space: Option<char>,
+    /// Колонка с суммой дохода
+    pub amount: String,
+    /// Десятичный разделитель в суммах
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+    /// Разделитель разрядов; пробелы убираются всегда
+    #[serde(default)]
+    pub thousands_separator: Option<char>,
+    /// Колонка с валютой строки: код (`RUB`, `USD`) или знак.
+    /// Если её нет в файле, берётся `default_currency`
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Валюта строк без колонки валюты
+    #[serde(default)]
+    pub default_currency: Currency,
+    /// Колонка с итогом распределения в валюте дохода: из неё считается
+    /// остаток. Без неё остаток - доход минус конверты в его валюте
+    #[serde(default)]
+    pub total: Option<String>,
+    /// Колонки-конверты. Если не заданы, конвертами считаются все колонки,
+    /// кроме перечисленных выше и `ignore`
+    #[serde(default)]
+    pub envelopes: Option<Vec<String>>,
+    /// Категория конверта по имени его колонки
+    #[serde(default)]
+    pub categories: BTreeMap<String, String>,
+    /// Колонки, которые не нужно импортировать
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_date_formats() -> Vec<String> {
+    vec!["%Y-%m-%d".into()]
+}
+
+fn default_source() -> String {
+    "неизвестно".into()
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+impl CsvProfile {
+    /// Раскладка таблицы Excel, которую вели до появления приложения
+    pub fn excel() -> Self {
+        Self {
+            delimiter: ',',
+            date: "Дата входа".into(),
+            date_formats: vec!["%d.%m.%Y".into()],
+            source: Some("Источник".into()),
+            default_source: default_source(),
+            source_space: Some('-'),
+            amount: "Вход".into(),
+            decimal_separator: ',',
+            thousands_separator: None,
+            currency: Some("Валюта".into()),
+            default_currency: Currency::RUB,
+            total: Some("Итого распределено".into()),
+            envelopes: None,
+            categories: BTreeMap::new(),
+            ignore: vec!["Период".into()],
+        }
+    }
+
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            io::Error::other(format!("Не удалось открыть профиль: {e}"))
+        })?;
+        serde_json::from_str(&content)
+            .map_err(|e| io::Error::other(format!("Ошибка в профиле: {e}")))
+    }
+
+    pub(crate) fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        self.date_formats
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+    }
+
+    pub(crate) fn parse_amount(&self, value: &str) -> Option<Decimal> {
+        let mut value: String = value
+            .chars()
+            .filter(|c| !c.is_whitespace() && Some(*c) != self.thousands_separator)
+            .collect();
+        for sign in ["₽", "руб.", "р.", "$"] {
+            value = value.replace(sign, "");
+        }
+        value.replace(self.decimal_separator, ".").parse().ok()
+    }
+}
+
+/// Распознаёт валюту по коду или знаку
+pub(crate) fn parse_currency(value: &str) -> Option<Currency> {
+    match value.trim().to_uppercase().as_str() {
+        "RUB" | "RUR" | "₽" | "Р." | "РУБ." | "РУБ" => Some(Currency::RUB),
+        "USD" | "$" => Some(Currency::USD),
+        _ => None,
+    }
+}
+
+/// Разобранная строка файла
+#[derive(Debug)]
+pub struct ParsedRow {
+    pub line: u64,
+    pub budget: Budget,
+}
+
+/// Строка, которую не удалось разобрать
+#[derive(Debug, PartialEq)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct CsvImport {
+    pub rows: Vec<ParsedRow>,
+    pub errors: Vec<RowError>,
+}
+
+/// Индексы колонок профиля в заголовке файла
+struct Columns {
+    date: usize,
+    source: Option<usize>,
+    amount: usize,
+    currency: Option<usize>,
+    total: Option<usize>,
+    envelopes: Vec<Envelope>,
+}
+
+/// Колонка-конверт: имя без валюты и валюта из заголовка, если указана
+struct Envelope {
+    column: usize,
+    name: String,
+    currency: Option<Currency>,
+}
+
+impl Envelope {
+    fn new(column: usize, header: &str) -> Self {
+        let suffix = header
+            .strip_suffix(')')
+            .and_then(|rest| rest.rsplit_once(" ("))
+            .and_then(|(name, code)| Some((name, parse_currency(code)?)));
+        match suffix {
+            Some((name, currency)) => Self {
+                column,
+                name: name.to_string(),
+                currency: Some(currency),
+            },
+            None => Self {
+                column,
+                name: header.to_string(),
+                currency: None,
+            },
+        }
+    }
+}
+
+impl Columns {
+    fn resolve(headers: &[String], profile: &CsvProfile) -> io::Result<Self> {
+        let find = |name: &str| {
+            headers.iter().position(|h| h == name).ok_or_else(|| {
+                io::Error::other(format!("В файле нет колонки '{name}'"))
+            })
+        };
+        let date = find(&profile.date)?;
+        let source = profile.source.as_deref().map(find).transpose()?;
+        let amount = find(&profile.amount)?;
+        // Валюты и итога может не быть в файле: в старой таблице их не было
+        let optional =
+            |name: Option<&String>| headers.iter().position(|h| Some(h) == name);
+        let currency = optional(profile.currency.as_ref());
+        let total = optional(profile.total.as_ref());
+        let envelopes = match &profile.envelopes {
+            Some(names) => names
+                .iter()
+                .map(|name| find(name).map(|i| Envelope::new(i, name)))
+                .collect::<io::Result<_>>()?,
+            None => {
+                let mapped = [Some(date), source, Some(amount), currency, total];
+                headers
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, h)| {
+                        !mapped.contains(&Some(*i))
+                            && !h.is_empty()
+                            && !profile.ignore.contains(h)
+                    })
+                    .map(|(i, h)| Envelope::new(i, h))
+                    .collect()
+            }
+        };
+        Ok(Self {
+            date,
+            source,
+            amount,
+            currency,
+            total,
+            envelopes,
+        })
+    }
+}
+
+pub fn parse_csv_file(path: &Path, profile: &CsvProfile) -> io::Result<CsvImport> {
+    let file = File::open(path)
+        .map_err(|e| io::Error::other(format!("Не удалось открыть файл: {e}")))?;
+    parse_csv(io::BufReader::new(file), profile)
+}
+
+/// Разбирает CSV по профилю. Ошибкой всего импорта считается только
+/// нечитаемый заголовок или отсутствие колонок профиля
+pub fn parse_csv<R: io::Read>(
+    reader: R,
+    profile: &CsvProfile,
+) -> io::Result<CsvImport> {
+    let delimiter = u8::try_from(profile.delimiter).map_err(|_| {
+        io::Error::other("Разделитель полей должен быть ASCII-символом")
+    })?;
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(reader);
+    let headers: Vec<String> = rdr
+        .headers()
+        .map_err(|e| io::Error::other(format!("Ошибка чтения заголовков: {e}")))?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+    let columns = Columns::resolve(&headers, profile)?;
+
+    let mut import = CsvImport::default();
+    for record in rdr.records() {
+        let (line, parsed) = match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line());
+                let fields: Vec<&str> = record.iter().map(str::trim).collect();
+                (line, parse_row(&fields, &columns, profile))
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                (line, Err(e.to_string()))
+            }
+        };
+        match parsed {
+            Ok(budget) => import.rows.push(ParsedRow { line, budget }),
+            Err(message) => import.errors.push(RowError { line, message }),
+        }
+    }
+    Ok(import)
+}
+
+fn parse_row(
+    fields: &[&str],
+    columns: &Columns,
+    profile: &CsvProfile,
+) -> Result<Budget, String> {
+    let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+
+    let date_raw = field(columns.date);
+    let date = profile
+        .parse_date(date_raw)
+        .ok_or_else(|| format!("не удалось разобрать дату '{date_raw}'"))?;
+    let mut source = columns
+        .source
+        .map(field)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(&profile.default_source)
+        .to_string();
+    if let Some(replacement) = profile.source_space {
+        source = source.replace(' ', &replacement.to_string());
+    }
+    let currency = match columns.currency.map(field).filter(|c| !c.is_empty()) {
+        Some(raw) => {
+            parse_currency(raw).ok_or_else(|| format!("неизвестная валюта '{raw}'"))?
+        }
+        None => profile.default_currency,
+    };
+    let amount_raw = field(columns.amount);
+    let amount = profile
+        .parse_amount(amount_raw)
+        .map(|value| Money::new(value, currency))
+        .ok_or_else(|| format!("не удалось разобрать сумму дохода '{amount_raw}'"))?;
+
+    let income_source =
+        IncomeSource::new(source, IncomeKind::Other { expected: amount });
+    let mut budget = Budget::new(Income::new(income_source, amount, date));
+    for envelope in &columns.envelopes {
+        let raw = field(envelope.column);
+        if raw.is_empty() {
+            continue;
+        }
+        let name = &envelope.name;
+        let value = profile.parse_amount(raw).ok_or_else(|| {
+            format!("конверт '{name}': не удалось разобрать сумму '{raw}'")
+        })?;
+        let money = Money::new(value, envelope.currency.unwrap_or(currency));
+        let category = profile.categories.get(name).cloned();
+        let expense = Expense::envelope(
+            name.clone(),
+            ExpenseValue::MONEY { value: money },
+            category.clone(),
+        );
+        let entry = BudgetEntry::new(expense, money);
+        if money.currency == currency {
+            budget.push(category, entry);
+        } else {
+            // Сколько это стоило в валюте дохода, знает только колонка итога
+            match category {
+                Some(category) => {
+                    budget.categories.entry(category).or_default().push(entry)
+                }
+                None => budget.no_category.push(entry),
+            }
+        }
+    }
+    if let Some(raw) = columns.total.map(field).filter(|t| !t.is_empty()) {
+        let total = profile
+            .parse_amount(raw)
+            .ok_or_else(|| format!("не удалось разобрать итог '{raw}'"))?;
+        budget.rest = Money::new(amount.value - total, currency);
+    }
+    Ok(budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn envelopes(budget: &Budget) -> Vec<(Option<String>, String, Money)> {
+        let mut got: Vec<_> = budget
+            .no_category
+            .iter()
+            .map(|e| (None, e.expense.name.clone(), e.amount))
+            .chain(budget.categories.iter().flat_map(|(category, entries)| {
+                entries
+                    .iter()
+                    .map(|e| (Some(category.clone()), e.expense.name.clone(), e.amount))
+            }))
+            .collect();
+        got.sort_by(|a, b| a.1.cmp(&b.1));
+        got
+    }
+
+    #[test]
+    fn profile_maps_columns_and_collects_bad_rows() {
+        let profile: CsvProfile = serde_json::from_str(
+            r#"{
+                "delimiter": ";",
+                "date": "Date",
+                "date_formats": ["%d/%m/%Y", "%Y-%m-%d"],
+                "source": "From",
+                "amount": "Total",
+                "decimal_separator": ",",
+                "thousands_separator": ".",
+                "currency": "Cur",
+                "envelopes": ["Rent", "Food"],
+                "categories": { "Food": "Быт" }
+            }"#,
+        )
+        .unwrap();
+        let csv = "Date;From;Total;Cur;Rent;Food;Note\n\
+                   05/03/2026;Salary;100.000,50;RUB;30.000;20 000,25;x\n\
+                   2026-03-20;;$1000;usd;;400;\n\
+                   31/02/2026;Salary;100;RUB;1;1;\n\
+                   06/03/2026;Salary;100;EUR;1;1;\n\
+                   07/03/2026;Salary;100;RUB;много;1;\n";
+
+        let import = parse_csv(csv.as_bytes(), &profile).unwrap();
+
+        assert_eq!(import.rows.len(), 2);
+        let first = &import.rows[0];
+        assert_eq!(first.line, 2);
+        assert_eq!(first.budget.income.source.name, "Salary");
+        assert_eq!(first.budget.income.amount, Money::new_rub(dec!(100000.50)));
+        assert_eq!(
+            envelopes(&first.budget),
+            vec![
+                (
+                    Some("Быт".into()),
+                    "Food".into(),
+                    Money::new_rub(dec!(20000.25))
+                ),
+                (None, "Rent".into(), Money::new_rub(dec!(30000))),
+            ]
+        );
+        let second = &import.rows[1].budget;
+        assert_eq!(second.income.source.name, "неизвестно");
+        assert_eq!(second.income.amount, Money::new(dec!(1000), Currency::USD));
+        assert_eq!(
+            second.income.date,
+            NaiveDate::from_ymd_opt(2026, 3, 20).unwrap()
+        );
+
+        let errors: Vec<_> = import
+            .errors
+            .iter()
+            .map(|e| (e.line, e.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (4, "не удалось разобрать дату '31/02/2026'"),
+                (5, "неизвестная валюта 'EUR'"),
+                (6, "конверт 'Rent': не удалось разобрать сумму 'много'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn excel_profile_reads_legacy_layout() {
+        let csv = "Дата входа,Источник,Вход,Период,Аренда,Итого распределено\n\
+                   21.06.2025,Золотой гусь,\"50000,00 р.\",июнь,\"15 000\",15000\n";
+        let import = parse_csv(csv.as_bytes(), &CsvProfile::excel()).unwrap();
+
+        assert!(import.errors.is_empty());
+        let budget = &import.rows[0].budget;
+        assert_eq!(budget.income.source.name, "Золотой-гусь");
+        assert_eq!(budget.income.amount, Money::new_rub(dec!(50000)));
+        assert_eq!(
+            envelopes(budget),
+            vec![(None, "Аренда".into(), Money::new_rub(dec!(15000)))]
+        );
+        assert!(matches!(
+            parse_csv("Дата,Вход\n".as_bytes(), &CsvProfile::excel()),
+            Err(e) if e.to_string() == "В файле нет колонки 'Дата входа'"
+        ));
+    }
+}
@@ -0,0 +1,441 @@
+//! Выгрузка истории распределений в таблицу: строка на доход,
+//! колонка на конверт.
+//!
+//! CSV повторяет раскладку старой таблицы Excel, поэтому выгрузку можно
+//! загрузить обратно через [`CsvProfile::excel`](super::csv_import::CsvProfile::excel).
+//! Если в таблице больше одной валюты, к имени конверта в заголовке
+//! добавляется код его валюты: `Подушка (USD)`.
+//! В XLSX конверты сгруппированы по категориям.
+
+use std::io;
+
+use chrono::NaiveDate;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use rust_xlsxwriter::{Format, FormatAlign, Workbook, XlsxError};
+use serde::Deserialize;
+
+use ai_app::storage::StorageBudget;
+use ai_core::{
+    distribute::BudgetEntry,
+    finance::{Currency, Money},
+};
+
+/// Колонки с доходом, как в таблице Excel
+const DATE: &str = "Дата входа";
+const SOURCE: &str = "Источник";
+const AMOUNT: &str = "Вход";
+const CURRENCY: &str = "Валюта";
+const TOTAL: &str = "Итого распределено";
+const NO_CATEGORY: &str = "Без категории";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+impl HistoryFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            HistoryFormat::Csv => "csv",
+            HistoryFormat::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            HistoryFormat::Csv => "text/csv; charset=utf-8",
+            HistoryFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeColumn {
+    pub category: Option<String>,
+    pub name: String,
+    pub currency: Currency,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryRow {
+    pub date: NaiveDate,
+    pub source: String,
+    pub income: Money,
+    /// Суммы по колонкам [`HistoryTable::envelopes`]
+    pub amounts: Vec<Option<Money>>,
+    /// Распределено в валюте дохода
+    pub total: Money,
+}
+
+/// История распределений, развёрнутая в таблицу
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryTable {
+    /// Сначала конверты без категории, затем категории по алфавиту
+    pub envelopes: Vec<EnvelopeColumn>,
+    pub rows: Vec<HistoryRow>,
+}
+
+impl HistoryTable {
+    pub fn new(budgets: &[StorageBudget]) -> Self {
+        let mut envelopes: Vec<EnvelopeColumn> = Vec::new();
+        for sb in budgets {
+            for (category, entry) in entries(sb) {
+                let column = EnvelopeColumn {
+                    category,
+                    name: entry.expense.name.clone(),
+                    currency: entry.amount.currency,
+                };
+                if !envelopes.contains(&column) {
+                    envelopes.push(column);
+                }
+            }
+        }
+        envelopes.sort_by(|a, b| a.category.cmp(&b.category));
+
+        let rows = budgets.iter().map(|sb| row(sb, &envelopes)).collect();
+        Self { envelopes, rows }
+    }
+
+    /// Валюта нужна в заголовках, только если в таблице их несколько
+    fn mixed_currencies(&self) -> bool {
+        let mut currencies = self
+            .rows
+            .iter()
+            .map(|r| r.income.currency)
+            .chain(self.envelopes.iter().map(|c| c.currency));
+        let first = currencies.next();
+        currencies.any(|c| Some(c) != first)
+    }
+
+    fn titles(&self) -> Vec<String> {
+        let mixed = self.mixed_currencies();
+        self.envelopes
+            .iter()
+            .map(|c| {
+                if mixed {
+                    format!("{} ({:?})", c.name, c.currency)
+                } else {
+                    c.name.clone()
+                }
+            })
+            .collect()
+    }
+
+    pub fn write(&self, format: HistoryFormat) -> io::Result<Vec<u8>> {
+        match format {
+            HistoryFormat::Csv => {
+                let mut buf = Vec::new();
+                self.write_csv(&mut buf)?;
+                Ok(buf)
+            }
+            HistoryFormat::Xlsx => self.to_xlsx().map_err(io::Error::other),
+        }
+    }
+
+    /// CSV в раскладке таблицы Excel: даты `дд.мм.гггг`, суммы с запятой
+    pub fn write_csv<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        let titles = self.titles();
+        let header = [DATE, SOURCE, AMOUNT, CURRENCY]
+            .into_iter()
+            .chain(titles.iter().map(String::as_str))
+            .chain([TOTAL]);
+        wtr.write_record(header)?;
+        for row in &self.rows {
+            let record = [
+                row.date.format("%d.%m.%Y").to_string(),
+                row.source.clone(),
+                csv_money(row.income),
+                format!("{:?}", row.income.currency),
+            ]
+            .into_iter()
+            .chain(
+                row.amounts
+                    .iter()
+                    .map(|m| m.map(csv_money).unwrap_or_default()),
+            )
+            .chain([csv_money(row.total)]);
+            wtr.write_record(record)?;
+        }
+        wtr.flush()
+    }
+
+    /// Книга с одним листом: над конвертами строка с их категориями
+    pub fn to_xlsx(&self) -> Result<Vec<u8>, XlsxError> {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("История")?;
+        let header = Format::new().set_bold().set_align(FormatAlign::Center);
+        let money = Format::new().set_num_format("#,##0.00");
+        let date = Format::new().set_num_format("dd.mm.yyyy");
+
+        let first = 4u16;
+        let total_col = first + self.envelopes.len() as u16;
+        let columns = [
+            (0, DATE),
+            (1, SOURCE),
+            (2, AMOUNT),
+            (3, CURRENCY),
+            (total_col, TOTAL),
+        ];
+        for (col, title) in columns {
+            sheet.merge_range(0, col, 1, col, title, &header)?;
+        }
+        let mut start = 0;
+        while start < self.envelopes.len() {
+            let category = &self.envelopes[start].category;
+            let len = self.envelopes[start..]
+                .iter()
+                .take_while(|c| &c.category == category)
+                .count();
+            let title = category.as_deref().unwrap_or(NO_CATEGORY);
+            let (from, to) = (first + start as u16, first + (start + len) as u16 - 1);
+            if from == to {
+                sheet.write_string_with_format(0, from, title, &header)?;
+            } else {
+                sheet.merge_range(0, from, 0, to, title, &header)?;
+            }
+            start += len;
+        }
+        for (i, title) in self.titles().iter().enumerate() {
+            sheet.write_string_with_format(1, first + i as u16, title, &header)?;
+        }
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let r = 2 + i as u32;
+            sheet.write_with_format(r, 0, &row.date, &date)?;
+            sheet.write_string(r, 1, &row.source)?;
+            sheet.write_with_format(r, 2, cell(row.income), &money)?;
+            sheet.write_string(r, 3, format!("{:?}", row.income.currency))?;
+            for (j, amount) in row.amounts.iter().enumerate() {
+                if let Some(amount) = amount {
+                    sheet.write_with_format(
+                        r,
+                        first + j as u16,
+                        cell(*amount),
+                        &money,
+                    )?;
+                }
+            }
+            sheet.write_with_format(r, total_col, cell(row.total), &money)?;
+        }
+        sheet.set_freeze_panes(2, first)?;
+        sheet.set_column_width(0, 12)?;
+        sheet.set_column_width(1, 20)?;
+        workbook.save_to_buffer()
+    }
+}
+
+/// Строка таблицы: конверт попадает в колонку со своими категорией,
+/// именем и валютой, поэтому суммы в ячейке всегда в одной валюте
+fn row(sb: &StorageBudget, envelopes: &[EnvelopeColumn]) -> HistoryRow {
+    let budget = &sb.budget;
+    let mut amounts = vec![None; envelopes.len()];
+    for (category, entry) in entries(sb) {
+        let cell = envelopes
+            .iter()
+            .position(|c| {
+                c.category == category
+                    && c.name == entry.expense.name
+                    && c.currency == entry.amount.currency
+            })
+            .and_then(|i| amounts.get_mut(i));
+        if let Some(cell) = cell {
+            *cell.get_or_insert(Money::new(Decimal::ZERO, entry.amount.currency)) +=
+                entry.amount;
+        }
+    }
+    HistoryRow {
+        date: budget.income.date,
+        source: budget.income.source.name.clone(),
+        income: budget.income.amount,
+        amounts,
+        // Доли конвертов в чужой валюте списаны из остатка уже по курсу
+        total: Money::new(
+            budget.income.amount.value - budget.rest.value,
+            budget.income.amount.currency,
+        ),
+    }
+}
+
+/// Конверты распределения вместе с категориями в порядке колонок таблицы
+fn entries(sb: &StorageBudget) -> Vec<(Option<String>, &BudgetEntry)> {
+    let budget = &sb.budget;
+    let mut categories: Vec<_> = budget.categories.iter().collect();
+    categories.sort_by_key(|(name, _)| *name);
+    budget
+        .no_category
+        .iter()
+        .map(|e| (None, e))
+        .chain(categories.into_iter().flat_map(|(category, entries)| {
+            entries.iter().map(move |e| (Some(category.clone()), e))
+        }))
+        .collect()
+}
+
+/// XLSX хранит числа как f64
+fn cell(money: Money) -> f64 {
+    money.value.to_f64().unwrap_or_default()
+}
+
+fn csv_money(money: Money) -> String {
+    format!("{:.2}", money.value).replace('.', ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use ai_core::{
+        distribute::{Budget, Income},
+        planning::{Expense, ExpenseValue, IncomeKind, IncomeSource},
+    };
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::interfaces::csv_import::{CsvProfile, parse_csv};
+
+    fn budget(
+        id: &str,
+        day: u32,
+        entries: &[(Option<&str>, &str, Decimal)],
+    ) -> StorageBudget {
+        let amount = Money::new_rub(dec!(100000));
+        let source = IncomeSource::new(
+            "Зарплата".into(),
+            IncomeKind::Other { expected: amount },
+        );
+        let date = NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+        let mut budget = Budget::new(Income::new(source, amount, date));
+        for (category, name, value) in entries {
+            let money = Money::new_rub(*value);
+            let category = category.map(String::from);
+            let expense = Expense::envelope(
+                name.to_string(),
+                ExpenseValue::MONEY { value: money },
+                category.clone(),
+            );
+            budget.push(category, BudgetEntry::new(expense, money));
+        }
+        StorageBudget {
+            id: id.into(),
+            budget,
+            plan: None,
+            voided: None,
+        }
+    }
+
+    fn history() -> Vec<StorageBudget> {
+        vec![
+            budget(
+                "b1",
+                1,
+                &[
+                    (Some("Быт"), "Продукты", dec!(20000)),
+                    (None, "Аренда", dec!(30000)),
+                ],
+            ),
+            budget("b2", 15, &[(Some("Капитал"), "Подушка", dec!(10000.5))]),
+        ]
+    }
+
+    #[test]
+    fn table_has_column_per_envelope() {
+        let table = HistoryTable::new(&history());
+
+        let columns: Vec<_> = table
+            .envelopes
+            .iter()
+            .map(|c| (c.category.as_deref(), c.name.as_str()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                (None, "Аренда"),
+                (Some("Быт"), "Продукты"),
+                (Some("Капитал"), "Подушка"),
+            ]
+        );
+        assert_eq!(
+            table.rows[1].amounts,
+            vec![None, None, Some(Money::new_rub(dec!(10000.5)))]
+        );
+        assert_eq!(table.rows[0].total, Money::new_rub(dec!(50000)));
+        assert!(table.write(HistoryFormat::Xlsx).unwrap().starts_with(b"PK"));
+    }
+
+    #[test]
+    fn csv_round_trips_through_excel_profile() {
+        assert_round_trip(&history());
+    }
+
+    #[test]
+    fn mixed_currencies_keep_own_columns() {
+        let mut rub = budget("b1", 1, &[(None, "Аренда", dec!(30000))]);
+        // Фиксированная сумма в долларах: из остатка списано по курсу 90
+        let usd = Money::new(dec!(100), Currency::USD);
+        let expense = Expense::envelope(
+            "Подушка".into(),
+            ExpenseValue::MONEY { value: usd },
+            None,
+        );
+        rub.budget
+            .no_category
+            .push(BudgetEntry::new(expense.clone(), usd));
+        rub.budget.rest -= Money::new_rub(dec!(9000));
+        let mut dollars = budget("b2", 15, &[]);
+        let income = Money::new(dec!(1000), Currency::USD);
+        dollars.budget = Budget::new(Income::new(
+            dollars.budget.income.source.clone(),
+            income,
+            dollars.budget.income.date,
+        ));
+        dollars.budget.push(
+            None,
+            BudgetEntry::new(expense, Money::new(dec!(500), Currency::USD)),
+        );
+        let history = vec![rub, dollars];
+
+        let table = HistoryTable::new(&history);
+        assert_eq!(table.rows[0].total, Money::new_rub(dec!(39000)));
+        assert_eq!(
+            table.rows[0].amounts,
+            vec![Some(Money::new_rub(dec!(30000))), Some(usd)]
+        );
+        assert_eq!(table.rows[1].total, Money::new(dec!(500), Currency::USD));
+
+        let csv = assert_round_trip(&history);
+        assert!(csv.starts_with(
+            "Дата входа,Источник,Вход,Валюта,Аренда (RUB),Подушка (USD),Итого распределено\n"
+        ));
+        assert!(table.write(HistoryFormat::Xlsx).unwrap().starts_with(b"PK"));
+    }
+
+    /// Выгрузка, загруженная обратно, выгружается так же
+    fn assert_round_trip(history: &[StorageBudget]) -> String {
+        let mut exported = Vec::new();
+        HistoryTable::new(history).write_csv(&mut exported).unwrap();
+
+        let import = parse_csv(exported.as_slice(), &CsvProfile::excel()).unwrap();
+        assert!(import.errors.is_empty());
+        let restored: Vec<_> = import
+            .rows
+            .into_iter()
+            .map(|row| StorageBudget {
+                id: row.line.to_string(),
+                budget: row.budget,
+                plan: None,
+                voided: None,
+            })
+            .collect();
+        let mut again = Vec::new();
+        HistoryTable::new(&restored).write_csv(&mut again).unwrap();
+
+        let exported = String::from_utf8(exported).unwrap();
+        assert_eq!(String::from_utf8(again).unwrap(), exported);
+        exported
+    }
+}
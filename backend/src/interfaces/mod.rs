@@ -1,4 +1,7 @@
-pub mod excel_parser;
+pub mod bank_statement;
+pub mod csv_import;
+pub mod history_export;
 pub mod presentation;
 pub mod tree;
+pub mod tui;
 pub mod web;
@@ -1,8 +1,21 @@
 use crate::interfaces::tree::{PlanNode, TreeNode};
+use std::collections::BTreeMap;
+
+use ai_app::{
+    api::Redistribution,
+    report::{Fulfillment, PlanReport},
+};
 use ai_core::{
-    distribute::Budget,
-    finance::{Money, Percentage},
-    planning::DistributionWeights,
+    distribute::{Budget, BudgetEntry},
+    finance::{Currency, Money, Percentage},
+    planning::{
+        CreditPayoff,
+        DistributionWeights,
+        ExpectedIncome,
+        ExpenseValue,
+        IncomeStatus,
+    },
+    simulate::Forecast,
 };
 pub(crate) fn plan_to_tree(plan: &DistributionWeights) -> TreeNode<PlanNode> {
     let mut root = TreeNode::new(PlanNode::Title("План бюджета".to_string()));
@@ -18,8 +31,12 @@ pub(crate) fn plan_to_tree(plan: &DistributionWeights) -> TreeNode<PlanNode> {
     }
     root.add_child(sources_node);
     // Остаток
-    let total_income = plan.sources.iter().map(|s| s.net()).sum::<Money>();
-    let rest_amount = Money::new_rub(plan.rest.apply_to(total_income.value));
+    // Веса построены из проверенного плана, так что курсы для всех валют есть
+    let total_income = plan.total_income().unwrap_or_default();
+    let in_base = |p: &Percentage| {
+        Money::new(p.apply_to(total_income.value), total_income.currency)
+    };
+    let rest_amount = in_base(&plan.rest);
     root.add_child(TreeNode::new(PlanNode::Other(format!(
         "🏦 Остаток: {rest_amount} [{}]",
         plan.rest
@@ -35,14 +52,13 @@ pub(crate) fn plan_to_tree(plan: &DistributionWeights) -> TreeNode<PlanNode> {
         };
         let mut cat_node =
             TreeNode::new(PlanNode::Category(format!("{cat_emoji} {category}")));
-        let mut cat_total_amount = Money::new_rub(rust_decimal::Decimal::ZERO);
+        let mut cat_total_amount =
+            Money::new(rust_decimal::Decimal::ZERO, total_income.currency);
         let mut cat_total_percent = Percentage::ZERO;
         // Итог категории
         for expense in &expenses {
             if let Some(percentage) = plan.get(expense) {
-                let estimated_amount =
-                    Money::new_rub(percentage.apply_to(total_income.value));
-                cat_total_amount += estimated_amount;
+                cat_total_amount += in_base(percentage);
                 cat_total_percent += percentage.clone();
             }
         }
@@ -53,10 +69,22 @@ pub(crate) fn plan_to_tree(plan: &DistributionWeights) -> TreeNode<PlanNode> {
         // Элементы расходов
         for expense in &expenses {
             if let Some(percentage) = plan.get(expense) {
-                let estimated_amount =
-                    Money::new_rub(percentage.apply_to(total_income.value));
+                // Фиксированную сумму показываем в валюте конверта
+                let estimated_amount = match expense.value() {
+                    ExpenseValue::MONEY { value } => plan
+                        .rates
+                        .convert(in_base(percentage), value.currency)
+                        .unwrap_or(value),
+                    ExpenseValue::RATE { .. } => in_base(percentage),
+                };
+                // Привязанный конверт наполняется только из своих источников
+                let name = if expense.is_bound() {
+                    format!("{} ← {}", expense.name, expense.sources.join(", "))
+                } else {
+                    expense.name.clone()
+                };
                 cat_node.add_child(TreeNode::new(PlanNode::Expense {
-                    name: expense.name.clone(),
+                    name,
                     amount: format!("{estimated_amount}"),
                     percent: format!("{percentage}"),
                 }));
@@ -84,19 +112,15 @@ pub(crate) fn budget_to_tree(budget: &Budget) -> TreeNode<PlanNode> {
     if !budget.no_category.is_empty() {
         let mut no_cat_node =
             TreeNode::new(PlanNode::Category("📦 Без категории".to_string()));
-        let mut total = Money::new_rub(rust_decimal::Decimal::ZERO);
-        for entry in &budget.no_category {
-            total += entry.amount;
-        }
         no_cat_node.add_child(TreeNode::new(PlanNode::Total {
-            amount: format!("{total}"),
+            amount: format_total(&budget.no_category),
             percent: String::new(),
         }));
         let mut sorted_entries = budget.no_category.clone();
         sorted_entries.sort_by_key(|e| e.expense.name.clone());
         for entry in &sorted_entries {
             no_cat_node.add_child(TreeNode::new(PlanNode::Expense {
-                name: entry.expense.name.clone(),
+                name: entry_name(entry),
                 amount: format!("{}", entry.amount),
                 percent: String::new(),
             }));
@@ -108,24 +132,241 @@ pub(crate) fn budget_to_tree(budget: &Budget) -> TreeNode<PlanNode> {
     sorted_categories.sort_by_key(|(cat, _)| *cat);
     for (category, entries) in sorted_categories {
         let mut cat_node = TreeNode::new(PlanNode::Category(format!("📂 {category}")));
-        let mut total = Money::new_rub(rust_decimal::Decimal::ZERO);
-        for entry in entries {
-            total += entry.amount;
-        }
         cat_node.add_child(TreeNode::new(PlanNode::Total {
-            amount: format!("{total}"),
+            amount: format_total(entries),
             percent: String::new(),
         }));
         let mut sorted_entries = entries.clone();
         sorted_entries.sort_by_key(|e| e.expense.name.clone());
         for entry in &sorted_entries {
             cat_node.add_child(TreeNode::new(PlanNode::Expense {
-                name: entry.expense.name.clone(),
+                name: entry_name(entry),
                 amount: format!("{}", entry.amount),
                 percent: String::new(),
             }));
         }
         root.add_child(cat_node);
     }
+    // Недополученное при распределении по приоритетам
+    if !budget.underfunded.is_empty() {
+        let mut underfunded_node =
+            TreeNode::new(PlanNode::Category("⚠️ Не хватило дохода".to_string()));
+        for entry in &budget.underfunded {
+            underfunded_node.add_child(TreeNode::new(PlanNode::Expense {
+                name: entry.name.clone(),
+                amount: format!("-{}", entry.shortfall),
+                percent: format!("получено {} из {}", entry.received, entry.planned),
+            }));
+        }
+        root.add_child(underfunded_node);
+    }
+    root
+}
+
+fn entry_name(entry: &BudgetEntry) -> String {
+    if entry.surplus {
+        format!("{} (из остатка)", entry.expense.name)
+    } else {
+        entry.expense.name.clone()
+    }
+}
+
+/// Итог по конвертам; суммы в разных валютах выводятся раздельно
+fn format_total(entries: &[BudgetEntry]) -> String {
+    let mut totals: BTreeMap<Currency, Money> = BTreeMap::new();
+    for entry in entries {
+        *totals.entry(entry.amount.currency).or_insert_with(|| {
+            Money::new(rust_decimal::Decimal::ZERO, entry.amount.currency)
+        }) += entry.amount;
+    }
+    if totals.is_empty() {
+        return Money::default().to_string();
+    }
+    totals
+        .values()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+pub(crate) fn credits_to_tree(credits: &[CreditPayoff]) -> TreeNode<PlanNode> {
+    let mut root = TreeNode::new(PlanNode::Title("Графики погашения".to_string()));
+    for credit in credits {
+        let mut credit_node =
+            TreeNode::new(PlanNode::Category(format!("🏦 {}", credit.name)));
+        credit_node.add_child(TreeNode::new(PlanNode::Other(format!(
+            "Платёж: {}",
+            credit.monthly_payment
+        ))));
+        if !credit.payment_matches_terms {
+            credit_node.add_child(TreeNode::new(PlanNode::Other(format!(
+                "⚠️ По ставке и сроку платёж должен быть {}",
+                credit.expected_payment
+            ))));
+        }
+        credit_node.add_child(TreeNode::new(PlanNode::Total {
+            amount: format!("Остаток долга: {}", credit.remaining_principal),
+            percent: String::new(),
+        }));
+        for installment in &credit.schedule.installments {
+            credit_node.add_child(TreeNode::new(PlanNode::Expense {
+                name: format!("{:>3}. {}", installment.number, installment.date),
+                amount: format!("{}", installment.payment),
+                percent: format!(
+                    "долг {}, проценты {}, остаток {}",
+                    installment.principal, installment.interest, installment.remaining
+                ),
+            }));
+        }
+        root.add_child(credit_node);
+    }
+    root
+}
+
+pub(crate) fn expected_incomes_to_tree(
+    incomes: &[ExpectedIncome],
+) -> TreeNode<PlanNode> {
+    let mut root = TreeNode::new(PlanNode::Title("Ожидаемые доходы".to_string()));
+    if incomes.is_empty() {
+        root.add_child(TreeNode::new(PlanNode::Other(
+            "У источников дохода нет графика поступлений".to_string(),
+        )));
+    }
+    for income in incomes {
+        let status = match income.status {
+            IncomeStatus::Arrived => "✅",
+            IncomeStatus::Late => "⏰ опаздывает",
+            IncomeStatus::Pending => "⏳ ожидается",
+        };
+        let details = match (income.actual, income.actual_date) {
+            (Some(actual), Some(date)) => {
+                let difference = income
+                    .difference
+                    .map(|d| format!(", разница {d}"))
+                    .unwrap_or_default();
+                format!("пришло {actual} {date}{difference}")
+            }
+            _ => format!("план {}", income.expected),
+        };
+        root.add_child(TreeNode::new(PlanNode::Expense {
+            name: format!("{} {}", income.date, income.source),
+            amount: status.to_string(),
+            percent: details,
+        }));
+    }
+    root
+}
+
+pub(crate) fn redistribution_to_tree(
+    redistribution: &Redistribution,
+) -> TreeNode<PlanNode> {
+    let mut root =
+        TreeNode::new(PlanNode::Title("Перераспределение дохода".to_string()));
+    let original = redistribution
+        .original
+        .as_ref()
+        .map_or("неизвестна".to_string(), |p| {
+            p.version.to_string()
+        });
+    root.add_child(TreeNode::new(PlanNode::Other(format!(
+        "📜 Версия плана: {original} → {}",
+        redistribution.target.version
+    ))));
+    root.add_child(TreeNode::new(PlanNode::Other(format!(
+        "🏦 Остаток: {}",
+        redistribution.budget.rest
+    ))));
+    for change in &redistribution.changes {
+        let sign = if change.difference.value.is_sign_positive() {
+            "+"
+        } else {
+            ""
+        };
+        root.add_child(TreeNode::new(PlanNode::Expense {
+            name: change.name.clone(),
+            amount: format!("{} → {}", change.before, change.after),
+            percent: format!("{sign}{}", change.difference),
+        }));
+    }
+    root
+}
+
+pub(crate) fn plan_report_to_tree(report: &PlanReport) -> TreeNode<PlanNode> {
+    let mut root = TreeNode::new(PlanNode::Title(format!(
+        "План/факт за {}",
+        report.month.format("%Y-%m")
+    )));
+    root.add_child(TreeNode::new(PlanNode::Other(format!(
+        "💸 Распределений: {}",
+        report.budgets
+    ))));
+    for category in &report.categories {
+        let title = match &category.category {
+            Some(category) => format!("📂 {category}"),
+            None => "📦 Без категории".to_string(),
+        };
+        let mut cat_node = TreeNode::new(PlanNode::Category(title));
+        cat_node.add_child(TreeNode::new(PlanNode::Total {
+            amount: fulfillment_amount(&category.amounts),
+            percent: fulfillment_details(&category.amounts),
+        }));
+        for expense in &category.expenses {
+            cat_node.add_child(TreeNode::new(PlanNode::Expense {
+                name: expense.name.clone(),
+                amount: fulfillment_amount(&expense.amounts),
+                percent: fulfillment_details(&expense.amounts),
+            }));
+        }
+        root.add_child(cat_node);
+    }
+    root.add_child(TreeNode::new(PlanNode::Total {
+        amount: fulfillment_amount(&report.total),
+        percent: fulfillment_details(&report.total),
+    }));
     root
 }
+
+pub(crate) fn forecast_to_tree(forecast: &Forecast) -> TreeNode<PlanNode> {
+    let mut root = TreeNode::new(PlanNode::Title("Прогноз по плану".to_string()));
+    for month in &forecast.months {
+        let mut month_node = TreeNode::new(PlanNode::Category(format!(
+            "📅 {}",
+            month.month.format("%Y-%m")
+        )));
+        month_node.add_child(TreeNode::new(PlanNode::Total {
+            amount: format!("Доход: {}", month.income),
+            percent: format!("остаток {}", month.rest),
+        }));
+        for (name, balance) in &month.envelopes {
+            month_node.add_child(TreeNode::new(PlanNode::Expense {
+                name: name.clone(),
+                amount: balance.to_string(),
+                percent: month
+                    .credits
+                    .get(name)
+                    .map(|remaining| format!("долг {remaining}"))
+                    .unwrap_or_default(),
+            }));
+        }
+        root.add_child(month_node);
+    }
+    root
+}
+
+/// Факт из плана
+fn fulfillment_amount(amounts: &Fulfillment) -> String {
+    format!("{} из {}", amounts.actual, amounts.planned)
+}
+
+/// Разница с планом и процент выполнения
+fn fulfillment_details(amounts: &Fulfillment) -> String {
+    let sign = if amounts.difference.value.is_sign_positive() {
+        "+"
+    } else {
+        ""
+    };
+    match &amounts.fulfilled {
+        Some(fulfilled) => format!("{sign}{}, {fulfilled}", amounts.difference),
+        None => format!("{sign}{}, вне плана", amounts.difference),
+    }
+}
@@ -0,0 +1,742 @@
+//! Полноэкранный интерфейс в терминале: дерево плана, история распределений
+//! с деталями и ввод дохода с предпросмотром распределения.
+//!
+//! Состояние и обработка клавиш в [`App`] не зависят от терминала,
+//! а [`run`] рисует состояние и выполняет запрошенные действия через [`CoreApi`].
+
+use std::io;
+
+use ai_app::{
+    api::{CoreApi, DistributedIncome},
+    storage::{BudgetId, CoreRepo, StorageBudget, StoragePlan, UserId, build_id},
+};
+use ai_core::{
+    distribute::{Budget, Income},
+    finance::Money,
+    planning::{DistributionWeights, IncomeSource, source_for_date},
+};
+use chrono::NaiveDate;
+use ratatui::{
+    DefaultTerminal,
+    Frame,
+    TerminalOptions,
+    Viewport,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        terminal::disable_raw_mode,
+    },
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs},
+};
+use rust_decimal::Decimal;
+use tracing::warn;
+
+use crate::interfaces::{
+    presentation::{budget_to_tree, plan_to_tree},
+    tree::{PlanNode, TreeNode},
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tab {
+    Plan,
+    History,
+}
+
+/// Поле формы дохода, куда идёт ввод с клавиатуры
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Amount,
+    Date,
+}
+
+#[derive(Debug, Clone)]
+struct IncomeForm {
+    amount: String,
+    date: String,
+    field: Field,
+    source: usize,
+    /// Источник выбран вручную и больше не подбирается по дате
+    source_chosen: bool,
+    error: Option<String>,
+}
+
+enum Mode {
+    Browse,
+    Income(IncomeForm),
+    /// Распределение посчитано и ждёт подтверждения
+    Preview {
+        form: IncomeForm,
+        distributed: Box<DistributedIncome>,
+    },
+}
+
+/// Что нужно сделать после нажатия клавиши
+#[derive(Debug, PartialEq)]
+pub(crate) enum Action {
+    None,
+    Quit,
+    Distribute(Income),
+    Save(DistributedIncome),
+}
+
+pub(crate) struct App {
+    plan: DistributionWeights,
+    plan_tree: TreeNode<PlanNode>,
+    /// Распределения, сначала новые
+    history: Vec<StorageBudget>,
+    selected: usize,
+    plan_scroll: usize,
+    tab: Tab,
+    mode: Mode,
+    status: Option<String>,
+    today: NaiveDate,
+}
+
+impl App {
+    pub(crate) fn new(
+        plan: DistributionWeights,
+        history: Vec<StorageBudget>,
+        today: NaiveDate,
+    ) -> Self {
+        Self {
+            plan_tree: plan_to_tree(&plan),
+            plan,
+            history: newest_first(history),
+            selected: 0,
+            plan_scroll: 0,
+            tab: Tab::Plan,
+            mode: Mode::Browse,
+            status: None,
+            today,
+        }
+    }
+
+    pub(crate) fn on_key(&mut self, key: KeyEvent) -> Action {
+        if key.kind != KeyEventKind::Press {
+            return Action::None;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL)
+            && key.code == KeyCode::Char('c')
+        {
+            return Action::Quit;
+        }
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => self.browse_key(key.code),
+            Mode::Income(form) => self.income_key(form, key.code),
+            Mode::Preview { form, distributed } => {
+                self.preview_key(form, distributed, key.code)
+            }
+        }
+    }
+
+    /// Результат расчёта распределения для формы дохода
+    pub(crate) fn on_distributed(&mut self, result: Result<DistributedIncome, String>) {
+        let Mode::Income(mut form) = std::mem::replace(&mut self.mode, Mode::Browse)
+        else {
+            return;
+        };
+        self.mode = match result {
+            Ok(distributed) => Mode::Preview {
+                form,
+                distributed: Box::new(distributed),
+            },
+            Err(e) => {
+                form.error = Some(format!("Не удалось распределить: {e}"));
+                Mode::Income(form)
+            }
+        };
+    }
+
+    /// Распределение сохранено: показываем его в обновлённой истории
+    pub(crate) fn on_saved(&mut self, id: BudgetId, history: Vec<StorageBudget>) {
+        self.history = newest_first(history);
+        self.selected = self.history.iter().position(|sb| sb.id == id).unwrap_or(0);
+        self.tab = Tab::History;
+        self.mode = Mode::Browse;
+        self.status = Some(format!("💾 Бюджет сохранён с ID: {id}"));
+    }
+
+    pub(crate) fn on_save_failed(&mut self, error: String) {
+        self.status = Some(format!("❌ Не удалось сохранить: {error}"));
+    }
+
+    fn browse_key(&mut self, code: KeyCode) -> Action {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                self.tab = match self.tab {
+                    Tab::Plan => Tab::History,
+                    Tab::History => Tab::Plan,
+                };
+            }
+            KeyCode::Char('1') => self.tab = Tab::Plan,
+            KeyCode::Char('2') => self.tab = Tab::History,
+            KeyCode::Up | KeyCode::Char('k') => match self.tab {
+                Tab::Plan => self.plan_scroll = self.plan_scroll.saturating_sub(1),
+                Tab::History => self.selected = self.selected.saturating_sub(1),
+            },
+            KeyCode::Down | KeyCode::Char('j') => match self.tab {
+                Tab::Plan => self.plan_scroll += 1,
+                Tab::History => {
+                    self.selected =
+                        (self.selected + 1).min(self.history.len().saturating_sub(1));
+                }
+            },
+            KeyCode::Char('i') => {
+                self.status = None;
+                self.mode = Mode::Income(self.new_form());
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn income_key(&mut self, mut form: IncomeForm, code: KeyCode) -> Action {
+        form.error = None;
+        match code {
+            KeyCode::Esc => return Action::None,
+            KeyCode::Enter => match self.parse(&form) {
+                Ok(income) => {
+                    self.mode = Mode::Income(form);
+                    return Action::Distribute(income);
+                }
+                Err(e) => form.error = Some(e),
+            },
+            KeyCode::Tab | KeyCode::BackTab => {
+                form.field = match form.field {
+                    Field::Amount => Field::Date,
+                    Field::Date => Field::Amount,
+                };
+            }
+            KeyCode::Up | KeyCode::Down => {
+                form.source =
+                    move_selection(form.source, self.plan.sources.len(), code);
+                form.source_chosen = true;
+            }
+            KeyCode::Backspace => {
+                match form.field {
+                    Field::Amount => form.amount.pop(),
+                    Field::Date => form.date.pop(),
+                };
+                self.follow_date(&mut form);
+            }
+            KeyCode::Char(c) => {
+                match form.field {
+                    Field::Amount if c.is_ascii_digit() || c == '.' => {
+                        form.amount.push(c)
+                    }
+                    Field::Amount if c == ',' => form.amount.push('.'),
+                    Field::Date if c.is_ascii_digit() || c == '-' => form.date.push(c),
+                    _ => {}
+                }
+                self.follow_date(&mut form);
+            }
+            _ => {}
+        }
+        self.mode = Mode::Income(form);
+        Action::None
+    }
+
+    fn preview_key(
+        &mut self,
+        form: IncomeForm,
+        distributed: Box<DistributedIncome>,
+        code: KeyCode,
+    ) -> Action {
+        match code {
+            KeyCode::Esc => {
+                self.mode = Mode::Income(form);
+                Action::None
+            }
+            KeyCode::Enter | KeyCode::Char('s') => {
+                let action = Action::Save((*distributed).clone());
+                self.mode = Mode::Preview { form, distributed };
+                action
+            }
+            _ => {
+                self.mode = Mode::Preview { form, distributed };
+                Action::None
+            }
+        }
+    }
+
+    fn new_form(&self) -> IncomeForm {
+        IncomeForm {
+            amount: String::new(),
+            date: self.today.format(DATE_FORMAT).to_string(),
+            field: Field::Amount,
+            source: self.scheduled_source(self.today).unwrap_or(0),
+            source_chosen: false,
+            error: None,
+        }
+    }
+
+    /// Пока источник не выбран вручную, он подбирается по графику на введённую дату
+    fn follow_date(&self, form: &mut IncomeForm) {
+        if form.source_chosen {
+            return;
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(&form.date, DATE_FORMAT)
+            && let Some(source) = self.scheduled_source(date)
+        {
+            form.source = source;
+        }
+    }
+
+    fn scheduled_source(&self, date: NaiveDate) -> Option<usize> {
+        let source = source_for_date(&self.plan.sources, date)?;
+        self.plan.sources.iter().position(|s| s == source)
+    }
+
+    fn parse(&self, form: &IncomeForm) -> Result<Income, String> {
+        let amount: Decimal = form
+            .amount
+            .parse()
+            .map_err(|_| "Неверная сумма".to_string())?;
+        if amount <= Decimal::ZERO {
+            return Err("Сумма должна быть больше 0".to_string());
+        }
+        let date = NaiveDate::parse_from_str(&form.date, DATE_FORMAT)
+            .map_err(|_| "Дата в формате ГГГГ-ММ-ДД".to_string())?;
+        let source = self
+            .plan
+            .sources
+            .get(form.source)
+            .ok_or_else(|| "В плане нет источников дохода".to_string())?;
+        let currency = source.net().currency;
+        Ok(Income::new(
+            source.clone(),
+            Money::new(amount, currency),
+            date,
+        ))
+    }
+
+    pub(crate) fn draw(&self, frame: &mut Frame) {
+        let [tabs, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let selected = match self.tab {
+            Tab::Plan => 0,
+            Tab::History => 1,
+        };
+        frame.render_widget(
+            Tabs::new(["1 План", "2 История"])
+                .select(selected)
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            tabs,
+        );
+        match self.tab {
+            Tab::Plan => self.draw_plan(frame, body),
+            Tab::History => self.draw_history(frame, body),
+        }
+        frame.render_widget(Paragraph::new(self.footer()), footer);
+
+        match &self.mode {
+            Mode::Browse => {}
+            Mode::Income(form) => self.draw_income(frame, form),
+            Mode::Preview { distributed, .. } => {
+                draw_preview(frame, &distributed.budget)
+            }
+        }
+    }
+
+    fn footer(&self) -> String {
+        if let Some(status) = &self.status {
+            return status.clone();
+        }
+        match self.mode {
+            Mode::Browse => "i — доход · Tab — вкладка · ↑/↓ — выбор · q — выход",
+            Mode::Income(_) => {
+                "Enter — посчитать · Tab — сумма/дата · ↑/↓ — источник · Esc — отмена"
+            }
+            Mode::Preview { .. } => "Enter — сохранить · Esc — изменить",
+        }
+        .to_string()
+    }
+
+    fn draw_plan(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = (&self.plan_tree).into();
+        let offset = self.plan_scroll.min(items.len().saturating_sub(1));
+        let mut state = ListState::default().with_offset(offset);
+        frame.render_stateful_widget(
+            List::new(items).block(Block::new().borders(Borders::ALL)),
+            area,
+            &mut state,
+        );
+    }
+
+    fn draw_history(&self, frame: &mut Frame, area: Rect) {
+        let [list_area, details_area] = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(60),
+        ])
+        .areas(area);
+        let block =
+            |title: &'static str| Block::new().borders(Borders::ALL).title(title);
+
+        let Some(current) = self.history.get(self.selected) else {
+            frame.render_widget(
+                Paragraph::new("Распределений пока нет").block(block("История")),
+                area,
+            );
+            return;
+        };
+        let items: Vec<ListItem> = self
+            .history
+            .iter()
+            .map(|sb| {
+                let income = &sb.budget.income;
+                ListItem::new(format!(
+                    "{} {} {}",
+                    income.date, income.source.name, income.amount
+                ))
+            })
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(block("История"))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            list_area,
+            &mut state,
+        );
+
+        let tree = budget_to_tree(&current.budget);
+        let details: Vec<ListItem> = (&tree).into();
+        frame.render_widget(
+            List::new(details).block(block("Распределение")),
+            details_area,
+        );
+    }
+
+    fn draw_income(&self, frame: &mut Frame, form: &IncomeForm) {
+        let field = |title: &str, value: &str, active: bool| {
+            let style = if active {
+                Style::new().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::new()
+            };
+            Line::from(vec![
+                Span::raw(format!("{title:<7}")),
+                Span::styled(format!("{value:<12}"), style),
+            ])
+        };
+        let mut lines = vec![
+            field("Сумма:", &form.amount, form.field == Field::Amount),
+            field("Дата:", &form.date, form.field == Field::Date),
+            Line::raw(""),
+            Line::raw("Источник:"),
+        ];
+        lines.extend(source_lines(&self.plan.sources, form.source));
+        if let Some(error) = &form.error {
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(error.clone(), Style::new().fg(Color::Red)));
+        }
+
+        let height = u16::try_from(lines.len() + 2).unwrap_or(u16::MAX);
+        let area = popup(
+            frame.area(),
+            Constraint::Length(60),
+            Constraint::Length(height),
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::new()
+                    .borders(Borders::ALL)
+                    .title("Поступление дохода"),
+            ),
+            area,
+        );
+    }
+}
+
+fn draw_preview(frame: &mut Frame, budget: &Budget) {
+    let area = popup(
+        frame.area(),
+        Constraint::Percentage(80),
+        Constraint::Percentage(80),
+    );
+    let tree = budget_to_tree(budget);
+    let items: Vec<ListItem> = (&tree).into();
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        List::new(items).block(
+            Block::new()
+                .borders(Borders::ALL)
+                .title("Предпросмотр распределения")
+                .title_bottom("Enter — сохранить · Esc — изменить"),
+        ),
+        area,
+    );
+}
+
+fn newest_first(mut history: Vec<StorageBudget>) -> Vec<StorageBudget> {
+    history.reverse();
+    history
+}
+
+/// Прямоугольник по центру `area`
+fn popup(area: Rect, width: Constraint, height: Constraint) -> Rect {
+    let [area] = Layout::horizontal([width]).flex(Flex::Center).areas(area);
+    let [area] = Layout::vertical([height]).flex(Flex::Center).areas(area);
+    area
+}
+
+/// Выбор по кругу стрелками вверх и вниз
+fn move_selection(selected: usize, len: usize, code: KeyCode) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    match code {
+        KeyCode::Up => (selected + len - 1) % len,
+        KeyCode::Down => (selected + 1) % len,
+        _ => selected,
+    }
+}
+
+fn source_lines(sources: &[IncomeSource], selected: usize) -> Vec<Line<'static>> {
+    sources
+        .iter()
+        .enumerate()
+        .map(|(n, source)| {
+            let text = format!("{} [{}]", source.name, source.net());
+            if n == selected {
+                Line::styled(
+                    format!("› {text}"),
+                    Style::new().add_modifier(Modifier::REVERSED),
+                )
+            } else {
+                Line::raw(format!("  {text}"))
+            }
+        })
+        .collect()
+}
+
+/// Полноэкранный интерфейс до выхода по `q`. Доходы распределяются
+/// по плану `sp`, `plan` - его доли
+pub(crate) async fn run<R>(
+    api: &CoreApi<R>,
+    user_id: &UserId,
+    sp: StoragePlan,
+    plan: DistributionWeights,
+) -> io::Result<()>
+where
+    R: CoreRepo + Clone + Send + Sync + 'static,
+{
+    let history = api.budget_history(user_id, None, None).await;
+    let mut app = App::new(plan, history, chrono::Local::now().date_naive());
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut app, api, user_id, &sp).await;
+    ratatui::restore();
+    result
+}
+
+async fn event_loop<R>(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    api: &CoreApi<R>,
+    user_id: &UserId,
+    sp: &StoragePlan,
+) -> io::Result<()>
+where
+    R: CoreRepo + Clone + Send + Sync + 'static,
+{
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        match app.on_key(key) {
+            Action::None => {}
+            Action::Quit => return Ok(()),
+            Action::Distribute(income) => {
+                let result = api
+                    .distribute_income(user_id, sp, &income)
+                    .await
+                    .map_err(|e| e.to_string());
+                app.on_distributed(result);
+            }
+            Action::Save(DistributedIncome { budget, plan }) => {
+                match api
+                    .save_budget(user_id, build_id(), budget, Some(plan))
+                    .await
+                {
+                    Ok(id) => {
+                        let history = api.budget_history(user_id, None, None).await;
+                        app.on_saved(id, history);
+                    }
+                    Err(e) => app.on_save_failed(e.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// Возвращает терминал в обычный режим при любом выходе из выбора,
+/// в том числе по ошибке отрисовки или чтения клавиш
+struct RawModeGuard;
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        if let Err(e) = disable_raw_mode() {
+            warn!("Не удалось вернуть терминал в обычный режим: {e}");
+        }
+        println!();
+    }
+}
+
+/// Выбор источника дохода стрелками прямо под строкой вывода.
+/// `None` - выбор отменён
+pub(crate) fn pick_source(sources: &[IncomeSource]) -> io::Result<Option<usize>> {
+    let height = u16::try_from(sources.len() + 1).unwrap_or(u16::MAX);
+    let mut terminal = ratatui::try_init_with_options(TerminalOptions {
+        viewport: Viewport::Inline(height),
+    })?;
+    let _guard = RawModeGuard;
+    let mut selected = 0;
+    loop {
+        terminal.draw(|frame| {
+            let mut lines = source_lines(sources, selected);
+            lines.push(Line::styled(
+                "↑/↓ — выбор · Enter — подтвердить · Esc — отмена",
+                Style::new().add_modifier(Modifier::DIM),
+            ));
+            frame.render_widget(Paragraph::new(lines), frame.area());
+        })?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(Some(selected)),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            code => selected = move_selection(selected, sources.len(), code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ai_app::storage::PlanRef;
+    use ai_core::{
+        plan::Plan,
+        planning::{Expense, ExpenseValue, IncomeKind},
+    };
+    use ratatui::{Terminal, backend::TestBackend};
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            assert_eq!(app.on_key(key(KeyCode::Char(c))), Action::None);
+        }
+    }
+
+    fn source(name: &str, expected: Decimal) -> IncomeSource {
+        IncomeSource::new(
+            name.into(),
+            IncomeKind::Other {
+                expected: Money::new_rub(expected),
+            },
+        )
+    }
+
+    fn app() -> App {
+        let plan = Plan::build(
+            &[
+                source("Зарплата", dec!(100000)),
+                source("Фриланс", dec!(20000)),
+            ],
+            &[Expense::envelope(
+                "Аренда".into(),
+                ExpenseValue::MONEY {
+                    value: Money::new_rub(dec!(30000)),
+                },
+                None,
+            )],
+        );
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        App::new(plan.try_into().unwrap(), vec![], today)
+    }
+
+    fn screen(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn income_is_previewed_and_saved() {
+        let mut app = app();
+        app.on_key(key(KeyCode::Char('i')));
+        type_text(&mut app, "5000,5");
+        app.on_key(key(KeyCode::Down));
+
+        let Action::Distribute(income) = app.on_key(key(KeyCode::Enter)) else {
+            panic!("ожидали расчёт распределения");
+        };
+        assert_eq!(income.source.name, "Фриланс");
+        assert_eq!(income.amount, Money::new_rub(dec!(5000.5)));
+        assert_eq!(income.date, NaiveDate::from_ymd_opt(2026, 3, 10).unwrap());
+
+        let budget = Budget::new(income);
+        let distributed = DistributedIncome {
+            budget: budget.clone(),
+            plan: PlanRef {
+                plan_id: "p1".into(),
+                version: 1,
+            },
+        };
+        app.on_distributed(Ok(distributed.clone()));
+        assert!(screen(&app).contains("Предпросмотр распределения"));
+        assert_eq!(app.on_key(key(KeyCode::Enter)), Action::Save(distributed));
+
+        let saved = StorageBudget::from(("b1".to_string(), budget));
+        app.on_saved("b1".into(), vec![saved]);
+        let screen = screen(&app);
+        assert!(screen.contains("Бюджет сохранён с ID: b1"));
+        assert!(screen.contains("2026-03-10 Фриланс"));
+    }
+
+    #[test]
+    fn invalid_amount_keeps_form_open() {
+        let mut app = app();
+        app.on_key(key(KeyCode::Char('i')));
+
+        assert_eq!(app.on_key(key(KeyCode::Enter)), Action::None);
+        assert!(screen(&app).contains("Неверная сумма"));
+
+        // Esc закрывает форму, второй Esc - выход
+        assert_eq!(app.on_key(key(KeyCode::Esc)), Action::None);
+        assert_eq!(app.on_key(key(KeyCode::Esc)), Action::Quit);
+    }
+
+    #[test]
+    fn plan_tab_shows_plan_tree() {
+        let screen = screen(&app());
+        assert!(screen.contains("План бюджета"));
+        assert!(screen.contains("Аренда"));
+    }
+}
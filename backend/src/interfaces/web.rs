@@ -1,27 +1,60 @@
+use crate::interfaces::{
+    bank_statement::{StatementFormat, parse_statement},
+    csv_import::CsvProfile,
+    history_export::{HistoryFormat, HistoryTable},
+};
 use ai_app::{
-    api::{CoreApi, Error as AppError},
+    analytics::{Analytics, Granularity, SAVINGS_CATEGORIES},
+    api::{
+        Contribution,
+        CoreApi,
+        DistributedIncome,
+        Error as AppError,
+        Redistribution,
+    },
+    archive::{Archive, ImportMode, ImportReport},
+    auth::{AuthApi, Error as AuthError, IssuedSession},
+    ledger::{EnvelopeBalance, LedgerEntry, LedgerOperation},
+    report::PlanReport,
+    statement::{Confirmation, MatchRule, Reconciliation},
     storage::{
+        Account,
+        AccountRepo,
         BudgetId,
         CoreRepo,
+        DEFAULT_PLAN_NAME,
+        DEFAULT_USER_ID,
         Page,
         PlanDraft,
+        PlanEvent,
         PlanId,
         StorageBudget,
         StoragePlan,
         UserId,
+        Version,
         build_id,
     },
 };
 use ai_core::{
-    distribute::{Budget, Income},
-    finance::Money,
+    distribute::Income,
+    finance::{Currency, Money},
+    plan::PlanDiff,
+    planning::{CreditPayoff, ExpectedIncome, GoalProgress, RepaymentKind},
+    simulate::{Forecast, Scenario},
 };
 use axum::{
+    Extension,
     Json,
     Router,
-    extract::{FromRequestParts, Path, Query, State},
-    http::{StatusCode, request::Parts},
-    response::IntoResponse,
+    extract::{DefaultBodyLimit, FromRequestParts, Path, Query, Request, State},
+    http::{
+        HeaderMap,
+        StatusCode,
+        header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE},
+        request::Parts,
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 use chrono::NaiveDate;
@@ -30,10 +63,17 @@ use serde::{Deserialize, Serialize};
 use tower_http::cors::CorsLayer;
 use tracing::info;
 
+/// Архив со всей историей больше обычного запроса
+const ARCHIVE_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
 #[derive(Debug)]
 enum ApiError {
+    /// Нет действующей сессии
+    Unauthorized,
     NotFound,
     Conflict(String),
+    /// План успели изменить: клиент правил устаревшую версию
+    StaleVersion(Version),
     Validation(String),
     Storage(String),
     Internal,
@@ -42,8 +82,16 @@ enum ApiError {
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let (status, message) = match self {
+            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".into()),
             ApiError::NotFound => (StatusCode::NOT_FOUND, "not found".into()),
             ApiError::Conflict(e) => (StatusCode::CONFLICT, e),
+            ApiError::StaleVersion(current) => {
+                let body = Json(serde_json::json!({
+                    "error": "plan version conflict",
+                    "current_version": current,
+                }));
+                return (StatusCode::CONFLICT, body).into_response();
+            }
             ApiError::Validation(e) => (StatusCode::UNPROCESSABLE_ENTITY, e),
             ApiError::Storage(e) => (StatusCode::BAD_REQUEST, e),
             ApiError::Internal => {
@@ -69,32 +117,146 @@ async fn plan_handler<R: CoreRepo>(
         .ok_or(ApiError::NotFound)
 }
 
+async fn plans_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+) -> Success<Vec<StoragePlan>> {
+    Success::new(api.list_plans(&user_id).await)
+}
+
+async fn shared_plans_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+) -> Success<Vec<StoragePlan>> {
+    Success::new(api.shared_plans(&user_id).await)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CreatePlanQuery {
+    name: Option<String>,
+}
+
 async fn create_plan_handler<R: CoreRepo>(
     State(api): State<CoreApi<R>>,
     CurrentUser(user_id): CurrentUser,
+    Query(CreatePlanQuery { name }): Query<CreatePlanQuery>,
     Json(draft): Json<PlanDraft>,
 ) -> Result<Success<PlanId>, ApiError> {
-    api.create_plan(&user_id, build_id(), draft)
+    let name = name.unwrap_or_else(|| DEFAULT_PLAN_NAME.into());
+    api.create_plan(&user_id, build_id(), name, draft)
+        .await
+        .map(Success::new)
+        .map_err(plan_error)
+}
+
+/// Ошибки операций над планами, общие для создания, переименования и копирования
+fn plan_error(e: AppError) -> ApiError {
+    match e {
+        AppError::PlanNotFound => ApiError::NotFound,
+        AppError::PlanAlreadyExists => ApiError::Conflict(e.to_string()),
+        AppError::InvalidPlan { .. } | AppError::EmptyPlanName => {
+            ApiError::Validation(e.to_string())
+        }
+        _ => ApiError::Internal,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PlanNameRequest {
+    name: String,
+}
+
+async fn rename_plan_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(plan_id): Path<PlanId>,
+    Json(PlanNameRequest { name }): Json<PlanNameRequest>,
+) -> Result<StatusCode, ApiError> {
+    api.rename_plan(&user_id, &plan_id, name)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(plan_error)
+}
+
+async fn clone_plan_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(plan_id): Path<PlanId>,
+    Json(PlanNameRequest { name }): Json<PlanNameRequest>,
+) -> Result<Success<PlanId>, ApiError> {
+    api.clone_plan(&user_id, &plan_id, build_id(), name)
+        .await
+        .map(Success::new)
+        .map_err(plan_error)
+}
+
+async fn activate_plan_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(plan_id): Path<PlanId>,
+) -> Result<StatusCode, ApiError> {
+    api.activate_plan(&user_id, &plan_id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(plan_error)
+}
+
+async fn contribute_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(plan_id): Path<PlanId>,
+    Json(income): Json<NewIncome>,
+) -> Result<Success<StorageBudget>, ApiError> {
+    let NewIncome {
+        source_id,
+        amount,
+        date,
+    } = income;
+    info!(source_id = source_id, date = %date, amount = %amount);
+    api.contribute(&user_id, &plan_id, build_id(), &source_id, amount, date)
         .await
         .map(Success::new)
         .map_err(|e| match e {
-            AppError::PlanAlreadyExists => ApiError::Conflict(e.to_string()),
-            AppError::InvalidPlan { .. } => ApiError::Validation(e.to_string()),
+            AppError::PlanNotFound | AppError::SourceNotFound => ApiError::NotFound,
+            AppError::ForeignSource | AppError::InvalidPlan { .. } => {
+                ApiError::Validation(e.to_string())
+            }
             _ => ApiError::Internal,
         })
 }
 
+async fn contributions_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(plan_id): Path<PlanId>,
+) -> Result<Success<Vec<Contribution>>, ApiError> {
+    api.contributions(&user_id, &plan_id)
+        .await
+        .map(Success::new)
+        .map_err(plan_error)
+}
+
+/// Версия плана, которую правил клиент
+#[derive(Debug, Deserialize)]
+struct ExpectedVersion {
+    version: Version,
+}
+
 async fn update_plan_handler<R: CoreRepo>(
     State(api): State<CoreApi<R>>,
     CurrentUser(user_id): CurrentUser,
     Path(plan_id): Path<PlanId>,
+    Query(ExpectedVersion { version }): Query<ExpectedVersion>,
     Json(draft): Json<PlanDraft>,
 ) -> Result<StatusCode, ApiError> {
-    api.update_plan(&user_id, plan_id, draft)
+    api.update_plan(&user_id, plan_id, version, draft)
         .await
         .map(|_| StatusCode::NO_CONTENT)
         .map_err(|e| match e {
             AppError::PlanNotFound => ApiError::NotFound,
+            AppError::PlanVersionConflict { current } => {
+                ApiError::StaleVersion(current)
+            }
             AppError::InvalidPlan { .. } => ApiError::Validation(e.to_string()),
             _ => ApiError::Internal,
         })
@@ -111,6 +273,37 @@ async fn delete_plan_handler<R: CoreRepo>(
         .map_err(|_| ApiError::Internal)
 }
 
+async fn plan_events_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(plan_id): Path<PlanId>,
+    Query(params): Query<PaginationQuery>,
+) -> Success<Page<PlanEvent>> {
+    let PaginationQuery { from, limit } = params;
+    Success::new(api.plan_events(&user_id, &plan_id, from, limit).await)
+}
+
+#[derive(Debug, Deserialize)]
+struct PlanDiffQuery {
+    from: Version,
+    to: Version,
+}
+
+async fn plan_diff_handler<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(plan_id): Path<PlanId>,
+    Query(params): Query<PlanDiffQuery>,
+) -> Result<Success<PlanDiff>, ApiError> {
+    api.plan_diff(&user_id, &plan_id, params.from, params.to)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::PlanVersionNotFound => ApiError::NotFound,
+            _ => ApiError::Internal,
+        })
+}
+
 #[derive(Debug, Deserialize)]
 struct PaginationQuery {
     from: Option<ai_app::storage::Cursor>,
@@ -119,13 +312,120 @@ struct PaginationQuery {
 
 async fn history<R: CoreRepo>(
     State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
     Query(params): Query<PaginationQuery>,
 ) -> Success<Page<StorageBudget>> {
     let PaginationQuery { from, limit } = params;
-    let page = api.budget_list(from, limit).await;
+    let page = api.budget_list(&user_id, from, limit).await;
     Success::new(page)
 }
 
+#[derive(Debug, Deserialize)]
+struct HistoryExportQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    #[serde(default)]
+    format: HistoryFormat,
+}
+
+/// Таблица с историей распределений для скачивания
+async fn history_export<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Query(params): Query<HistoryExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let HistoryExportQuery { from, to, format } = params;
+    let budgets = api.budget_history(&user_id, from, to).await;
+    let content = HistoryTable::new(&budgets)
+        .write(format)
+        .map_err(|_| ApiError::Internal)?;
+    let file_name = format!(
+        "attachment; filename=\"anna_ivanovna_history.{}\"",
+        format.extension()
+    );
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CONTENT_DISPOSITION, file_name),
+        ],
+        content,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyticsQuery {
+    #[serde(default)]
+    granularity: Granularity,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    /// Категории сбережений через запятую
+    savings: Option<String>,
+}
+
+async fn analytics<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Query(params): Query<AnalyticsQuery>,
+) -> Success<Analytics> {
+    let AnalyticsQuery {
+        granularity,
+        from,
+        to,
+        savings,
+    } = params;
+    let savings: Vec<String> = match savings {
+        Some(savings) => savings
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect(),
+        None => SAVINGS_CATEGORIES.map(String::from).to_vec(),
+    };
+    let analytics = api
+        .analytics(&user_id, granularity, from, to, &savings)
+        .await;
+    Success::new(analytics)
+}
+
+#[derive(Debug, Deserialize)]
+struct ReportQuery {
+    /// Любая дата нужного месяца
+    month: Option<NaiveDate>,
+}
+
+async fn plan_report<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Query(params): Query<ReportQuery>,
+) -> Result<Success<PlanReport>, ApiError> {
+    let month = params
+        .month
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    api.plan_report(&user_id, month)
+        .await
+        .map(Success::new)
+        .map_err(plan_error)
+}
+
+/// Прогноз сценария по активному плану; ничего не сохраняет
+async fn simulate<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Json(scenario): Json<Scenario>,
+) -> Result<Success<Forecast>, ApiError> {
+    api.simulate(&user_id, scenario)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::PlanNotFound => ApiError::NotFound,
+            AppError::CantDistribute { .. } | AppError::ForecastTooLong { .. } => {
+                ApiError::Validation(e.to_string())
+            }
+            _ => ApiError::Internal,
+        })
+}
+
 #[derive(Debug, Deserialize)]
 struct NewIncome {
     source_id: String,
@@ -137,7 +437,7 @@ async fn add_income<R: CoreRepo>(
     State(api): State<CoreApi<R>>,
     CurrentUser(user_id): CurrentUser,
     Json(income): Json<NewIncome>,
-) -> Result<Success<Budget>, ApiError> {
+) -> Result<Success<DistributedIncome>, ApiError> {
     let sp = api.get_plan(&user_id).await.ok_or(ApiError::NotFound)?;
     let source = sp
         .plan
@@ -151,12 +451,14 @@ async fn add_income<R: CoreRepo>(
         date,
     } = income;
     info!(source_id = source_id, date = %date, amount = %amount);
-    let income = Income::new(source.clone(), Money::new_rub(amount), date);
-    let weights = sp.plan.try_into().map_err(|_| ApiError::Internal)?;
-    let budget = api
-        .distribute(&weights, &income)
+    // Доход приходит в валюте источника
+    let currency = source.net().currency;
+    let income = Income::new(source.clone(), Money::new(amount, currency), date);
+    let distributed = api
+        .distribute_income(&user_id, &sp, &income)
+        .await
         .map_err(|_| ApiError::Internal)?;
-    Ok(Success::new(budget))
+    Ok(Success::new(distributed))
 }
 
 #[derive(Debug, Serialize)]
@@ -179,10 +481,11 @@ impl<T: Serialize> IntoResponse for Success<T> {
 
 async fn save_budget<R: CoreRepo>(
     State(api): State<CoreApi<R>>,
-    Json(budget): Json<Budget>,
+    CurrentUser(user_id): CurrentUser,
+    Json(DistributedIncome { budget, plan }): Json<DistributedIncome>,
 ) -> Result<Success<BudgetId>, ApiError> {
     let budget_id = api
-        .save_budget(build_id(), budget)
+        .save_budget(&user_id, build_id(), budget, Some(plan))
         .await
         .map_err(|e| ApiError::Storage(e.to_string()))?;
     Ok(Success::new(budget_id))
@@ -190,27 +493,369 @@ async fn save_budget<R: CoreRepo>(
 
 async fn budget<R: CoreRepo>(
     State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
     Path(id): Path<BudgetId>,
 ) -> Result<Success<StorageBudget>, ApiError> {
-    api.budget_by_id(&id)
+    api.budget_by_id(&user_id, &id)
         .await
         .map(Success::new)
         .ok_or(ApiError::NotFound)
 }
 
+#[derive(Debug, Deserialize)]
+struct RedistributeQuery {
+    version: Option<Version>,
+}
+
+async fn redistribute<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<BudgetId>,
+    Query(params): Query<RedistributeQuery>,
+) -> Result<Success<Redistribution>, ApiError> {
+    api.redistribute(&user_id, &id, params.version)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::BudgetNotFound
+            | AppError::PlanNotFound
+            | AppError::PlanVersionNotFound => ApiError::NotFound,
+            _ => ApiError::Validation(e.to_string()),
+        })
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct VoidRequest {
+    date: Option<NaiveDate>,
+    reason: Option<String>,
+}
+
+async fn void_budget<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<BudgetId>,
+    body: Option<Json<VoidRequest>>,
+) -> Result<Success<StorageBudget>, ApiError> {
+    let Json(req) = body.unwrap_or_default();
+    let date = req.date.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    api.void_budget(&user_id, &id, date, req.reason)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::BudgetNotFound => ApiError::NotFound,
+            AppError::BudgetAlreadyVoided => ApiError::Conflict(e.to_string()),
+            AppError::BudgetInUse { .. } => ApiError::Validation(e.to_string()),
+            _ => ApiError::Storage(e.to_string()),
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct GoalsQuery {
+    date: Option<NaiveDate>,
+}
+
+async fn goals<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Query(params): Query<GoalsQuery>,
+) -> Result<Success<Vec<GoalProgress>>, ApiError> {
+    let on = params
+        .date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    api.goals(&user_id, on)
+        .await
+        .map(Success::new)
+        .ok_or(ApiError::NotFound)
+}
+
+#[derive(Debug, Deserialize)]
+struct CreditsQuery {
+    #[serde(default)]
+    kind: RepaymentKind,
+    date: Option<NaiveDate>,
+}
+
+async fn credits<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Query(params): Query<CreditsQuery>,
+) -> Result<Success<Vec<CreditPayoff>>, ApiError> {
+    let on = params
+        .date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    api.credits(&user_id, params.kind, on)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::PlanNotFound => ApiError::NotFound,
+            AppError::InvalidPlan { .. } => ApiError::Validation(e.to_string()),
+            _ => ApiError::Internal,
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct ExpectedIncomesQuery {
+    date: Option<NaiveDate>,
+}
+
+async fn expected_incomes<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Query(params): Query<ExpectedIncomesQuery>,
+) -> Result<Success<Vec<ExpectedIncome>>, ApiError> {
+    let on = params
+        .date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    api.expected_incomes(&user_id, on)
+        .await
+        .map(Success::new)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn envelopes<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+) -> Success<Vec<EnvelopeBalance>> {
+    Success::new(api.envelope_balances(&user_id).await)
+}
+
+#[derive(Debug, Deserialize)]
+struct NewWithdrawal {
+    envelope: String,
+    amount: Decimal,
+    #[serde(default)]
+    currency: Currency,
+    date: NaiveDate,
+    comment: Option<String>,
+}
+
+async fn withdraw<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Json(req): Json<NewWithdrawal>,
+) -> Result<Success<LedgerEntry>, ApiError> {
+    let operation = LedgerOperation::Withdrawal {
+        envelope: req.envelope,
+        amount: Money::new(req.amount, req.currency),
+    };
+    record_ledger_operation(&api, &user_id, req.date, operation, req.comment).await
+}
+
+#[derive(Debug, Deserialize)]
+struct NewTransfer {
+    from: String,
+    to: String,
+    amount: Decimal,
+    #[serde(default)]
+    currency: Currency,
+    date: NaiveDate,
+    comment: Option<String>,
+}
+
+async fn transfer<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Json(req): Json<NewTransfer>,
+) -> Result<Success<LedgerEntry>, ApiError> {
+    let operation = LedgerOperation::Transfer {
+        from: req.from,
+        to: req.to,
+        amount: Money::new(req.amount, req.currency),
+    };
+    record_ledger_operation(&api, &user_id, req.date, operation, req.comment).await
+}
+
+async fn record_ledger_operation<R: CoreRepo>(
+    api: &CoreApi<R>,
+    user_id: &UserId,
+    date: NaiveDate,
+    operation: LedgerOperation,
+    comment: Option<String>,
+) -> Result<Success<LedgerEntry>, ApiError> {
+    api.record_ledger_operation(user_id, date, operation, comment)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::InvalidLedgerOperation { .. } => {
+                ApiError::Validation(e.to_string())
+            }
+            _ => ApiError::Storage(e.to_string()),
+        })
+}
+
+/// Архив отдаётся как есть, без обёртки: его можно сохранить в файл
+/// и отправить обратно в `/v1/import`
+async fn export<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+) -> impl IntoResponse {
+    let archive = api.export(&user_id, chrono::Utc::now()).await;
+    let file_name = format!(
+        "attachment; filename=\"anna_ivanovna_{}.json\"",
+        archive.exported_at.format("%Y-%m-%d")
+    );
+    ([(CONTENT_DISPOSITION, file_name)], Json(archive))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImportQuery {
+    #[serde(default)]
+    mode: ImportMode,
+}
+
+async fn import<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Query(ImportQuery { mode }): Query<ImportQuery>,
+    Json(archive): Json<Archive>,
+) -> Result<Success<ImportReport>, ApiError> {
+    api.import(&user_id, archive, mode)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::UnsupportedArchive { .. } | AppError::InvalidArchive { .. } => {
+                ApiError::Validation(e.to_string())
+            }
+            _ => ApiError::Storage(e.to_string()),
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct StatementRequest {
+    format: StatementFormat,
+    /// Содержимое файла выписки
+    content: String,
+    /// Профиль колонок CSV-выписки
+    #[serde(default)]
+    profile: Option<CsvProfile>,
+    #[serde(default)]
+    rules: Vec<MatchRule>,
+}
+
+/// Предложенные распределения для поступлений из выписки; ничего не сохраняет
+async fn statement_preview<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Json(req): Json<StatementRequest>,
+) -> Result<Success<Reconciliation>, ApiError> {
+    let transactions = parse_statement(&req.content, req.format, req.profile.as_ref())
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    api.reconcile_statement(&user_id, transactions, &req.rules)
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AppError::PlanNotFound => ApiError::NotFound,
+            AppError::InvalidPlan { .. } | AppError::CantDistribute { .. } => {
+                ApiError::Validation(e.to_string())
+            }
+            _ => ApiError::Internal,
+        })
+}
+
+/// Сохраняет подтверждённые пользователем распределения из выписки
+async fn statement_confirm<R: CoreRepo>(
+    State(api): State<CoreApi<R>>,
+    CurrentUser(user_id): CurrentUser,
+    Json(budgets): Json<Vec<DistributedIncome>>,
+) -> Result<Success<Confirmation>, ApiError> {
+    api.confirm_statement(&user_id, budgets)
+        .await
+        .map(Success::new)
+        .map_err(|e| ApiError::Storage(e.to_string()))
+}
+
 async fn collections_handler() -> Success<Vec<ai_core::templates::Collection>> {
     Success {
         response: ai_core::templates::collections(),
     }
 }
 
-pub fn create_router<R>(api: CoreApi<R>) -> Router
+#[derive(Debug, Deserialize)]
+struct LoginRequest {
+    login: String,
+    password: String,
+}
+
+async fn login<R: AccountRepo>(
+    State(auth): State<AuthApi<R>>,
+    Json(req): Json<LoginRequest>,
+) -> Result<Success<IssuedSession>, ApiError> {
+    auth.login(&req.login, &req.password, chrono::Utc::now())
+        .await
+        .map(Success::new)
+        .map_err(|e| match e {
+            AuthError::InvalidCredentials => ApiError::Unauthorized,
+            AuthError::EmptyLogin => ApiError::Validation(e.to_string()),
+            _ => ApiError::Internal,
+        })
+}
+
+async fn logout<R: AccountRepo>(
+    State(auth): State<AuthApi<R>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    let token = bearer_token(&headers).ok_or(ApiError::Unauthorized)?;
+    auth.logout(token)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|_| ApiError::Internal)
+}
+
+async fn me(Extension(account): Extension<Account>) -> Success<Account> {
+    Success::new(account)
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Пускает дальше только запросы с действующим токеном сессии
+async fn require_session<R>(
+    State(auth): State<AuthApi<R>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError>
 where
-    R: CoreRepo + Clone + Send + Sync + 'static,
+    R: AccountRepo + Clone + Send + Sync + 'static,
 {
+    let token = bearer_token(request.headers()).ok_or(ApiError::Unauthorized)?;
+    let account = auth
+        .authenticate(token, chrono::Utc::now())
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    request
+        .extensions_mut()
+        .insert(CurrentUser(account.id.clone()));
+    request.extensions_mut().insert(account);
+    Ok(next.run(request).await)
+}
+
+/// Без учётных записей все запросы выполняются от локального пользователя
+async fn local_user(mut request: Request, next: Next) -> Response {
+    request
+        .extensions_mut()
+        .insert(CurrentUser(DEFAULT_USER_ID.to_string()));
+    next.run(request).await
+}
+
+fn public_routes() -> Router {
     Router::new()
         .route("/health", get(health_handler))
         .route("/v1/collections", get(collections_handler))
+}
+
+/// Маршруты с данными пользователя; пользователя определяет слой поверх них
+fn user_routes<R>(api: CoreApi<R>) -> Router
+where
+    R: CoreRepo + Clone + Send + Sync + 'static,
+{
+    Router::new()
         .route(
             "/v1/plan",
             get(plan_handler::<R>).post(create_plan_handler::<R>),
@@ -220,10 +865,52 @@ where
             axum::routing::put(update_plan_handler::<R>)
                 .delete(delete_plan_handler::<R>),
         )
+        .route("/v1/plans", get(plans_handler::<R>))
+        .route("/v1/plans/shared", get(shared_plans_handler::<R>))
+        .route("/v1/plan/{plan_id}/rename", post(rename_plan_handler::<R>))
+        .route("/v1/plan/{plan_id}/clone", post(clone_plan_handler::<R>))
+        .route(
+            "/v1/plan/{plan_id}/activate",
+            post(activate_plan_handler::<R>),
+        )
+        .route("/v1/plan/{plan_id}/events", get(plan_events_handler::<R>))
+        .route("/v1/plan/{plan_id}/diff", get(plan_diff_handler::<R>))
+        .route(
+            "/v1/plan/{plan_id}/contribute",
+            post(contribute_handler::<R>),
+        )
+        .route(
+            "/v1/plan/{plan_id}/contributions",
+            get(contributions_handler::<R>),
+        )
         .route("/v1/history", get(history::<R>))
+        .route("/v1/history/export", get(history_export::<R>))
+        .route("/v1/analytics", get(analytics::<R>))
+        .route("/v1/report", get(plan_report::<R>))
+        .route("/v1/simulate", post(simulate::<R>))
         .route("/v1/add_income", post(add_income::<R>))
         .route("/v1/save_budget", post(save_budget::<R>))
         .route("/v1/budget/{id}", get(budget::<R>))
+        .route("/v1/budget/{id}/void", post(void_budget::<R>))
+        .route("/v1/budget/{id}/redistribute", get(redistribute::<R>))
+        .route("/v1/goals", get(goals::<R>))
+        .route("/v1/credits", get(credits::<R>))
+        .route("/v1/incomes/expected", get(expected_incomes::<R>))
+        .route("/v1/envelopes", get(envelopes::<R>))
+        .route("/v1/envelopes/withdraw", post(withdraw::<R>))
+        .route("/v1/envelopes/transfer", post(transfer::<R>))
+        .route("/v1/statement/preview", post(statement_preview::<R>))
+        .route("/v1/statement/confirm", post(statement_confirm::<R>))
+        .route("/v1/export", get(export::<R>))
+        .route(
+            "/v1/import",
+            post(import::<R>).layer(DefaultBodyLimit::max(ARCHIVE_BODY_LIMIT)),
+        )
+        .with_state(api)
+}
+
+fn with_layers(router: Router) -> Router {
+    router
         .layer(
             CorsLayer::new()
                 .allow_origin(tower_http::cors::Any)
@@ -233,7 +920,7 @@ where
                     axum::http::Method::PUT,
                     axum::http::Method::DELETE,
                 ])
-                .allow_headers(tower_http::cors::Any),
+                .allow_headers(tower_http::cors::AllowHeaders::mirror_request()),
         )
         .layer(
             tower_http::trace::TraceLayer::new_for_http()
@@ -264,14 +951,36 @@ where
                     },
                 ),
         )
-        .with_state(api)
 }
 
-pub async fn run<R>(api: CoreApi<R>, addr: &str) -> Result<(), std::io::Error>
+/// Роутер без входа для настольного приложения: сервер слушает localhost,
+/// а все данные принадлежат [`DEFAULT_USER_ID`].
+pub fn create_router<R>(api: CoreApi<R>) -> Router
 where
     R: CoreRepo + Clone + Send + Sync + 'static,
 {
-    let app = create_router(api);
+    let user = user_routes(api).route_layer(middleware::from_fn(local_user));
+    with_layers(public_routes().merge(user))
+}
+
+/// Роутер с учётными записями: данные доступны только с токеном сессии из
+/// `POST /v1/auth/login`, переданным в заголовке `Authorization: Bearer`.
+pub fn create_router_with_accounts<R>(api: CoreApi<R>, auth: AuthApi<R>) -> Router
+where
+    R: CoreRepo + AccountRepo + Clone + Send + Sync + 'static,
+{
+    let session = middleware::from_fn_with_state(auth.clone(), require_session::<R>);
+    let user = user_routes(api).route_layer(session.clone());
+    let accounts = Router::new()
+        .route("/v1/auth/logout", post(logout::<R>))
+        .route("/v1/auth/me", get(me))
+        .route_layer(session)
+        .route("/v1/auth/login", post(login::<R>))
+        .with_state(auth);
+    with_layers(public_routes().merge(accounts).merge(user))
+}
+
+pub async fn run(app: Router, addr: &str) -> Result<(), std::io::Error> {
     let listener = tokio::net::TcpListener::bind(addr).await?;
     info!("web interface is listening on {addr}");
     axum::serve(listener, app)
@@ -292,15 +1001,12 @@ where
         parts: &mut Parts,
         _state: &S,
     ) -> Result<Self, Self::Rejection> {
-        // Временно: пока нет auth, user_id приходит из заголовка,
-        // но оставляем default для совместимости со старым клиентом.
-        let user_id = parts
-            .headers
-            .get("x-user-id")
-            .and_then(|v| v.to_str().ok())
-            .filter(|s| !s.trim().is_empty())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "default".to_string());
-        Ok(Self(user_id))
+        // Пользователя кладёт в запрос слой роутера: `local_user` или
+        // `require_session`
+        parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or(ApiError::Unauthorized)
     }
 }
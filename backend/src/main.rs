@@ -1,32 +1,102 @@
 use std::{path::Path, sync::Arc};
 
-use ai_app::api::CoreApi;
-use anna_ivanovna_lib::{cli, infra, interfaces, storage};
+use ai_app::{
+    api::CoreApi,
+    auth::AuthApi,
+    config::{Auth, Config, Database},
+    storage::{AccountRepo, CoreRepo},
+};
+use anna_ivanovna_lib::{
+    cli,
+    infra,
+    interfaces::{
+        self,
+        csv_import::{self, CsvProfile},
+    },
+    storage,
+};
 use clap::Parser;
 
-async fn migrate_excel<T: ai_app::storage::CoreRepo>(
+async fn migrate_excel<T: CoreRepo>(
     target: &T,
     file: std::path::PathBuf,
 ) -> Result<(), String> {
-    let budgets = interfaces::excel_parser::parse_excel_csv(file)
+    let import = csv_import::parse_csv_file(&file, &CsvProfile::excel())
         .map_err(|e| format!("Ошибка парсинга CSV: {e}"))?;
+    if let Some(error) = import.errors.first() {
+        return Err(format!(
+            "Ошибка парсинга строки {}: {}",
+            error.line, error.message
+        ));
+    }
+    let count = save_imported(target, import.rows).await?;
+    println!("Мигрировано из Excel: {count} бюджетов");
+    Ok(())
+}
 
-    let mut count = 0u32;
-    for b in budgets {
+/// Сохраняет распределения из CSV как исторические, без ссылки на план
+async fn save_imported<T: CoreRepo>(
+    target: &T,
+    rows: Vec<csv_import::ParsedRow>,
+) -> Result<usize, String> {
+    let user_id = ai_app::storage::DEFAULT_USER_ID.to_string();
+    let count = rows.len();
+    for row in rows {
         let id = ai_app::storage::build_id();
-        target.save_budget(id, b).await.map_err(|e| e.to_string())?;
-        count += 1;
+        target
+            .save_budget(&user_id, id, row.budget, None)
+            .await
+            .map_err(|e| e.to_string())?;
     }
-    println!("Мигрировано из Excel: {count} бюджетов");
+    Ok(count)
+}
+
+async fn import_csv<T: CoreRepo>(
+    target: &T,
+    file: &Path,
+    profile: Option<&Path>,
+    dry_run: bool,
+) -> Result<(), String> {
+    let profile = match profile {
+        Some(path) => CsvProfile::from_file(path).map_err(|e| e.to_string())?,
+        None => CsvProfile::excel(),
+    };
+    let import = csv_import::parse_csv_file(file, &profile)
+        .map_err(|e| format!("Ошибка парсинга CSV: {e}"))?;
+
+    if dry_run {
+        for row in &import.rows {
+            let budget = &row.budget;
+            let entries = budget.no_category.len()
+                + budget.categories.values().map(Vec::len).sum::<usize>();
+            println!(
+                "{:>5}: {} {} {} (конвертов: {entries})",
+                row.line,
+                budget.income.date,
+                budget.income.source.name,
+                budget.income.amount
+            );
+        }
+    }
+    for error in &import.errors {
+        println!("⚠️ Строка {} пропущена: {}", error.line, error.message);
+    }
+    if dry_run {
+        println!(
+            "Будет загружено {} распределений, пропущено строк: {}",
+            import.rows.len(),
+            import.errors.len()
+        );
+        return Ok(());
+    }
+    let skipped = import.errors.len();
+    let count = save_imported(target, import.rows).await?;
+    println!("Загружено из CSV: {count} распределений, пропущено строк: {skipped}");
     Ok(())
 }
 
-async fn run_web<R: ai_app::storage::CoreRepo + Clone + Send + Sync + 'static>(
-    api: CoreApi<R>,
-    host: &str,
-    port: u16,
-) {
-    if let Err(err) = interfaces::web::run(api, &format!("{host}:{port}")).await {
+async fn run_web(app: axum::Router, host: &str, port: u16) {
+    if let Err(err) = interfaces::web::run(app, &format!("{host}:{port}")).await {
         eprintln!("Ошибка web-сервера: {err}");
         std::process::exit(1);
     }
@@ -40,7 +110,14 @@ async fn main() {
         cli::Commands::Web { host, port } => infra::config::ConfigOverrides {
             host: host.clone(),
             port: *port,
+            quiet: false,
         },
+        cli::Commands::Budget(cli::BudgetCommand::Tui) => {
+            infra::config::ConfigOverrides {
+                quiet: true,
+                ..Default::default()
+            }
+        }
         _ => infra::config::ConfigOverrides::default(),
     };
 
@@ -52,25 +129,52 @@ async fn main() {
         }
     };
 
-    let repo = match storage::sqlite::SqliteRepo::init(Path::new(
-        config.database.connection_string(),
-    ))
-    .await
-    {
-        Ok(r) => r,
-        Err(e) => {
-            eprintln!("Ошибка инициализации SQLite: {e}");
-            std::process::exit(1);
+    match &config.database {
+        Database::Sqlite { name } => {
+            let repo = match storage::sqlite::SqliteRepo::init(Path::new(name)).await {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Ошибка инициализации SQLite: {e}");
+                    std::process::exit(1);
+                }
+            };
+            tracing::info!("location {}", repo.db_path());
+            let location = format!("SQLite {}", repo.db_path());
+            run(cli.command, &config, repo, &location).await;
         }
-    };
-    tracing::info!("location {}", repo.db_path());
+        Database::Postgres { url } => {
+            let repo = match storage::postgres::PostgresRepo::init(url).await {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Ошибка инициализации PostgreSQL: {e}");
+                    std::process::exit(1);
+                }
+            };
+            run(cli.command, &config, repo, "PostgreSQL").await;
+        }
+        Database::Files { path, format } => {
+            let repo = match storage::files::FileRepo::init(Path::new(path), *format) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Ошибка инициализации файлового хранилища: {e}");
+                    std::process::exit(1);
+                }
+            };
+            let location = format!("файлы {}", repo.root().display());
+            run(cli.command, &config, repo, &location).await;
+        }
+    }
+}
 
-    match cli.command {
+/// Выполняет команду поверх выбранного хранилища; `location` - куда пишутся данные
+async fn run<R>(command: cli::Commands, config: &Config, repo: R, location: &str)
+where
+    R: CoreRepo + AccountRepo + Clone + Send + Sync + 'static,
+{
+    match command {
         cli::Commands::MigrateExcel { ref file } => {
-            let description = format!("Excel ({}) → SQLite", file.display());
-            let db_path = repo.db_path().to_owned();
-            println!("Миграция: {description}");
-            println!("БД: {db_path}");
+            println!("Миграция: Excel ({})", file.display());
+            println!("БД: {location}");
             print!("Продолжить? [y/N] ");
             std::io::Write::flush(&mut std::io::stdout()).unwrap();
             let mut input = String::new();
@@ -85,13 +189,57 @@ async fn main() {
                 std::process::exit(1);
             }
         }
+        cli::Commands::ImportCsv {
+            ref file,
+            ref profile,
+            dry_run,
+        } => {
+            if !dry_run {
+                println!("Импорт: CSV ({})", file.display());
+                println!("БД: {location}");
+                print!("Продолжить? [y/N] ");
+                std::io::Write::flush(&mut std::io::stdout()).unwrap();
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input).unwrap();
+                if input.trim().to_lowercase() != "y" {
+                    println!("Отменено");
+                    return;
+                }
+            }
+            if let Err(e) = import_csv(&repo, file, profile.as_deref(), dry_run).await {
+                eprintln!("Ошибка импорта: {e}");
+                std::process::exit(1);
+            }
+        }
         cli::Commands::Web { .. } => {
-            run_web(
-                CoreApi::new(Arc::new(repo)),
-                &config.server.host,
-                config.server.port,
-            )
-            .await;
+            let repo = Arc::new(repo);
+            let api = CoreApi::new(repo.clone());
+            let app = match config.server.auth {
+                Auth::None => interfaces::web::create_router(api),
+                Auth::Accounts => interfaces::web::create_router_with_accounts(
+                    api,
+                    AuthApi::new(repo),
+                ),
+            };
+            run_web(app, &config.server.host, config.server.port).await;
+        }
+        cli::Commands::Plans(cmd) => {
+            if let Err(e) = cli::run_plans(CoreApi::new(Arc::new(repo)), cmd).await {
+                eprintln!("Ошибка CLI: {e}");
+                std::process::exit(1);
+            }
+        }
+        cli::Commands::Archive(cmd) => {
+            if let Err(e) = cli::run_archive(CoreApi::new(Arc::new(repo)), cmd).await {
+                eprintln!("Ошибка CLI: {e}");
+                std::process::exit(1);
+            }
+        }
+        cli::Commands::Users(cmd) => {
+            if let Err(e) = cli::run_users(AuthApi::new(Arc::new(repo)), cmd).await {
+                eprintln!("Ошибка CLI: {e}");
+                std::process::exit(1);
+            }
         }
         cli::Commands::Budget(cmd) => {
             if let Err(e) = cli::run(CoreApi::new(Arc::new(repo)), cmd).await {
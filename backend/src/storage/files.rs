@@ -0,0 +1,1053 @@
+//! Хранилище в обычных файлах: каталог, который удобно держать в git.
+//!
+//! ```text
+//! <root>/
+//!   accounts.yaml
+//!   sessions.yaml
+//!   users/<user_id>/plans/<plan_id>/plan.yaml
+//!   users/<user_id>/plans/<plan_id>/events.yaml
+//!   users/<user_id>/budgets/<budget_id>.yaml
+//!   users/<user_id>/ledger/<entry_id>.yaml
+//! ```
+//!
+//! Файл пишется во временный рядом и переименовывается поверх старого,
+//! поэтому после сбоя остаётся либо прежняя, либо новая версия.
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use ai_app::{
+    config::FileFormat,
+    ledger::{LedgerEntry, LedgerEntryId},
+    storage::{
+        Account,
+        AccountRepo,
+        BudgetId,
+        BudgetVoid,
+        CoreRepo,
+        Cursor,
+        Page,
+        PlanAction,
+        PlanEvent,
+        PlanId,
+        PlanRef,
+        PlanStatus,
+        Session,
+        StorageBudget,
+        StorageError,
+        StoragePlan,
+        StoredAccount,
+        UserId,
+        Version,
+    },
+};
+use ai_core::{distribute::Budget, plan::Plan};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thiserror::Error;
+use tracing::{error, info, instrument};
+
+const USERS_DIR: &str = "users";
+const PLANS_DIR: &str = "plans";
+const BUDGETS_DIR: &str = "budgets";
+const LEDGER_DIR: &str = "ledger";
+const PLAN_FILE: &str = "plan";
+const EVENTS_FILE: &str = "events";
+const ACCOUNTS_FILE: &str = "accounts";
+const SESSIONS_FILE: &str = "sessions";
+
+#[derive(Debug, Error)]
+enum FileError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("identifier {0:?} can not be used as a file name")]
+    InvalidId(String),
+}
+
+/// Состояние плана в `plan.yaml`
+#[derive(Debug, Serialize, Deserialize)]
+struct PlanRecord {
+    name: String,
+    version: Version,
+    status: PlanStatus,
+    active: bool,
+    created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<DateTime<Utc>>,
+    plan: Plan,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AccountRecord {
+    id: UserId,
+    login: String,
+    password_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionRecord {
+    token_hash: String,
+    user_id: UserId,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileRepo {
+    root: PathBuf,
+    format: FileFormat,
+    /// Изменения идут по одному: иначе два запроса прочитают одну версию
+    /// файла и второй затрёт запись первого
+    lock: Arc<Mutex<()>>,
+}
+
+impl FileRepo {
+    pub fn init(root: &Path, format: FileFormat) -> Result<Self, String> {
+        fs::create_dir_all(root.join(USERS_DIR))
+            .map_err(|e| format!("Ошибка создания каталога {}: {e}", root.display()))?;
+        info!("Файловое хранилище инициализировано: {}", root.display());
+        Ok(Self {
+            root: root.to_path_buf(),
+            format,
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn file_name(&self, stem: &str) -> String {
+        format!("{stem}.{}", self.format.extension())
+    }
+
+    fn user_dir(&self, user_id: &str) -> Result<PathBuf, FileError> {
+        Ok(self.root.join(USERS_DIR).join(segment(user_id)?))
+    }
+
+    fn plan_dir(&self, user_id: &str, plan_id: &str) -> Result<PathBuf, FileError> {
+        Ok(self
+            .user_dir(user_id)?
+            .join(PLANS_DIR)
+            .join(segment(plan_id)?))
+    }
+
+    fn plan_path(&self, user_id: &str, plan_id: &str) -> Result<PathBuf, FileError> {
+        Ok(self
+            .plan_dir(user_id, plan_id)?
+            .join(self.file_name(PLAN_FILE)))
+    }
+
+    fn events_path(&self, user_id: &str, plan_id: &str) -> Result<PathBuf, FileError> {
+        Ok(self
+            .plan_dir(user_id, plan_id)?
+            .join(self.file_name(EVENTS_FILE)))
+    }
+
+    fn item_path(
+        &self,
+        user_id: &str,
+        dir: &str,
+        id: &str,
+    ) -> Result<PathBuf, FileError> {
+        Ok(self
+            .user_dir(user_id)?
+            .join(dir)
+            .join(self.file_name(segment(id)?)))
+    }
+
+    fn read<T: DeserializeOwned>(&self, path: &Path) -> Result<Option<T>, FileError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let value = match self.format {
+            FileFormat::Yaml => {
+                serde_json::from_value(serde_yaml::from_str(&content)?)?
+            }
+            FileFormat::Json => serde_json::from_str(&content)?,
+        };
+        Ok(Some(value))
+    }
+
+    fn write<T: Serialize>(&self, path: &Path, value: &T) -> Result<(), FileError> {
+        let content = match self.format {
+            // Через JSON-значение: иначе варианты перечислений станут
+            // YAML-тегами, которые serde_yaml не читает во вложенных enum
+            FileFormat::Yaml => serde_yaml::to_string(&serde_json::to_value(value)?)?,
+            FileFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+        };
+        let dir = path.parent().unwrap_or(&self.root);
+        fs::create_dir_all(dir)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let tmp = dir.join(format!(".{name}.tmp"));
+        let mut file = File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Имена подкаталогов или файлов каталога без расширения
+    fn entries(&self, dir: &Path, files: bool) -> Result<Vec<String>, FileError> {
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let extension = format!(".{}", self.format.extension());
+        let mut names = vec![];
+        for entry in read_dir {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') || entry.file_type()?.is_file() != files {
+                continue;
+            }
+            if !files {
+                names.push(name);
+            } else if let Some(stem) = name.strip_suffix(&extension) {
+                names.push(stem.to_string());
+            }
+        }
+        Ok(names)
+    }
+
+    /// Все планы пользователя, включая удалённые
+    fn plans_of(&self, user_id: &str) -> Result<Vec<StoragePlan>, FileError> {
+        let dir = self.user_dir(user_id)?.join(PLANS_DIR);
+        let mut plans = vec![];
+        for plan_id in self.entries(&dir, false)? {
+            let Some(record) =
+                self.read::<PlanRecord>(&self.plan_path(user_id, &plan_id)?)?
+            else {
+                continue;
+            };
+            plans.push((record.created_at, to_storage_plan(user_id, plan_id, record)));
+        }
+        plans.sort_by(|(a, p), (b, q)| a.cmp(b).then_with(|| p.id.cmp(&q.id)));
+        Ok(plans.into_iter().map(|(_, plan)| plan).collect())
+    }
+
+    fn active_plans_of(&self, user_id: &str) -> Result<Vec<StoragePlan>, FileError> {
+        let mut plans = self.plans_of(user_id)?;
+        plans.retain(|p| p.status == PlanStatus::Active);
+        Ok(plans)
+    }
+
+    fn users(&self) -> Result<Vec<String>, FileError> {
+        self.entries(&self.root.join(USERS_DIR), false)
+    }
+
+    fn read_plan(
+        &self,
+        user_id: &str,
+        plan_id: &str,
+    ) -> Result<Option<PlanRecord>, FileError> {
+        Ok(self
+            .read::<PlanRecord>(&self.plan_path(user_id, plan_id)?)?
+            .filter(|r| r.status == PlanStatus::Active))
+    }
+
+    fn name_taken(
+        &self,
+        user_id: &str,
+        name: &str,
+        except: Option<&PlanId>,
+    ) -> Result<bool, FileError> {
+        Ok(self
+            .active_plans_of(user_id)?
+            .iter()
+            .any(|p| p.name == name && Some(&p.id) != except))
+    }
+
+    /// Снимает отметку активного со всех планов пользователя, кроме `except`
+    fn deactivate_plans(&self, user_id: &str, except: &str) -> Result<(), FileError> {
+        for other in self.active_plans_of(user_id)? {
+            if other.active && other.id != except {
+                let path = self.plan_path(user_id, &other.id)?;
+                if let Some(mut other) = self.read::<PlanRecord>(&path)? {
+                    other.active = false;
+                    self.write(&path, &other)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Занят ли идентификатор плана: он уникален среди всех пользователей
+    fn plan_id_taken(&self, plan_id: &str) -> Result<bool, FileError> {
+        for owner in self.users()? {
+            if self.plan_path(&owner, plan_id)?.exists() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn push_event(
+        &self,
+        user_id: &str,
+        plan_id: &str,
+        record: &PlanRecord,
+        action: PlanAction,
+    ) -> Result<(), FileError> {
+        let path = self.events_path(user_id, plan_id)?;
+        // Созданный план начинает историю заново, даже если от прерванной
+        // попытки остались события
+        let mut events: Vec<PlanEvent> = match action {
+            PlanAction::Created => vec![],
+            _ => self.read(&path)?.unwrap_or_default(),
+        };
+        events.push(PlanEvent {
+            id: events.last().map_or(1, |e| e.id + 1),
+            plan_id: plan_id.to_string(),
+            version: record.version,
+            action,
+            content: Some(record.plan.clone()),
+            created_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+        self.write(&path, &events)
+    }
+
+    /// Сохраняет изменение плана: сначала событие, затем сам план,
+    /// который и делает изменение видимым
+    fn commit_plan(
+        &self,
+        user_id: &str,
+        plan_id: &str,
+        record: &PlanRecord,
+        action: PlanAction,
+    ) -> Result<(), FileError> {
+        self.push_event(user_id, plan_id, record, action)?;
+        self.write(&self.plan_path(user_id, plan_id)?, record)
+    }
+
+    /// Страница идентификаторов каталога от новых к старым
+    fn page_ids(
+        &self,
+        dir: &Path,
+        from: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Vec<String>, FileError> {
+        let mut ids = self.entries(dir, true)?;
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(ids
+            .into_iter()
+            .filter(|id| from.is_none_or(|cursor| id < cursor))
+            .take(limit)
+            .collect())
+    }
+
+    fn accounts(&self) -> Result<Vec<AccountRecord>, FileError> {
+        Ok(self
+            .read(&self.root.join(self.file_name(ACCOUNTS_FILE)))?
+            .unwrap_or_default())
+    }
+
+    fn sessions(&self) -> Result<Vec<SessionRecord>, FileError> {
+        Ok(self
+            .read(&self.root.join(self.file_name(SESSIONS_FILE)))?
+            .unwrap_or_default())
+    }
+}
+
+impl CoreRepo for FileRepo {
+    #[instrument(skip(self))]
+    async fn get_plan(&self, user_id: &UserId) -> Option<StoragePlan> {
+        self.active_plans_of(user_id)
+            .inspect_err(|e| error!("Ошибка чтения планов: {e}"))
+            .ok()?
+            .into_iter()
+            .find(|p| p.active)
+    }
+
+    #[instrument(skip(self))]
+    async fn list_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        self.active_plans_of(user_id)
+            .inspect_err(|e| error!("Ошибка чтения планов: {e}"))
+            .unwrap_or_default()
+    }
+
+    #[instrument(skip(self))]
+    async fn shared_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        let shared = || -> Result<Vec<StoragePlan>, FileError> {
+            let mut plans = vec![];
+            for owner in self.users()? {
+                if &owner == user_id {
+                    continue;
+                }
+                plans.extend(
+                    self.active_plans_of(&owner)?
+                        .into_iter()
+                        .filter(|p| p.plan.members.iter().any(|m| &m.id == user_id)),
+                );
+            }
+            Ok(plans)
+        };
+        shared()
+            .inspect_err(|e| error!("Ошибка чтения общих планов: {e}"))
+            .unwrap_or_default()
+    }
+
+    #[instrument(skip(self, plan))]
+    async fn create_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: PlanId,
+        name: String,
+        plan: Plan,
+    ) -> Result<PlanId, StorageError> {
+        let _guard = self.lock();
+        let create = || -> Result<Result<(), StorageError>, FileError> {
+            if self.name_taken(user_id, &name, None)? {
+                return Ok(Err(StorageError::PlanAlreadyExists));
+            }
+            if self.plan_id_taken(&plan_id)? {
+                return Ok(Err(StorageError::PlanAlreadyExists));
+            }
+            // Первый план пользователя сразу становится активным
+            let active = !self.active_plans_of(user_id)?.iter().any(|p| p.active);
+            let record = PlanRecord {
+                name,
+                version: 1,
+                status: PlanStatus::Active,
+                active,
+                created_at: Utc::now(),
+                deleted_at: None,
+                plan,
+            };
+            self.commit_plan(user_id, &plan_id, &record, PlanAction::Created)?;
+            Ok(Ok(()))
+        };
+        create()
+            .map_err(|e| {
+                error!("Ошибка создания плана {plan_id}: {e}");
+                StorageError::CreatePlan
+            })
+            .flatten()?;
+
+        info!("План создан в файлах: {plan_id}");
+        Ok(plan_id)
+    }
+
+    #[instrument(skip(self))]
+    async fn rename_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        name: String,
+    ) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let rename = || -> Result<Result<(), StorageError>, FileError> {
+            if self.name_taken(user_id, &name, Some(plan_id))? {
+                return Ok(Err(StorageError::PlanAlreadyExists));
+            }
+            let Some(mut record) = self.read_plan(user_id, plan_id)? else {
+                return Ok(Err(StorageError::PlanNotFound));
+            };
+            record.name.clone_from(&name);
+            self.write(&self.plan_path(user_id, plan_id)?, &record)?;
+            Ok(Ok(()))
+        };
+        rename()
+            .map_err(|e| {
+                error!("Ошибка переименования плана {plan_id}: {e}");
+                StorageError::RenamePlan
+            })
+            .flatten()?;
+
+        info!("План {plan_id} переименован в {name}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn activate_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let activate = || -> Result<Result<(), StorageError>, FileError> {
+            let Some(mut record) = self.read_plan(user_id, plan_id)? else {
+                return Ok(Err(StorageError::PlanNotFound));
+            };
+            self.deactivate_plans(user_id, plan_id)?;
+            record.active = true;
+            self.write(&self.plan_path(user_id, plan_id)?, &record)?;
+            Ok(Ok(()))
+        };
+        activate()
+            .map_err(|e| {
+                error!("Ошибка смены активного плана {plan_id}: {e}");
+                StorageError::ActivatePlan
+            })
+            .flatten()?;
+
+        info!("Активный план: {plan_id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self, plan))]
+    async fn update_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        expected: Version,
+        plan: Plan,
+    ) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let update = || -> Result<Result<(), StorageError>, FileError> {
+            let Some(mut record) = self.read_plan(user_id, plan_id)? else {
+                return Ok(Err(StorageError::UpdatePlan));
+            };
+            if record.version != expected {
+                return Ok(Err(StorageError::VersionConflict {
+                    current: record.version,
+                }));
+            }
+            record.version += 1;
+            record.plan = plan;
+            self.commit_plan(user_id, plan_id, &record, PlanAction::Updated)?;
+            Ok(Ok(()))
+        };
+        update()
+            .map_err(|e| {
+                error!("Ошибка обновления плана {plan_id}: {e}");
+                StorageError::UpdatePlan
+            })
+            .flatten()?;
+
+        info!("План обновлён: {plan_id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let delete = || -> Result<bool, FileError> {
+            let Some(mut record) = self.read_plan(user_id, plan_id)? else {
+                return Ok(false);
+            };
+            record.status = PlanStatus::Deleted;
+            record.active = false;
+            record.version += 1;
+            record.deleted_at = Some(Utc::now());
+            self.commit_plan(user_id, plan_id, &record, PlanAction::Deleted)?;
+            Ok(true)
+        };
+        match delete() {
+            Ok(true) => {}
+            Ok(false) => return Err(StorageError::DeletePlan),
+            Err(e) => {
+                error!("Ошибка удаления плана {plan_id}: {e}");
+                return Err(StorageError::DeletePlan);
+            }
+        }
+
+        info!("План удалён (soft delete): {plan_id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn plan_events(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<PlanEvent> {
+        let from = match from.as_deref().map(str::parse::<i64>).transpose() {
+            Ok(from) => from,
+            Err(e) => {
+                error!("Некорректный курсор событий плана: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+        let events = self
+            .events_path(user_id, plan_id)
+            .and_then(|path| self.read::<Vec<PlanEvent>>(&path));
+        let events = match events {
+            Ok(events) => events.unwrap_or_default(),
+            Err(e) => {
+                error!("Ошибка чтения событий плана: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+
+        let items: Vec<PlanEvent> = events
+            .into_iter()
+            .rev()
+            .filter(|e| from.is_none_or(|from| e.id < from))
+            .take(limit)
+            .collect();
+        let next_cursor = if items.len() < limit {
+            None
+        } else {
+            items.last().map(|e| e.id.to_string())
+        };
+        Page::new(items, next_cursor)
+    }
+
+    #[instrument(skip(self, budget))]
+    async fn save_budget(
+        &self,
+        user_id: &UserId,
+        budget_id: BudgetId,
+        budget: Budget,
+        plan: Option<PlanRef>,
+    ) -> Result<BudgetId, StorageError> {
+        let _guard = self.lock();
+        let stored = StorageBudget {
+            id: budget_id,
+            budget,
+            plan,
+            voided: None,
+        };
+        self.item_path(user_id, BUDGETS_DIR, &stored.id)
+            .and_then(|path| self.write(&path, &stored))
+            .map_err(|e| {
+                error!("Ошибка сохранения бюджета {}: {e}", stored.id);
+                StorageError::SaveBudget
+            })?;
+
+        info!("Бюджет сохранён в файлах: {}", stored.id);
+        Ok(stored.id)
+    }
+
+    #[instrument(skip(self))]
+    async fn budget_by_id(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+    ) -> Option<StorageBudget> {
+        self.item_path(user_id, BUDGETS_DIR, id)
+            .and_then(|path| self.read(&path))
+            .inspect_err(|e| error!("Ошибка чтения бюджета {id}: {e}"))
+            .ok()
+            .flatten()
+    }
+
+    #[instrument(skip(self))]
+    async fn budgets(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<StorageBudget> {
+        let page = || -> Result<Vec<StorageBudget>, FileError> {
+            let dir = self.user_dir(user_id)?.join(BUDGETS_DIR);
+            let mut items = vec![];
+            for id in self.page_ids(&dir, from.as_ref(), limit)? {
+                items.extend(self.read(&self.item_path(
+                    user_id,
+                    BUDGETS_DIR,
+                    &id,
+                )?)?);
+            }
+            Ok(items)
+        };
+        let items = match page() {
+            Ok(items) => items,
+            Err(e) => {
+                error!("Ошибка чтения бюджетов: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+
+        let next_cursor = if items.len() < limit {
+            None
+        } else {
+            items.last().map(|b| b.id.clone())
+        };
+        Page::new(items, next_cursor)
+    }
+
+    #[instrument(skip(self))]
+    async fn void_budget(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+        void: BudgetVoid,
+    ) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let void_budget = || -> Result<bool, FileError> {
+            let path = self.item_path(user_id, BUDGETS_DIR, id)?;
+            let Some(mut stored) = self.read::<StorageBudget>(&path)? else {
+                return Ok(false);
+            };
+            if stored.is_voided() {
+                return Ok(false);
+            }
+            stored.voided = Some(void);
+            self.write(&path, &stored)?;
+            Ok(true)
+        };
+        match void_budget() {
+            Ok(true) => {}
+            Ok(false) => return Err(StorageError::VoidBudget),
+            Err(e) => {
+                error!("Ошибка аннулирования бюджета {id}: {e}");
+                return Err(StorageError::VoidBudget);
+            }
+        }
+
+        info!("Бюджет аннулирован в файлах: {id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self, entry))]
+    async fn save_ledger_entry(
+        &self,
+        user_id: &UserId,
+        entry: LedgerEntry,
+    ) -> Result<LedgerEntryId, StorageError> {
+        let _guard = self.lock();
+        self.item_path(user_id, LEDGER_DIR, &entry.id)
+            .and_then(|path| self.write(&path, &entry))
+            .map_err(|e| {
+                error!("Ошибка сохранения операции с конвертами: {e}");
+                StorageError::SaveLedgerEntry
+            })?;
+
+        info!("Операция с конвертами сохранена в файлах: {}", entry.id);
+        Ok(entry.id)
+    }
+
+    #[instrument(skip(self))]
+    async fn ledger_entries(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<LedgerEntry> {
+        let page = || -> Result<Vec<LedgerEntry>, FileError> {
+            let dir = self.user_dir(user_id)?.join(LEDGER_DIR);
+            let mut items = vec![];
+            for id in self.page_ids(&dir, from.as_ref(), limit)? {
+                items.extend(self.read(&self.item_path(user_id, LEDGER_DIR, &id)?)?);
+            }
+            Ok(items)
+        };
+        let items = match page() {
+            Ok(items) => items,
+            Err(e) => {
+                error!("Ошибка чтения операций с конвертами: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+
+        let next_cursor = if items.len() < limit {
+            None
+        } else {
+            items.last().map(|e| e.id.clone())
+        };
+        Page::new(items, next_cursor)
+    }
+
+    #[instrument(skip(self, plan, events), fields(plan_id = %plan.id))]
+    async fn restore_plan(
+        &self,
+        user_id: &UserId,
+        plan: StoragePlan,
+        events: Vec<PlanEvent>,
+    ) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let restore = || -> Result<Result<(), StorageError>, FileError> {
+            if self.name_taken(user_id, &plan.name, None)?
+                || self.plan_id_taken(&plan.id)?
+            {
+                return Ok(Err(StorageError::PlanAlreadyExists));
+            }
+            if plan.active {
+                self.deactivate_plans(user_id, &plan.id)?;
+            }
+            let events: Vec<PlanEvent> = events
+                .into_iter()
+                .zip(1..)
+                .map(|(event, id)| PlanEvent {
+                    id,
+                    plan_id: plan.id.clone(),
+                    ..event
+                })
+                .collect();
+            self.write(&self.events_path(user_id, &plan.id)?, &events)?;
+            let now = Utc::now();
+            let record = PlanRecord {
+                deleted_at: (plan.status == PlanStatus::Deleted).then_some(now),
+                name: plan.name,
+                version: plan.version,
+                status: plan.status,
+                active: plan.active,
+                created_at: now,
+                plan: plan.plan,
+            };
+            self.write(&self.plan_path(user_id, &plan.id)?, &record)?;
+            Ok(Ok(()))
+        };
+        restore()
+            .map_err(|e| {
+                error!("Ошибка восстановления плана {}: {e}", plan.id);
+                StorageError::RestorePlan
+            })
+            .flatten()?;
+
+        info!("План восстановлен в файлах: {}", plan.id);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn clear_user_data(&self, user_id: &UserId) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let dir = self
+            .user_dir(user_id)
+            .map_err(|_| StorageError::ClearUserData)?;
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                error!("Ошибка удаления данных пользователя {user_id}: {e}");
+                return Err(StorageError::ClearUserData);
+            }
+        }
+
+        info!("Данные пользователя {user_id} удалены из файлов");
+        Ok(())
+    }
+}
+
+impl AccountRepo for FileRepo {
+    #[instrument(skip(self))]
+    async fn accounts_count(&self) -> usize {
+        self.accounts()
+            .inspect_err(|e| error!("Ошибка чтения учётных записей: {e}"))
+            .map_or(0, |accounts| accounts.len())
+    }
+
+    #[instrument(skip(self, password_hash))]
+    async fn create_account(
+        &self,
+        account: Account,
+        password_hash: String,
+    ) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        let create = || -> Result<Result<(), StorageError>, FileError> {
+            let mut accounts = self.accounts()?;
+            if accounts
+                .iter()
+                .any(|a| a.login == account.login || a.id == account.id)
+            {
+                return Ok(Err(StorageError::AccountAlreadyExists));
+            }
+            accounts.push(AccountRecord {
+                id: account.id.clone(),
+                login: account.login.clone(),
+                password_hash,
+            });
+            self.write(&self.root.join(self.file_name(ACCOUNTS_FILE)), &accounts)?;
+            Ok(Ok(()))
+        };
+        create()
+            .map_err(|e| {
+                error!("Ошибка создания учётной записи: {e}");
+                StorageError::CreateAccount
+            })
+            .flatten()?;
+
+        info!("Учётная запись создана: {}", account.login);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn account_by_login(&self, login: &str) -> Option<StoredAccount> {
+        let record = self
+            .accounts()
+            .inspect_err(|e| error!("Ошибка чтения учётных записей: {e}"))
+            .ok()?
+            .into_iter()
+            .find(|a| a.login == login)?;
+        Some(StoredAccount {
+            account: Account {
+                id: record.id,
+                login: record.login,
+            },
+            password_hash: record.password_hash,
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn create_session(&self, session: Session) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        self.sessions()
+            .and_then(|mut sessions| {
+                sessions.push(SessionRecord {
+                    token_hash: session.token_hash,
+                    user_id: session.user_id,
+                    expires_at: session.expires_at,
+                });
+                self.write(&self.root.join(self.file_name(SESSIONS_FILE)), &sessions)
+            })
+            .map_err(|e| {
+                error!("Ошибка создания сессии: {e}");
+                StorageError::CreateSession
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn session_account(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Option<Account> {
+        let user_id = self
+            .sessions()
+            .inspect_err(|e| error!("Ошибка чтения сессий: {e}"))
+            .ok()?
+            .into_iter()
+            .find(|s| s.token_hash == token_hash && s.expires_at > now)?
+            .user_id;
+        self.accounts()
+            .ok()?
+            .into_iter()
+            .find(|a| a.id == user_id)
+            .map(|a| Account {
+                id: a.id,
+                login: a.login,
+            })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_session(&self, token_hash: &str) -> Result<(), StorageError> {
+        let _guard = self.lock();
+        self.sessions()
+            .and_then(|mut sessions| {
+                sessions.retain(|s| s.token_hash != token_hash);
+                self.write(&self.root.join(self.file_name(SESSIONS_FILE)), &sessions)
+            })
+            .map_err(|_| StorageError::DeleteSession)
+    }
+}
+
+/// Идентификатор как часть пути: не должен выводить за пределы каталога
+fn segment(id: &str) -> Result<&str, FileError> {
+    if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\', '\0']) {
+        return Err(FileError::InvalidId(id.to_string()));
+    }
+    Ok(id)
+}
+
+fn to_storage_plan(user_id: &str, id: PlanId, record: PlanRecord) -> StoragePlan {
+    StoragePlan {
+        user_id: user_id.to_string(),
+        id,
+        name: record.name,
+        plan: record.plan,
+        version: record.version,
+        status: record.status,
+        active: record.active,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::storage::suite::{self, valid_plan};
+
+    fn temp_root() -> PathBuf {
+        let ts = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("anna_ivanovna_files_{ts}"))
+    }
+
+    #[tokio::test]
+    async fn plan_stored_as_readable_file() {
+        let root = temp_root();
+        let repo = FileRepo::init(&root, FileFormat::Yaml).unwrap();
+        let user_id: UserId = "default".to_string();
+
+        repo.create_plan(&user_id, "plan-1".into(), "Основной".into(), valid_plan())
+            .await
+            .unwrap();
+
+        let plan_dir = root.join("users/default/plans/plan-1");
+        let content = fs::read_to_string(plan_dir.join("plan.yaml")).unwrap();
+        assert!(content.contains("name: Основной"));
+        assert!(content.contains("Аренда"));
+        assert!(plan_dir.join("events.yaml").exists());
+        // Временные файлы не остаются после записи
+        let names: Vec<_> = fs::read_dir(&plan_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names.len(), 2);
+        // Идентификатор не выводит за пределы каталога
+        assert!(matches!(
+            repo.create_plan(
+                &"../x".into(),
+                "plan-2".into(),
+                "Другой".into(),
+                valid_plan()
+            )
+            .await,
+            Err(StorageError::CreatePlan)
+        ));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    /// Прогоняет общую проверку хранилища в чистом каталоге
+    macro_rules! suite_tests {
+        ($format:expr => $($name:ident),* $(,)?) => {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    let root = temp_root();
+                    let repo = FileRepo::init(&root, $format).unwrap();
+                    suite::$name(&repo).await;
+                    let _ = fs::remove_dir_all(root);
+                }
+            )*
+        };
+    }
+
+    suite_tests!(FileFormat::Yaml =>
+        plan_events_paginate_newest_first,
+        switch_active_plan,
+        shared_plans_found_by_member,
+        update_plan_checks_version,
+        ledger_entries_paginate_newest_first,
+        budget_keeps_plan_version_and_void,
+        budgets_are_scoped_to_user,
+        import_same_archive_for_two_users,
+        restore_plan_keeps_history,
+        sessions_expire_and_close,
+    );
+
+    mod json {
+        use super::*;
+
+        suite_tests!(FileFormat::Json =>
+            ledger_entries_paginate_newest_first,
+            budget_keeps_plan_version_and_void,
+        );
+    }
+}
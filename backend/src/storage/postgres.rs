@@ -0,0 +1,1102 @@
+use ai_app::{
+    ledger::{LedgerEntry, LedgerEntryId, LedgerOperation},
+    storage::{
+        Account,
+        AccountRepo,
+        BudgetId,
+        BudgetVoid,
+        CoreRepo,
+        Cursor,
+        Page,
+        PlanAction,
+        PlanEvent,
+        PlanId,
+        PlanRef,
+        PlanStatus,
+        Session,
+        StorageBudget,
+        StorageError,
+        StoragePlan,
+        StoredAccount,
+        UserId,
+        Version,
+    },
+};
+use ai_core::{distribute::Budget, plan::Plan};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use sqlx::{
+    PgPool,
+    Row,
+    postgres::{PgPoolOptions, PgRow},
+};
+use tracing::{error, info, instrument, warn};
+
+const PLAN_COLUMNS: &str =
+    "id, user_id, name, content, version, status, is_active FROM plans";
+const BUDGET_COLUMNS: &str = "id, content, plan_id, plan_version, \
+     voided_at::text AS voided_at, void_reason FROM budgets";
+
+#[derive(Debug, Clone)]
+pub struct PostgresRepo {
+    pub(crate) pool: PgPool,
+}
+
+impl PostgresRepo {
+    pub async fn init(url: &str) -> Result<Self, String> {
+        let pool = PgPoolOptions::new()
+            .max_connections(10)
+            .connect(url)
+            .await
+            .map_err(|e| format!("Ошибка подключения к PostgreSQL: {e}"))?;
+
+        sqlx::migrate!("./migrations_postgres")
+            .run(&pool)
+            .await
+            .map_err(|e| format!("Ошибка миграции PostgreSQL: {e}"))?;
+
+        info!("PostgreSQL инициализирован");
+        Ok(Self { pool })
+    }
+}
+
+impl CoreRepo for PostgresRepo {
+    #[instrument(skip(self))]
+    async fn get_plan(&self, user_id: &UserId) -> Option<StoragePlan> {
+        let row = sqlx::query(&format!(
+            "SELECT {PLAN_COLUMNS} \
+             WHERE user_id = $1 AND status = 'active' AND is_active"
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .ok()
+        .flatten()?;
+
+        plan_from_row(&row)
+    }
+
+    #[instrument(skip(self))]
+    async fn list_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        let rows = sqlx::query(&format!(
+            "SELECT {PLAN_COLUMNS} \
+             WHERE user_id = $1 AND status = 'active' ORDER BY created_at, id"
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .inspect_err(|e| error!("Ошибка загрузки планов: {e}"))
+        .unwrap_or_default();
+
+        rows.iter().filter_map(plan_from_row).collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn shared_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        // Участники хранятся в содержимом плана
+        let rows = sqlx::query(&format!(
+            "SELECT {PLAN_COLUMNS} \
+             WHERE user_id != $1 AND status = 'active' AND EXISTS ( \
+                 SELECT 1 FROM jsonb_array_elements(content::jsonb -> 'members') AS m \
+                 WHERE m ->> 'id' = $1) \
+             ORDER BY created_at, id"
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .inspect_err(|e| error!("Ошибка загрузки общих планов: {e}"))
+        .unwrap_or_default();
+
+        rows.iter().filter_map(plan_from_row).collect()
+    }
+
+    #[instrument(skip(self, plan))]
+    async fn create_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: PlanId,
+        name: String,
+        plan: Plan,
+    ) -> Result<PlanId, StorageError> {
+        let content =
+            serde_json::to_string(&plan).map_err(|_| StorageError::CreatePlan)?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::CreatePlan)?;
+
+        if name_taken(&mut tx, user_id, &name, None)
+            .await
+            .map_err(|_| StorageError::CreatePlan)?
+        {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        // Первый план пользователя сразу становится активным
+        let result = sqlx::query(
+            "INSERT INTO plans (id, user_id, name, content, version, status, is_active) \
+             SELECT $1, $2, $3, $4, 1, 'active', NOT EXISTS ( \
+                 SELECT 1 FROM plans WHERE user_id = $2 AND is_active \
+             ) ON CONFLICT DO NOTHING",
+        )
+        .bind(&plan_id)
+        .bind(user_id)
+        .bind(&name)
+        .bind(&content)
+        .execute(&mut *tx)
+        .await
+        .inspect_err(|err| warn!("{err} cant create_plan"))
+        .map_err(|_| StorageError::CreatePlan)?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::PlanAlreadyExists);
+        };
+
+        sqlx::query(
+            "INSERT INTO plan_events (plan_id, version, action, content) \
+             VALUES ($1, 1, 'created', $2)",
+        )
+        .bind(&plan_id)
+        .bind(&content)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::CreatePlan)?;
+
+        tx.commit().await.map_err(|_| StorageError::CreatePlan)?;
+
+        info!("План создан в PostgreSQL: {plan_id}");
+        Ok(plan_id)
+    }
+
+    #[instrument(skip(self))]
+    async fn rename_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        name: String,
+    ) -> Result<(), StorageError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::RenamePlan)?;
+
+        if name_taken(&mut tx, user_id, &name, Some(plan_id))
+            .await
+            .map_err(|_| StorageError::RenamePlan)?
+        {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        let result = sqlx::query(
+            "UPDATE plans SET name = $1, updated_at = now() \
+             WHERE user_id = $2 AND id = $3 AND status = 'active'",
+        )
+        .bind(&name)
+        .bind(user_id)
+        .bind(plan_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::RenamePlan)?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::PlanNotFound);
+        }
+
+        tx.commit().await.map_err(|_| StorageError::RenamePlan)?;
+
+        info!("План {plan_id} переименован в {name}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn activate_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<(), StorageError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::ActivatePlan)?;
+
+        let exists: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM plans WHERE user_id = $1 AND id = $2 AND status = 'active'",
+        )
+        .bind(user_id)
+        .bind(plan_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StorageError::ActivatePlan)?;
+
+        if exists.is_none() {
+            return Err(StorageError::PlanNotFound);
+        }
+
+        // Сначала снимаем отметку, иначе сработает уникальный индекс
+        sqlx::query(
+            "UPDATE plans SET is_active = FALSE WHERE user_id = $1 AND is_active",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::ActivatePlan)?;
+
+        sqlx::query("UPDATE plans SET is_active = TRUE WHERE user_id = $1 AND id = $2")
+            .bind(user_id)
+            .bind(plan_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StorageError::ActivatePlan)?;
+
+        tx.commit().await.map_err(|_| StorageError::ActivatePlan)?;
+
+        info!("Активный план: {plan_id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self, plan))]
+    async fn update_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        expected: Version,
+        plan: Plan,
+    ) -> Result<(), StorageError> {
+        let content =
+            serde_json::to_string(&plan).map_err(|_| StorageError::UpdatePlan)?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::UpdatePlan)?;
+
+        let result = sqlx::query(
+            "UPDATE plans SET version = version + 1, content = $1, updated_at = now() \
+             WHERE user_id = $2 AND id = $3 AND status = 'active' AND version = $4",
+        )
+        .bind(&content)
+        .bind(user_id)
+        .bind(plan_id)
+        .bind(expected)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::UpdatePlan)?;
+
+        if result.rows_affected() == 0 {
+            // План есть, но его уже обновили с другой вкладки
+            let current: Option<Version> = sqlx::query_scalar(
+                "SELECT version FROM plans \
+                 WHERE user_id = $1 AND id = $2 AND status = 'active'",
+            )
+            .bind(user_id)
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|_| StorageError::UpdatePlan)?;
+            return Err(match current {
+                Some(current) => StorageError::VersionConflict { current },
+                None => StorageError::UpdatePlan,
+            });
+        }
+
+        sqlx::query(
+            "INSERT INTO plan_events (plan_id, version, action, content) \
+             SELECT id, version, 'updated', $1 FROM plans \
+             WHERE user_id = $2 AND id = $3",
+        )
+        .bind(&content)
+        .bind(user_id)
+        .bind(plan_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::UpdatePlan)?;
+
+        tx.commit().await.map_err(|_| StorageError::UpdatePlan)?;
+
+        info!("План обновлён: {plan_id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<(), StorageError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::DeletePlan)?;
+
+        let result = sqlx::query(
+            "UPDATE plans SET status = 'deleted', is_active = FALSE, \
+             version = version + 1, deleted_at = now(), updated_at = now() \
+             WHERE user_id = $1 AND id = $2 AND status = 'active'",
+        )
+        .bind(user_id)
+        .bind(plan_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::DeletePlan)?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::DeletePlan);
+        }
+
+        sqlx::query(
+            "INSERT INTO plan_events (plan_id, version, action, content) \
+             SELECT id, version, 'deleted', content FROM plans \
+             WHERE user_id = $1 AND id = $2",
+        )
+        .bind(user_id)
+        .bind(plan_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::DeletePlan)?;
+
+        tx.commit().await.map_err(|_| StorageError::DeletePlan)?;
+
+        info!("План удалён (soft delete): {plan_id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn plan_events(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<PlanEvent> {
+        // Курсор - идентификатор последнего события; без курсора - с самого нового
+        let before = match from.as_deref().map(str::parse::<i64>) {
+            None => i64::MAX,
+            Some(Ok(id)) => id,
+            Some(Err(_)) => {
+                warn!("Некорректный курсор событий плана: {from:?}");
+                return Page::new(vec![], None);
+            }
+        };
+        let rows = sqlx::query(
+            "SELECT id, plan_id, version, action, content, \
+             to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') AS created_at \
+             FROM plan_events WHERE plan_id = $1 AND id < $2 \
+             AND plan_id IN (SELECT id FROM plans WHERE user_id = $3) \
+             ORDER BY id DESC LIMIT $4",
+        )
+        .bind(plan_id)
+        .bind(before)
+        .bind(user_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await;
+
+        let rows = match rows {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Ошибка запроса событий плана: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+
+        let items: Vec<PlanEvent> = rows
+            .iter()
+            .filter_map(|row| {
+                let id: i64 = row.get("id");
+                let action_raw: String = row.get("action");
+                let content_str: Option<String> = row.get("content");
+
+                let action: PlanAction = match action_raw.parse() {
+                    Ok(a) => a,
+                    Err(_) => {
+                        error!("Неизвестное действие плана в БД: {action_raw}");
+                        return None;
+                    }
+                };
+
+                let content = content_str.and_then(|c| {
+                    serde_json::from_str(&c)
+                        .map_err(|e| {
+                            error!("Ошибка десериализации плана в событии {id}: {e}")
+                        })
+                        .ok()
+                });
+
+                Some(PlanEvent {
+                    id,
+                    plan_id: row.get("plan_id"),
+                    version: row.get("version"),
+                    action,
+                    content,
+                    created_at: row.get("created_at"),
+                })
+            })
+            .collect();
+
+        let next_cursor = if items.len() < limit {
+            None
+        } else {
+            items.last().map(|e| e.id.to_string())
+        };
+        Page::new(items, next_cursor)
+    }
+
+    #[instrument(skip(self, budget))]
+    async fn save_budget(
+        &self,
+        user_id: &UserId,
+        budget_id: BudgetId,
+        budget: Budget,
+        plan: Option<PlanRef>,
+    ) -> Result<BudgetId, StorageError> {
+        let source = &budget.income.source.name;
+        let income_date = budget.income_date().format("%Y-%m-%d").to_string();
+        let content =
+            serde_json::to_string(&budget).map_err(|_| StorageError::SaveBudget)?;
+
+        let result = sqlx::query(
+            "INSERT INTO budgets \
+             (id, user_id, source, income_date, content, plan_id, plan_version) \
+             VALUES ($1, $2, $3, $4::date, $5, $6, $7) \
+             ON CONFLICT (id) DO UPDATE SET user_id = excluded.user_id, \
+             source = excluded.source, income_date = excluded.income_date, \
+             content = excluded.content, plan_id = excluded.plan_id, \
+             plan_version = excluded.plan_version, voided_at = NULL, void_reason = NULL \
+             WHERE budgets.user_id = excluded.user_id",
+        )
+        .bind(&budget_id)
+        .bind(user_id)
+        .bind(source)
+        .bind(&income_date)
+        .bind(&content)
+        .bind(plan.as_ref().map(|p| &p.plan_id))
+        .bind(plan.as_ref().map(|p| p.version))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Ошибка сохранения бюджета {budget_id}: {e}");
+            StorageError::SaveBudget
+        })?;
+        // Идентификатор занят распределением другого пользователя
+        if result.rows_affected() == 0 {
+            return Err(StorageError::BudgetAlreadyExists);
+        }
+
+        info!("Бюджет сохранён в PostgreSQL: {budget_id}");
+        Ok(budget_id)
+    }
+
+    #[instrument(skip(self))]
+    async fn budget_by_id(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+    ) -> Option<StorageBudget> {
+        let row = sqlx::query(&format!(
+            "SELECT {BUDGET_COLUMNS} WHERE id = $1 AND user_id = $2"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .ok()
+        .flatten()?;
+
+        budget_from_row(&row)
+    }
+
+    #[instrument(skip(self))]
+    async fn budgets(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<StorageBudget> {
+        let rows = match &from {
+            Some(cursor) => {
+                sqlx::query(&format!(
+                    "SELECT {BUDGET_COLUMNS} \
+                     WHERE user_id = $1 AND id < $2 ORDER BY id DESC LIMIT $3"
+                ))
+                .bind(user_id)
+                .bind(cursor)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+            }
+            None => {
+                sqlx::query(&format!(
+                    "SELECT {BUDGET_COLUMNS} \
+                     WHERE user_id = $1 ORDER BY id DESC LIMIT $2"
+                ))
+                .bind(user_id)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+            }
+        };
+
+        let rows = match rows {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Ошибка запроса бюджетов: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+
+        let items: Vec<StorageBudget> =
+            rows.iter().filter_map(budget_from_row).collect();
+
+        let next_cursor = if items.len() < limit {
+            None
+        } else {
+            items.last().map(|b| b.id.clone())
+        };
+        Page::new(items, next_cursor)
+    }
+
+    #[instrument(skip(self))]
+    async fn void_budget(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+        void: BudgetVoid,
+    ) -> Result<(), StorageError> {
+        let result = sqlx::query(
+            "UPDATE budgets SET voided_at = $1::date, void_reason = $2 \
+             WHERE id = $3 AND user_id = $4 AND voided_at IS NULL",
+        )
+        .bind(void.date.format("%Y-%m-%d").to_string())
+        .bind(&void.reason)
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Ошибка аннулирования бюджета {id}: {e}");
+            StorageError::VoidBudget
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(StorageError::VoidBudget);
+        }
+
+        info!("Бюджет аннулирован в PostgreSQL: {id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self, entry))]
+    async fn save_ledger_entry(
+        &self,
+        user_id: &UserId,
+        entry: LedgerEntry,
+    ) -> Result<LedgerEntryId, StorageError> {
+        let kind = match entry.operation {
+            LedgerOperation::Withdrawal { .. } => "withdrawal",
+            LedgerOperation::Transfer { .. } => "transfer",
+        };
+        let entry_date = entry.date.format("%Y-%m-%d").to_string();
+        let content =
+            serde_json::to_string(&entry).map_err(|_| StorageError::SaveLedgerEntry)?;
+
+        let result = sqlx::query(
+            "INSERT INTO ledger_entries (id, user_id, kind, entry_date, content) \
+             VALUES ($1, $2, $3, $4::date, $5) ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&entry.id)
+        .bind(user_id)
+        .bind(kind)
+        .bind(&entry_date)
+        .bind(&content)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Ошибка сохранения операции с конвертами: {e}");
+            StorageError::SaveLedgerEntry
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(StorageError::LedgerEntryAlreadyExists);
+        }
+
+        info!("Операция с конвертами сохранена в PostgreSQL: {}", entry.id);
+        Ok(entry.id)
+    }
+
+    #[instrument(skip(self))]
+    async fn ledger_entries(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<LedgerEntry> {
+        let rows = match &from {
+            Some(cursor) => {
+                sqlx::query(
+                    "SELECT id, content FROM ledger_entries \
+                     WHERE user_id = $1 AND id < $2 ORDER BY id DESC LIMIT $3",
+                )
+                .bind(user_id)
+                .bind(cursor)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+            }
+            None => {
+                sqlx::query(
+                    "SELECT id, content FROM ledger_entries \
+                     WHERE user_id = $1 ORDER BY id DESC LIMIT $2",
+                )
+                .bind(user_id)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+            }
+        };
+
+        let rows = match rows {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Ошибка запроса операций с конвертами: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+
+        let items: Vec<LedgerEntry> = rows
+            .iter()
+            .filter_map(|row| {
+                let id: String = row.get("id");
+                let content: String = row.get("content");
+                serde_json::from_str(&content)
+                    .map_err(|e| error!("Ошибка десериализации операции {id}: {e}"))
+                    .ok()
+            })
+            .collect();
+
+        let next_cursor = if items.len() < limit {
+            None
+        } else {
+            items.last().map(|e| e.id.clone())
+        };
+        Page::new(items, next_cursor)
+    }
+
+    #[instrument(skip(self, plan, events), fields(plan_id = %plan.id))]
+    async fn restore_plan(
+        &self,
+        user_id: &UserId,
+        plan: StoragePlan,
+        events: Vec<PlanEvent>,
+    ) -> Result<(), StorageError> {
+        let content =
+            serde_json::to_string(&plan.plan).map_err(|_| StorageError::RestorePlan)?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::RestorePlan)?;
+
+        if name_taken(&mut tx, user_id, &plan.name, None)
+            .await
+            .map_err(|_| StorageError::RestorePlan)?
+        {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        if plan.active {
+            sqlx::query(
+                "UPDATE plans SET is_active = FALSE WHERE user_id = $1 AND is_active = TRUE",
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StorageError::RestorePlan)?;
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO plans (id, user_id, name, content, version, status, is_active) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT DO NOTHING",
+        )
+        .bind(&plan.id)
+        .bind(user_id)
+        .bind(&plan.name)
+        .bind(&content)
+        .bind(plan.version)
+        .bind(plan.status.as_str())
+        .bind(plan.active)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Ошибка восстановления плана {}: {e}", plan.id);
+            StorageError::RestorePlan
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        for event in &events {
+            let content = event
+                .content
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|_| StorageError::RestorePlan)?;
+            sqlx::query(
+                "INSERT INTO plan_events (plan_id, version, action, content, created_at) \
+                 VALUES ($1, $2, $3, $4, $5::timestamp AT TIME ZONE 'UTC')",
+            )
+            .bind(&plan.id)
+            .bind(event.version)
+            .bind(event.action.as_str())
+            .bind(content)
+            .bind(&event.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("Ошибка восстановления события плана {}: {e}", plan.id);
+                StorageError::RestorePlan
+            })?;
+        }
+
+        tx.commit().await.map_err(|_| StorageError::RestorePlan)?;
+
+        info!("План восстановлен в PostgreSQL: {}", plan.id);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn clear_user_data(&self, user_id: &UserId) -> Result<(), StorageError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::ClearUserData)?;
+
+        // События ссылаются на планы, поэтому удаляются первыми
+        for query in [
+            "DELETE FROM plan_events WHERE plan_id IN (SELECT id FROM plans WHERE user_id = $1)",
+            "DELETE FROM plans WHERE user_id = $1",
+            "DELETE FROM budgets WHERE user_id = $1",
+            "DELETE FROM ledger_entries WHERE user_id = $1",
+        ] {
+            sqlx::query(query)
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    error!("Ошибка удаления данных пользователя {user_id}: {e}");
+                    StorageError::ClearUserData
+                })?;
+        }
+
+        tx.commit().await.map_err(|_| StorageError::ClearUserData)?;
+
+        info!("Данные пользователя {user_id} удалены из PostgreSQL");
+        Ok(())
+    }
+}
+
+impl AccountRepo for PostgresRepo {
+    #[instrument(skip(self))]
+    async fn accounts_count(&self) -> usize {
+        sqlx::query_scalar::<_, i64>("SELECT count(*) FROM accounts")
+            .fetch_one(&self.pool)
+            .await
+            .inspect_err(|e| error!("Ошибка подсчёта учётных записей: {e}"))
+            .map_or(0, |n| n as usize)
+    }
+
+    #[instrument(skip(self, password_hash))]
+    async fn create_account(
+        &self,
+        account: Account,
+        password_hash: String,
+    ) -> Result<(), StorageError> {
+        sqlx::query(
+            "INSERT INTO accounts (id, login, password_hash) VALUES ($1, $2, $3)",
+        )
+        .bind(&account.id)
+        .bind(&account.login)
+        .bind(&password_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => StorageError::AccountAlreadyExists,
+            _ => {
+                error!("Ошибка создания учётной записи: {e}");
+                StorageError::CreateAccount
+            }
+        })?;
+
+        info!("Учётная запись создана: {}", account.login);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn account_by_login(&self, login: &str) -> Option<StoredAccount> {
+        let row = sqlx::query(
+            "SELECT id, login, password_hash FROM accounts WHERE login = $1",
+        )
+        .bind(login)
+        .fetch_optional(&self.pool)
+        .await
+        .ok()
+        .flatten()?;
+
+        Some(StoredAccount {
+            account: Account {
+                id: row.get("id"),
+                login: row.get("login"),
+            },
+            password_hash: row.get("password_hash"),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn create_session(&self, session: Session) -> Result<(), StorageError> {
+        sqlx::query(
+            "INSERT INTO sessions (token_hash, user_id, expires_at) \
+             VALUES ($1, $2, $3::timestamptz)",
+        )
+        .bind(&session.token_hash)
+        .bind(&session.user_id)
+        .bind(timestamp(session.expires_at))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Ошибка создания сессии: {e}");
+            StorageError::CreateSession
+        })?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn session_account(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Option<Account> {
+        let row = sqlx::query(
+            "SELECT a.id, a.login FROM sessions s JOIN accounts a ON a.id = s.user_id \
+             WHERE s.token_hash = $1 AND s.expires_at > $2::timestamptz",
+        )
+        .bind(token_hash)
+        .bind(timestamp(now))
+        .fetch_optional(&self.pool)
+        .await
+        .ok()
+        .flatten()?;
+
+        Some(Account {
+            id: row.get("id"),
+            login: row.get("login"),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_session(&self, token_hash: &str) -> Result<(), StorageError> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+            .bind(token_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| StorageError::DeleteSession)?;
+        Ok(())
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Есть ли у пользователя другой неудалённый план с таким именем
+async fn name_taken(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &UserId,
+    name: &str,
+    except: Option<&PlanId>,
+) -> Result<bool, sqlx::Error> {
+    let found: Option<i32> = sqlx::query_scalar(
+        "SELECT 1 FROM plans \
+         WHERE user_id = $1 AND name = $2 AND status = 'active' \
+         AND id IS DISTINCT FROM $3",
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(except)
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(found.is_some())
+}
+
+fn plan_from_row(row: &PgRow) -> Option<StoragePlan> {
+    let id: String = row.get("id");
+    let content: String = row.get("content");
+    let status_raw: String = row.get("status");
+
+    let plan: Plan = serde_json::from_str(&content)
+        .map_err(|e| error!("Ошибка десериализации плана {id}: {e}"))
+        .ok()?;
+
+    let status: PlanStatus = match status_raw.parse() {
+        Ok(s) => s,
+        Err(_) => {
+            error!("Неизвестный статус плана в БД: {status_raw}");
+            return None;
+        }
+    };
+
+    Some(StoragePlan {
+        user_id: row.get("user_id"),
+        id,
+        name: row.get("name"),
+        plan,
+        version: row.get("version"),
+        status,
+        active: row.get("is_active"),
+    })
+}
+
+fn budget_from_row(row: &PgRow) -> Option<StorageBudget> {
+    let id: String = row.get("id");
+    let content: String = row.get("content");
+    let budget: Budget = serde_json::from_str(&content)
+        .map_err(|e| error!("Ошибка десериализации бюджета {id}: {e}"))
+        .ok()?;
+    let plan_id: Option<String> = row.get("plan_id");
+    let plan_version: Option<i64> = row.get("plan_version");
+    let plan = plan_id
+        .zip(plan_version)
+        .map(|(plan_id, version)| PlanRef { plan_id, version });
+    let voided_at: Option<String> = row.get("voided_at");
+    let voided = voided_at.and_then(|date| {
+        NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|e| error!("Некорректная дата аннулирования бюджета {id}: {e}"))
+            .ok()
+            .map(|date| BudgetVoid {
+                date,
+                reason: row.get("void_reason"),
+            })
+    });
+    Some(StorageBudget {
+        id,
+        budget,
+        plan,
+        voided,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::TcpListener,
+        path::PathBuf,
+        process::{Command, Stdio},
+    };
+
+    use super::*;
+    use crate::storage::suite;
+
+    /// Одноразовый сервер PostgreSQL во временном каталоге.
+    /// Останавливается и удаляется вместе со значением.
+    struct TestServer {
+        dir: PathBuf,
+        port: u16,
+    }
+
+    impl TestServer {
+        /// Требует установленный PostgreSQL: без него тест падает,
+        /// а не проходит молча
+        fn start() -> Self {
+            let port = TcpListener::bind("127.0.0.1:0")
+                .and_then(|l| l.local_addr())
+                .unwrap()
+                .port();
+            let dir = std::env::temp_dir().join(format!("anna_ivanovna_pg_{port}"));
+            let dir_arg = dir.to_string_lossy().into_owned();
+            let initdb = pg_command("initdb")
+                .args(["-D", &dir_arg, "-A", "trust", "-U", "postgres", "--no-sync"])
+                .stdout(Stdio::null())
+                .status()
+                .expect("initdb not found, install PostgreSQL to run these tests");
+            assert!(initdb.success(), "initdb failed");
+            let options = format!(
+                "-p {port} -k {dir_arg} -c listen_addresses=127.0.0.1 -c fsync=off"
+            );
+            let started = pg_command("pg_ctl")
+                .args(["-D", &dir_arg, "-o", &options, "-w", "start"])
+                .stdout(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(started.success(), "pg_ctl start failed");
+            Self { dir, port }
+        }
+
+        fn url(&self) -> String {
+            format!("postgres://postgres@127.0.0.1:{}/postgres", self.port)
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            let dir_arg = self.dir.to_string_lossy().into_owned();
+            let _ = pg_command("pg_ctl")
+                .args(["-D", &dir_arg, "-m", "immediate", "stop"])
+                .stdout(Stdio::null())
+                .status();
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// PostgreSQL не запускается от root, поэтому в таком случае сервер
+    /// поднимается от имени пользователя postgres
+    fn pg_command(program: &str) -> Command {
+        let is_root = Command::new("id")
+            .arg("-u")
+            .output()
+            .is_ok_and(|o| o.stdout.trim_ascii() == b"0");
+        let mut command = if is_root {
+            let mut command = Command::new("runuser");
+            command.args(["-u", "postgres", "--", program]);
+            command
+        } else {
+            Command::new(program)
+        };
+        // У postgres может не быть доступа к каталогу проекта
+        command.current_dir(std::env::temp_dir());
+        command
+    }
+
+    /// Прогоняет общую проверку хранилища на чистой базе.
+    /// Тесты требуют PostgreSQL и запускаются явно: `cargo test -- --ignored`
+    macro_rules! suite_tests {
+        ($($name:ident),* $(,)?) => {
+            $(
+                #[tokio::test]
+                #[ignore = "requires PostgreSQL, run with `cargo test -- --ignored`"]
+                async fn $name() {
+                    let server = TestServer::start();
+                    let repo = PostgresRepo::init(&server.url()).await.unwrap();
+                    suite::$name(&repo).await;
+                }
+            )*
+        };
+    }
+
+    suite_tests!(
+        plan_events_paginate_newest_first,
+        switch_active_plan,
+        shared_plans_found_by_member,
+        update_plan_checks_version,
+        ledger_entries_paginate_newest_first,
+        budget_keeps_plan_version_and_void,
+        budgets_are_scoped_to_user,
+        import_same_archive_for_two_users,
+        restore_plan_keeps_history,
+        sessions_expire_and_close,
+    );
+}
@@ -1,19 +1,34 @@
-use ai_app::storage::{
-    BudgetId,
-    CoreRepo,
-    Cursor,
-    Page,
-    PlanAction,
-    PlanEvent,
-    PlanId,
-    PlanStatus,
-    StorageBudget,
-    StorageError,
-    StoragePlan,
-    UserId,
+use ai_app::{
+    ledger::{LedgerEntry, LedgerEntryId, LedgerOperation},
+    storage::{
+        Account,
+        AccountRepo,
+        BudgetId,
+        BudgetVoid,
+        CoreRepo,
+        Cursor,
+        Page,
+        PlanAction,
+        PlanEvent,
+        PlanId,
+        PlanRef,
+        PlanStatus,
+        Session,
+        StorageBudget,
+        StorageError,
+        StoragePlan,
+        StoredAccount,
+        UserId,
+        Version,
+    },
 };
 use ai_core::{distribute::Budget, plan::Plan};
-use sqlx::{Row, SqlitePool, sqlite::SqlitePoolOptions};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use sqlx::{
+    Row,
+    SqlitePool,
+    sqlite::{SqlitePoolOptions, SqliteRow},
+};
 use std::path::Path;
 use tracing::{error, info, instrument, warn};
 
@@ -54,8 +69,8 @@ impl CoreRepo for SqliteRepo {
     #[instrument(skip(self))]
     async fn get_plan(&self, user_id: &UserId) -> Option<StoragePlan> {
         let row = sqlx::query(
-            "SELECT id, content, version, status FROM plans \
-             WHERE user_id = ? AND status = 'active' LIMIT 1",
+            "SELECT id, user_id, name, content, version, status, is_active FROM plans \
+             WHERE user_id = ? AND status = 'active' AND is_active = 1",
         )
         .bind(user_id)
         .fetch_optional(&self.pool)
@@ -63,30 +78,42 @@ impl CoreRepo for SqliteRepo {
         .ok()
         .flatten()?;
 
-        let id: String = row.get("id");
-        let content: String = row.get("content");
-        let version: i64 = row.get("version");
-        let status_raw: String = row.get("status");
+        plan_from_row(&row)
+    }
 
-        let plan: Plan = serde_json::from_str(&content)
-            .map_err(|e| error!("Ошибка десериализации плана: {e}"))
-            .ok()?;
+    #[instrument(skip(self))]
+    async fn list_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        let rows = sqlx::query(
+            "SELECT id, user_id, name, content, version, status, is_active FROM plans \
+             WHERE user_id = ? AND status = 'active' ORDER BY created_at, rowid",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .inspect_err(|e| error!("Ошибка загрузки планов: {e}"))
+        .unwrap_or_default();
 
-        let status: PlanStatus = match status_raw.parse() {
-            Ok(s) => s,
-            Err(_) => {
-                error!("Неизвестный статус плана в БД: {status_raw}");
-                return None;
-            }
-        };
+        rows.iter().filter_map(plan_from_row).collect()
+    }
 
-        Some(StoragePlan {
-            user_id: user_id.clone(),
-            id,
-            plan,
-            version,
-            status,
-        })
+    #[instrument(skip(self))]
+    async fn shared_plans(&self, user_id: &UserId) -> Vec<StoragePlan> {
+        // Участники хранятся в содержимом плана
+        let rows = sqlx::query(
+            "SELECT id, user_id, name, content, version, status, is_active FROM plans \
+             WHERE user_id != ? AND status = 'active' AND EXISTS ( \
+                 SELECT 1 FROM json_each(plans.content, '$.members') AS m \
+                 WHERE json_extract(m.value, '$.id') = ?) \
+             ORDER BY created_at, rowid",
+        )
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .inspect_err(|e| error!("Ошибка загрузки общих планов: {e}"))
+        .unwrap_or_default();
+
+        rows.iter().filter_map(plan_from_row).collect()
     }
 
     #[instrument(skip(self, plan))]
@@ -94,6 +121,7 @@ impl CoreRepo for SqliteRepo {
         &self,
         user_id: &UserId,
         plan_id: PlanId,
+        name: String,
         plan: Plan,
     ) -> Result<PlanId, StorageError> {
         let content =
@@ -105,13 +133,25 @@ impl CoreRepo for SqliteRepo {
             .await
             .map_err(|_| StorageError::CreatePlan)?;
 
+        if name_taken(&mut tx, user_id, &name, None)
+            .await
+            .map_err(|_| StorageError::CreatePlan)?
+        {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        // Первый план пользователя сразу становится активным
         let result = sqlx::query(
-            "INSERT INTO plans (id, user_id, content, version, status) \
-             VALUES (?, ?, ?, 1, 'active') ON CONFLICT DO NOTHING",
+            "INSERT INTO plans (id, user_id, name, content, version, status, is_active) \
+             SELECT ?, ?, ?, ?, 1, 'active', NOT EXISTS ( \
+                 SELECT 1 FROM plans WHERE user_id = ? AND is_active = 1 \
+             ) ON CONFLICT DO NOTHING",
         )
         .bind(&plan_id)
         .bind(user_id)
+        .bind(&name)
         .bind(&content)
+        .bind(user_id)
         .execute(&mut *tx)
         .await
         .inspect_err(|err| warn!("{err} cant create_plan"))
@@ -137,11 +177,100 @@ impl CoreRepo for SqliteRepo {
         Ok(plan_id)
     }
 
+    #[instrument(skip(self))]
+    async fn rename_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+        name: String,
+    ) -> Result<(), StorageError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::RenamePlan)?;
+
+        if name_taken(&mut tx, user_id, &name, Some(plan_id))
+            .await
+            .map_err(|_| StorageError::RenamePlan)?
+        {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        let result = sqlx::query(
+            "UPDATE plans SET name = ?, updated_at = datetime('now') \
+             WHERE user_id = ? AND id = ? AND status = 'active'",
+        )
+        .bind(&name)
+        .bind(user_id)
+        .bind(plan_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::RenamePlan)?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::PlanNotFound);
+        }
+
+        tx.commit().await.map_err(|_| StorageError::RenamePlan)?;
+
+        info!("План {plan_id} переименован в {name}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn activate_plan(
+        &self,
+        user_id: &UserId,
+        plan_id: &PlanId,
+    ) -> Result<(), StorageError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::ActivatePlan)?;
+
+        let exists: Option<i64> = sqlx::query_scalar(
+            "SELECT 1 FROM plans WHERE user_id = ? AND id = ? AND status = 'active'",
+        )
+        .bind(user_id)
+        .bind(plan_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StorageError::ActivatePlan)?;
+
+        if exists.is_none() {
+            return Err(StorageError::PlanNotFound);
+        }
+
+        // Сначала снимаем отметку, иначе сработает уникальный индекс
+        sqlx::query(
+            "UPDATE plans SET is_active = 0 WHERE user_id = ? AND is_active = 1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StorageError::ActivatePlan)?;
+
+        sqlx::query("UPDATE plans SET is_active = 1 WHERE user_id = ? AND id = ?")
+            .bind(user_id)
+            .bind(plan_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StorageError::ActivatePlan)?;
+
+        tx.commit().await.map_err(|_| StorageError::ActivatePlan)?;
+
+        info!("Активный план: {plan_id}");
+        Ok(())
+    }
+
     #[instrument(skip(self, plan))]
     async fn update_plan(
         &self,
         user_id: &UserId,
         plan_id: &PlanId,
+        expected: Version,
         plan: Plan,
     ) -> Result<(), StorageError> {
         let content =
@@ -155,17 +284,31 @@ impl CoreRepo for SqliteRepo {
 
         let result = sqlx::query(
             "UPDATE plans SET version = version + 1, content = ?, updated_at = datetime('now') \
-             WHERE user_id = ? AND id = ? AND status = 'active'",
+             WHERE user_id = ? AND id = ? AND status = 'active' AND version = ?",
         )
         .bind(&content)
         .bind(user_id)
         .bind(plan_id)
+        .bind(expected)
         .execute(&mut *tx)
         .await
         .map_err(|_| StorageError::UpdatePlan)?;
 
         if result.rows_affected() == 0 {
-            return Err(StorageError::UpdatePlan);
+            // План есть, но его уже обновили с другой вкладки
+            let current: Option<Version> = sqlx::query_scalar(
+                "SELECT version FROM plans \
+                 WHERE user_id = ? AND id = ? AND status = 'active'",
+            )
+            .bind(user_id)
+            .bind(plan_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|_| StorageError::UpdatePlan)?;
+            return Err(match current {
+                Some(current) => StorageError::VersionConflict { current },
+                None => StorageError::UpdatePlan,
+            });
         }
 
         sqlx::query(
@@ -199,7 +342,7 @@ impl CoreRepo for SqliteRepo {
             .map_err(|_| StorageError::DeletePlan)?;
 
         let result = sqlx::query(
-            "UPDATE plans SET status = 'deleted', version = version + 1, \
+            "UPDATE plans SET status = 'deleted', is_active = 0, version = version + 1, \
              deleted_at = datetime('now'), updated_at = datetime('now') \
              WHERE user_id = ? AND id = ? AND status = 'active'",
         )
@@ -233,7 +376,7 @@ impl CoreRepo for SqliteRepo {
     #[instrument(skip(self))]
     async fn plan_events(
         &self,
-        _user_id: &UserId,
+        user_id: &UserId,
         plan_id: &PlanId,
         from: Option<Cursor>,
         limit: usize,
@@ -242,10 +385,13 @@ impl CoreRepo for SqliteRepo {
             Some(cursor) => {
                 sqlx::query(
                     "SELECT id, plan_id, version, action, content, created_at \
-                     FROM plan_events WHERE plan_id = ? AND id < ? ORDER BY id DESC LIMIT ?",
+                     FROM plan_events WHERE plan_id = ? AND id < ? \
+                     AND plan_id IN (SELECT id FROM plans WHERE user_id = ?) \
+                     ORDER BY id DESC LIMIT ?",
                 )
                 .bind(plan_id)
                 .bind(cursor)
+                .bind(user_id)
                 .bind(limit as i64)
                 .fetch_all(&self.pool)
                 .await
@@ -253,9 +399,12 @@ impl CoreRepo for SqliteRepo {
             None => {
                 sqlx::query(
                     "SELECT id, plan_id, version, action, content, created_at \
-                     FROM plan_events WHERE plan_id = ? ORDER BY id DESC LIMIT ?",
+                     FROM plan_events WHERE plan_id = ? \
+                     AND plan_id IN (SELECT id FROM plans WHERE user_id = ?) \
+                     ORDER BY id DESC LIMIT ?",
                 )
                 .bind(plan_id)
+                .bind(user_id)
                 .bind(limit as i64)
                 .fetch_all(&self.pool)
                 .await
@@ -318,105 +467,545 @@ impl CoreRepo for SqliteRepo {
     #[instrument(skip(self, budget))]
     async fn save_budget(
         &self,
+        user_id: &UserId,
         budget_id: BudgetId,
         budget: Budget,
+        plan: Option<PlanRef>,
     ) -> Result<BudgetId, StorageError> {
         let source = &budget.income.source.name;
         let income_date = budget.income_date().format("%Y-%m-%d").to_string();
         let content =
             serde_json::to_string(&budget).map_err(|_| StorageError::SaveBudget)?;
 
-        sqlx::query(
-            "INSERT OR REPLACE INTO budgets (id, source, income_date, content) \
-             VALUES (?, ?, ?, ?)",
+        let result = sqlx::query(
+            "INSERT INTO budgets \
+             (id, user_id, source, income_date, content, plan_id, plan_version) \
+             VALUES (?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT (id) DO UPDATE SET source = excluded.source, \
+             income_date = excluded.income_date, content = excluded.content, \
+             plan_id = excluded.plan_id, plan_version = excluded.plan_version, \
+             voided_at = NULL, void_reason = NULL \
+             WHERE budgets.user_id = excluded.user_id",
         )
         .bind(&budget_id)
+        .bind(user_id)
         .bind(source)
         .bind(&income_date)
         .bind(&content)
+        .bind(plan.as_ref().map(|p| &p.plan_id))
+        .bind(plan.as_ref().map(|p| p.version))
         .execute(&self.pool)
         .await
         .map_err(|_| StorageError::SaveBudget)?;
+        // Идентификатор занят распределением другого пользователя
+        if result.rows_affected() == 0 {
+            return Err(StorageError::BudgetAlreadyExists);
+        }
 
         info!("Бюджет сохранён в SQLite: {budget_id}");
         Ok(budget_id)
     }
 
     #[instrument(skip(self))]
-    async fn budget_by_id(&self, id: &BudgetId) -> Option<StorageBudget> {
-        let row = sqlx::query("SELECT content FROM budgets WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await
-            .ok()
-            .flatten()?;
+    async fn budget_by_id(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+    ) -> Option<StorageBudget> {
+        let row = sqlx::query(
+            "SELECT id, content, plan_id, plan_version, voided_at, void_reason \
+             FROM budgets WHERE id = ? AND user_id = ?",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .ok()
+        .flatten()?;
 
-        let content: String = row.get("content");
-        let budget: Budget = serde_json::from_str(&content)
-            .map_err(|e| error!("Ошибка десериализации бюджета {id}: {e}"))
-            .ok()?;
+        budget_from_row(&row)
+    }
 
-        Some(StorageBudget {
-            id: id.clone(),
-            budget,
-        })
+    #[instrument(skip(self))]
+    async fn budgets(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<StorageBudget> {
+        let rows =
+            match &from {
+                Some(cursor) => sqlx::query(
+                    "SELECT id, content, plan_id, plan_version, voided_at, void_reason \
+                     FROM budgets WHERE user_id = ? AND id < ? ORDER BY id DESC LIMIT ?",
+                )
+                .bind(user_id)
+                .bind(cursor)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await,
+                None => sqlx::query(
+                    "SELECT id, content, plan_id, plan_version, voided_at, void_reason \
+                     FROM budgets WHERE user_id = ? ORDER BY id DESC LIMIT ?",
+                )
+                .bind(user_id)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await,
+            };
+
+        let rows = match rows {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Ошибка запроса бюджетов: {e}");
+                return Page::new(vec![], None);
+            }
+        };
+
+        let items: Vec<StorageBudget> =
+            rows.iter().filter_map(budget_from_row).collect();
+
+        let next_cursor = if items.len() < limit {
+            None
+        } else {
+            items.last().map(|b| b.id.clone())
+        };
+        Page::new(items, next_cursor)
     }
 
     #[instrument(skip(self))]
-    async fn budgets(&self, from: Option<Cursor>, limit: usize) -> Page<StorageBudget> {
+    async fn void_budget(
+        &self,
+        user_id: &UserId,
+        id: &BudgetId,
+        void: BudgetVoid,
+    ) -> Result<(), StorageError> {
+        let result = sqlx::query(
+            "UPDATE budgets SET voided_at = ?, void_reason = ? \
+             WHERE id = ? AND user_id = ? AND voided_at IS NULL",
+        )
+        .bind(void.date.format("%Y-%m-%d").to_string())
+        .bind(&void.reason)
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Ошибка аннулирования бюджета {id}: {e}");
+            StorageError::VoidBudget
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(StorageError::VoidBudget);
+        }
+
+        info!("Бюджет аннулирован в SQLite: {id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self, entry))]
+    async fn save_ledger_entry(
+        &self,
+        user_id: &UserId,
+        entry: LedgerEntry,
+    ) -> Result<LedgerEntryId, StorageError> {
+        let kind = match entry.operation {
+            LedgerOperation::Withdrawal { .. } => "withdrawal",
+            LedgerOperation::Transfer { .. } => "transfer",
+        };
+        let entry_date = entry.date.format("%Y-%m-%d").to_string();
+        let content =
+            serde_json::to_string(&entry).map_err(|_| StorageError::SaveLedgerEntry)?;
+
+        let result = sqlx::query(
+            "INSERT INTO ledger_entries (id, user_id, kind, entry_date, content) \
+             VALUES (?, ?, ?, ?, ?) ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&entry.id)
+        .bind(user_id)
+        .bind(kind)
+        .bind(&entry_date)
+        .bind(&content)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Ошибка сохранения операции с конвертами: {e}");
+            StorageError::SaveLedgerEntry
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(StorageError::LedgerEntryAlreadyExists);
+        }
+
+        info!("Операция с конвертами сохранена в SQLite: {}", entry.id);
+        Ok(entry.id)
+    }
+
+    #[instrument(skip(self))]
+    async fn ledger_entries(
+        &self,
+        user_id: &UserId,
+        from: Option<Cursor>,
+        limit: usize,
+    ) -> Page<LedgerEntry> {
         let rows = match &from {
-            Some(cursor) => sqlx::query(
-                "SELECT id, content FROM budgets WHERE id < ? ORDER BY id DESC LIMIT ?",
-            )
-            .bind(cursor)
-            .bind(limit as i64)
-            .fetch_all(&self.pool)
-            .await,
+            Some(cursor) => {
+                sqlx::query(
+                    "SELECT id, content FROM ledger_entries \
+                     WHERE user_id = ? AND id < ? ORDER BY id DESC LIMIT ?",
+                )
+                .bind(user_id)
+                .bind(cursor)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
+            }
             None => {
-                sqlx::query("SELECT id, content FROM budgets ORDER BY id DESC LIMIT ?")
-                    .bind(limit as i64)
-                    .fetch_all(&self.pool)
-                    .await
+                sqlx::query(
+                    "SELECT id, content FROM ledger_entries \
+                     WHERE user_id = ? ORDER BY id DESC LIMIT ?",
+                )
+                .bind(user_id)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await
             }
         };
 
         let rows = match rows {
             Ok(r) => r,
             Err(e) => {
-                error!("Ошибка запроса бюджетов: {e}");
+                error!("Ошибка запроса операций с конвертами: {e}");
                 return Page::new(vec![], None);
             }
         };
 
-        let items: Vec<StorageBudget> = rows
+        let items: Vec<LedgerEntry> = rows
             .iter()
             .filter_map(|row| {
                 let id: String = row.get("id");
                 let content: String = row.get("content");
-                let budget: Budget = serde_json::from_str(&content)
-                    .map_err(|e| error!("Ошибка десериализации бюджета {id}: {e}"))
-                    .ok()?;
-                Some(StorageBudget { id, budget })
+                serde_json::from_str(&content)
+                    .map_err(|e| error!("Ошибка десериализации операции {id}: {e}"))
+                    .ok()
             })
             .collect();
 
         let next_cursor = if items.len() < limit {
             None
         } else {
-            items.last().map(|b| b.id.clone())
+            items.last().map(|e| e.id.clone())
         };
         Page::new(items, next_cursor)
     }
+
+    #[instrument(skip(self, plan, events), fields(plan_id = %plan.id))]
+    async fn restore_plan(
+        &self,
+        user_id: &UserId,
+        plan: StoragePlan,
+        events: Vec<PlanEvent>,
+    ) -> Result<(), StorageError> {
+        let content =
+            serde_json::to_string(&plan.plan).map_err(|_| StorageError::RestorePlan)?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::RestorePlan)?;
+
+        if name_taken(&mut tx, user_id, &plan.name, None)
+            .await
+            .map_err(|_| StorageError::RestorePlan)?
+        {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        if plan.active {
+            sqlx::query(
+                "UPDATE plans SET is_active = 0 WHERE user_id = ? AND is_active = 1",
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StorageError::RestorePlan)?;
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO plans (id, user_id, name, content, version, status, is_active) \
+             VALUES (?, ?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+        )
+        .bind(&plan.id)
+        .bind(user_id)
+        .bind(&plan.name)
+        .bind(&content)
+        .bind(plan.version)
+        .bind(plan.status.as_str())
+        .bind(plan.active)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Ошибка восстановления плана {}: {e}", plan.id);
+            StorageError::RestorePlan
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::PlanAlreadyExists);
+        }
+
+        for event in &events {
+            let content = event
+                .content
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|_| StorageError::RestorePlan)?;
+            sqlx::query(
+                "INSERT INTO plan_events (plan_id, version, action, content, created_at) \
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&plan.id)
+            .bind(event.version)
+            .bind(event.action.as_str())
+            .bind(content)
+            .bind(&event.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("Ошибка восстановления события плана {}: {e}", plan.id);
+                StorageError::RestorePlan
+            })?;
+        }
+
+        tx.commit().await.map_err(|_| StorageError::RestorePlan)?;
+
+        info!("План восстановлен в SQLite: {}", plan.id);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn clear_user_data(&self, user_id: &UserId) -> Result<(), StorageError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| StorageError::ClearUserData)?;
+
+        // События ссылаются на планы, поэтому удаляются первыми
+        for query in [
+            "DELETE FROM plan_events WHERE plan_id IN (SELECT id FROM plans WHERE user_id = ?)",
+            "DELETE FROM plans WHERE user_id = ?",
+            "DELETE FROM budgets WHERE user_id = ?",
+            "DELETE FROM ledger_entries WHERE user_id = ?",
+        ] {
+            sqlx::query(query)
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    error!("Ошибка удаления данных пользователя {user_id}: {e}");
+                    StorageError::ClearUserData
+                })?;
+        }
+
+        tx.commit().await.map_err(|_| StorageError::ClearUserData)?;
+
+        info!("Данные пользователя {user_id} удалены из SQLite");
+        Ok(())
+    }
+}
+
+impl AccountRepo for SqliteRepo {
+    #[instrument(skip(self))]
+    async fn accounts_count(&self) -> usize {
+        sqlx::query_scalar::<_, i64>("SELECT count(*) FROM accounts")
+            .fetch_one(&self.pool)
+            .await
+            .inspect_err(|e| error!("Ошибка подсчёта учётных записей: {e}"))
+            .map_or(0, |n| n as usize)
+    }
+
+    #[instrument(skip(self, password_hash))]
+    async fn create_account(
+        &self,
+        account: Account,
+        password_hash: String,
+    ) -> Result<(), StorageError> {
+        sqlx::query("INSERT INTO accounts (id, login, password_hash) VALUES (?, ?, ?)")
+            .bind(&account.id)
+            .bind(&account.login)
+            .bind(&password_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| match e.as_database_error() {
+                Some(db) if db.is_unique_violation() => {
+                    StorageError::AccountAlreadyExists
+                }
+                _ => {
+                    error!("Ошибка создания учётной записи: {e}");
+                    StorageError::CreateAccount
+                }
+            })?;
+
+        info!("Учётная запись создана: {}", account.login);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn account_by_login(&self, login: &str) -> Option<StoredAccount> {
+        let row = sqlx::query(
+            "SELECT id, login, password_hash FROM accounts WHERE login = ?",
+        )
+        .bind(login)
+        .fetch_optional(&self.pool)
+        .await
+        .ok()
+        .flatten()?;
+
+        Some(StoredAccount {
+            account: Account {
+                id: row.get("id"),
+                login: row.get("login"),
+            },
+            password_hash: row.get("password_hash"),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn create_session(&self, session: Session) -> Result<(), StorageError> {
+        sqlx::query(
+            "INSERT INTO sessions (token_hash, user_id, expires_at) VALUES (?, ?, ?)",
+        )
+        .bind(&session.token_hash)
+        .bind(&session.user_id)
+        .bind(timestamp(session.expires_at))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Ошибка создания сессии: {e}");
+            StorageError::CreateSession
+        })?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn session_account(
+        &self,
+        token_hash: &str,
+        now: DateTime<Utc>,
+    ) -> Option<Account> {
+        let row = sqlx::query(
+            "SELECT a.id, a.login FROM sessions s JOIN accounts a ON a.id = s.user_id \
+             WHERE s.token_hash = ? AND s.expires_at > ?",
+        )
+        .bind(token_hash)
+        .bind(timestamp(now))
+        .fetch_optional(&self.pool)
+        .await
+        .ok()
+        .flatten()?;
+
+        Some(Account {
+            id: row.get("id"),
+            login: row.get("login"),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_session(&self, token_hash: &str) -> Result<(), StorageError> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
+            .bind(token_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| StorageError::DeleteSession)?;
+        Ok(())
+    }
+}
+
+/// Время в UTC с фиксированной точностью: такие строки сравниваются как даты
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Есть ли у пользователя другой неудалённый план с таким именем
+async fn name_taken(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    user_id: &UserId,
+    name: &str,
+    except: Option<&PlanId>,
+) -> Result<bool, sqlx::Error> {
+    let found: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM plans \
+         WHERE user_id = ? AND name = ? AND status = 'active' AND id IS NOT ?",
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(except)
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(found.is_some())
+}
+
+fn plan_from_row(row: &SqliteRow) -> Option<StoragePlan> {
+    let id: String = row.get("id");
+    let content: String = row.get("content");
+    let status_raw: String = row.get("status");
+
+    let plan: Plan = serde_json::from_str(&content)
+        .map_err(|e| error!("Ошибка десериализации плана {id}: {e}"))
+        .ok()?;
+
+    let status: PlanStatus = match status_raw.parse() {
+        Ok(s) => s,
+        Err(_) => {
+            error!("Неизвестный статус плана в БД: {status_raw}");
+            return None;
+        }
+    };
+
+    Some(StoragePlan {
+        user_id: row.get("user_id"),
+        id,
+        name: row.get("name"),
+        plan,
+        version: row.get("version"),
+        status,
+        active: row.get("is_active"),
+    })
+}
+
+fn budget_from_row(row: &SqliteRow) -> Option<StorageBudget> {
+    let id: String = row.get("id");
+    let content: String = row.get("content");
+    let budget: Budget = serde_json::from_str(&content)
+        .map_err(|e| error!("Ошибка десериализации бюджета {id}: {e}"))
+        .ok()?;
+    let plan_id: Option<String> = row.get("plan_id");
+    let plan_version: Option<i64> = row.get("plan_version");
+    let plan = plan_id
+        .zip(plan_version)
+        .map(|(plan_id, version)| PlanRef { plan_id, version });
+    let voided_at: Option<String> = row.get("voided_at");
+    let voided = voided_at.and_then(|date| {
+        NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|e| error!("Некорректная дата аннулирования бюджета {id}: {e}"))
+            .ok()
+            .map(|date| BudgetVoid {
+                date,
+                reason: row.get("void_reason"),
+            })
+    });
+    Some(StorageBudget {
+        id,
+        budget,
+        plan,
+        voided,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ai_core::{
-        finance::{Money, Percentage},
-        planning::{Expense, ExpenseValue, IncomeKind, IncomeSource},
-    };
-    use rust_decimal_macros::dec;
+    use crate::storage::suite::{self, valid_plan};
     use std::{path::PathBuf, time::SystemTime};
 
     fn temp_db_path() -> PathBuf {
@@ -427,33 +1016,6 @@ mod tests {
         std::env::temp_dir().join(format!("anna_ivanovna_test_{ts}.db"))
     }
 
-    fn valid_plan() -> Plan {
-        Plan::build(
-            &[IncomeSource::new(
-                "Зарплата".into(),
-                IncomeKind::Other {
-                    expected: Money::new_rub(dec!(100000)),
-                },
-            )],
-            &[
-                Expense::envelope(
-                    "Аренда".into(),
-                    ExpenseValue::MONEY {
-                        value: Money::new_rub(dec!(30000)),
-                    },
-                    Some("Жильё".into()),
-                ),
-                Expense::envelope(
-                    "Накопления".into(),
-                    ExpenseValue::RATE {
-                        value: Percentage::from_int(20),
-                    },
-                    None,
-                ),
-            ],
-        )
-    }
-
     #[tokio::test]
     async fn delete_plan_writes_deleted_event() {
         let db_path = temp_db_path();
@@ -462,7 +1024,7 @@ mod tests {
         let user_id: UserId = "default".to_string();
         let plan_id: PlanId = "plan-1".to_string();
 
-        repo.create_plan(&user_id, plan_id.clone(), valid_plan())
+        repo.create_plan(&user_id, plan_id.clone(), "Основной".into(), valid_plan())
             .await
             .unwrap();
         repo.delete_plan(&user_id, &plan_id).await.unwrap();
@@ -490,4 +1052,84 @@ mod tests {
 
         let _ = std::fs::remove_file(db_path);
     }
+
+    #[tokio::test]
+    async fn plan_events_paginate_newest_first() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::plan_events_paginate_newest_first(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn switch_active_plan() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::switch_active_plan(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn shared_plans_found_by_member() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::shared_plans_found_by_member(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn update_plan_checks_version() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::update_plan_checks_version(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn ledger_entries_paginate_newest_first() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::ledger_entries_paginate_newest_first(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn budget_keeps_plan_version_and_void() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::budget_keeps_plan_version_and_void(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn budgets_are_scoped_to_user() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::budgets_are_scoped_to_user(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn import_same_archive_for_two_users() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::import_same_archive_for_two_users(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn restore_plan_keeps_history() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::restore_plan_keeps_history(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn sessions_expire_and_close() {
+        let db_path = temp_db_path();
+        let repo = SqliteRepo::init(&db_path).await.unwrap();
+        suite::sessions_expire_and_close(&repo).await;
+        let _ = std::fs::remove_file(db_path);
+    }
 }
@@ -0,0 +1,449 @@
+//! Общие проверки хранилищ: каждая реализация [`CoreRepo`] и [`AccountRepo`]
+//! должна их проходить.
+
+use std::sync::Arc;
+
+use ai_app::{
+    api::CoreApi,
+    archive::{ConflictKind, ImportMode},
+    ledger::{LedgerEntry, LedgerOperation},
+    storage::{
+        Account,
+        AccountRepo,
+        BudgetVoid,
+        CoreRepo,
+        PlanAction,
+        PlanId,
+        PlanRef,
+        Session,
+        StorageError,
+        UserId,
+    },
+};
+use ai_core::{
+    finance::{Money, Percentage},
+    plan::Plan,
+    planning::{Expense, ExpenseValue, IncomeKind, IncomeSource, Member},
+};
+use chrono::{DateTime, NaiveDate};
+use rust_decimal_macros::dec;
+
+pub(super) fn valid_plan() -> Plan {
+    Plan::build(
+        &[IncomeSource::new(
+            "Зарплата".into(),
+            IncomeKind::Other {
+                expected: Money::new_rub(dec!(100000)),
+            },
+        )],
+        &[
+            Expense::envelope(
+                "Аренда".into(),
+                ExpenseValue::MONEY {
+                    value: Money::new_rub(dec!(30000)),
+                },
+                Some("Жильё".into()),
+            ),
+            Expense::envelope(
+                "Накопления".into(),
+                ExpenseValue::RATE {
+                    value: Percentage::from_int(20),
+                },
+                None,
+            ),
+        ],
+    )
+}
+
+pub(super) async fn plan_events_paginate_newest_first<R: CoreRepo>(repo: &R) {
+    let user_id: UserId = "default".to_string();
+    let plan_id: PlanId = "plan-1".to_string();
+
+    repo.create_plan(&user_id, plan_id.clone(), "Основной".into(), valid_plan())
+        .await
+        .unwrap();
+    repo.update_plan(&user_id, &plan_id, 1, valid_plan())
+        .await
+        .unwrap();
+    repo.delete_plan(&user_id, &plan_id).await.unwrap();
+
+    let first = repo.plan_events(&user_id, &plan_id, None, 2).await;
+    let second = repo
+        .plan_events(&user_id, &plan_id, first.next_cursor.clone(), 2)
+        .await;
+    let got: Vec<_> = first
+        .iter()
+        .chain(second.iter())
+        .map(|e| (e.version, e.action.clone(), e.content.is_some()))
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            (3, PlanAction::Deleted, true),
+            (2, PlanAction::Updated, true),
+            (1, PlanAction::Created, true),
+        ]
+    );
+    assert!(second.next_cursor.is_none());
+    assert!(repo.list_plans(&user_id).await.is_empty());
+    // Чужой план не отдаёт своих событий
+    assert!(
+        repo.plan_events(&"ivan".into(), &plan_id, None, 10)
+            .await
+            .is_empty()
+    );
+}
+
+pub(super) async fn switch_active_plan<R: CoreRepo>(repo: &R) {
+    let user_id: UserId = "default".to_string();
+    let usual: PlanId = "plan-1".to_string();
+    let vacation: PlanId = "plan-2".to_string();
+
+    repo.create_plan(
+        &user_id,
+        usual.clone(),
+        "Обычный месяц".into(),
+        valid_plan(),
+    )
+    .await
+    .unwrap();
+    repo.create_plan(&user_id, vacation.clone(), "Отпуск".into(), valid_plan())
+        .await
+        .unwrap();
+    assert!(matches!(
+        repo.create_plan(&user_id, "plan-3".into(), "Отпуск".into(), valid_plan())
+            .await,
+        Err(StorageError::PlanAlreadyExists)
+    ));
+    assert_eq!(repo.get_plan(&user_id).await.unwrap().id, usual);
+
+    repo.activate_plan(&user_id, &vacation).await.unwrap();
+    repo.rename_plan(&user_id, &vacation, "Декрет".into())
+        .await
+        .unwrap();
+
+    let plans = repo.list_plans(&user_id).await;
+    let got: Vec<_> = plans
+        .iter()
+        .map(|p| (p.id.as_str(), p.name.as_str(), p.active))
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            ("plan-1", "Обычный месяц", false),
+            ("plan-2", "Декрет", true)
+        ]
+    );
+
+    // Удалённый активный план больше не возвращается как текущий
+    repo.delete_plan(&user_id, &vacation).await.unwrap();
+    assert!(repo.get_plan(&user_id).await.is_none());
+    assert!(matches!(
+        repo.activate_plan(&user_id, &vacation).await,
+        Err(StorageError::PlanNotFound)
+    ));
+}
+
+pub(super) async fn shared_plans_found_by_member<R: CoreRepo>(repo: &R) {
+    let anna: UserId = "anna".to_string();
+    let ivan: UserId = "ivan".to_string();
+    let family = valid_plan().with_members(vec![
+        Member::new(anna.clone(), vec!["Зарплата".into()]),
+        Member::new(ivan.clone(), vec![]),
+    ]);
+    repo.create_plan(&anna, "plan-1".into(), "Семья".into(), family)
+        .await
+        .unwrap();
+    repo.create_plan(&anna, "plan-2".into(), "Личный".into(), valid_plan())
+        .await
+        .unwrap();
+
+    let shared: Vec<_> = repo
+        .shared_plans(&ivan)
+        .await
+        .into_iter()
+        .map(|sp| (sp.id, sp.user_id))
+        .collect();
+    assert_eq!(shared, vec![("plan-1".to_string(), anna.clone())]);
+    // Свои планы общими не считаются
+    assert!(repo.shared_plans(&anna).await.is_empty());
+
+    repo.delete_plan(&anna, &"plan-1".into()).await.unwrap();
+    assert!(repo.shared_plans(&ivan).await.is_empty());
+}
+
+pub(super) async fn update_plan_checks_version<R: CoreRepo>(repo: &R) {
+    let user_id: UserId = "default".to_string();
+    let plan_id: PlanId = "plan-1".to_string();
+
+    repo.create_plan(&user_id, plan_id.clone(), "Основной".into(), valid_plan())
+        .await
+        .unwrap();
+    repo.update_plan(&user_id, &plan_id, 1, valid_plan())
+        .await
+        .unwrap();
+
+    let err = repo
+        .update_plan(&user_id, &plan_id, 1, valid_plan())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, StorageError::VersionConflict { current: 2 }));
+    assert_eq!(repo.get_plan(&user_id).await.unwrap().version, 2);
+
+    let missing = repo
+        .update_plan(&user_id, &"plan-2".to_string(), 1, valid_plan())
+        .await
+        .unwrap_err();
+    assert!(matches!(missing, StorageError::UpdatePlan));
+}
+
+pub(super) async fn ledger_entries_paginate_newest_first<R: CoreRepo>(repo: &R) {
+    let user_id: UserId = "default".to_string();
+    let date = chrono::NaiveDate::from_ymd_opt(2026, 3, 15).unwrap();
+    let mut ids = vec![];
+    for n in 1..=3 {
+        let entry = LedgerEntry {
+            id: ai_app::storage::build_id(),
+            date,
+            operation: LedgerOperation::Withdrawal {
+                envelope: "Аренда".into(),
+                amount: Money::new_rub(rust_decimal::Decimal::from(n)),
+            },
+            comment: None,
+        };
+        ids.push(repo.save_ledger_entry(&user_id, entry).await.unwrap());
+    }
+
+    let first = repo.ledger_entries(&user_id, None, 2).await;
+    let second = repo
+        .ledger_entries(&user_id, first.next_cursor.clone(), 2)
+        .await;
+
+    let got: Vec<_> = first.iter().chain(second.iter()).map(|e| &e.id).collect();
+    ids.reverse();
+    assert_eq!(got, ids.iter().collect::<Vec<_>>());
+    assert!(second.next_cursor.is_none());
+    assert!(
+        repo.ledger_entries(&"other".into(), None, 2)
+            .await
+            .is_empty()
+    );
+}
+
+pub(super) async fn budget_keeps_plan_version_and_void<R: CoreRepo>(repo: &R) {
+    let plan: ai_core::planning::DistributionWeights = valid_plan().try_into().unwrap();
+    let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+    let income = ai_core::distribute::Income::new(
+        plan.sources[0].clone(),
+        Money::new_rub(dec!(100000)),
+        date,
+    );
+    let budget = ai_core::distribute::distribute(&plan, &income).unwrap();
+    let plan_ref = PlanRef {
+        plan_id: "plan-1".into(),
+        version: 3,
+    };
+    let user_id: UserId = "default".to_string();
+    let id = repo
+        .save_budget(
+            &user_id,
+            ai_app::storage::build_id(),
+            budget,
+            Some(plan_ref.clone()),
+        )
+        .await
+        .unwrap();
+
+    let void = BudgetVoid {
+        date,
+        reason: Some("Опечатка".into()),
+    };
+    repo.void_budget(&user_id, &id, void.clone()).await.unwrap();
+    assert!(repo.void_budget(&user_id, &id, void.clone()).await.is_err());
+
+    let stored = repo.budget_by_id(&user_id, &id).await.unwrap();
+    assert_eq!(stored.voided, Some(void));
+    assert_eq!(stored.plan, Some(plan_ref));
+    assert!(repo.budgets(&user_id, None, 10).await[0].is_voided());
+}
+
+pub(super) async fn budgets_are_scoped_to_user<R: CoreRepo>(repo: &R) {
+    let plan: ai_core::planning::DistributionWeights = valid_plan().try_into().unwrap();
+    let income = ai_core::distribute::Income::new(
+        plan.sources[0].clone(),
+        Money::new_rub(dec!(100000)),
+        NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+    );
+    let budget = ai_core::distribute::distribute(&plan, &income).unwrap();
+    let anna: UserId = "anna".into();
+    let ivan: UserId = "ivan".into();
+    let id = repo
+        .save_budget(&anna, ai_app::storage::build_id(), budget, None)
+        .await
+        .unwrap();
+
+    assert_eq!(repo.budgets(&anna, None, 10).await.len(), 1);
+    assert!(repo.budgets(&ivan, None, 10).await.is_empty());
+    assert!(repo.budget_by_id(&ivan, &id).await.is_none());
+    let void = BudgetVoid {
+        date: income.date,
+        reason: None,
+    };
+    assert!(repo.void_budget(&ivan, &id, void).await.is_err());
+    assert!(!repo.budget_by_id(&anna, &id).await.unwrap().is_voided());
+}
+
+/// Архив одного пользователя загружается другому целиком, даже если
+/// идентификаторы записей в хранилище общие для всех пользователей
+pub(super) async fn import_same_archive_for_two_users<R>(repo: &R)
+where
+    R: CoreRepo + Clone + Send + Sync + 'static,
+{
+    let api = CoreApi::new(Arc::new(repo.clone()));
+    let anna: UserId = "anna".into();
+    let ivan: UserId = "ivan".into();
+    repo.create_plan(&anna, "plan-1".into(), "Основной".into(), valid_plan())
+        .await
+        .unwrap();
+    let sp = repo.get_plan(&anna).await.unwrap();
+    let income = ai_core::distribute::Income::new(
+        sp.plan.sources[0].clone(),
+        Money::new_rub(dec!(100000)),
+        NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+    );
+    let distributed = api.distribute_income(&anna, &sp, &income).await.unwrap();
+    let budget_id = api
+        .save_budget(
+            &anna,
+            ai_app::storage::build_id(),
+            distributed.budget,
+            Some(distributed.plan),
+        )
+        .await
+        .unwrap();
+    let entry = LedgerEntry {
+        id: ai_app::storage::build_id(),
+        date: NaiveDate::from_ymd_opt(2026, 3, 15).unwrap(),
+        operation: LedgerOperation::Withdrawal {
+            envelope: "Аренда".into(),
+            amount: Money::new_rub(dec!(1000)),
+        },
+        comment: None,
+    };
+    repo.save_ledger_entry(&anna, entry).await.unwrap();
+    let archive = api.export(&anna, chrono::Utc::now()).await;
+
+    let report = api.import(&ivan, archive, ImportMode::Merge).await.unwrap();
+
+    let conflicts = |kind| report.conflicts.iter().filter(|c| c.kind == kind).count();
+    assert_eq!(report.plans + conflicts(ConflictKind::Plan), 1);
+    assert_eq!(report.budgets + conflicts(ConflictKind::Budget), 1);
+    assert_eq!(report.ledger + conflicts(ConflictKind::LedgerEntry), 1);
+    assert_eq!(
+        repo.budget_by_id(&ivan, &budget_id).await.is_some(),
+        report.budgets == 1
+    );
+    assert_eq!(repo.budgets(&anna, None, 10).await.len(), 1);
+    assert_eq!(repo.ledger_entries(&anna, None, 10).await.len(), 1);
+}
+
+pub(super) async fn restore_plan_keeps_history<R: CoreRepo>(repo: &R) {
+    let user_id: UserId = "default".to_string();
+    let plan_id: PlanId = "plan-1".to_string();
+    repo.create_plan(&user_id, plan_id.clone(), "Основной".into(), valid_plan())
+        .await
+        .unwrap();
+    repo.update_plan(&user_id, &plan_id, 1, valid_plan())
+        .await
+        .unwrap();
+    let entry = LedgerEntry {
+        id: ai_app::storage::build_id(),
+        date: NaiveDate::from_ymd_opt(2026, 3, 15).unwrap(),
+        operation: LedgerOperation::Withdrawal {
+            envelope: "Аренда".into(),
+            amount: Money::new_rub(dec!(1000)),
+        },
+        comment: None,
+    };
+    repo.save_ledger_entry(&user_id, entry).await.unwrap();
+    let plan = repo.get_plan(&user_id).await.unwrap();
+    let mut events = repo.plan_events(&user_id, &plan_id, None, 10).await.items;
+    events.reverse();
+
+    repo.clear_user_data(&user_id).await.unwrap();
+    assert!(repo.list_plans(&user_id).await.is_empty());
+    assert!(repo.ledger_entries(&user_id, None, 10).await.is_empty());
+    assert!(
+        repo.plan_events(&user_id, &plan_id, None, 10)
+            .await
+            .is_empty()
+    );
+
+    repo.restore_plan(&user_id, plan.clone(), events.clone())
+        .await
+        .unwrap();
+    let restored = repo.get_plan(&user_id).await.unwrap();
+    assert_eq!((restored.id, restored.version), (plan_id.clone(), 2));
+    let got: Vec<_> = repo
+        .plan_events(&user_id, &plan_id, None, 10)
+        .await
+        .iter()
+        .map(|e| (e.version, e.action.clone()))
+        .collect();
+    assert_eq!(
+        got,
+        vec![(2, PlanAction::Updated), (1, PlanAction::Created)]
+    );
+    // Занятый идентификатор повторно не восстанавливается
+    assert!(matches!(
+        repo.restore_plan(&"ivan".into(), plan, events).await,
+        Err(StorageError::PlanAlreadyExists)
+    ));
+}
+
+pub(super) async fn sessions_expire_and_close<R: AccountRepo>(repo: &R) {
+    let anna = Account {
+        id: "default".into(),
+        login: "anna".into(),
+    };
+    assert_eq!(repo.accounts_count().await, 0);
+    repo.create_account(anna.clone(), "hash".into())
+        .await
+        .unwrap();
+    assert!(matches!(
+        repo.create_account(
+            Account {
+                id: "other".into(),
+                login: "anna".into(),
+            },
+            "hash".into(),
+        )
+        .await,
+        Err(StorageError::AccountAlreadyExists)
+    ));
+    assert_eq!(repo.accounts_count().await, 1);
+    assert_eq!(
+        repo.account_by_login("anna").await.unwrap().password_hash,
+        "hash"
+    );
+
+    let now = DateTime::parse_from_rfc3339("2026-05-01T10:00:00Z")
+        .unwrap()
+        .to_utc();
+    repo.create_session(Session {
+        token_hash: "token".into(),
+        user_id: anna.id.clone(),
+        expires_at: now + chrono::Duration::hours(1),
+    })
+    .await
+    .unwrap();
+    assert_eq!(repo.session_account("token", now).await, Some(anna));
+    assert!(
+        repo.session_account("token", now + chrono::Duration::hours(2))
+            .await
+            .is_none()
+    );
+
+    repo.delete_session("token").await.unwrap();
+    assert!(repo.session_account("token", now).await.is_none());
+}
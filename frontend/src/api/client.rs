@@ -1,13 +1,32 @@
 use crate::api::{
     error::ApiError,
-    types::{BudgetEntry, Collection, Cursor, Page, StoragePlanFrontend},
+    session,
+    types::{
+        Account,
+        Analytics,
+        BudgetEntry,
+        Collection,
+        Cursor,
+        DistributedIncome,
+        Granularity,
+        Page,
+        PlanEvent,
+        PlanReport,
+        Session,
+        StoragePlanFrontend,
+    },
+};
+use ai_core::{
+    plan::{Plan, PlanDiff},
+    planning::{ExpectedIncome, GoalProgress},
+    simulate::{Forecast, Scenario},
 };
-use ai_core::{distribute::Budget, plan::Plan};
 use chrono::NaiveDate;
-use gloo_net::http::Request;
+use gloo_net::http::{Request, RequestBuilder};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use url::Url;
+use yew::Callback;
 
 #[derive(Deserialize)]
 struct ResponseWrapper<T> {
@@ -17,6 +36,9 @@ struct ResponseWrapper<T> {
 #[derive(Deserialize)]
 struct ErrorResponse {
     error: String,
+    /// Текущая версия плана, если запись отклонена из-за устаревшей версии
+    #[serde(default)]
+    current_version: Option<i64>,
 }
 
 #[derive(Serialize)]
@@ -26,14 +48,45 @@ pub struct AddIncomeRequest {
     pub date: NaiveDate,
 }
 
+#[derive(Serialize)]
+struct LoginRequest<'a> {
+    login: &'a str,
+    password: &'a str,
+}
+
+#[derive(Serialize)]
+struct VoidBudgetRequest {
+    reason: Option<String>,
+}
+
 #[derive(PartialEq)]
 pub struct ApiClient {
     base_url: Url,
+    /// Вызывается, когда сервер отклонил токен сессии
+    on_unauthorized: Option<Callback<()>>,
 }
 
 impl ApiClient {
     pub fn new(base_url: Url) -> Self {
-        Self { base_url }
+        Self {
+            base_url,
+            on_unauthorized: None,
+        }
+    }
+
+    pub fn on_unauthorized(self, callback: Callback<()>) -> Self {
+        Self {
+            on_unauthorized: Some(callback),
+            ..self
+        }
+    }
+
+    /// Добавляет токен сессии, если пользователь вошёл
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match session::token() {
+            Some(token) => bearer(request, &token),
+            None => request,
+        }
     }
 
     fn build_url(&self, path: &str) -> Result<Url, ApiError> {
@@ -42,28 +95,46 @@ impl ApiClient {
             .map_err(|e| ApiError::InvalidUrl(format!("Failed to build URL: {e}")))
     }
 
-    /// Проверяет HTTP-ответ на ошибки, возвращая тело как текст при успехе
-    async fn read_response(
+    /// Проверяет HTTP-ответ на ошибки, возвращая его при успехе
+    async fn ensure_ok(
         &self,
         response: gloo_net::http::Response,
-    ) -> Result<String, ApiError> {
+    ) -> Result<gloo_net::http::Response, ApiError> {
         let status = response.status();
-        let status_text = response.status_text();
+        if status == 401 {
+            session::clear_token();
+            if let Some(callback) = &self.on_unauthorized {
+                callback.emit(());
+            }
+            return Err(ApiError::Unauthorized);
+        }
+        if response.ok() {
+            return Ok(response);
+        }
 
+        let status_text = response.status_text();
         let body_text = response.text().await.map_err(|e| {
             ApiError::Parse(format!("Failed to read response body: {e}"))
         })?;
-
-        if !response.ok() {
-            if let Ok(error_response) =
-                serde_json::from_str::<ErrorResponse>(&body_text)
-            {
-                return Err(ApiError::Http(status, error_response.error));
+        if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&body_text) {
+            if let Some(current) = error_response.current_version {
+                return Err(ApiError::VersionConflict { current });
             }
-            return Err(ApiError::Http(status, format!("{status} {status_text}")));
+            return Err(ApiError::Http(status, error_response.error));
         }
+        Err(ApiError::Http(status, format!("{status} {status_text}")))
+    }
 
-        Ok(body_text)
+    /// Проверяет HTTP-ответ на ошибки, возвращая тело как текст при успехе
+    async fn read_response(
+        &self,
+        response: gloo_net::http::Response,
+    ) -> Result<String, ApiError> {
+        self.ensure_ok(response)
+            .await?
+            .text()
+            .await
+            .map_err(|e| ApiError::Parse(format!("Failed to read response body: {e}")))
     }
 
     /// Обработка ответов с JSON-телом в обёртке `{ "response": T }`
@@ -90,18 +161,90 @@ impl ApiClient {
         self.read_response(response).await.map(|_| ())
     }
 
+    pub async fn login(
+        &self,
+        login: &str,
+        password: &str,
+    ) -> Result<Session, ApiError> {
+        let url = self.build_url("auth/login")?;
+        let response = Request::post(url.as_str())
+            .json(&LoginRequest { login, password })
+            .map_err(|e| {
+                ApiError::Serialization(format!("Failed to serialize request: {e}"))
+            })?
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    /// Закрывает сессию `token` на сервере
+    pub async fn logout(&self, token: &str) -> Result<(), ApiError> {
+        let url = self.build_url("auth/logout")?;
+        let response = bearer(Request::post(url.as_str()), token)
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.check_response(response).await
+    }
+
+    /// Текущая учётная запись. `None`, если сервер работает без входа
+    pub async fn me(&self) -> Result<Option<Account>, ApiError> {
+        let url = self.build_url("auth/me")?;
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        match self.parse_response(response).await {
+            Ok(account) => Ok(Some(account)),
+            Err(ApiError::Http(404, _)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     pub async fn get_plan(&self) -> Result<StoragePlanFrontend, ApiError> {
         let url = self.build_url("plan")?;
-        let response = Request::get(url.as_str())
+        let response = self
+            .authorized(Request::get(url.as_str()))
             .send()
             .await
             .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
         self.parse_response(response).await
     }
 
-    pub async fn update_plan(&self, id: &str, plan: &Plan) -> Result<(), ApiError> {
-        let url = self.build_url(&format!("plan/{id}"))?;
-        let response = Request::put(url.as_str())
+    pub async fn get_goals(&self) -> Result<Vec<GoalProgress>, ApiError> {
+        let url = self.build_url("goals")?;
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    pub async fn get_expected_incomes(&self) -> Result<Vec<ExpectedIncome>, ApiError> {
+        let url = self.build_url("incomes/expected")?;
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    /// Сохраняет план, отредактированный на основе версии `version`
+    pub async fn update_plan(
+        &self,
+        id: &str,
+        version: i64,
+        plan: &Plan,
+    ) -> Result<(), ApiError> {
+        let mut url = self.build_url(&format!("plan/{id}"))?;
+        url.query_pairs_mut()
+            .append_pair("version", &version.to_string());
+        let response = self
+            .authorized(Request::put(url.as_str()))
             .json(plan)
             .map_err(|e| {
                 ApiError::Serialization(format!("Failed to serialize request: {e}"))
@@ -125,7 +268,110 @@ impl ApiClient {
             url.query_pairs_mut().append_pair("from", &cursor);
         }
 
-        let response = Request::get(url.as_str())
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    /// Таблица со всей историей распределений: `csv` или `xlsx`
+    pub async fn history_export(&self, format: &str) -> Result<Vec<u8>, ApiError> {
+        let mut url = self.build_url("history/export")?;
+        url.query_pairs_mut().append_pair("format", format);
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.ensure_ok(response)
+            .await?
+            .binary()
+            .await
+            .map_err(|e| ApiError::Parse(format!("Failed to read response body: {e}")))
+    }
+
+    /// Сводка всей истории распределений по периодам
+    pub async fn get_analytics(
+        &self,
+        granularity: Granularity,
+    ) -> Result<Analytics, ApiError> {
+        let mut url = self.build_url("analytics")?;
+        url.query_pairs_mut()
+            .append_pair("granularity", granularity.as_str());
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    /// План/факт за месяц даты `month`
+    pub async fn get_plan_report(
+        &self,
+        month: NaiveDate,
+    ) -> Result<PlanReport, ApiError> {
+        let mut url = self.build_url("report")?;
+        url.query_pairs_mut()
+            .append_pair("month", &month.to_string());
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    /// Прогноз сценария по активному плану
+    pub async fn simulate(&self, scenario: &Scenario) -> Result<Forecast, ApiError> {
+        let url = self.build_url("simulate")?;
+        let response = self
+            .authorized(Request::post(url.as_str()))
+            .json(scenario)
+            .map_err(|e| {
+                ApiError::Serialization(format!("Failed to serialize request: {e}"))
+            })?
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    pub async fn get_plan_events(
+        &self,
+        plan_id: &str,
+        from: Option<Cursor>,
+    ) -> Result<Page<PlanEvent>, ApiError> {
+        let mut url = self.build_url(&format!("plan/{plan_id}/events"))?;
+
+        url.query_pairs_mut().append_pair("limit", "20");
+
+        if let Some(cursor) = from {
+            url.query_pairs_mut().append_pair("from", &cursor);
+        }
+
+        let response = self
+            .authorized(Request::get(url.as_str()))
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+        self.parse_response(response).await
+    }
+
+    pub async fn get_plan_diff(
+        &self,
+        plan_id: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<PlanDiff, ApiError> {
+        let mut url = self.build_url(&format!("plan/{plan_id}/diff"))?;
+        url.query_pairs_mut()
+            .append_pair("from", &from.to_string())
+            .append_pair("to", &to.to_string());
+        let response = self
+            .authorized(Request::get(url.as_str()))
             .send()
             .await
             .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
@@ -135,9 +381,10 @@ impl ApiClient {
     pub async fn add_income(
         &self,
         request: AddIncomeRequest,
-    ) -> Result<Budget, ApiError> {
+    ) -> Result<DistributedIncome, ApiError> {
         let url = self.build_url("add_income")?;
-        let response = Request::post(url.as_str())
+        let response = self
+            .authorized(Request::post(url.as_str()))
             .json(&request)
             .map_err(|e| {
                 ApiError::Serialization(format!("Failed to serialize request: {e}"))
@@ -149,10 +396,33 @@ impl ApiClient {
         self.parse_response(response).await
     }
 
-    pub async fn save_budget(&self, budget: &Budget) -> Result<String, ApiError> {
+    pub async fn save_budget(
+        &self,
+        distributed: &DistributedIncome,
+    ) -> Result<String, ApiError> {
         let url = self.build_url("save_budget")?;
-        let response = Request::post(url.as_str())
-            .json(budget)
+        let response = self
+            .authorized(Request::post(url.as_str()))
+            .json(distributed)
+            .map_err(|e| {
+                ApiError::Serialization(format!("Failed to serialize request: {e}"))
+            })?
+            .send()
+            .await
+            .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
+
+        self.parse_response(response).await
+    }
+
+    pub async fn void_budget(
+        &self,
+        id: &str,
+        reason: Option<String>,
+    ) -> Result<BudgetEntry, ApiError> {
+        let url = self.build_url(&format!("budget/{id}/void"))?;
+        let response = self
+            .authorized(Request::post(url.as_str()))
+            .json(&VoidBudgetRequest { reason })
             .map_err(|e| {
                 ApiError::Serialization(format!("Failed to serialize request: {e}"))
             })?
@@ -165,7 +435,8 @@ impl ApiClient {
 
     pub async fn get_collections(&self) -> Result<Vec<Collection>, ApiError> {
         let url = self.build_url("collections")?;
-        let response = Request::get(url.as_str())
+        let response = self
+            .authorized(Request::get(url.as_str()))
             .send()
             .await
             .map_err(|e| ApiError::Network(format!("Request failed: {e}")))?;
@@ -174,7 +445,8 @@ impl ApiClient {
 
     pub async fn create_plan(&self, plan: &Plan) -> Result<String, ApiError> {
         let url = self.build_url("plan")?;
-        let response = Request::post(url.as_str())
+        let response = self
+            .authorized(Request::post(url.as_str()))
             .json(plan)
             .map_err(|e| {
                 ApiError::Serialization(format!("Failed to serialize request: {e}"))
@@ -185,3 +457,7 @@ impl ApiClient {
         self.parse_response(response).await
     }
 }
+
+fn bearer(request: RequestBuilder, token: &str) -> RequestBuilder {
+    request.header("Authorization", &format!("Bearer {token}"))
+}
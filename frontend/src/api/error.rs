@@ -4,6 +4,12 @@ use serde::{Deserialize, Serialize};
 pub enum ApiError {
     Network(String),
     Http(u16, String),
+    /// Нет действующей сессии: токена нет или он истёк
+    Unauthorized,
+    /// План изменили с другой вкладки или окна; `current` - его текущая версия
+    VersionConflict {
+        current: i64,
+    },
     Parse(String),
     InvalidUrl(String),
     Serialization(String),
@@ -14,6 +20,10 @@ impl std::fmt::Display for ApiError {
         match self {
             ApiError::Network(msg) => write!(f, "Network error: {msg}"),
             ApiError::Http(code, msg) => write!(f, "HTTP {code}: {msg}"),
+            ApiError::Unauthorized => write!(f, "Session expired, please sign in"),
+            ApiError::VersionConflict { current } => {
+                write!(f, "Plan version conflict: current version is {current}")
+            }
             ApiError::Parse(msg) => write!(f, "Parse error: {msg}"),
             ApiError::InvalidUrl(msg) => write!(f, "Invalid URL: {msg}"),
             ApiError::Serialization(msg) => write!(f, "Serialization error: {msg}"),
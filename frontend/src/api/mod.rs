@@ -1,7 +1,16 @@
 pub mod client;
 pub mod error;
+pub mod session;
 pub mod types;
 
 pub use client::{AddIncomeRequest, ApiClient};
 pub use error::ApiError;
-pub use types::{BudgetEntry, Collection, Cursor, Page, StoragePlanFrontend};
+pub use types::{
+    Account,
+    BudgetEntry,
+    Collection,
+    Cursor,
+    Page,
+    Session,
+    StoragePlanFrontend,
+};
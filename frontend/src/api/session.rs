@@ -0,0 +1,21 @@
+//! Токен сессии веб-интерфейса в `localStorage`
+
+use web_sys::{Storage, window};
+
+const TOKEN_KEY: &str = "session_token";
+
+fn storage() -> Option<Storage> {
+    window().and_then(|w| w.local_storage().ok().flatten())
+}
+
+pub fn token() -> Option<String> {
+    storage().and_then(|ls| ls.get_item(TOKEN_KEY).ok().flatten())
+}
+
+pub fn store_token(token: &str) {
+    let _ = storage().map(|ls| ls.set_item(TOKEN_KEY, token));
+}
+
+pub fn clear_token() {
+    let _ = storage().map(|ls| ls.remove_item(TOKEN_KEY));
+}
@@ -1,8 +1,15 @@
 use std::ops::Deref;
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-use ai_core::{distribute::Budget, plan::Plan};
+use ai_core::{
+    distribute::Budget,
+    finance::{Currency, Money, Percentage},
+    plan::Plan,
+};
+use chrono::{DateTime, NaiveDate, Utc};
 
 pub type Cursor = String;
 
@@ -10,6 +17,15 @@ pub type Cursor = String;
 pub struct BudgetEntry {
     pub id: String,
     pub budget: Budget,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voided: Option<BudgetVoid>,
+}
+
+/// Отметка об аннулировании распределения
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct BudgetVoid {
+    pub date: NaiveDate,
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -33,7 +49,116 @@ pub enum PlanStatus {
     Deleted,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// Версия плана, по которой посчитано распределение
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct PlanRef {
+    pub plan_id: String,
+    pub version: i64,
+}
+
+/// Распределение дохода, ещё не сохранённое, и версия его плана
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct DistributedIncome {
+    pub budget: Budget,
+    pub plan: PlanRef,
+}
+
+/// Запись журнала изменений плана
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct PlanEvent {
+    pub id: i64,
+    pub plan_id: String,
+    pub version: i64,
+    pub action: PlanAction,
+    pub content: Option<Plan>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    #[default]
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Granularity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Granularity::Month => "month",
+            Granularity::Quarter => "quarter",
+            Granularity::Year => "year",
+        }
+    }
+}
+
+/// Сводка распределений за месяц, квартал или год
 #[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct PeriodStats {
+    pub period: String,
+    pub start: NaiveDate,
+    pub budgets: usize,
+    pub income: Money,
+    pub by_source: BTreeMap<String, Money>,
+    pub by_envelope: BTreeMap<String, Money>,
+    pub by_category: BTreeMap<String, Money>,
+    pub average_rest: Money,
+    pub savings: Money,
+    pub savings_rate: Percentage,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct Analytics {
+    pub granularity: Granularity,
+    pub currency: Currency,
+    pub periods: Vec<PeriodStats>,
+}
+
+/// План и факт в базовой валюте плана
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct Fulfillment {
+    pub planned: Money,
+    pub actual: Money,
+    pub difference: Money,
+    pub fulfilled: Option<Percentage>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct ExpenseLine {
+    pub name: String,
+    #[serde(flatten)]
+    pub amounts: Fulfillment,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct CategoryLine {
+    pub category: Option<String>,
+    #[serde(flatten)]
+    pub amounts: Fulfillment,
+    pub expenses: Vec<ExpenseLine>,
+}
+
+/// План/факт за месяц
+#[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
+pub struct PlanReport {
+    pub month: NaiveDate,
+    pub currency: Currency,
+    pub budgets: usize,
+    #[serde(flatten)]
+    pub total: Fulfillment,
+    pub categories: Vec<CategoryLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct StoragePlanFrontend {
     pub id: String,
     pub version: i64,
@@ -75,3 +200,17 @@ pub struct PlanTemplate {
     pub tag: Tag,
     pub plan: Plan,
 }
+
+/// Учётная запись, под которой выполнен вход
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Account {
+    pub id: String,
+    pub login: String,
+}
+
+/// Сессия, выданная при входе
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Session {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::types::{Analytics, Granularity},
+    engine::core::{DataState, Model},
+};
+
+/// Сводка истории распределений по месяцам, кварталам или годам
+#[derive(Clone, Deserialize, Serialize)]
+pub struct AnalyticsModel {
+    pub(crate) granularity: Granularity,
+    pub(crate) data: DataState<Analytics>,
+}
+
+impl AnalyticsModel {
+    pub fn loading() -> Self {
+        Self {
+            granularity: Granularity::default(),
+            data: DataState::Loading,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum Msg {
+    Load(Granularity),
+    Loaded(Result<Analytics, String>),
+}
+
+#[derive(Serialize)]
+pub enum Cmd {
+    Fetch { granularity: Granularity },
+}
+
+impl Model for AnalyticsModel {
+    type Msg = Msg;
+    type Cmd = Cmd;
+
+    fn handle(self, msg: Self::Msg) -> (Self, Vec<Self::Cmd>) {
+        match msg {
+            Msg::Load(granularity) => (
+                Self {
+                    granularity,
+                    data: DataState::Loading,
+                },
+                vec![Cmd::Fetch { granularity }],
+            ),
+            // Ответ на разбивку, которую уже сменили, не нужен
+            Msg::Loaded(Ok(analytics)) if analytics.granularity != self.granularity => {
+                (self, vec![])
+            }
+            Msg::Loaded(Ok(analytics)) => (
+                Self {
+                    data: DataState::Loaded(analytics),
+                    ..self
+                },
+                vec![],
+            ),
+            Msg::Loaded(Err(e)) => (
+                Self {
+                    data: DataState::Error(e),
+                    ..self
+                },
+                vec![],
+            ),
+        }
+    }
+}
@@ -1,7 +1,21 @@
-use crate::engine::{history, onboarding, plan};
+use crate::engine::{
+    analytics,
+    auth,
+    forecast,
+    history,
+    onboarding,
+    plan,
+    report,
+    timeline,
+};
 
 pub enum Cmd {
+    Auth(auth::Cmd),
     Plan(plan::cmd::Cmd),
     History(history::Cmd),
+    Timeline(timeline::Cmd),
+    Analytics(analytics::Cmd),
+    Report(report::Cmd),
+    Forecast(forecast::Cmd),
     Onboarding(onboarding::Cmd),
 }
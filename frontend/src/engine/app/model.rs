@@ -1,23 +1,37 @@
 use crate::engine::{
+    analytics,
     app::{cmd, msg},
+    auth,
     core::Model,
+    forecast,
     history,
     onboarding,
     plan,
+    report,
+    timeline,
 };
 
 #[derive(Clone)]
 pub struct AppModel {
     pub(crate) onboarding: onboarding::OnboardingModel,
+    pub(crate) auth: auth::AuthModel,
     pub(crate) view: View,
     pub(crate) plan: plan::model::PlanModel,
     pub(crate) history: history::HistoryModel,
+    pub(crate) timeline: timeline::TimelineModel,
+    pub(crate) analytics: analytics::AnalyticsModel,
+    pub(crate) report: report::ReportModel,
+    pub(crate) forecast: forecast::ForecastModel,
 }
 
 #[derive(Clone, PartialEq)]
 pub enum View {
     Plan,
     History,
+    Timeline,
+    Analytics,
+    Report,
+    Forecast,
 }
 
 impl Model for AppModel {
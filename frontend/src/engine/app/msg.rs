@@ -1,8 +1,23 @@
-use crate::engine::{app::model::View, history, onboarding, plan};
+use crate::engine::{
+    analytics,
+    app::model::View,
+    auth,
+    forecast,
+    history,
+    onboarding,
+    plan,
+    report,
+    timeline,
+};
 
 pub enum Msg {
     Onboarding(onboarding::Msg),
+    Auth(auth::Msg),
     SwitchView(View),
     Plan(plan::msg::Msg),
     History(history::Msg),
+    Timeline(timeline::Msg),
+    Analytics(analytics::Msg),
+    Report(report::Msg),
+    Forecast(forecast::Msg),
 }
@@ -1,18 +1,24 @@
 use crate::engine::{
+    analytics,
     app::{
         cmd::Cmd,
         model::{AppModel, View},
         msg::Msg,
     },
-    core::{Model, PaginatedList},
+    auth,
+    core::Model,
+    forecast,
     history,
     onboarding,
     plan,
+    report,
+    timeline,
 };
 
 pub(crate) fn handle(model: AppModel, msg: Msg) -> (AppModel, Vec<Cmd>) {
     match msg {
         Msg::Onboarding(msg) => handle_onboarding(model, msg),
+        Msg::Auth(msg) => handle_auth(model, msg),
         Msg::SwitchView(view) => handle_switch_view(model, view),
         Msg::Plan(plan_msg) => {
             let (new_plan, cmds) = model.plan.handle(plan_msg);
@@ -36,16 +42,73 @@ pub(crate) fn handle(model: AppModel, msg: Msg) -> (AppModel, Vec<Cmd>) {
                 cmds,
             )
         }
+        Msg::Timeline(timeline_msg) => handle_timeline(model, timeline_msg),
+        Msg::Analytics(analytics_msg) => {
+            let (new_analytics, cmds) = model.analytics.handle(analytics_msg);
+            let cmds = cmds.into_iter().map(Cmd::Analytics).collect();
+            (
+                AppModel {
+                    analytics: new_analytics,
+                    ..model
+                },
+                cmds,
+            )
+        }
+        Msg::Report(report_msg) => {
+            let (new_report, cmds) = model.report.handle(report_msg);
+            let cmds = cmds.into_iter().map(Cmd::Report).collect();
+            (
+                AppModel {
+                    report: new_report,
+                    ..model
+                },
+                cmds,
+            )
+        }
+        Msg::Forecast(forecast_msg) => {
+            let (new_forecast, cmds) = model.forecast.handle(forecast_msg);
+            let cmds = cmds.into_iter().map(Cmd::Forecast).collect();
+            (
+                AppModel {
+                    forecast: new_forecast,
+                    ..model
+                },
+                cmds,
+            )
+        }
     }
 }
 
+/// После восстановления старой версии план перезагружается
+fn handle_timeline(model: AppModel, msg: timeline::Msg) -> (AppModel, Vec<Cmd>) {
+    let restored = matches!(msg, timeline::Msg::Restored(Ok(())));
+    let (new_timeline, cmds) = model.timeline.handle(msg);
+    let mut cmds: Vec<Cmd> = cmds.into_iter().map(Cmd::Timeline).collect();
+    let plan = if restored {
+        cmds.push(Cmd::Plan(plan::cmd::Cmd::LoadPlan));
+        plan::model::PlanModel::Loading
+    } else {
+        model.plan
+    };
+    (
+        AppModel {
+            timeline: new_timeline,
+            plan,
+            ..model
+        },
+        cmds,
+    )
+}
+
 fn handle_onboarding(model: AppModel, msg: onboarding::Msg) -> (AppModel, Vec<Cmd>) {
     let was_not_ready = model.onboarding != onboarding::OnboardingModel::Ready;
     let (new_onboarding, cmds) = model.onboarding.handle(msg);
 
     let mut app_cmds: Vec<Cmd> = cmds.into_iter().map(Cmd::Onboarding).collect();
 
-    let plan = if was_not_ready && new_onboarding == onboarding::OnboardingModel::Ready
+    let plan = if was_not_ready
+        && new_onboarding == onboarding::OnboardingModel::Ready
+        && model.auth.is_ready()
     {
         app_cmds.push(Cmd::Plan(plan::cmd::Cmd::LoadPlan));
         plan::model::PlanModel::Loading
@@ -56,26 +119,118 @@ fn handle_onboarding(model: AppModel, msg: onboarding::Msg) -> (AppModel, Vec<Cm
     (
         AppModel {
             onboarding: new_onboarding,
+            auth: model.auth,
             plan,
             view: model.view,
             history: model.history,
+            timeline: model.timeline,
+            analytics: model.analytics,
+            report: model.report,
+            forecast: model.forecast,
         },
         app_cmds,
     )
 }
 
+/// После входа план загружается заново, после выхода данные сбрасываются
+fn handle_auth(model: AppModel, msg: auth::Msg) -> (AppModel, Vec<Cmd>) {
+    let was_ready = model.auth.is_ready();
+    let (new_auth, cmds) = model.auth.handle(msg);
+    let mut cmds: Vec<Cmd> = cmds.into_iter().map(Cmd::Auth).collect();
+    let model = AppModel {
+        auth: new_auth,
+        ..model
+    };
+    match (was_ready, model.auth.is_ready()) {
+        (false, true) if model.onboarding == onboarding::OnboardingModel::Ready => {
+            cmds.push(Cmd::Plan(plan::cmd::Cmd::LoadPlan));
+            (
+                AppModel {
+                    view: View::Plan,
+                    plan: plan::model::PlanModel::Loading,
+                    ..model
+                },
+                cmds,
+            )
+        }
+        (true, false) => (
+            AppModel {
+                view: View::Plan,
+                plan: plan::model::PlanModel::Loading,
+                history: history::HistoryModel::loading(),
+                ..model
+            },
+            cmds,
+        ),
+        _ => (model, cmds),
+    }
+}
+
 fn handle_switch_view(model: AppModel, view: View) -> (AppModel, Vec<Cmd>) {
     if view == View::History {
         (
             AppModel {
                 view,
-                history: history::HistoryModel {
-                    data: PaginatedList::loading(),
-                },
+                history: history::HistoryModel::loading(),
                 ..model
             },
             vec![Cmd::History(history::Cmd::Fetch { cursor: None })],
         )
+    } else if view == View::Timeline {
+        let plan_id = match &model.plan {
+            plan::model::PlanModel::Viewing { origin, .. }
+            | plan::model::PlanModel::Editing { origin, .. } => Some(origin.id.clone()),
+            _ => None,
+        };
+        let (timeline, cmds) = timeline::TimelineModel::open(plan_id);
+        (
+            AppModel {
+                view,
+                timeline,
+                ..model
+            },
+            cmds.into_iter().map(Cmd::Timeline).collect(),
+        )
+    } else if view == View::Analytics {
+        let granularity = model.analytics.granularity;
+        let (analytics, cmds) =
+            model.analytics.handle(analytics::Msg::Load(granularity));
+        (
+            AppModel {
+                view,
+                analytics,
+                ..model
+            },
+            cmds.into_iter().map(Cmd::Analytics).collect(),
+        )
+    } else if view == View::Report {
+        let (report, cmds) = model.report.handle(report::Msg::Load);
+        (
+            AppModel {
+                view,
+                report,
+                ..model
+            },
+            cmds.into_iter().map(Cmd::Report).collect(),
+        )
+    } else if view == View::Forecast {
+        // Плановые доходы прогноза строятся по источникам загруженного плана
+        let sources = match &model.plan {
+            plan::model::PlanModel::Viewing { origin, .. }
+            | plan::model::PlanModel::Editing { origin, .. } => {
+                origin.plan.sources.clone()
+            }
+            _ => vec![],
+        };
+        let (forecast, cmds) = model.forecast.handle(forecast::Msg::Open(sources));
+        (
+            AppModel {
+                view,
+                forecast,
+                ..model
+            },
+            cmds.into_iter().map(Cmd::Forecast).collect(),
+        )
     } else {
         (AppModel { view, ..model }, vec![])
     }
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+use crate::engine::core::Model;
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub enum AuthModel {
+    /// Проверяем сохранённый токен
+    Checking,
+    /// Сервер работает без учётных записей, вход не нужен
+    Disabled,
+    SignedIn {
+        login: String,
+    },
+    /// Экран входа
+    SignedOut {
+        login: String,
+        password: String,
+        error: Option<String>,
+        submitting: bool,
+    },
+}
+
+impl AuthModel {
+    pub fn signed_out(error: Option<String>) -> Self {
+        AuthModel::SignedOut {
+            login: String::new(),
+            password: String::new(),
+            error,
+            submitting: false,
+        }
+    }
+
+    /// Можно показывать данные пользователя
+    pub fn is_ready(&self) -> bool {
+        matches!(self, AuthModel::Disabled | AuthModel::SignedIn { .. })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum Msg {
+    Resolved(AuthModel),
+    LoginChanged(String),
+    PasswordChanged(String),
+    Submit,
+    /// Логин вошедшего пользователя или текст ошибки
+    LoggedIn(Result<String, String>),
+    Logout,
+    /// Сервер отклонил токен посреди работы
+    SessionExpired,
+}
+
+#[derive(Serialize)]
+pub enum Cmd {
+    Check,
+    Login { login: String, password: String },
+    Logout,
+}
+
+impl Model for AuthModel {
+    type Msg = Msg;
+    type Cmd = Cmd;
+
+    fn handle(self, msg: Self::Msg) -> (Self, Vec<Self::Cmd>) {
+        match msg {
+            Msg::Resolved(phase) => (phase, vec![]),
+            Msg::LoginChanged(value) => {
+                let mut new = self;
+                if let AuthModel::SignedOut { login, .. } = &mut new {
+                    *login = value;
+                }
+                (new, vec![])
+            }
+            Msg::PasswordChanged(value) => {
+                let mut new = self;
+                if let AuthModel::SignedOut { password, .. } = &mut new {
+                    *password = value;
+                }
+                (new, vec![])
+            }
+            Msg::Submit => match self {
+                AuthModel::SignedOut {
+                    login,
+                    password,
+                    submitting: false,
+                    ..
+                } if !login.trim().is_empty() => {
+                    let cmd = Cmd::Login {
+                        login: login.clone(),
+                        password: password.clone(),
+                    };
+                    (
+                        AuthModel::SignedOut {
+                            login,
+                            password,
+                            error: None,
+                            submitting: true,
+                        },
+                        vec![cmd],
+                    )
+                }
+                _ => (self, vec![]),
+            },
+            Msg::LoggedIn(Ok(login)) => (AuthModel::SignedIn { login }, vec![]),
+            Msg::LoggedIn(Err(e)) => match self {
+                AuthModel::SignedOut { login, .. } => (
+                    AuthModel::SignedOut {
+                        login,
+                        password: String::new(),
+                        error: Some(e),
+                        submitting: false,
+                    },
+                    vec![],
+                ),
+                _ => (self, vec![]),
+            },
+            Msg::Logout => match self {
+                AuthModel::SignedIn { .. } => {
+                    (AuthModel::signed_out(None), vec![Cmd::Logout])
+                }
+                _ => (self, vec![]),
+            },
+            Msg::SessionExpired => match self {
+                AuthModel::SignedIn { .. } => (
+                    AuthModel::signed_out(Some(
+                        "Сессия истекла, войдите снова".to_string(),
+                    )),
+                    vec![],
+                ),
+                _ => (self, vec![]),
+            },
+        }
+    }
+}
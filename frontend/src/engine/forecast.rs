@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use ai_core::{
+    distribute::Income,
+    finance::Money,
+    planning::{IncomeSource, RepaymentKind},
+    simulate::{Forecast, Scenario, planned_incomes},
+};
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::core::{DataState, Model};
+
+pub const DEFAULT_MONTHS: u32 = 12;
+
+/// Доход сверх плановых, например премия
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ExtraIncome {
+    pub source: String,
+    pub amount: Decimal,
+    pub date: NaiveDate,
+}
+
+/// Прогноз накоплений и долгов по активному плану
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ForecastModel {
+    /// Первый день первого месяца прогноза
+    pub(crate) start: NaiveDate,
+    pub(crate) months: u32,
+    /// Источники активного плана: по ним строятся плановые доходы
+    pub(crate) sources: Vec<IncomeSource>,
+    pub(crate) extra: Vec<ExtraIncome>,
+    /// Номер последнего запроса; ответы на прежние не нужны
+    pub(crate) revision: u32,
+    pub(crate) data: DataState<Forecast>,
+}
+
+impl ForecastModel {
+    /// Прогноз с месяца даты `today`, ещё не загруженный
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            start: today.with_day(1).expect("первое число есть в любом месяце"),
+            months: DEFAULT_MONTHS,
+            sources: vec![],
+            extra: vec![],
+            revision: 0,
+            data: DataState::Loading,
+        }
+    }
+
+    /// Плановые доходы по источникам и дополнительные доходы
+    fn scenario(&self) -> Scenario {
+        let mut incomes = planned_incomes(&self.sources, self.start, self.months);
+        incomes.extend(self.extra.iter().filter_map(|extra| {
+            let source = self.sources.iter().find(|s| s.name == extra.source)?;
+            let amount = Money::new(extra.amount, source.net().currency);
+            Some(Income::new(source.clone(), amount, extra.date))
+        }));
+        Scenario {
+            start: self.start,
+            months: self.months,
+            incomes,
+            changes: vec![],
+            opening: HashMap::new(),
+            repayment: RepaymentKind::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum Msg {
+    /// Открыть прогноз по источникам дохода плана
+    Open(Vec<IncomeSource>),
+    Load,
+    SetMonths(u32),
+    AddIncome(ExtraIncome),
+    RemoveIncome(usize),
+    Loaded {
+        revision: u32,
+        result: Result<Forecast, String>,
+    },
+}
+
+#[derive(Serialize)]
+pub enum Cmd {
+    Fetch { revision: u32, scenario: Scenario },
+}
+
+impl Model for ForecastModel {
+    type Msg = Msg;
+    type Cmd = Cmd;
+
+    fn handle(self, msg: Self::Msg) -> (Self, Vec<Self::Cmd>) {
+        match msg {
+            Msg::Open(sources) => Self { sources, ..self }.handle(Msg::Load),
+            Msg::Load => {
+                let revision = self.revision + 1;
+                let model = Self {
+                    revision,
+                    data: DataState::Loading,
+                    ..self
+                };
+                let scenario = model.scenario();
+                (model, vec![Cmd::Fetch { revision, scenario }])
+            }
+            Msg::SetMonths(months) => Self { months, ..self }.handle(Msg::Load),
+            Msg::AddIncome(income) => {
+                let mut extra = self.extra;
+                extra.push(income);
+                extra.sort_by_key(|i| i.date);
+                Self { extra, ..self }.handle(Msg::Load)
+            }
+            Msg::RemoveIncome(index) if index < self.extra.len() => {
+                let mut extra = self.extra;
+                extra.remove(index);
+                Self { extra, ..self }.handle(Msg::Load)
+            }
+            Msg::RemoveIncome(_) => (self, vec![]),
+            Msg::Loaded { revision, .. } if revision != self.revision => (self, vec![]),
+            Msg::Loaded {
+                result: Ok(forecast),
+                ..
+            } => (
+                Self {
+                    data: DataState::Loaded(forecast),
+                    ..self
+                },
+                vec![],
+            ),
+            Msg::Loaded { result: Err(e), .. } => (
+                Self {
+                    data: DataState::Error(e),
+                    ..self
+                },
+                vec![],
+            ),
+        }
+    }
+}
@@ -9,17 +9,45 @@ use crate::{
 #[derive(Clone, Deserialize, Serialize)]
 pub struct HistoryModel {
     pub(crate) data: PaginatedList<HistoryEntry>,
+    /// Ошибка последнего аннулирования; список при этом остаётся на экране
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) void_error: Option<String>,
+    /// Ошибка последней выгрузки таблицы
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) download_error: Option<String>,
+}
+
+impl HistoryModel {
+    pub fn loading() -> Self {
+        Self {
+            data: PaginatedList::loading(),
+            void_error: None,
+            download_error: None,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
 pub enum Msg {
     Load,
     Loaded(Result<Page<BudgetEntry>, String>),
+    Void {
+        id: String,
+        reason: Option<String>,
+    },
+    Voided(Result<Box<BudgetEntry>, String>),
+    /// Скачать всю историю таблицей: `csv` или `xlsx`
+    Download {
+        format: String,
+    },
+    Downloaded(Result<(), String>),
 }
 
 #[derive(Serialize)]
 pub enum Cmd {
     Fetch { cursor: Option<Cursor> },
+    Void { id: String, reason: Option<String> },
+    Download { format: String },
 }
 
 impl Model for HistoryModel {
@@ -35,6 +63,7 @@ impl Model for HistoryModel {
                         status: PageStatus::Loading,
                         ..self.data.clone()
                     },
+                    ..self
                 };
                 (new, vec![Cmd::Fetch { cursor }])
             }
@@ -51,6 +80,7 @@ impl Model for HistoryModel {
                                 next_cursor: page.next_cursor,
                                 status: PageStatus::Idle,
                             },
+                            ..self
                         },
                         vec![],
                     )
@@ -61,10 +91,53 @@ impl Model for HistoryModel {
                             status: PageStatus::Error(e),
                             ..self.data.clone()
                         },
+                        ..self
                     },
                     vec![],
                 ),
             },
+            Msg::Void { id, reason } => (
+                HistoryModel {
+                    void_error: None,
+                    ..self
+                },
+                vec![Cmd::Void { id, reason }],
+            ),
+            Msg::Voided(Ok(budget)) => {
+                let mut data = self.data;
+                if let Some(entry) = data.items.iter_mut().find(|e| e.id == budget.id) {
+                    *entry = HistoryEntry::from(budget.as_ref());
+                }
+                (
+                    HistoryModel {
+                        data,
+                        void_error: None,
+                        ..self
+                    },
+                    vec![],
+                )
+            }
+            Msg::Voided(Err(e)) => (
+                HistoryModel {
+                    void_error: Some(e),
+                    ..self
+                },
+                vec![],
+            ),
+            Msg::Download { format } => (
+                HistoryModel {
+                    download_error: None,
+                    ..self
+                },
+                vec![Cmd::Download { format }],
+            ),
+            Msg::Downloaded(result) => (
+                HistoryModel {
+                    download_error: result.err(),
+                    ..self
+                },
+                vec![],
+            ),
         }
     }
 }
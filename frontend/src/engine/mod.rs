@@ -1,5 +1,10 @@
+pub mod analytics;
 pub mod app;
+pub mod auth;
 pub mod core;
+pub mod forecast;
 pub mod history;
 pub mod onboarding;
 pub mod plan;
+pub mod report;
+pub mod timeline;
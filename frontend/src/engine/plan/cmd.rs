@@ -5,8 +5,18 @@ use ai_core::plan::Plan;
 #[derive(Clone, PartialEq, Serialize)]
 pub enum Cmd {
     LoadPlan,
+    LoadGoals,
+    LoadExpectedIncomes,
     LoadTemplates,
-    SavePlan { id: String, plan: Plan },
-    CreatePlan { plan: Plan },
+    SavePlan {
+        id: String,
+        version: i64,
+        plan: Plan,
+    },
+    /// Загрузить свежую версию плана после конфликта при сохранении
+    LoadLatestPlan,
+    CreatePlan {
+        plan: Plan,
+    },
     ScrollToTop,
 }
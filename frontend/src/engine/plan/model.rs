@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use ai_core::plan::Plan as CorePlan;
+use ai_core::{
+    plan::Plan as CorePlan,
+    planning::{DistributionMode, ExpectedIncome, GoalProgress},
+};
 
 use crate::{
     api::{Collection, StoragePlanFrontend},
@@ -29,9 +32,22 @@ pub enum SaveState {
 pub struct EditState {
     pub(crate) incomes: Vec<editable::IncomeSource>,
     pub(crate) expenses: Vec<editable::Expense>,
+    #[serde(default)]
+    pub(crate) mode: DistributionMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) surplus: Vec<editable::SurplusRule>,
     pub(crate) validation: PlanValidation,
     pub(crate) save_state: SaveState,
     pub(crate) core_plan: Option<CorePlan>,
+    /// План успели изменить в другом месте, пока мы его правили
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) conflict: Option<VersionConflict>,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct VersionConflict {
+    pub(crate) current: i64,
+    pub(crate) latest: DataState<StoragePlanFrontend>,
 }
 
 #[derive(Clone, PartialEq, Deserialize, Serialize)]
@@ -47,6 +63,10 @@ pub enum PlanModel {
     },
     Viewing {
         origin: StoragePlanFrontend,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        goals: Vec<GoalProgress>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        expected_incomes: Vec<ExpectedIncome>,
     },
     Editing {
         origin: StoragePlanFrontend,
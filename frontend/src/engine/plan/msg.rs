@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use ai_core::plan::Plan;
+use ai_core::{
+    plan::Plan,
+    planning::{DistributionMode, ExpectedIncome, GoalProgress},
+};
 
 use crate::{
     api::{ApiError, Collection, StoragePlanFrontend},
@@ -21,6 +24,8 @@ pub enum Msg {
 pub enum LoadingMsg {
     Reload,
     Loaded(Result<StoragePlanFrontend, ApiError>),
+    GoalsLoaded(Result<Vec<GoalProgress>, ApiError>),
+    ExpectedIncomesLoaded(Result<Vec<ExpectedIncome>, ApiError>),
 }
 
 #[derive(Deserialize, Serialize)]
@@ -39,6 +44,8 @@ pub enum EditMsg {
     Cancel,
     IncomesChanged(Vec<editable::IncomeSource>),
     ExpensesChanged(Vec<editable::Expense>),
+    ModeChanged(DistributionMode),
+    SurplusChanged(Vec<editable::SurplusRule>),
 }
 
 #[derive(Deserialize, Serialize)]
@@ -46,6 +53,9 @@ pub enum EditMsg {
 pub enum PersistMsg {
     Save,
     SaveFinished(Result<(), ApiError>),
+    LatestLoaded(Result<StoragePlanFrontend, ApiError>),
+    /// Перенести свои правки на свежую версию плана
+    Merge,
     Create,
     CreateFinished(Result<String, ApiError>),
 }
@@ -9,7 +9,7 @@ use crate::{
         core::DataState,
         plan::{
             cmd::Cmd,
-            model::{EditState, PlanModel, PlanValidation, SaveState},
+            model::{EditState, PlanModel, PlanValidation, SaveState, VersionConflict},
             msg::{EditMsg, LoadingMsg, Msg, PersistMsg, TemplateMsg},
         },
     },
@@ -25,15 +25,17 @@ pub(crate) fn handle(model: PlanModel, msg: Msg) -> (PlanModel, Vec<Cmd>) {
     }
 }
 
-fn handle_loading(_model: PlanModel, msg: LoadingMsg) -> (PlanModel, Vec<Cmd>) {
+fn handle_loading(model: PlanModel, msg: LoadingMsg) -> (PlanModel, Vec<Cmd>) {
     match msg {
         LoadingMsg::Reload => (PlanModel::Loading, vec![Cmd::LoadPlan]),
         LoadingMsg::Loaded(result) => match result {
             Ok(storage_plan) => (
                 PlanModel::Viewing {
                     origin: storage_plan,
+                    goals: vec![],
+                    expected_incomes: vec![],
                 },
-                vec![],
+                vec![Cmd::LoadGoals, Cmd::LoadExpectedIncomes],
             ),
             Err(ApiError::Http(404, _)) => (
                 PlanModel::SelectingTemplate {
@@ -43,6 +45,38 @@ fn handle_loading(_model: PlanModel, msg: LoadingMsg) -> (PlanModel, Vec<Cmd>) {
             ),
             Err(e) => (PlanModel::Error(e.to_string()), vec![]),
         },
+        LoadingMsg::GoalsLoaded(result) => match (model, result) {
+            (
+                PlanModel::Viewing {
+                    origin,
+                    expected_incomes,
+                    ..
+                },
+                Ok(goals),
+            ) => (
+                PlanModel::Viewing {
+                    origin,
+                    goals,
+                    expected_incomes,
+                },
+                vec![],
+            ),
+            // Прогресс целей - дополнительная информация, без него план всё равно
+            // можно смотреть
+            (model, _) => (model, vec![]),
+        },
+        LoadingMsg::ExpectedIncomesLoaded(result) => match (model, result) {
+            (PlanModel::Viewing { origin, goals, .. }, Ok(expected_incomes)) => (
+                PlanModel::Viewing {
+                    origin,
+                    goals,
+                    expected_incomes,
+                },
+                vec![],
+            ),
+            // Как и цели, ожидаемые доходы не обязательны для просмотра плана
+            (model, _) => (model, vec![]),
+        },
     }
 }
 
@@ -115,7 +149,14 @@ fn handle_edit(model: PlanModel, msg: EditMsg) -> (PlanModel, Vec<Cmd>) {
         }
         EditMsg::Cancel => {
             if let PlanModel::Editing { origin, .. } = model {
-                (PlanModel::Viewing { origin }, vec![])
+                (
+                    PlanModel::Viewing {
+                        origin,
+                        goals: vec![],
+                        expected_incomes: vec![],
+                    },
+                    vec![Cmd::LoadGoals, Cmd::LoadExpectedIncomes],
+                )
             } else {
                 (model, vec![])
             }
@@ -126,6 +167,12 @@ fn handle_edit(model: PlanModel, msg: EditMsg) -> (PlanModel, Vec<Cmd>) {
         EditMsg::ExpensesChanged(expenses) => {
             update_edit(model, |edit| EditState { expenses, ..edit })
         }
+        EditMsg::ModeChanged(mode) => {
+            update_edit(model, |edit| EditState { mode, ..edit })
+        }
+        EditMsg::SurplusChanged(surplus) => {
+            update_edit(model, |edit| EditState { surplus, ..edit })
+        }
     }
 }
 
@@ -153,6 +200,7 @@ fn handle_persist(model: PlanModel, msg: PersistMsg) -> (PlanModel, Vec<Cmd>) {
                 return (PlanModel::Editing { origin, edit }, vec![]);
             };
             let id = origin.id.clone();
+            let version = origin.version;
             (
                 PlanModel::Editing {
                     origin,
@@ -163,6 +211,7 @@ fn handle_persist(model: PlanModel, msg: PersistMsg) -> (PlanModel, Vec<Cmd>) {
                 },
                 vec![Cmd::SavePlan {
                     id,
+                    version,
                     plan: core_plan,
                 }],
             )
@@ -180,9 +229,68 @@ fn handle_persist(model: PlanModel, msg: PersistMsg) -> (PlanModel, Vec<Cmd>) {
                     },
                     vec![],
                 ),
+                // Правки не теряем: подтягиваем свежую версию и предлагаем
+                // перезагрузить план или перенести правки на неё
+                Err(ApiError::VersionConflict { current }) => (
+                    PlanModel::Editing {
+                        origin,
+                        edit: EditState {
+                            save_state: SaveState::Disabled,
+                            conflict: Some(VersionConflict {
+                                current,
+                                latest: DataState::Loading,
+                            }),
+                            ..edit
+                        },
+                    },
+                    vec![Cmd::LoadLatestPlan],
+                ),
                 Err(e) => (PlanModel::Error(e.to_string()), vec![]),
             }
         }
+        PersistMsg::LatestLoaded(result) => {
+            let PlanModel::Editing { origin, edit } = model else {
+                return (model, vec![]);
+            };
+            let Some(conflict) = edit.conflict.clone() else {
+                return (PlanModel::Editing { origin, edit }, vec![]);
+            };
+            let latest = match result {
+                Ok(latest) => DataState::Loaded(latest),
+                Err(e) => DataState::Error(e.to_string()),
+            };
+            (
+                PlanModel::Editing {
+                    origin,
+                    edit: EditState {
+                        conflict: Some(VersionConflict { latest, ..conflict }),
+                        ..edit
+                    },
+                },
+                vec![],
+            )
+        }
+        PersistMsg::Merge => {
+            let PlanModel::Editing { origin, edit } = model else {
+                return (model, vec![]);
+            };
+            let Some(DataState::Loaded(latest)) =
+                edit.conflict.as_ref().map(|c| c.latest.clone())
+            else {
+                return (PlanModel::Editing { origin, edit }, vec![]);
+            };
+            let mine = edit.core_plan.as_ref().unwrap_or(&origin.plan);
+            let merged = mine.merge(&origin.plan, &latest.plan);
+            let edit = edit_state_from_core_plan(&merged);
+            let edit = rebuild_edit(edit, &merged);
+            (
+                PlanModel::Editing {
+                    origin: latest,
+                    edit,
+                },
+                vec![],
+            )
+        }
         PersistMsg::Create => {
             let PlanModel::Creating { edit } = model else {
                 return (model, vec![]);
@@ -237,9 +345,12 @@ fn edit_state_from_core_plan(plan: &CorePlan) -> EditState {
     EditState {
         incomes,
         expenses,
+        mode: plan.mode,
+        surplus: editable::surplus_from_core_plan(plan),
         validation: PlanValidation::Valid,
         save_state: SaveState::Idle,
         core_plan: Some(plan.clone()),
+        conflict: None,
     }
 }
 
@@ -275,7 +386,7 @@ fn update_edit(
                 (PlanModel::Creating { edit: new_edit }, vec![])
             } else {
                 let (validation, save_state) =
-                    validate::recompute_validation(&new_edit, false);
+                    validate::recompute_validation(&new_edit, false, &[]);
                 let new_edit = EditState {
                     validation,
                     save_state,
@@ -1,6 +1,10 @@
 use rust_decimal::Decimal;
 
-use ai_core::{finance::Percentage, plan::Plan as CorePlan, planning::ExpenseValue};
+use ai_core::{
+    finance::Percentage,
+    plan::Plan as CorePlan,
+    planning::{DistributionWeights, Error, ExpenseValue},
+};
 
 use crate::{
     engine::plan::{model::EditState, update::validate::recompute_validation},
@@ -12,10 +16,16 @@ pub(crate) fn rebuild_and_validate(
     base_plan: &CorePlan,
 ) -> EditState {
     let updated_plan =
-        editable::build_updated_plan(base_plan, &edit.incomes, &edit.expenses);
+        editable::build_updated_plan(base_plan, &edit.incomes, &edit.expenses)
+            .with_mode(edit.mode)
+            .with_surplus(editable::surplus_to_core(&edit.surplus));
 
-    let incomes_total = updated_plan.total_incomes();
-    let expenses_total = updated_plan.total_expenses();
+    // Без курса для какой-то из валют итоги не посчитать - такой план не сохранить
+    let exceeds_income =
+        match (updated_plan.total_incomes(), updated_plan.total_expenses()) {
+            (Ok(incomes), Ok(expenses)) => expenses.value > incomes.value,
+            _ => true,
+        };
     let non_positive_incomes = updated_plan
         .sources
         .iter()
@@ -36,20 +46,30 @@ pub(crate) fn rebuild_and_validate(
             _ => None,
         })
         .any(|p| p <= Percentage::ZERO);
+    // Привязанные к источнику конверты должны помещаться в его доход
+    let overloaded_sources = match DistributionWeights::try_from(updated_plan.clone()) {
+        Err(Error::TooBigSourceExpenses(source)) => vec![source],
+        _ => vec![],
+    };
     let is_empty = updated_plan.sources.is_empty() || updated_plan.expenses.is_empty();
     let business_invalid = is_empty
-        || expenses_total.value > incomes_total.value
+        || exceeds_income
         || non_positive_incomes
         || non_positive_expenses_money
-        || non_positive_expenses_rate;
+        || non_positive_expenses_rate
+        || !overloaded_sources.is_empty();
 
-    let (validation, save_state) = recompute_validation(edit, business_invalid);
+    let (validation, save_state) =
+        recompute_validation(edit, business_invalid, &overloaded_sources);
 
     EditState {
         incomes: edit.incomes.clone(),
         expenses: edit.expenses.clone(),
+        mode: edit.mode,
+        surplus: edit.surplus.clone(),
         validation,
         save_state,
         core_plan: Some(updated_plan),
+        conflict: edit.conflict.clone(),
     }
 }
@@ -10,6 +10,7 @@ use crate::{
 pub(crate) fn recompute_validation(
     edit: &crate::engine::plan::model::EditState,
     business_invalid: bool,
+    overloaded_sources: &[String],
 ) -> (PlanValidation, SaveState) {
     let mut format_messages = Vec::new();
 
@@ -29,15 +30,40 @@ pub(crate) fn recompute_validation(
         &mut format_messages,
     );
 
+    for income in &edit.incomes {
+        for error in income.schedule.validation_errors() {
+            let label = item_display_name(&income.name, "Доход");
+            format_messages.push(format!("{label}: {error}"));
+        }
+    }
+
     for expense in &edit.expenses {
-        if expense.active_type == editable::ActiveType::Credit {
-            for error in expense.credit.validation_errors() {
+        let priority = expense.priority.trim();
+        if !priority.is_empty() && priority.parse::<u32>().is_err() {
+            let label = item_display_name(&expense.name, "Расход");
+            format_messages.push(format!("{label}: некорректный приоритет"));
+        }
+        for source in &expense.sources {
+            if !edit.incomes.iter().any(|i| i.name == *source) {
                 let label = item_display_name(&expense.name, "Расход");
-                format_messages.push(format!("{label}: {error}"));
+                format_messages.push(format!(
+                    "{label}: привязан к неизвестному источнику \"{source}\""
+                ));
             }
         }
+        let errors = match expense.active_type {
+            editable::ActiveType::Credit => expense.credit.validation_errors(),
+            editable::ActiveType::Goal => expense.goal.validation_errors(),
+            editable::ActiveType::Envelope => continue,
+        };
+        for error in errors {
+            let label = item_display_name(&expense.name, "Расход");
+            format_messages.push(format!("{label}: {error}"));
+        }
     }
 
+    validate_surplus(edit, &mut format_messages);
+
     if !format_messages.is_empty() {
         let validation = PlanValidation::FormatInvalid {
             messages: format_messages,
@@ -69,6 +95,12 @@ pub(crate) fn recompute_validation(
             }
         }
 
+        for source in overloaded_sources {
+            business_messages.push(format!(
+                "Расходы, привязанные к источнику \"{source}\", превышают его доход"
+            ));
+        }
+
         if business_messages.is_empty() {
             business_messages
                 .push(crate::engine::plan::update::EXPENSES_EXCEED_INCOME.into());
@@ -124,6 +156,31 @@ fn validate_named_items<'a>(
     }
 }
 
+fn validate_surplus(
+    edit: &crate::engine::plan::model::EditState,
+    messages: &mut Vec<String>,
+) {
+    let mut total = Decimal::ZERO;
+    for (pos, rule) in edit.surplus.iter().enumerate() {
+        let label = format!("Правило остатка {}", pos + 1);
+        if rule.envelope.is_empty() {
+            messages.push(format!("{label}: не выбран конверт"));
+        } else if !edit.expenses.iter().any(|e| e.name == rule.envelope) {
+            messages.push(format!(
+                "{label}: конверт \"{}\" не найден в плане",
+                rule.envelope
+            ));
+        }
+        match Decimal::from_str(rule.share.trim()) {
+            Ok(share) if share > Decimal::ZERO => total += share,
+            _ => messages.push(format!("{label}: некорректная доля")),
+        }
+    }
+    if total > Decimal::ONE_HUNDRED {
+        messages.push("Доли правил остатка в сумме больше 100%".into());
+    }
+}
+
 fn item_display_name(name: &str, label: &str) -> String {
     let trimmed = name.trim();
     if trimmed.is_empty() {
@@ -0,0 +1,92 @@
+use chrono::{Datelike, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::types::PlanReport,
+    engine::core::{DataState, Model},
+};
+
+/// План/факт за выбранный месяц
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ReportModel {
+    /// Первый день месяца отчёта
+    pub(crate) month: NaiveDate,
+    pub(crate) data: DataState<PlanReport>,
+}
+
+impl ReportModel {
+    /// Отчёт за месяц даты `today`, ещё не загруженный
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            month: first_day(today),
+            data: DataState::Loading,
+        }
+    }
+}
+
+fn first_day(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("первое число есть в любом месяце")
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum Msg {
+    Load,
+    /// Сдвинуть месяц отчёта на `months` вперёд или назад
+    Shift {
+        months: i32,
+    },
+    Loaded(Result<PlanReport, String>),
+}
+
+#[derive(Serialize)]
+pub enum Cmd {
+    Fetch { month: NaiveDate },
+}
+
+impl Model for ReportModel {
+    type Msg = Msg;
+    type Cmd = Cmd;
+
+    fn handle(self, msg: Self::Msg) -> (Self, Vec<Self::Cmd>) {
+        match msg {
+            Msg::Load => {
+                let month = self.month;
+                (
+                    Self {
+                        data: DataState::Loading,
+                        ..self
+                    },
+                    vec![Cmd::Fetch { month }],
+                )
+            }
+            Msg::Shift { months } => {
+                let shift = Months::new(months.unsigned_abs());
+                let month = if months < 0 {
+                    self.month.checked_sub_months(shift)
+                } else {
+                    self.month.checked_add_months(shift)
+                };
+                let Some(month) = month else {
+                    return (self, vec![]);
+                };
+                Self { month, ..self }.handle(Msg::Load)
+            }
+            // Ответ за месяц, с которого уже ушли, не нужен
+            Msg::Loaded(Ok(report)) if report.month != self.month => (self, vec![]),
+            Msg::Loaded(Ok(report)) => (
+                Self {
+                    data: DataState::Loaded(report),
+                    ..self
+                },
+                vec![],
+            ),
+            Msg::Loaded(Err(e)) => (
+                Self {
+                    data: DataState::Error(e),
+                    ..self
+                },
+                vec![],
+            ),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use ai_core::plan::{Plan, PlanDiff};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{Cursor, Page, types::PlanEvent},
+    engine::core::{DataState, Model, PageStatus, PaginatedList},
+};
+
+/// Журнал версий плана: изменения каждой версии и восстановление старой
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TimelineModel {
+    /// План, чей журнал показываем; без плана журнал пуст
+    pub(crate) plan_id: Option<String>,
+    pub(crate) data: PaginatedList<PlanEvent>,
+    /// Версия, изменения которой раскрыты
+    pub(crate) selected: Option<SelectedVersion>,
+    pub(crate) restore: RestoreState,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct SelectedVersion {
+    pub(crate) version: i64,
+    /// Разница с предыдущей версией
+    pub(crate) diff: DataState<PlanDiff>,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub enum RestoreState {
+    Idle,
+    Restoring { version: i64 },
+    Failed(String),
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum Msg {
+    Load,
+    Loaded(Result<Page<PlanEvent>, String>),
+    Select {
+        version: i64,
+    },
+    DiffLoaded {
+        version: i64,
+        result: Result<PlanDiff, String>,
+    },
+    Restore {
+        version: i64,
+    },
+    Restored(Result<(), String>),
+}
+
+#[derive(Serialize)]
+pub enum Cmd {
+    Fetch {
+        plan_id: String,
+        cursor: Option<Cursor>,
+    },
+    LoadDiff {
+        plan_id: String,
+        from: i64,
+        to: i64,
+    },
+    /// Сохранить содержимое старой версии как новое обновление плана
+    Restore {
+        plan_id: String,
+        /// Последняя версия в журнале - на её основе делается обновление
+        current: i64,
+        plan: Box<Plan>,
+    },
+}
+
+impl TimelineModel {
+    /// Журнал плана `plan_id` и команда загрузить первую страницу
+    pub fn open(plan_id: Option<String>) -> (Self, Vec<Cmd>) {
+        let model = Self {
+            plan_id,
+            data: PaginatedList::loading(),
+            selected: None,
+            restore: RestoreState::Idle,
+        };
+        let cmds = model.fetch(None);
+        let data = if cmds.is_empty() {
+            PaginatedList {
+                status: PageStatus::Idle,
+                ..model.data
+            }
+        } else {
+            model.data
+        };
+        (Self { data, ..model }, cmds)
+    }
+
+    fn fetch(&self, cursor: Option<Cursor>) -> Vec<Cmd> {
+        self.plan_id
+            .clone()
+            .map(|plan_id| Cmd::Fetch { plan_id, cursor })
+            .into_iter()
+            .collect()
+    }
+
+    fn event(&self, version: i64) -> Option<&PlanEvent> {
+        self.data.items.iter().find(|e| e.version == version)
+    }
+}
+
+impl Model for TimelineModel {
+    type Msg = Msg;
+    type Cmd = Cmd;
+
+    fn handle(self, msg: Self::Msg) -> (Self, Vec<Self::Cmd>) {
+        match msg {
+            Msg::Load => {
+                let cmds = self.fetch(self.data.next_cursor.clone());
+                if cmds.is_empty() {
+                    return (self, cmds);
+                }
+                let data = PaginatedList {
+                    status: PageStatus::Loading,
+                    ..self.data
+                };
+                (Self { data, ..self }, cmds)
+            }
+            Msg::Loaded(Ok(page)) => {
+                let mut items = self.data.items;
+                items.extend(page.items);
+                let data = PaginatedList {
+                    items,
+                    next_cursor: page.next_cursor,
+                    status: PageStatus::Idle,
+                };
+                (Self { data, ..self }, vec![])
+            }
+            Msg::Loaded(Err(e)) => {
+                let data = PaginatedList {
+                    status: PageStatus::Error(e),
+                    ..self.data
+                };
+                (Self { data, ..self }, vec![])
+            }
+            Msg::Select { version } => {
+                let already =
+                    self.selected.as_ref().map(|s| s.version) == Some(version);
+                let Some(plan_id) =
+                    self.plan_id.clone().filter(|_| !already && version > 1)
+                else {
+                    return (
+                        Self {
+                            selected: None,
+                            ..self
+                        },
+                        vec![],
+                    );
+                };
+                (
+                    Self {
+                        selected: Some(SelectedVersion {
+                            version,
+                            diff: DataState::Loading,
+                        }),
+                        ..self
+                    },
+                    vec![Cmd::LoadDiff {
+                        plan_id,
+                        from: version - 1,
+                        to: version,
+                    }],
+                )
+            }
+            Msg::DiffLoaded { version, result } => {
+                // Ответ по версии, которую уже свернули, не нужен
+                let Some(selected) =
+                    self.selected.clone().filter(|s| s.version == version)
+                else {
+                    return (self, vec![]);
+                };
+                let diff = match result {
+                    Ok(diff) => DataState::Loaded(diff),
+                    Err(e) => DataState::Error(e),
+                };
+                (
+                    Self {
+                        selected: Some(SelectedVersion { diff, ..selected }),
+                        ..self
+                    },
+                    vec![],
+                )
+            }
+            Msg::Restore { version } => {
+                let plan = self.event(version).and_then(|e| e.content.clone());
+                let current = self.data.items.iter().map(|e| e.version).max();
+                let (Some(plan_id), Some(plan), Some(current)) =
+                    (self.plan_id.clone(), plan, current)
+                else {
+                    return (self, vec![]);
+                };
+                (
+                    Self {
+                        restore: RestoreState::Restoring { version },
+                        ..self
+                    },
+                    vec![Cmd::Restore {
+                        plan_id,
+                        current,
+                        plan: Box::new(plan),
+                    }],
+                )
+            }
+            Msg::Restored(Ok(())) => Self::open(self.plan_id),
+            Msg::Restored(Err(e)) => (
+                Self {
+                    restore: RestoreState::Failed(e),
+                    ..self
+                },
+                vec![],
+            ),
+        }
+    }
+}
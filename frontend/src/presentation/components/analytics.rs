@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use ai_core::finance::Money;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use yew::prelude::*;
+
+use crate::{
+    api::types::{Analytics, Granularity, PeriodStats},
+    presentation::formatting::{FormattedMoney, FormattedPercentage},
+};
+
+#[derive(Properties, PartialEq)]
+pub struct AnalyticsProps {
+    pub analytics: Analytics,
+    pub on_granularity: Callback<Granularity>,
+}
+
+#[function_component(AnalyticsView)]
+pub fn analytics_view(props: &AnalyticsProps) -> Html {
+    let analytics = &props.analytics;
+    html! {
+        <div class="space-y-6">
+            {render_granularity(analytics.granularity, &props.on_granularity)}
+            {render_periods(analytics)}
+            <div class="grid gap-6 md:grid-cols-2">
+                {render_totals("По источникам", analytics, |p| &p.by_source)}
+                {render_totals("По категориям", analytics, |p| &p.by_category)}
+            </div>
+            {render_totals("По конвертам", analytics, |p| &p.by_envelope)}
+        </div>
+    }
+}
+
+fn render_granularity(current: Granularity, on_change: &Callback<Granularity>) -> Html {
+    let options = [
+        (Granularity::Month, "Месяцы"),
+        (Granularity::Quarter, "Кварталы"),
+        (Granularity::Year, "Годы"),
+    ];
+    html! {
+        <div class="join flex justify-end">
+            {for options.into_iter().map(|(granularity, title)| html! {
+                <button
+                    class={classes!("btn", "btn-sm", "join-item", (granularity == current).then_some("btn-active"))}
+                    onclick={on_change.reform(move |_| granularity)}
+                >
+                    { title }
+                </button>
+            })}
+        </div>
+    }
+}
+
+/// Доход по периодам: полоса дохода, в ней отложенная в сбережения часть
+fn render_periods(analytics: &Analytics) -> Html {
+    let max = analytics
+        .periods
+        .iter()
+        .map(|p| p.income.value)
+        .max()
+        .unwrap_or_default();
+    html! {
+        <div class="card bg-base-100 shadow">
+            <div class="card-body">
+                <h3 class="card-title">{ "Доход и сбережения" }</h3>
+                <div class="flex gap-4 text-xs text-base-content/60">
+                    <span class="flex items-center gap-1">
+                        <span class="inline-block w-3 h-3 rounded bg-primary/40"></span>
+                        { "Доход" }
+                    </span>
+                    <span class="flex items-center gap-1">
+                        <span class="inline-block w-3 h-3 rounded bg-success"></span>
+                        { "Сбережения" }
+                    </span>
+                </div>
+                <div class="space-y-3">
+                    {for analytics.periods.iter().map(|p| html! {
+                        <div>
+                            <div class="flex justify-between text-sm">
+                                <span class="font-semibold">{ &p.period }</span>
+                                <span>
+                                    { FormattedMoney(p.income).to_string() }
+                                    <span class="text-base-content/60">
+                                        { format!(
+                                            " · сбережения {} · средний остаток {}",
+                                            FormattedPercentage(p.savings_rate.clone()),
+                                            FormattedMoney(p.average_rest),
+                                        ) }
+                                    </span>
+                                </span>
+                            </div>
+                            <div class="h-4 bg-base-200 rounded">
+                                <div
+                                    class="h-4 bg-primary/40 rounded flex"
+                                    style={width(p.income.value, max)}
+                                >
+                                    <div
+                                        class="h-4 bg-success rounded"
+                                        style={width(p.savings.value, p.income.value)}
+                                    ></div>
+                                </div>
+                            </div>
+                        </div>
+                    })}
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Суммы за все периоды, от большей к меньшей
+fn render_totals(
+    title: &str,
+    analytics: &Analytics,
+    amounts: impl Fn(&PeriodStats) -> &BTreeMap<String, Money>,
+) -> Html {
+    let mut totals: BTreeMap<&str, Money> = BTreeMap::new();
+    for period in &analytics.periods {
+        for (name, amount) in amounts(period) {
+            *totals
+                .entry(name)
+                .or_insert(Money::new(Decimal::ZERO, amount.currency)) += *amount;
+        }
+    }
+    let mut totals: Vec<_> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.value.cmp(&a.1.value));
+    let max = totals.first().map(|(_, m)| m.value).unwrap_or_default();
+    html! {
+        <div class="card bg-base-100 shadow">
+            <div class="card-body">
+                <h3 class="card-title">{ title }</h3>
+                {if totals.is_empty() {
+                    html! { <p class="text-sm text-base-content/60">{ "Нет данных" }</p> }
+                } else {
+                    html! {
+                        <div class="space-y-2">
+                            {for totals.iter().map(|(name, amount)| html! {
+                                <div>
+                                    <div class="flex justify-between text-sm">
+                                        <span>{ *name }</span>
+                                        <span>{ FormattedMoney(*amount).to_string() }</span>
+                                    </div>
+                                    <div class="h-2 bg-base-200 rounded">
+                                        <div
+                                            class="h-2 bg-primary rounded"
+                                            style={width(amount.value, max)}
+                                        ></div>
+                                    </div>
+                                </div>
+                            })}
+                        </div>
+                    }
+                }}
+            </div>
+        </div>
+    }
+}
+
+/// Ширина полосы в процентах от `whole`
+fn width(part: Decimal, whole: Decimal) -> String {
+    let share = if whole.is_zero() {
+        0.0
+    } else {
+        (part / whole * Decimal::ONE_HUNDRED)
+            .to_f64()
+            .unwrap_or_default()
+            .clamp(0.0, 100.0)
+    };
+    format!("width: {share:.1}%")
+}
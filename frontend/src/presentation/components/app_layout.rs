@@ -7,6 +7,11 @@ pub struct AppLayoutProps {
     pub on_switch_view: Callback<View>,
     /// Дополнительный хедер под табами (например, Totals для плана)
     pub sticky_header: Html,
+    /// Логин вошедшего пользователя; без учётных записей кнопки выхода нет
+    #[prop_or_default]
+    pub account: Option<String>,
+    #[prop_or_default]
+    pub on_logout: Callback<()>,
     pub children: Children,
 }
 
@@ -29,7 +34,18 @@ impl Component for AppLayout {
                             <h1 class="text-4xl font-bold">
                                 { "Anna Ivanovna" }
                             </h1>
-                            <ThemeSwitcher />
+                            <div class="flex items-center gap-2">
+                                if let Some(login) = &ctx.props().account {
+                                    <span class="text-sm text-base-content/70">{ login }</span>
+                                    <button
+                                        class="btn btn-ghost btn-sm"
+                                        onclick={ctx.props().on_logout.reform(|_| ())}
+                                    >
+                                        { "Выйти" }
+                                    </button>
+                                }
+                                <ThemeSwitcher />
+                            </div>
                         </div>
                         <div class="tabs tabs-boxed mb-4">
                             <button
@@ -44,6 +60,30 @@ impl Component for AppLayout {
                             >
                                 { "История" }
                             </button>
+                            <button
+                                class={format!("tab {}", if ctx.props().current_view == View::Timeline { "tab-active" } else { "" })}
+                                onclick={ctx.props().on_switch_view.reform(|_| View::Timeline)}
+                            >
+                                { "Версии плана" }
+                            </button>
+                            <button
+                                class={format!("tab {}", if ctx.props().current_view == View::Analytics { "tab-active" } else { "" })}
+                                onclick={ctx.props().on_switch_view.reform(|_| View::Analytics)}
+                            >
+                                { "Аналитика" }
+                            </button>
+                            <button
+                                class={format!("tab {}", if ctx.props().current_view == View::Report { "tab-active" } else { "" })}
+                                onclick={ctx.props().on_switch_view.reform(|_| View::Report)}
+                            >
+                                { "План/факт" }
+                            </button>
+                            <button
+                                class={format!("tab {}", if ctx.props().current_view == View::Forecast { "tab-active" } else { "" })}
+                                onclick={ctx.props().on_switch_view.reform(|_| View::Forecast)}
+                            >
+                                { "Прогноз" }
+                            </button>
                         </div>
                         { ctx.props().sticky_header.clone() }
                     </div>
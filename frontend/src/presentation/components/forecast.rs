@@ -0,0 +1,221 @@
+use std::collections::BTreeSet;
+
+use ai_core::{finance::Money, simulate::Forecast};
+use chrono::{Local, NaiveDate};
+use rust_decimal::Decimal;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{
+    engine::forecast::ExtraIncome,
+    presentation::{components::icons::XIcon, formatting::FormattedMoney},
+};
+
+#[derive(Properties, PartialEq)]
+pub struct ForecastIncomesProps {
+    /// Имена источников дохода плана
+    pub sources: Vec<String>,
+    pub extra: Vec<ExtraIncome>,
+    pub on_add: Callback<ExtraIncome>,
+    pub on_remove: Callback<usize>,
+}
+
+pub enum ForecastIncomesMsg {
+    SetSource(String),
+    SetAmount(String),
+    SetDate(String),
+    Add,
+}
+
+/// Доходы сверх плановых: премии, разовые поступления
+pub struct ForecastIncomes {
+    source: String,
+    amount: String,
+    date: NaiveDate,
+}
+
+impl Component for ForecastIncomes {
+    type Message = ForecastIncomesMsg;
+    type Properties = ForecastIncomesProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            source: ctx.props().sources.first().cloned().unwrap_or_default(),
+            amount: String::new(),
+            date: Local::now().date_naive(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            ForecastIncomesMsg::SetSource(source) => self.source = source,
+            ForecastIncomesMsg::SetAmount(amount) => self.amount = amount,
+            ForecastIncomesMsg::SetDate(date) => {
+                if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                    self.date = date;
+                }
+            }
+            ForecastIncomesMsg::Add => {
+                let Some(amount) = self.amount() else {
+                    return false;
+                };
+                ctx.props().on_add.emit(ExtraIncome {
+                    source: self.source.clone(),
+                    amount,
+                    date: self.date,
+                });
+                self.amount.clear();
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        if props.sources.is_empty() {
+            return html! {
+                <p class="text-sm text-base-content/60 mb-4">
+                    { "В плане нет источников дохода: прогноз только по текущим накоплениям" }
+                </p>
+            };
+        }
+        html! {
+            <div class="card bg-base-100 shadow mb-6">
+                <div class="card-body">
+                    <h3 class="card-title">{ "Доходы сверх плана" }</h3>
+                    {for props.extra.iter().enumerate().map(|(pos, income)| html! {
+                        <div class="flex items-center gap-2 text-sm">
+                            <span class="flex-1">
+                                { format!("{} · {}", income.date, income.source) }
+                            </span>
+                            <span>{ income.amount.to_string() }</span>
+                            <button
+                                class="btn btn-sm btn-ghost btn-square text-error"
+                                onclick={props.on_remove.reform(move |_| pos)}
+                            >
+                                <XIcon />
+                            </button>
+                        </div>
+                    })}
+                    <div class="flex flex-wrap gap-2">
+                        <select
+                            class="select select-bordered select-sm"
+                            onchange={ctx.link().callback(|e: Event| {
+                                let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                                ForecastIncomesMsg::SetSource(value)
+                            })}
+                        >
+                            {for props.sources.iter().map(|name| html! {
+                                <option value={name.clone()} selected={*name == self.source}>
+                                    { name }
+                                </option>
+                            })}
+                        </select>
+                        <input
+                            type="number"
+                            step="0.01"
+                            placeholder="Сумма"
+                            class="input input-bordered input-sm w-32"
+                            value={self.amount.clone()}
+                            oninput={ctx.link().callback(|e: InputEvent| {
+                                let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                                ForecastIncomesMsg::SetAmount(value)
+                            })}
+                        />
+                        <input
+                            type="date"
+                            class="input input-bordered input-sm"
+                            value={self.date.format("%Y-%m-%d").to_string()}
+                            oninput={ctx.link().callback(|e: InputEvent| {
+                                let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                                ForecastIncomesMsg::SetDate(value)
+                            })}
+                        />
+                        <button
+                            class="btn btn-sm btn-primary"
+                            disabled={self.amount().is_none()}
+                            onclick={ctx.link().callback(|_| ForecastIncomesMsg::Add)}
+                        >
+                            { "+ Добавить" }
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
+
+impl ForecastIncomes {
+    /// Введённая сумма, если она больше нуля
+    fn amount(&self) -> Option<Decimal> {
+        self.amount
+            .parse::<Decimal>()
+            .ok()
+            .filter(|amount| *amount > Decimal::ZERO)
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ForecastProps {
+    pub forecast: Forecast,
+}
+
+/// Накопления конвертов и остаток долгов на конец каждого месяца
+#[function_component(ForecastView)]
+pub fn forecast_view(props: &ForecastProps) -> Html {
+    let months = &props.forecast.months;
+    let envelopes: BTreeSet<&String> =
+        months.iter().flat_map(|m| m.envelopes.keys()).collect();
+    let credits: BTreeSet<&String> =
+        months.iter().flat_map(|m| m.credits.keys()).collect();
+    html! {
+        <div class="card bg-base-100 shadow">
+            <div class="card-body overflow-x-auto">
+                <table class="table table-sm">
+                    <thead>
+                        <tr>
+                            <th></th>
+                            {for months.iter().map(|m| html! {
+                                <th class="text-right">{ m.month.format("%Y-%m").to_string() }</th>
+                            })}
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {render_row(html! { <b>{ "Доход" }</b> }, months.iter().map(|m| Some(m.income)), "bg-base-200")}
+                        {render_row(html! { { "Остаток" } }, months.iter().map(|m| Some(m.rest)), "")}
+                        {for envelopes.iter().map(|name| render_row(
+                            html! { { name.as_str() } },
+                            months.iter().map(|m| m.envelopes.get(*name).copied()),
+                            "",
+                        ))}
+                        {for credits.iter().map(|name| render_row(
+                            html! { <span class="text-error">{ format!("Долг: {name}") }</span> },
+                            months.iter().map(|m| m.credits.get(*name).copied()),
+                            "",
+                        ))}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+fn render_row(
+    name: Html,
+    amounts: impl Iterator<Item = Option<Money>>,
+    class: &'static str,
+) -> Html {
+    html! {
+        <tr class={class}>
+            <td class="whitespace-nowrap">{ name }</td>
+            {for amounts.map(|amount| {
+                let negative = amount.is_some_and(|a| a.value.is_sign_negative());
+                html! {
+                    <td class={classes!("text-right", "whitespace-nowrap", negative.then_some("text-error"))}>
+                        { amount.map(|a| FormattedMoney(a).to_string()).unwrap_or_default() }
+                    </td>
+                }
+            })}
+        </tr>
+    }
+}
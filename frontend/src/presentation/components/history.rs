@@ -5,6 +5,8 @@ use crate::presentation::{history::HistoryEntry, income::SourceKind};
 #[derive(Properties, PartialEq)]
 pub struct HistoryProps {
     pub entries: Vec<HistoryEntry>,
+    /// Аннулировать распределение: id и необязательная причина
+    pub on_void: Callback<(String, Option<String>)>,
 }
 
 pub struct HistoryView;
@@ -23,12 +25,22 @@ impl Component for HistoryView {
                 <div class="join join-vertical w-full">
                     {for ctx.props().entries.iter().map(|entry| {
                         html! {
-                            <div class="collapse collapse-arrow join-item border border-base-300 bg-base-100">
+                            <div class={classes!(
+                                "collapse", "collapse-arrow", "join-item", "border", "border-base-300", "bg-base-100",
+                                entry.voided.is_some().then_some("opacity-60"),
+                            )}>
                                 <input type="checkbox" />
                                 <div class="collapse-title text-xl font-medium">
                                     <div class="flex justify-between items-center w-full pr-8">
                                         <div>
-                                            <h3 class="text-xl font-bold">{ &entry.date }</h3>
+                                            <h3 class="text-xl font-bold">
+                                                { &entry.date }
+                                                {if entry.voided.is_some() {
+                                                    html! { <span class="badge badge-error ml-2">{"Аннулировано"}</span> }
+                                                } else {
+                                                    html! {}
+                                                }}
+                                            </h3>
                                             <p class="text-sm text-base-content/70">
                                                 { &entry.source_name }
                                                 <span class="badge badge-sm badge-ghost ml-1">{ entry.source_kind.kind_label() }</span>
@@ -41,6 +53,7 @@ impl Component for HistoryView {
                                     </div>
                                 </div>
                                 <div class="collapse-content">
+                                    { Self::render_void(ctx, entry) }
                                     {if let SourceKind::Salary { gross, tax_rate, tax_amount } = &entry.source_kind {
                                         html! {
                                             <div class="card bg-warning/10 border border-warning/30 shadow mb-4 mt-4">
@@ -77,8 +90,23 @@ impl Component for HistoryView {
                                                             {for category.entries.iter().map(|expense| {
                                                                 html! {
                                                                     <div class="flex justify-between items-center text-sm">
-                                                                        <span>{ &expense.name }</span>
-                                                                        <span class="font-bold">{ expense.amount.to_string() }</span>
+                                                                        <span>
+                                                                            { &expense.name }
+                                                                            {if expense.surplus {
+                                                                                html! { <span class="badge badge-xs badge-accent ml-1">{"из остатка"}</span> }
+                                                                            } else {
+                                                                                html! {}
+                                                                            }}
+                                                                        </span>
+                                                                        <span class="font-bold">
+                                                                            { expense.amount.to_string() }
+                                                                            {match &expense.shortfall {
+                                                                                Some(shortfall) => html! {
+                                                                                    <span class="text-error text-xs ml-1" title="Недополучено">{ format!("−{shortfall}") }</span>
+                                                                                },
+                                                                                None => html! {},
+                                                                            }}
+                                                                        </span>
                                                                     </div>
                                                                 }
                                                             })}
@@ -97,3 +125,41 @@ impl Component for HistoryView {
         }
     }
 }
+
+impl HistoryView {
+    fn render_void(ctx: &Context<Self>, entry: &HistoryEntry) -> Html {
+        if let Some(voided) = &entry.voided {
+            let reason = voided
+                .reason
+                .as_ref()
+                .map(|r| format!(": {r}"))
+                .unwrap_or_default();
+            return html! {
+                <div class="alert alert-error mt-4 text-sm">
+                    { format!("Аннулировано {}{reason}. Суммы не учитываются в балансах конвертов", voided.date) }
+                </div>
+            };
+        }
+        let id = entry.id.clone();
+        let on_void = ctx.props().on_void.clone();
+        let onclick = Callback::from(move |_: MouseEvent| {
+            let reason = web_sys::window().and_then(|w| {
+                w.prompt_with_message("Аннулировать распределение? Укажите причину")
+                    .ok()
+                    .flatten()
+            });
+            // Отмена диалога - ничего не делаем
+            if let Some(reason) = reason {
+                let reason = Some(reason.trim().to_string()).filter(|r| !r.is_empty());
+                on_void.emit((id.clone(), reason));
+            }
+        });
+        html! {
+            <div class="flex justify-end mt-4">
+                <button class="btn btn-sm btn-outline btn-error" {onclick}>
+                    {"Аннулировать"}
+                </button>
+            </div>
+        }
+    }
+}
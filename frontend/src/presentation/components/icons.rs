@@ -27,6 +27,20 @@ pub fn landmark_icon(props: &IconProps) -> Html {
     }
 }
 
+/// Lucide "target" — savings goal
+#[function_component(TargetIcon)]
+pub fn target_icon(props: &IconProps) -> Html {
+    html! {
+        <svg class={props.class.clone()} viewBox="0 0 24 24" fill="none"
+            stroke="currentColor" stroke-width="2"
+            stroke-linecap="round" stroke-linejoin="round">
+            <circle cx="12" cy="12" r="10"/>
+            <circle cx="12" cy="12" r="6"/>
+            <circle cx="12" cy="12" r="2"/>
+        </svg>
+    }
+}
+
 /// Lucide "mail" — envelope
 #[function_component(MailIcon)]
 pub fn mail_icon(props: &IconProps) -> Html {
@@ -8,7 +8,13 @@ use yew::prelude::*;
 use ai_core::distribute::Budget;
 
 use crate::{
-    api::{AddIncomeRequest, ApiClient, ApiError, BudgetEntry},
+    api::{
+        AddIncomeRequest,
+        ApiClient,
+        ApiError,
+        BudgetEntry,
+        types::DistributedIncome,
+    },
     presentation::{
         history::HistoryEntry,
         income::{SourceKind, tax_from_net},
@@ -28,7 +34,7 @@ pub enum IncomeModalMsg {
     SetAmount(String),
     SetDate(String),
     Calculate,
-    Calculated(Result<Budget, String>),
+    Calculated(Result<Box<DistributedIncome>, String>),
     Save,
     Saved(Result<String, String>),
     Close,
@@ -44,7 +50,7 @@ pub struct IncomeModal {
 enum IncomeModalState {
     Input,
     Calculating,
-    Result(Budget),
+    Result(Box<DistributedIncome>),
     Saving,
     Saved,
     Error(String),
@@ -94,6 +100,7 @@ impl Component for IncomeModal {
                         let result = api
                             .add_income(request)
                             .await
+                            .map(Box::new)
                             .map_err(|e: ApiError| e.to_string());
                         link.send_message(IncomeModalMsg::Calculated(result));
                     });
@@ -103,8 +110,8 @@ impl Component for IncomeModal {
                     true
                 }
             }
-            IncomeModalMsg::Calculated(Ok(budget)) => {
-                self.state = IncomeModalState::Result(budget);
+            IncomeModalMsg::Calculated(Ok(distributed)) => {
+                self.state = IncomeModalState::Result(distributed);
                 true
             }
             IncomeModalMsg::Calculated(Err(e)) => {
@@ -112,8 +119,8 @@ impl Component for IncomeModal {
                 true
             }
             IncomeModalMsg::Save => {
-                let budget = match &self.state {
-                    IncomeModalState::Result(budget) => budget.clone(),
+                let distributed = match &self.state {
+                    IncomeModalState::Result(distributed) => (**distributed).clone(),
                     _ => return false,
                 };
                 self.state = IncomeModalState::Saving;
@@ -121,7 +128,7 @@ impl Component for IncomeModal {
                 let link = ctx.link().clone();
                 wasm_bindgen_futures::spawn_local(async move {
                     let result = api
-                        .save_budget(&budget)
+                        .save_budget(&distributed)
                         .await
                         .map_err(|e: ApiError| e.to_string());
                     link.send_message(IncomeModalMsg::Saved(result));
@@ -207,10 +214,10 @@ impl Component for IncomeModal {
                                 <span class="loading loading-spinner loading-lg"></span>
                             </div>
                         },
-                        IncomeModalState::Result(budget) => html! {
+                        IncomeModalState::Result(distributed) => html! {
                             <div class="collapse collapse-open">
                                 <div class="collapse-content p-0">
-                                    {self.render_result(ctx, budget)}
+                                    {self.render_result(ctx, &distributed.budget)}
                                 </div>
                             </div>
                         },
@@ -320,6 +327,7 @@ impl IncomeModal {
         let budget_entry = BudgetEntry {
             id: String::new(),
             budget: budget.clone(),
+            voided: None,
         };
         let entry = HistoryEntry::from(&budget_entry);
 
@@ -354,8 +362,23 @@ impl IncomeModal {
                                         {for category.entries.iter().map(|expense| {
                                             html! {
                                                 <div class="flex justify-between items-center text-sm">
-                                                    <span>{ &expense.name }</span>
-                                                    <span class="font-bold">{ expense.amount.to_string() }</span>
+                                                    <span>
+                                                        { &expense.name }
+                                                        {if expense.surplus {
+                                                            html! { <span class="badge badge-xs badge-accent ml-1">{"из остатка"}</span> }
+                                                        } else {
+                                                            html! {}
+                                                        }}
+                                                    </span>
+                                                    <span class="font-bold">
+                                                        { expense.amount.to_string() }
+                                                        {match &expense.shortfall {
+                                                            Some(shortfall) => html! {
+                                                                <span class="text-error text-xs ml-1" title="Недополучено">{ format!("−{shortfall}") }</span>
+                                                            },
+                                                            None => html! {},
+                                                        }}
+                                                    </span>
                                                 </div>
                                             }
                                         })}
@@ -0,0 +1,100 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct LoginProps {
+    pub login: String,
+    pub password: String,
+    pub error: Option<String>,
+    pub submitting: bool,
+    pub on_login_change: Callback<String>,
+    pub on_password_change: Callback<String>,
+    pub on_submit: Callback<()>,
+}
+
+pub struct LoginScreen;
+
+impl Component for LoginScreen {
+    type Message = ();
+    type Properties = LoginProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+
+        let on_login = props.on_login_change.reform(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            input.value()
+        });
+        let on_password = props.on_password_change.reform(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            input.value()
+        });
+        let on_submit = {
+            let cb = props.on_submit.clone();
+            Callback::from(move |e: SubmitEvent| {
+                e.prevent_default();
+                cb.emit(());
+            })
+        };
+
+        html! {
+            <div class="min-h-screen flex items-center justify-center bg-base-200">
+                <form class="card w-96 bg-base-100 shadow-xl" onsubmit={on_submit}>
+                    <div class="card-body">
+                        <h2 class="card-title text-2xl mb-4 justify-center">
+                            {"Anna Ivanovna"}
+                        </h2>
+
+                        <label class="form-control w-full mb-2">
+                            <span class="label-text mb-1">{"Логин"}</span>
+                            <input
+                                type="text"
+                                class="input input-bordered w-full"
+                                autocomplete="username"
+                                value={props.login.clone()}
+                                oninput={on_login}
+                                disabled={props.submitting}
+                            />
+                        </label>
+                        <label class="form-control w-full mb-4">
+                            <span class="label-text mb-1">{"Пароль"}</span>
+                            <input
+                                type="password"
+                                class="input input-bordered w-full"
+                                autocomplete="current-password"
+                                value={props.password.clone()}
+                                oninput={on_password}
+                                disabled={props.submitting}
+                            />
+                        </label>
+
+                        if let Some(err) = &props.error {
+                            <div class="alert alert-error text-sm mb-4">
+                                {err}
+                            </div>
+                        }
+
+                        <div class="card-actions w-full">
+                            <button
+                                type="submit"
+                                class="btn btn-primary w-full"
+                                disabled={props.submitting || props.login.trim().is_empty()}
+                            >
+                                if props.submitting {
+                                    <span class="loading loading-spinner loading-sm"></span>
+                                    {"Вход..."}
+                                } else {
+                                    {"Войти"}
+                                }
+                            </button>
+                        </div>
+                    </div>
+                </form>
+            </div>
+        }
+    }
+}
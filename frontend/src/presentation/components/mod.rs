@@ -1,20 +1,30 @@
+mod analytics;
 mod app_layout;
 mod error;
+mod forecast;
 mod history;
 pub mod icons;
 mod income_modal;
 mod loading;
+mod login;
 mod plan;
+mod report;
 mod template_selector;
 mod theme_switcher;
+mod timeline;
 mod welcome;
 
+pub use analytics::AnalyticsView;
 pub use app_layout::AppLayout;
 pub use error::Error;
+pub use forecast::{ForecastIncomes, ForecastView};
 pub use history::HistoryView;
 pub use income_modal::IncomeModal;
 pub use loading::Loading;
+pub use login::LoginScreen;
 pub use plan::{EditLayout, PlanView, Totals};
+pub use report::PlanReportView;
 pub use template_selector::TemplateSelector;
 pub use theme_switcher::{DEFAULT_THEME, ThemeSwitcher, set_theme, user_prefer_theme};
+pub use timeline::TimelineView;
 pub use welcome::WelcomeScreen;
@@ -1,5 +1,5 @@
 use crate::presentation::{
-    components::icons::{LandmarkIcon, MailIcon},
+    components::icons::{LandmarkIcon, MailIcon, TargetIcon},
     plan::read::{AccountingUnit, Expense, ExpenseKindView},
 };
 use yew::prelude::*;
@@ -65,6 +65,38 @@ impl Component for ExpenseCard {
                     </div>
                 }
             }
+            ExpenseKindView::Goal {
+                target,
+                deadline,
+                progress,
+            } => {
+                let details = match progress {
+                    Some(progress) => html! {
+                        <>
+                            <div>{ format!("Накоплено: {} ({})", progress.saved, progress.percent) }</div>
+                            <div>{ format!("Нужно в месяц: {}", progress.monthly_contribution) }</div>
+                            {match progress.projected_date {
+                                Some(date) => html! { <div>{ format!("Прогноз: {date}") }</div> },
+                                None => html! { <div>{"Прогноз: накоплений ещё нет"}</div> },
+                            }}
+                        </>
+                    },
+                    None => html! {},
+                };
+                html! {
+                    <div class="relative group/goal">
+                        <span class="badge badge-sm badge-primary w-12 justify-center cursor-help">
+                            <TargetIcon class="w-3 h-3" />
+                        </span>
+                        <div class="absolute right-0 top-full mt-1 hidden group-hover/goal:block bg-base-300 text-base-content text-xs rounded-lg py-2 px-3 z-20 whitespace-nowrap shadow-lg">
+                            <div class="font-semibold mb-1">{"Цель"}</div>
+                            <div>{ format!("Сумма: {target}") }</div>
+                            <div>{ format!("Срок: {deadline}") }</div>
+                            { details }
+                        </div>
+                    </div>
+                }
+            }
         };
 
         html! {
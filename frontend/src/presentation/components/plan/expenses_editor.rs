@@ -16,6 +16,11 @@ use yew::prelude::*;
 pub struct ExpensesEditorProps {
     pub expenses: Vec<Expense>,
     pub total_income: Option<Decimal>,
+    #[prop_or_default]
+    pub priority_mode: bool,
+    /// Названия источников дохода для привязки конвертов
+    #[prop_or_default]
+    pub income_sources: Vec<String>,
     pub on_change: Callback<Vec<Expense>>,
 }
 
@@ -27,6 +32,12 @@ pub enum CreditField {
     StartDate,
 }
 
+pub enum GoalField {
+    Target,
+    StartDate,
+    Deadline,
+}
+
 pub enum ExpensesEditorMsg {
     AmountChanged {
         pos: usize,
@@ -44,15 +55,28 @@ pub enum ExpensesEditorMsg {
         pos: usize,
         value: String,
     },
+    PriorityChanged {
+        pos: usize,
+        value: String,
+    },
     ExpenseTypeChanged {
         pos: usize,
-        is_credit: bool,
+        active_type: ActiveType,
+    },
+    SourceToggled {
+        pos: usize,
+        source: String,
     },
     CreditFieldChanged {
         pos: usize,
         field: CreditField,
         value: String,
     },
+    GoalFieldChanged {
+        pos: usize,
+        field: GoalField,
+        value: String,
+    },
     StartAdding,
     ConfirmNew,
     CancelNew,
@@ -101,13 +125,25 @@ impl Component for ExpensesEditor {
                 }
                 ctx.props().on_change.emit(updated);
             }
-            ExpensesEditorMsg::ExpenseTypeChanged { pos, is_credit } => {
+            ExpensesEditorMsg::PriorityChanged { pos, value } => {
+                if let Some(expense) = updated.get_mut(pos) {
+                    expense.priority = value;
+                }
+                ctx.props().on_change.emit(updated);
+            }
+            ExpensesEditorMsg::SourceToggled { pos, source } => {
                 if let Some(expense) = updated.get_mut(pos) {
-                    expense.active_type = if is_credit {
-                        ActiveType::Credit
+                    if let Some(i) = expense.sources.iter().position(|s| *s == source) {
+                        expense.sources.remove(i);
                     } else {
-                        ActiveType::Envelope
-                    };
+                        expense.sources.push(source);
+                    }
+                }
+                ctx.props().on_change.emit(updated);
+            }
+            ExpensesEditorMsg::ExpenseTypeChanged { pos, active_type } => {
+                if let Some(expense) = updated.get_mut(pos) {
+                    expense.active_type = active_type;
                 }
                 ctx.props().on_change.emit(updated);
             }
@@ -124,6 +160,17 @@ impl Component for ExpensesEditor {
                 }
                 ctx.props().on_change.emit(updated);
             }
+            ExpensesEditorMsg::GoalFieldChanged { pos, field, value } => {
+                if let Some(expense) = updated.get_mut(pos) {
+                    let goal = &mut expense.goal;
+                    match field {
+                        GoalField::Target => goal.target = value,
+                        GoalField::StartDate => goal.start_date = value,
+                        GoalField::Deadline => goal.deadline = value,
+                    }
+                }
+                ctx.props().on_change.emit(updated);
+            }
             ExpensesEditorMsg::StartAdding => {
                 self.adding = true;
                 updated.insert(0, Expense::empty());
@@ -199,7 +246,6 @@ impl Component for ExpensesEditor {
 
 impl ExpensesEditor {
     fn render_new_expense_card(&self, ctx: &Context<Self>, expense: &Expense) -> Html {
-        let is_credit = expense.active_type == ActiveType::Credit;
         html! {
             <div class="card bg-base-200 shadow border-2 border-primary">
                 <div class="card-body p-3 space-y-2">
@@ -224,9 +270,11 @@ impl ExpensesEditor {
                                 ExpensesEditorMsg::CategoryChanged { pos: 0, value }
                             })}
                         />
+                        { Self::render_priority_input(ctx, 0, expense) }
                     </div>
-                    { Self::render_type_toggle(ctx, 0, is_credit) }
-                    { Self::render_expense_fields(ctx, 0, &expense.expense_type()) }
+                    { Self::render_type_toggle(ctx, 0, expense.active_type) }
+                    { Self::render_source_bindings(ctx, 0, expense) }
+                    { Self::render_expense_fields(ctx, 0, expense) }
                     <div class="flex gap-2 justify-end">
                         <button
                             class="btn btn-sm btn-ghost"
@@ -252,7 +300,6 @@ impl ExpensesEditor {
         pos: usize,
         expense: &Expense,
     ) -> Html {
-        let is_credit = expense.active_type == ActiveType::Credit;
         html! {
             <div class="card bg-base-100 shadow">
                 <div class="card-body p-3 space-y-2">
@@ -276,6 +323,7 @@ impl ExpensesEditor {
                                 ExpensesEditorMsg::CategoryChanged { pos, value }
                             })}
                         />
+                        { Self::render_priority_input(ctx, pos, expense) }
                         <button
                             class="btn btn-sm btn-ghost btn-square text-error"
                             onclick={ctx.link().callback(move |_| ExpensesEditorMsg::DeleteExpense { pos })}
@@ -283,38 +331,100 @@ impl ExpensesEditor {
                             <XIcon />
                         </button>
                     </div>
-                    { Self::render_type_toggle(ctx, pos, is_credit) }
-                    { Self::render_expense_fields(ctx, pos, &expense.expense_type()) }
+                    { Self::render_type_toggle(ctx, pos, expense.active_type) }
+                    { Self::render_source_bindings(ctx, pos, expense) }
+                    { Self::render_expense_fields(ctx, pos, expense) }
                 </div>
             </div>
         }
     }
 
-    fn render_type_toggle(ctx: &Context<Self>, pos: usize, is_credit: bool) -> Html {
-        let envelope_class = if !is_credit {
-            "btn btn-sm join-item btn-primary"
-        } else {
-            "btn btn-sm join-item btn-outline"
-        };
-        let credit_class = if is_credit {
-            "btn btn-sm join-item btn-primary"
-        } else {
-            "btn btn-sm join-item btn-outline"
-        };
+    fn render_priority_input(
+        ctx: &Context<Self>,
+        pos: usize,
+        expense: &Expense,
+    ) -> Html {
+        if !ctx.props().priority_mode {
+            return html! {};
+        }
         html! {
-            <div class="join">
-                <button
-                    class={envelope_class}
-                    onclick={ctx.link().callback(move |_| ExpensesEditorMsg::ExpenseTypeChanged { pos, is_credit: false })}
-                >
-                    {"Конверт"}
-                </button>
+            <input
+                class="input input-bordered input-sm w-20"
+                placeholder="Приор."
+                title="Чем меньше число, тем раньше конверт наполняется при нехватке дохода"
+                value={expense.priority.clone()}
+                oninput={ctx.link().callback(move |e: InputEvent| {
+                    let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                    ExpensesEditorMsg::PriorityChanged { pos, value }
+                })}
+            />
+        }
+    }
+
+    /// Привязка имеет смысл, только когда источников больше одного
+    fn render_source_bindings(
+        ctx: &Context<Self>,
+        pos: usize,
+        expense: &Expense,
+    ) -> Html {
+        let sources = &ctx.props().income_sources;
+        if sources.len() < 2 {
+            return html! {};
+        }
+        html! {
+            <div
+                class="flex flex-wrap items-center gap-1"
+                title="Конверт наполняется только из выбранных источников. Без выбора - из источников без привязок"
+            >
+                <span class="text-xs text-base-content/60">{"Из дохода:"}</span>
+                {for sources.iter().map(|source| {
+                    let class = if expense.sources.contains(source) {
+                        "btn btn-xs btn-primary"
+                    } else {
+                        "btn btn-xs btn-outline"
+                    };
+                    let source = source.clone();
+                    html! {
+                        <button
+                            class={class}
+                            onclick={ctx.link().callback({
+                                let source = source.clone();
+                                move |_| ExpensesEditorMsg::SourceToggled { pos, source: source.clone() }
+                            })}
+                        >
+                            { source }
+                        </button>
+                    }
+                })}
+            </div>
+        }
+    }
+
+    fn render_type_toggle(
+        ctx: &Context<Self>,
+        pos: usize,
+        active_type: ActiveType,
+    ) -> Html {
+        let button = |target: ActiveType, label: &str| {
+            let class = if active_type == target {
+                "btn btn-sm join-item btn-primary"
+            } else {
+                "btn btn-sm join-item btn-outline"
+            };
+            html! {
                 <button
-                    class={credit_class}
-                    onclick={ctx.link().callback(move |_| ExpensesEditorMsg::ExpenseTypeChanged { pos, is_credit: true })}
+                    class={class}
+                    onclick={ctx.link().callback(move |_| ExpensesEditorMsg::ExpenseTypeChanged { pos, active_type: target })}
                 >
-                    {"Кредит"}
+                    { label.to_owned() }
                 </button>
+            }
+        };
+        html! {
+            <div class="join">
+                { button(ActiveType::Envelope, "Конверт") }
+                { button(ActiveType::Credit, "Кредит") }
+                { button(ActiveType::Goal, "Цель") }
             </div>
         }
     }
@@ -322,9 +432,9 @@ impl ExpensesEditor {
     fn render_expense_fields(
         ctx: &Context<Self>,
         pos: usize,
-        expense_type: &ExpenseType,
+        expense: &Expense,
     ) -> Html {
-        match expense_type {
+        match &expense.expense_type() {
             ExpenseType::Envelope { value_kind, amount } => {
                 Self::render_envelope_fields(ctx, pos, *value_kind, amount)
             }
@@ -334,15 +444,80 @@ impl ExpensesEditor {
                 interest_rate,
                 term_months,
                 start_date,
-            } => Self::render_credit_fields(
-                ctx,
-                pos,
-                monthly_payment,
-                total_amount,
-                interest_rate,
-                term_months,
+            } => html! {
+                <>
+                    { Self::render_credit_fields(
+                        ctx,
+                        pos,
+                        monthly_payment,
+                        total_amount,
+                        interest_rate,
+                        term_months,
+                        start_date,
+                    ) }
+                    {match expense.credit.payment_warning(expense.currency) {
+                        Some(warning) => html! {
+                            <div class="text-xs text-warning">{ warning }</div>
+                        },
+                        None => html! {},
+                    }}
+                </>
+            },
+            ExpenseType::Goal {
+                target,
                 start_date,
-            ),
+                deadline,
+            } => Self::render_goal_fields(ctx, pos, target, start_date, deadline),
+        }
+    }
+
+    fn render_goal_fields(
+        ctx: &Context<Self>,
+        pos: usize,
+        target: &str,
+        start_date: &str,
+        deadline: &str,
+    ) -> Html {
+        html! {
+            <div class="space-y-2">
+                <div>
+                    <label class="text-xs text-base-content/60">{"Сколько накопить"}</label>
+                    <input
+                        class="input input-bordered input-sm w-full"
+                        value={target.to_owned()}
+                        oninput={ctx.link().callback(move |e: InputEvent| {
+                            let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                            ExpensesEditorMsg::GoalFieldChanged { pos, field: GoalField::Target, value }
+                        })}
+                    />
+                </div>
+                <div class="flex items-center gap-2">
+                    <div class="flex-1">
+                        <label class="text-xs text-base-content/60">{"Начало накоплений"}</label>
+                        <input
+                            type="date"
+                            class="input input-bordered input-sm w-full"
+                            value={start_date.to_owned()}
+                            oninput={ctx.link().callback(move |e: InputEvent| {
+                                let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                                ExpensesEditorMsg::GoalFieldChanged { pos, field: GoalField::StartDate, value }
+                            })}
+                        />
+                    </div>
+                    <div class="flex-1">
+                        <label class="text-xs text-base-content/60">{"Срок"}</label>
+                        <input
+                            type="date"
+                            class="input input-bordered input-sm w-full"
+                            value={deadline.to_owned()}
+                            oninput={ctx.link().callback(move |e: InputEvent| {
+                                let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                                ExpensesEditorMsg::GoalFieldChanged { pos, field: GoalField::Deadline, value }
+                            })}
+                        />
+                    </div>
+                </div>
+            </div>
         }
     }
 
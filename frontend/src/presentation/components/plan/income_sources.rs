@@ -7,9 +7,10 @@ use crate::{
     presentation::{
         components::IncomeModal,
         income::SourceKind,
-        plan::read::IncomeSource,
+        plan::read::{ExpectedIncome, IncomeSource},
     },
 };
+use ai_core::planning::IncomeStatus;
 
 #[derive(Properties, PartialEq)]
 pub struct IncomeSourcesProps {
@@ -97,6 +98,7 @@ impl Component for IncomeSources {
                                                     </p>
                                                 }
                                             }}
+                                            { render_expected(&source.expected) }
                                         </div>
                                         <button
                                             class="btn btn-primary btn-sm"
@@ -130,3 +132,36 @@ impl Component for IncomeSources {
         }
     }
 }
+
+/// Поступления по графику на этот месяц: что пришло, что опаздывает
+fn render_expected(expected: &[ExpectedIncome]) -> Html {
+    if expected.is_empty() {
+        return html! {};
+    }
+    html! {
+        <ul class="mt-2 space-y-1 text-sm">
+            {for expected.iter().map(|income| {
+                let date = income.date.format("%d.%m").to_string();
+                let (badge, label) = match income.status {
+                    IncomeStatus::Arrived => ("badge badge-sm badge-success", "пришло"),
+                    IncomeStatus::Late => ("badge badge-sm badge-warning", "опаздывает"),
+                    IncomeStatus::Pending => ("badge badge-sm badge-ghost", "ожидается"),
+                };
+                let details = match (&income.actual, &income.difference) {
+                    (Some(actual), Some(difference)) => {
+                        format!("{actual} (разница {difference})")
+                    }
+                    (Some(actual), None) => actual.to_string(),
+                    _ => String::new(),
+                };
+                html! {
+                    <li class="flex items-center gap-2">
+                        <span class="text-base-content/60">{ date }</span>
+                        <span class={badge}>{ label }</span>
+                        <span>{ details }</span>
+                    </li>
+                }
+            })}
+        </ul>
+    }
+}
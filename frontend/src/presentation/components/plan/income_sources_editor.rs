@@ -32,6 +32,10 @@ pub enum IncomeSourcesEditorMsg {
         pos: usize,
         value: String,
     },
+    ScheduleChanged {
+        pos: usize,
+        schedule: editable::ScheduleData,
+    },
     StartAdding,
     ConfirmNew,
     CancelNew,
@@ -79,6 +83,12 @@ impl Component for IncomeSourcesEditor {
                 }
                 ctx.props().on_change.emit(updated);
             }
+            IncomeSourcesEditorMsg::ScheduleChanged { pos, schedule } => {
+                if let Some(source) = updated.get_mut(pos) {
+                    source.schedule = schedule;
+                }
+                ctx.props().on_change.emit(updated);
+            }
             IncomeSourcesEditorMsg::StartAdding => {
                 self.adding = true;
                 updated.insert(0, editable::IncomeSource::empty());
@@ -191,6 +201,7 @@ impl IncomeSourcesEditor {
                     } else {
                         html! {}
                     }}
+                    { Self::render_schedule(ctx, 0, &source.schedule) }
                     <div class="flex gap-2 justify-end">
                         <button
                             class="btn btn-sm btn-ghost"
@@ -268,11 +279,82 @@ impl IncomeSourcesEditor {
                     } else {
                         html! {}
                     }}
+                    { Self::render_schedule(ctx, pos, &source.schedule) }
                 </div>
             </div>
         }
     }
 
+    fn render_schedule(
+        ctx: &Context<Self>,
+        pos: usize,
+        schedule: &editable::ScheduleData,
+    ) -> Html {
+        let changed = |f: fn(&mut editable::ScheduleData, String)| {
+            let schedule = schedule.clone();
+            ctx.link().callback(move |e: Event| {
+                let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                let mut schedule = schedule.clone();
+                f(&mut schedule, value);
+                IncomeSourcesEditorMsg::ScheduleChanged { pos, schedule }
+            })
+        };
+        let kind = schedule.kind;
+        html! {
+            <div class="flex flex-wrap items-center gap-2">
+                <select
+                    class="select select-bordered select-sm"
+                    title="Когда ожидать поступление"
+                    onchange={changed(|s, value| {
+                        s.kind = match value.as_str() {
+                            "monthly" => editable::ScheduleKind::MonthlyDays,
+                            "weeks" => editable::ScheduleKind::EveryWeeks,
+                            _ => editable::ScheduleKind::None,
+                        };
+                    })}
+                >
+                    <option value="none" selected={kind == editable::ScheduleKind::None}>
+                        {"Без графика"}
+                    </option>
+                    <option value="monthly" selected={kind == editable::ScheduleKind::MonthlyDays}>
+                        {"По числам месяца"}
+                    </option>
+                    <option value="weeks" selected={kind == editable::ScheduleKind::EveryWeeks}>
+                        {"Каждые N недель"}
+                    </option>
+                </select>
+                {match kind {
+                    editable::ScheduleKind::None => html! {},
+                    editable::ScheduleKind::MonthlyDays => html! {
+                        <input
+                            class="input input-bordered input-sm w-32"
+                            placeholder="10, 25"
+                            value={schedule.days.clone()}
+                            onchange={changed(|s, value| s.days = value)}
+                        />
+                    },
+                    editable::ScheduleKind::EveryWeeks => html! {
+                        <>
+                            <input
+                                class="input input-bordered input-sm w-20"
+                                placeholder="Недель"
+                                value={schedule.weeks.clone()}
+                                onchange={changed(|s, value| s.weeks = value)}
+                            />
+                            <input
+                                type="date"
+                                class="input input-bordered input-sm"
+                                title="Дата первого поступления"
+                                value={schedule.start.clone()}
+                                onchange={changed(|s, value| s.start = value)}
+                            />
+                        </>
+                    },
+                }}
+            </div>
+        }
+    }
+
     fn render_kind_select(
         ctx: &Context<Self>,
         pos: usize,
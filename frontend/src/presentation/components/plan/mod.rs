@@ -6,6 +6,7 @@ mod income_sources_editor;
 mod plan_edit_actions_bar;
 mod plan_edit_layout;
 mod plan_section_card;
+mod surplus_editor;
 mod totals;
 mod view;
 
@@ -16,5 +17,6 @@ use income_sources_editor::IncomeSourcesEditor;
 use plan_edit_actions_bar::EditActionsBar;
 pub use plan_edit_layout::EditLayout;
 use plan_section_card::SectionCard;
+use surplus_editor::SurplusEditor;
 pub use totals::Totals;
 pub use view::PlanView;
@@ -1,11 +1,14 @@
 use rust_decimal::Decimal;
 
+use ai_core::planning::DistributionMode;
+
 use crate::presentation::{
     components::plan::{
         EditActionsBar,
         ExpensesEditor,
         IncomeSourcesEditor,
         SectionCard,
+        SurplusEditor,
     },
     plan::editable,
 };
@@ -16,11 +19,15 @@ pub struct EditLayoutProps {
     pub incomes: Vec<editable::IncomeSource>,
     pub expenses: Vec<editable::Expense>,
     pub total_income: Option<Decimal>,
+    pub mode: DistributionMode,
+    pub surplus: Vec<editable::SurplusRule>,
     pub disable_save: bool,
     pub on_cancel: Callback<()>,
     pub on_save: Callback<()>,
     pub on_incomes_change: Callback<Vec<editable::IncomeSource>>,
     pub on_expenses_change: Callback<Vec<editable::Expense>>,
+    pub on_mode_change: Callback<DistributionMode>,
+    pub on_surplus_change: Callback<Vec<editable::SurplusRule>>,
 }
 
 pub struct EditLayout;
@@ -35,7 +42,17 @@ impl Component for EditLayout {
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let incomes = ctx.props().incomes.clone();
+        let income_sources: Vec<String> = incomes
+            .iter()
+            .map(|i| i.name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
         let expenses = ctx.props().expenses.clone();
+        let envelopes: Vec<String> = expenses
+            .iter()
+            .map(|e| e.name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
 
         let incomes_on_change = ctx.props().on_incomes_change.clone();
         let expenses_on_change = ctx.props().on_expenses_change.clone();
@@ -48,6 +65,30 @@ impl Component for EditLayout {
             />
         };
 
+        let mode = ctx.props().mode;
+        let mode_button = |target: DistributionMode, label: &str| {
+            let class = if mode == target {
+                "btn btn-xs join-item btn-primary"
+            } else {
+                "btn btn-xs join-item btn-outline"
+            };
+            let on_mode_change = ctx.props().on_mode_change.clone();
+            html! {
+                <button class={class} onclick={move |_| on_mode_change.emit(target)}>
+                    { label.to_owned() }
+                </button>
+            }
+        };
+        let mode_toggle = html! {
+            <div
+                class="join"
+                title="Как делить доход, которого не хватает на весь план"
+            >
+                { mode_button(DistributionMode::Proportional, "Пропорционально") }
+                { mode_button(DistributionMode::Priority, "По приоритетам") }
+            </div>
+        };
+
         html! {
             <div class="space-y-6">
                 <SectionCard
@@ -61,13 +102,23 @@ impl Component for EditLayout {
                 </SectionCard>
                 <SectionCard
                     title={"Редактирование расходов плана"}
+                    header_right={Some(mode_toggle)}
                 >
                     <ExpensesEditor
                         expenses={expenses}
                         total_income={ctx.props().total_income}
+                        priority_mode={mode == DistributionMode::Priority}
+                        income_sources={income_sources}
                         on_change={expenses_on_change}
                     />
                 </SectionCard>
+                <SectionCard title={"Распределение остатка"}>
+                    <SurplusEditor
+                        rules={ctx.props().surplus.clone()}
+                        envelopes={envelopes}
+                        on_change={ctx.props().on_surplus_change.clone()}
+                    />
+                </SectionCard>
             </div>
         }
     }
@@ -0,0 +1,117 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::presentation::{components::icons::XIcon, plan::editable};
+
+#[derive(Properties, PartialEq)]
+pub struct SurplusEditorProps {
+    pub rules: Vec<editable::SurplusRule>,
+    /// Названия конвертов, в которые можно направить остаток
+    pub envelopes: Vec<String>,
+    pub on_change: Callback<Vec<editable::SurplusRule>>,
+}
+
+pub enum SurplusEditorMsg {
+    EnvelopeChanged { pos: usize, value: String },
+    ShareChanged { pos: usize, value: String },
+    Add,
+    Delete { pos: usize },
+}
+
+pub struct SurplusEditor;
+
+impl Component for SurplusEditor {
+    type Message = SurplusEditorMsg;
+    type Properties = SurplusEditorProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let mut updated = ctx.props().rules.clone();
+        match msg {
+            SurplusEditorMsg::EnvelopeChanged { pos, value } => {
+                if let Some(rule) = updated.get_mut(pos) {
+                    rule.envelope = value;
+                }
+            }
+            SurplusEditorMsg::ShareChanged { pos, value } => {
+                if let Some(rule) = updated.get_mut(pos) {
+                    rule.share = value;
+                }
+            }
+            SurplusEditorMsg::Add => updated.push(editable::SurplusRule::default()),
+            SurplusEditorMsg::Delete { pos } => {
+                if pos < updated.len() {
+                    updated.remove(pos);
+                }
+            }
+        }
+        ctx.props().on_change.emit(updated);
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let rules = &ctx.props().rules;
+        html! {
+            <div class="space-y-2">
+                <p class="text-xs text-base-content/60">
+                    {"Остаток после распределения делится между конвертами в указанных долях"}
+                </p>
+                {for rules.iter().enumerate().map(|(pos, rule)| self.render_rule(ctx, pos, rule))}
+                <button
+                    class="btn btn-outline btn-primary btn-sm w-full"
+                    onclick={ctx.link().callback(|_| SurplusEditorMsg::Add)}
+                >
+                    {"+ Добавить правило"}
+                </button>
+            </div>
+        }
+    }
+}
+
+impl SurplusEditor {
+    fn render_rule(
+        &self,
+        ctx: &Context<Self>,
+        pos: usize,
+        rule: &editable::SurplusRule,
+    ) -> Html {
+        html! {
+            <div class="flex items-center gap-2">
+                <select
+                    class="select select-bordered select-sm flex-1"
+                    onchange={ctx.link().callback(move |e: Event| {
+                        let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                        SurplusEditorMsg::EnvelopeChanged { pos, value }
+                    })}
+                >
+                    <option value="" selected={rule.envelope.is_empty()} disabled=true>
+                        {"Конверт"}
+                    </option>
+                    {for ctx.props().envelopes.iter().map(|name| html! {
+                        <option value={name.clone()} selected={*name == rule.envelope}>
+                            { name }
+                        </option>
+                    })}
+                </select>
+                <input
+                    class="input input-bordered input-sm w-24"
+                    placeholder="%"
+                    value={rule.share.clone()}
+                    oninput={ctx.link().callback(move |e: InputEvent| {
+                        let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                        SurplusEditorMsg::ShareChanged { pos, value }
+                    })}
+                />
+                <button
+                    class="btn btn-sm btn-ghost btn-square text-error"
+                    onclick={ctx.link().callback(move |_| SurplusEditorMsg::Delete { pos })}
+                >
+                    <XIcon />
+                </button>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use yew::prelude::*;
+
+use crate::{
+    api::types::{Fulfillment, PlanReport},
+    presentation::formatting::{FormattedMoney, FormattedPercentage},
+};
+
+#[derive(Properties, PartialEq)]
+pub struct PlanReportProps {
+    pub report: PlanReport,
+}
+
+#[function_component(PlanReportView)]
+pub fn plan_report_view(props: &PlanReportProps) -> Html {
+    let report = &props.report;
+    html! {
+        <div class="card bg-base-100 shadow">
+            <div class="card-body overflow-x-auto">
+                <p class="text-sm text-base-content/60">
+                    { format!("Распределений за месяц: {}", report.budgets) }
+                </p>
+                <table class="table table-sm">
+                    <thead>
+                        <tr>
+                            <th>{ "Конверт" }</th>
+                            <th class="text-right">{ "План" }</th>
+                            <th class="text-right">{ "Факт" }</th>
+                            <th class="text-right">{ "Разница" }</th>
+                            <th class="w-40">{ "Выполнено" }</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {for report.categories.iter().map(|category| {
+                            let title = category
+                                .category
+                                .clone()
+                                .unwrap_or_else(|| "Без категории".to_string());
+                            html! {
+                                <>
+                                    {render_row(html! { <b>{ title }</b> }, &category.amounts, "bg-base-200")}
+                                    {for category.expenses.iter().map(|expense| {
+                                        render_row(
+                                            html! { <span class="pl-4">{ &expense.name }</span> },
+                                            &expense.amounts,
+                                            "",
+                                        )
+                                    })}
+                                </>
+                            }
+                        })}
+                    </tbody>
+                    <tfoot>
+                        {render_row(html! { { "Итого" } }, &report.total, "")}
+                    </tfoot>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+fn render_row(name: Html, amounts: &Fulfillment, class: &'static str) -> Html {
+    let difference_class = if amounts.difference.value.is_sign_negative() {
+        "text-error"
+    } else {
+        "text-success"
+    };
+    html! {
+        <tr class={class}>
+            <td>{ name }</td>
+            <td class="text-right">{ FormattedMoney(amounts.planned).to_string() }</td>
+            <td class="text-right">{ FormattedMoney(amounts.actual).to_string() }</td>
+            <td class={classes!("text-right", difference_class)}>
+                { FormattedMoney(amounts.difference).to_string() }
+            </td>
+            <td>{render_fulfilled(amounts)}</td>
+        </tr>
+    }
+}
+
+fn render_fulfilled(amounts: &Fulfillment) -> Html {
+    match &amounts.fulfilled {
+        Some(fulfilled) => {
+            let percentage = FormattedPercentage(fulfilled.clone());
+            html! {
+                <div class="flex items-center gap-2">
+                    <progress
+                        class="progress progress-primary w-20"
+                        value={percentage.raw_value()}
+                        max="100"
+                    ></progress>
+                    <span class="text-xs">{ percentage.to_string() }</span>
+                </div>
+            }
+        }
+        None => {
+            html! { <span class="text-xs text-base-content/60">{ "вне плана" }</span> }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use ai_core::{
+    plan::{Change, PlanDiff},
+    planning::{Expense, ExpenseValue, IncomeSource},
+};
+use yew::prelude::*;
+
+use crate::{
+    api::types::{PlanAction, PlanEvent},
+    engine::{
+        core::DataState,
+        timeline::{RestoreState, SelectedVersion},
+    },
+    presentation::formatting::{FormattedMoney, FormattedPercentage},
+};
+
+#[derive(Properties, PartialEq)]
+pub struct TimelineProps {
+    pub events: Vec<PlanEvent>,
+    pub selected: Option<SelectedVersion>,
+    pub restore: RestoreState,
+    pub on_select: Callback<i64>,
+    pub on_restore: Callback<i64>,
+}
+
+#[function_component(TimelineView)]
+pub fn timeline_view(props: &TimelineProps) -> Html {
+    let latest = props.events.iter().map(|e| e.version).max();
+    html! {
+        <ul class="timeline timeline-vertical timeline-compact">
+            {for props.events.iter().map(|event| {
+                let selected = props
+                    .selected
+                    .as_ref()
+                    .filter(|s| s.version == event.version);
+                render_event(props, event, selected, Some(event.version) == latest)
+            })}
+        </ul>
+    }
+}
+
+fn render_event(
+    props: &TimelineProps,
+    event: &PlanEvent,
+    selected: Option<&SelectedVersion>,
+    is_latest: bool,
+) -> Html {
+    let version = event.version;
+    let (label, badge) = match event.action {
+        PlanAction::Created => ("План создан", "badge-success"),
+        PlanAction::Updated => ("План изменён", "badge-info"),
+        PlanAction::Deleted => ("План удалён", "badge-error"),
+    };
+    let can_restore =
+        !is_latest && event.action != PlanAction::Deleted && event.content.is_some();
+    let restoring = props.restore == RestoreState::Restoring { version };
+    html! {
+        <li>
+            <div class="timeline-middle">
+                <span class={classes!("badge", "badge-sm", badge)}>{ format!("v{version}") }</span>
+            </div>
+            <div class="timeline-end timeline-box w-full mb-4">
+                <div class="flex justify-between items-center gap-2">
+                    <div>
+                        <p class="font-semibold">{ label }</p>
+                        <p class="text-xs text-base-content/60">{ &event.created_at }</p>
+                    </div>
+                    <div class="flex gap-2">
+                        {if event.action == PlanAction::Updated {
+                            html! {
+                                <button
+                                    class="btn btn-xs btn-ghost"
+                                    onclick={props.on_select.reform(move |_| version)}
+                                >
+                                    { if selected.is_some() { "Скрыть" } else { "Изменения" } }
+                                </button>
+                            }
+                        } else {
+                            html! {}
+                        }}
+                        {if can_restore {
+                            html! {
+                                <button
+                                    class="btn btn-xs btn-outline btn-primary"
+                                    disabled={matches!(props.restore, RestoreState::Restoring { .. })}
+                                    onclick={props.on_restore.reform(move |_| version)}
+                                >
+                                    {if restoring {
+                                        html! { <span class="loading loading-spinner loading-xs"></span> }
+                                    } else {
+                                        html! { "Восстановить" }
+                                    }}
+                                </button>
+                            }
+                        } else {
+                            html! {}
+                        }}
+                    </div>
+                </div>
+                {match selected.map(|s| &s.diff) {
+                    Some(DataState::Loading) => html! {
+                        <span class="loading loading-spinner loading-sm mt-2"></span>
+                    },
+                    Some(DataState::Error(e)) => html! {
+                        <p class="text-error text-sm mt-2">{ e }</p>
+                    },
+                    Some(DataState::Loaded(diff)) => render_diff(diff),
+                    None => html! {},
+                }}
+            </div>
+            <hr />
+        </li>
+    }
+}
+
+fn render_diff(diff: &PlanDiff) -> Html {
+    if diff.is_empty() {
+        return html! { <p class="text-sm text-base-content/60 mt-2">{"Без изменений"}</p> };
+    }
+    html! {
+        <div class="mt-2 space-y-1 text-sm">
+            {for diff.sources.iter().map(|c| render_change("Доход", c, source_summary, |s| &s.name))}
+            {for diff.expenses.iter().map(|c| render_change("Расход", c, expense_summary, |e| &e.name))}
+            {if diff.settings_changed {
+                html! { <p class="text-base-content/70">{"Изменены курсы, режим распределения или правила остатка"}</p> }
+            } else {
+                html! {}
+            }}
+        </div>
+    }
+}
+
+fn render_change<T>(
+    kind: &str,
+    change: &Change<T>,
+    summary: fn(&T) -> String,
+    name: fn(&T) -> &str,
+) -> Html {
+    let (mark, class, text) = match change {
+        Change::Added { new } => ("+", "text-success", summary(new)),
+        Change::Removed { old } => ("−", "text-error", summary(old)),
+        Change::Changed { old, new } => {
+            let (was, now) = (summary(old), summary(new));
+            let text = if was == now {
+                format!("{}: изменены параметры", name(new))
+            } else {
+                format!("{was} → {now}")
+            };
+            ("~", "text-warning", text)
+        }
+    };
+    html! {
+        <p class={class}>{ format!("{mark} {kind} {text}") }</p>
+    }
+}
+
+fn source_summary(source: &IncomeSource) -> String {
+    format!(
+        "{}: {}",
+        source.name,
+        FormattedMoney::from_money(source.net())
+    )
+}
+
+fn expense_summary(expense: &Expense) -> String {
+    let value = match expense.value() {
+        ExpenseValue::MONEY { value } => FormattedMoney::from_money(value).to_string(),
+        ExpenseValue::RATE { value } => {
+            FormattedPercentage::from_percentage(value).to_string()
+        }
+    };
+    format!("{}: {value}", expense.name)
+}
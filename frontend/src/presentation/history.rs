@@ -18,6 +18,15 @@ pub struct HistoryEntry {
     pub source_kind: SourceKind,
     pub rest: FormattedMoney,
     pub categories: Vec<Category>,
+    /// Распределение аннулировано и не учитывается в балансах
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voided: Option<Voided>,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct Voided {
+    pub date: String,
+    pub reason: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Deserialize, Serialize)]
@@ -30,6 +39,11 @@ pub struct Category {
 pub struct ExpenseEntry {
     pub name: String,
     pub amount: FormattedMoney,
+    /// Сколько конверт недополучил при распределении по приоритетам
+    pub shortfall: Option<FormattedMoney>,
+    /// Сумма пришла из остатка по правилу плана
+    #[serde(default)]
+    pub surplus: bool,
 }
 
 impl From<&BudgetEntry> for HistoryEntry {
@@ -41,6 +55,13 @@ impl From<&BudgetEntry> for HistoryEntry {
         let income_amount = FormattedMoney::from_money(budget.income.amount);
         let rest = FormattedMoney::from_money(budget.rest);
         let source_kind = SourceKind::from(&budget.income.source.kind);
+        let shortfall = |name: &str| {
+            budget
+                .underfunded
+                .iter()
+                .find(|u| u.name == name)
+                .map(|u| FormattedMoney::from_money(u.shortfall))
+        };
 
         // Группируем расходы по категориям
         let mut categories_map: HashMap<String, Vec<ExpenseEntry>> = HashMap::new();
@@ -53,6 +74,8 @@ impl From<&BudgetEntry> for HistoryEntry {
                 .map(|entry| ExpenseEntry {
                     name: entry.expense.name.clone(),
                     amount: FormattedMoney::from_money(entry.amount),
+                    shortfall: shortfall(&entry.expense.name),
+                    surplus: entry.surplus,
                 })
                 .collect();
             categories_map.insert(NO_CATEGORY.to_string(), entries);
@@ -65,6 +88,8 @@ impl From<&BudgetEntry> for HistoryEntry {
                 .map(|entry| ExpenseEntry {
                     name: entry.expense.name.clone(),
                     amount: FormattedMoney::from_money(entry.amount),
+                    shortfall: shortfall(&entry.expense.name),
+                    surplus: entry.surplus,
                 })
                 .collect();
             categories_map.insert(category_name.clone(), expense_entries);
@@ -77,7 +102,9 @@ impl From<&BudgetEntry> for HistoryEntry {
                 name: name.clone(),
                 entries: {
                     let mut sorted = entries.clone();
-                    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+                    sorted.sort_by(|a, b| {
+                        (&a.name, a.surplus).cmp(&(&b.name, b.surplus))
+                    });
                     sorted
                 },
             })
@@ -98,6 +125,10 @@ impl From<&BudgetEntry> for HistoryEntry {
             source_kind,
             rest,
             categories,
+            voided: storage_budget.voided.as_ref().map(|v| Voided {
+                date: v.date.format("%Y-%m-%d").to_string(),
+                reason: v.reason.clone(),
+            }),
         }
     }
 }
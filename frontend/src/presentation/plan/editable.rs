@@ -5,19 +5,22 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use ai_core::{
-    finance::{Money, Percentage},
+    finance::{Currency, Money, Percentage},
     plan::Plan as CorePlan,
     planning::{
         CreditExpense,
         Expense as CoreExpense,
         ExpenseKind as CoreExpenseKind,
         ExpenseValue as CoreExpenseValue,
+        GoalExpense,
         IncomeKind as CoreIncomeKind,
+        IncomeSchedule as CoreIncomeSchedule,
         IncomeSource as CoreIncomeSource,
+        SurplusRule as CoreSurplusRule,
     },
 };
 
-use crate::presentation::formatting::FormattedPercentage;
+use crate::presentation::formatting::{FormattedMoney, FormattedPercentage};
 
 #[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum IncomeKind {
@@ -31,6 +34,107 @@ pub struct IncomeSource {
     pub kind: IncomeKind,
     pub amount: String,
     pub tax_rate: String,
+    #[serde(default)]
+    pub currency: Currency,
+    #[serde(default, skip_serializing_if = "ScheduleData::is_none")]
+    pub schedule: ScheduleData,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum ScheduleKind {
+    #[default]
+    None,
+    MonthlyDays,
+    EveryWeeks,
+}
+
+/// График поступлений в том виде, в каком его вводит пользователь
+#[derive(Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ScheduleData {
+    pub kind: ScheduleKind,
+    /// Числа месяца через запятую: "10, 25"
+    pub days: String,
+    pub weeks: String,
+    pub start: String,
+}
+
+impl ScheduleData {
+    pub fn is_none(&self) -> bool {
+        self.kind == ScheduleKind::None
+    }
+
+    fn from_core(schedule: Option<&CoreIncomeSchedule>) -> Self {
+        match schedule {
+            Some(CoreIncomeSchedule::MonthlyDays { days, .. }) => Self {
+                kind: ScheduleKind::MonthlyDays,
+                days: days
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                ..Self::default()
+            },
+            Some(CoreIncomeSchedule::EveryWeeks { weeks, start, .. }) => Self {
+                kind: ScheduleKind::EveryWeeks,
+                weeks: weeks.to_string(),
+                start: start.to_string(),
+                ..Self::default()
+            },
+            None => Self::default(),
+        }
+    }
+
+    fn parse_days(&self) -> Option<Vec<u32>> {
+        self.days
+            .split(',')
+            .map(|d| d.trim().parse::<u32>().ok())
+            .collect()
+    }
+
+    pub fn to_core(&self) -> Option<CoreIncomeSchedule> {
+        match self.kind {
+            ScheduleKind::None => None,
+            ScheduleKind::MonthlyDays => {
+                CoreIncomeSchedule::monthly(self.parse_days()?).ok()
+            }
+            ScheduleKind::EveryWeeks => {
+                let weeks = self.weeks.trim().parse::<u32>().ok()?;
+                let start = NaiveDate::parse_from_str(&self.start, "%Y-%m-%d").ok()?;
+                CoreIncomeSchedule::every_weeks(weeks, start).ok()
+            }
+        }
+    }
+
+    pub fn validation_errors(&self) -> Vec<&'static str> {
+        let mut errors = Vec::new();
+        match self.kind {
+            ScheduleKind::None => {}
+            ScheduleKind::MonthlyDays => CreditData::validate_field(
+                &self.days,
+                |_| self.to_core().is_some(),
+                "не указаны числа поступлений",
+                "числа поступлений должны быть от 1 до 31 через запятую",
+                &mut errors,
+            ),
+            ScheduleKind::EveryWeeks => {
+                CreditData::validate_field(
+                    &self.weeks,
+                    |v| v.trim().parse::<u32>().is_ok_and(|w| w > 0),
+                    "не указан период в неделях",
+                    "некорректный период в неделях",
+                    &mut errors,
+                );
+                CreditData::validate_field(
+                    &self.start,
+                    |v| NaiveDate::parse_from_str(v, "%Y-%m-%d").is_ok(),
+                    "не указана дата первого поступления",
+                    "некорректная дата первого поступления",
+                    &mut errors,
+                );
+            }
+        }
+        errors
+    }
 }
 
 impl IncomeSource {
@@ -40,6 +144,8 @@ impl IncomeSource {
             kind: IncomeKind::Other,
             amount: String::new(),
             tax_rate: "13".into(),
+            currency: Currency::default(),
+            schedule: ScheduleData::default(),
         }
     }
 }
@@ -53,12 +159,16 @@ pub fn incomes_from_core_plan(plan: &CorePlan) -> Vec<IncomeSource> {
                 kind: IncomeKind::Salary,
                 amount: gross.value.to_string(),
                 tax_rate: FormattedPercentage::from(tax_rate.clone()).raw_value(),
+                currency: gross.currency,
+                schedule: ScheduleData::from_core(source.schedule.as_ref()),
             },
             CoreIncomeKind::Other { expected } => IncomeSource {
                 name: source.name.clone(),
                 kind: IncomeKind::Other,
                 amount: expected.value.to_string(),
                 tax_rate: "13".into(),
+                currency: expected.currency,
+                schedule: ScheduleData::from_core(source.schedule.as_ref()),
             },
         })
         .collect()
@@ -75,15 +185,19 @@ fn apply_incomes_to_core_plan(plan: &CorePlan, incomes: &[IncomeSource]) -> Core
                 IncomeKind::Salary => {
                     let rate = Decimal::from_str(&editable.tax_rate).ok()?;
                     CoreIncomeKind::Salary {
-                        gross: Money::new_rub(amount),
+                        gross: Money::new(amount, editable.currency),
                         tax_rate: Percentage::from(rate),
                     }
                 }
                 IncomeKind::Other => CoreIncomeKind::Other {
-                    expected: Money::new_rub(amount),
+                    expected: Money::new(amount, editable.currency),
                 },
             };
-            Some(CoreIncomeSource::new(editable.name.clone(), kind))
+            let source = CoreIncomeSource::new(editable.name.clone(), kind);
+            Some(match editable.schedule.to_core() {
+                Some(schedule) => source.with_schedule(schedule),
+                None => source,
+            })
         })
         .collect();
 
@@ -109,12 +223,18 @@ pub enum ExpenseType {
         term_months: String,
         start_date: String,
     },
+    Goal {
+        target: String,
+        start_date: String,
+        deadline: String,
+    },
 }
 
 #[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum ActiveType {
     Envelope,
     Credit,
+    Goal,
 }
 
 #[derive(Clone, PartialEq, Deserialize, Serialize)]
@@ -173,6 +293,35 @@ impl CreditData {
         errors
     }
 
+    pub fn to_core(&self, currency: Currency) -> Option<CreditExpense> {
+        let monthly = Decimal::from_str(&self.monthly_payment).ok()?;
+        let total = Decimal::from_str(&self.total_amount).ok()?;
+        let rate = Decimal::from_str(&self.interest_rate).ok()?;
+        let months = self.term_months.parse::<u32>().ok()?;
+        let date = NaiveDate::parse_from_str(&self.start_date, "%Y-%m-%d").ok()?;
+        CreditExpense::new(
+            Money::new(monthly, currency),
+            Money::new(total, currency),
+            Percentage::from(rate),
+            months,
+            date,
+        )
+        .ok()
+    }
+
+    /// Предупреждение, если платёж не сходится со ставкой и сроком.
+    /// Сохранять такой план можно - банк мог округлить платёж иначе.
+    pub fn payment_warning(&self, currency: Currency) -> Option<String> {
+        let credit = self.to_core(currency)?;
+        if credit.payment_matches_terms().ok()? {
+            return None;
+        }
+        let expected = FormattedMoney::from_money(credit.annuity_payment().ok()?);
+        Some(format!(
+            "По ставке и сроку ежемесячный платёж должен быть около {expected}"
+        ))
+    }
+
     fn validate_field(
         value: &str,
         is_valid: impl Fn(&str) -> bool,
@@ -188,6 +337,48 @@ impl CreditData {
     }
 }
 
+#[derive(Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct GoalData {
+    pub target: String,
+    pub start_date: String,
+    pub deadline: String,
+}
+
+impl GoalData {
+    pub fn validation_errors(&self) -> Vec<&'static str> {
+        let mut errors = Vec::new();
+        let parse_date = |v: &str| NaiveDate::parse_from_str(v, "%Y-%m-%d").ok();
+        CreditData::validate_field(
+            &self.target,
+            |v| Decimal::from_str(v).is_ok(),
+            "не указана цель",
+            "некорректная цель",
+            &mut errors,
+        );
+        CreditData::validate_field(
+            &self.start_date,
+            |v| parse_date(v).is_some(),
+            "не указана дата начала",
+            "некорректная дата начала",
+            &mut errors,
+        );
+        CreditData::validate_field(
+            &self.deadline,
+            |v| parse_date(v).is_some(),
+            "не указан срок",
+            "некорректный срок",
+            &mut errors,
+        );
+        if let (Some(start), Some(deadline)) =
+            (parse_date(&self.start_date), parse_date(&self.deadline))
+            && deadline < start
+        {
+            errors.push("срок раньше даты начала");
+        }
+        errors
+    }
+}
+
 #[derive(Clone, PartialEq, Deserialize, Serialize)]
 pub struct Expense {
     pub name: String,
@@ -195,6 +386,17 @@ pub struct Expense {
     pub active_type: ActiveType,
     pub envelope: EnvelopeData,
     pub credit: CreditData,
+    #[serde(default)]
+    pub goal: GoalData,
+    /// Валюта фиксированной суммы конверта и платежей по кредиту
+    #[serde(default)]
+    pub currency: Currency,
+    /// Очередь наполнения в режиме приоритетов; пусто - без приоритета
+    #[serde(default)]
+    pub priority: String,
+    /// Источники дохода, к которым привязан конверт
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
 }
 
 impl Expense {
@@ -202,6 +404,7 @@ impl Expense {
         match self.active_type {
             ActiveType::Envelope => &self.envelope.amount,
             ActiveType::Credit => &self.credit.monthly_payment,
+            ActiveType::Goal => &self.goal.target,
         }
     }
 
@@ -218,6 +421,11 @@ impl Expense {
                 term_months: self.credit.term_months.clone(),
                 start_date: self.credit.start_date.clone(),
             },
+            ActiveType::Goal => ExpenseType::Goal {
+                target: self.goal.target.clone(),
+                start_date: self.goal.start_date.clone(),
+                deadline: self.goal.deadline.clone(),
+            },
         }
     }
 
@@ -228,6 +436,10 @@ impl Expense {
             active_type: ActiveType::Envelope,
             envelope: default_envelope(),
             credit: default_credit(),
+            goal: GoalData::default(),
+            currency: Currency::default(),
+            priority: String::new(),
+            sources: vec![],
         }
     }
 }
@@ -252,42 +464,99 @@ fn default_credit() -> CreditData {
 pub fn expenses_from_core_plan(plan: &CorePlan) -> Vec<Expense> {
     plan.expenses
         .iter()
-        .map(|expense| match &expense.kind {
-            CoreExpenseKind::Envelope { value } => {
-                let (value_kind, amount) = match value {
-                    CoreExpenseValue::MONEY { value } => {
-                        (ValueKind::Money, value.value.to_string())
-                    }
-                    CoreExpenseValue::RATE { value } => {
-                        let raw = value.to_string();
-                        let trimmed = raw.trim_end_matches('%').trim().to_string();
-                        (ValueKind::Rate, trimmed)
+        .map(|expense| {
+            let priority = expense.priority.map(|p| p.to_string()).unwrap_or_default();
+            match &expense.kind {
+                CoreExpenseKind::Envelope { value } => {
+                    let (value_kind, amount, currency) = match value {
+                        CoreExpenseValue::MONEY { value } => {
+                            (ValueKind::Money, value.value.to_string(), value.currency)
+                        }
+                        CoreExpenseValue::RATE { value } => {
+                            let raw = value.to_string();
+                            let trimmed = raw.trim_end_matches('%').trim().to_string();
+                            (ValueKind::Rate, trimmed, plan.currency())
+                        }
+                    };
+                    Expense {
+                        name: expense.name.clone(),
+                        category: expense.category.clone(),
+                        active_type: ActiveType::Envelope,
+                        envelope: EnvelopeData { value_kind, amount },
+                        credit: default_credit(),
+                        goal: GoalData::default(),
+                        currency,
+                        priority: priority.clone(),
+                        sources: expense.sources.clone(),
                     }
-                };
-                Expense {
+                }
+                CoreExpenseKind::Credit(credit) => Expense {
                     name: expense.name.clone(),
                     category: expense.category.clone(),
-                    active_type: ActiveType::Envelope,
-                    envelope: EnvelopeData { value_kind, amount },
+                    active_type: ActiveType::Credit,
+                    envelope: default_envelope(),
+                    credit: CreditData {
+                        monthly_payment: credit.monthly_payment.value.to_string(),
+                        total_amount: credit.total_amount.value.to_string(),
+                        interest_rate: FormattedPercentage::from_percentage(
+                            credit.interest_rate.clone(),
+                        )
+                        .raw_value(),
+                        term_months: credit.term_months.to_string(),
+                        start_date: credit.start_date.to_string(),
+                    },
+                    goal: GoalData::default(),
+                    currency: credit.monthly_payment.currency,
+                    priority,
+                    sources: expense.sources.clone(),
+                },
+                CoreExpenseKind::Goal(goal) => Expense {
+                    name: expense.name.clone(),
+                    category: expense.category.clone(),
+                    active_type: ActiveType::Goal,
+                    envelope: default_envelope(),
                     credit: default_credit(),
-                }
-            }
-            CoreExpenseKind::Credit(credit) => Expense {
-                name: expense.name.clone(),
-                category: expense.category.clone(),
-                active_type: ActiveType::Credit,
-                envelope: default_envelope(),
-                credit: CreditData {
-                    monthly_payment: credit.monthly_payment.value.to_string(),
-                    total_amount: credit.total_amount.value.to_string(),
-                    interest_rate: FormattedPercentage::from_percentage(
-                        credit.interest_rate.clone(),
-                    )
-                    .raw_value(),
-                    term_months: credit.term_months.to_string(),
-                    start_date: credit.start_date.to_string(),
+                    goal: GoalData {
+                        target: goal.target.value.to_string(),
+                        start_date: goal.start_date.to_string(),
+                        deadline: goal.deadline.to_string(),
+                    },
+                    currency: goal.target.currency,
+                    priority,
+                    sources: expense.sources.clone(),
                 },
-            },
+            }
+        })
+        .collect()
+}
+
+/// Правило распределения остатка: доля остатка уходит в конверт
+#[derive(Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct SurplusRule {
+    pub envelope: String,
+    pub share: String,
+}
+
+pub fn surplus_from_core_plan(plan: &CorePlan) -> Vec<SurplusRule> {
+    plan.surplus
+        .iter()
+        .map(|rule| SurplusRule {
+            envelope: rule.envelope.clone(),
+            share: FormattedPercentage::from(rule.share.clone()).raw_value(),
+        })
+        .collect()
+}
+
+/// Неразобранные правила пропускаются - о них сообщит валидация
+pub fn surplus_to_core(rules: &[SurplusRule]) -> Vec<CoreSurplusRule> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let share = Decimal::from_str(rule.share.trim()).ok()?;
+            Some(CoreSurplusRule::new(
+                rule.envelope.clone(),
+                Percentage::from(share),
+            ))
         })
         .collect()
 }
@@ -306,48 +575,72 @@ fn apply_expenses_to_core_plan(plan: &CorePlan, expenses: &[Expense]) -> CorePla
 
     updated.expenses = expenses
         .iter()
-        .filter_map(|editable| match &editable.expense_type() {
-            ExpenseType::Envelope { value_kind, amount } => {
-                let amount = Decimal::from_str(amount).ok()?;
-                let value = match value_kind {
-                    ValueKind::Money => CoreExpenseValue::MONEY {
-                        value: Money::new_rub(amount),
-                    },
-                    ValueKind::Rate => CoreExpenseValue::RATE {
-                        value: Percentage::from(amount),
-                    },
-                };
-                Some(CoreExpense::envelope(
-                    editable.name.clone(),
-                    value,
-                    editable.category.clone(),
-                ))
-            }
-            ExpenseType::Credit {
-                monthly_payment,
-                total_amount,
-                interest_rate,
-                term_months,
-                start_date,
-            } => {
-                let monthly = Decimal::from_str(monthly_payment).ok()?;
-                let total = Decimal::from_str(total_amount).ok()?;
-                let rate = Decimal::from_str(interest_rate).ok()?;
-                let months = term_months.parse::<u32>().ok()?;
-                let date = NaiveDate::parse_from_str(start_date, "%Y-%m-%d").ok()?;
-                let credit = CreditExpense::new(
-                    Money::new_rub(monthly),
-                    Money::new_rub(total),
-                    Percentage::from(rate),
-                    months,
-                    date,
-                )
-                .ok()?;
-                Some(CoreExpense::credit(
-                    editable.name.clone(),
-                    credit,
-                    editable.category.clone(),
-                ))
+        .filter_map(|editable| {
+            let expense = match &editable.expense_type() {
+                ExpenseType::Envelope { value_kind, amount } => {
+                    let amount = Decimal::from_str(amount).ok()?;
+                    let value = match value_kind {
+                        ValueKind::Money => CoreExpenseValue::MONEY {
+                            value: Money::new(amount, editable.currency),
+                        },
+                        ValueKind::Rate => CoreExpenseValue::RATE {
+                            value: Percentage::from(amount),
+                        },
+                    };
+                    CoreExpense::envelope(
+                        editable.name.clone(),
+                        value,
+                        editable.category.clone(),
+                    )
+                }
+                ExpenseType::Credit {
+                    monthly_payment,
+                    total_amount,
+                    interest_rate,
+                    term_months,
+                    start_date,
+                } => {
+                    let credit = CreditData {
+                        monthly_payment: monthly_payment.clone(),
+                        total_amount: total_amount.clone(),
+                        interest_rate: interest_rate.clone(),
+                        term_months: term_months.clone(),
+                        start_date: start_date.clone(),
+                    }
+                    .to_core(editable.currency)?;
+                    CoreExpense::credit(
+                        editable.name.clone(),
+                        credit,
+                        editable.category.clone(),
+                    )
+                }
+                ExpenseType::Goal {
+                    target,
+                    start_date,
+                    deadline,
+                } => {
+                    let target = Decimal::from_str(target).ok()?;
+                    let start =
+                        NaiveDate::parse_from_str(start_date, "%Y-%m-%d").ok()?;
+                    let deadline =
+                        NaiveDate::parse_from_str(deadline, "%Y-%m-%d").ok()?;
+                    let goal = GoalExpense::new(
+                        Money::new(target, editable.currency),
+                        start,
+                        deadline,
+                    )
+                    .ok()?;
+                    CoreExpense::goal(
+                        editable.name.clone(),
+                        goal,
+                        editable.category.clone(),
+                    )
+                }
+            };
+            let expense = expense.with_sources(editable.sources.clone());
+            match editable.priority.trim().parse::<u32>() {
+                Ok(priority) => Some(expense.with_priority(priority)),
+                Err(_) => Some(expense),
             }
         })
         .collect();
@@ -5,13 +5,16 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use ai_core::{
-    finance::{Money, Percentage},
+    finance::{ExchangeRates, Money, Percentage},
     plan::Plan as CorePlan,
     planning::{
+        ExpectedIncome as ExpectedIncomeCore,
         Expense as ExpenseCore,
         ExpenseKind as CoreExpenseKind,
         ExpenseValue as ExpenseValueCore,
+        GoalProgress as GoalProgressCore,
         IncomeSource as IncomeSourceCore,
+        IncomeStatus,
     },
 };
 
@@ -43,6 +46,9 @@ pub struct IncomeSource {
     pub name: String,
     pub amount: FormattedMoney,
     pub source_kind: SourceKind,
+    /// Ожидаемые в этом месяце поступления по графику
+    #[serde(default)]
+    pub expected: Vec<ExpectedIncome>,
 }
 
 impl From<&IncomeSourceCore> for IncomeSource {
@@ -52,6 +58,27 @@ impl From<&IncomeSourceCore> for IncomeSource {
             name: source.name.clone(),
             amount: FormattedMoney::from_money(source.net()),
             source_kind: SourceKind::from(&source.kind),
+            expected: vec![],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct ExpectedIncome {
+    pub date: NaiveDate,
+    pub status: IncomeStatus,
+    pub actual: Option<FormattedMoney>,
+    /// Факт минус план
+    pub difference: Option<FormattedMoney>,
+}
+
+impl From<&ExpectedIncomeCore> for ExpectedIncome {
+    fn from(expected: &ExpectedIncomeCore) -> Self {
+        Self {
+            date: expected.date,
+            status: expected.status,
+            actual: expected.actual.map(FormattedMoney::from_money),
+            difference: expected.difference.map(FormattedMoney::from_money),
         }
     }
 }
@@ -70,13 +97,19 @@ pub struct ExpenseValue {
 }
 
 impl ExpenseValue {
-    fn from_core(value: &ExpenseValueCore, total_income: Money) -> Self {
+    fn from_core(
+        value: &ExpenseValueCore,
+        total_income: Money,
+        rates: &ExchangeRates,
+    ) -> Self {
         match value {
             ExpenseValueCore::MONEY { value } => {
-                let rate = if total_income.value == Decimal::ZERO {
-                    Percentage::ZERO
-                } else {
-                    Percentage::of(value.value, total_income.value)
+                let converted = rates.convert(*value, total_income.currency).ok();
+                let rate = match converted {
+                    Some(v) if total_income.value != Decimal::ZERO => {
+                        Percentage::of(v.value, total_income.value)
+                    }
+                    _ => Percentage::ZERO,
                 };
                 Self {
                     rate: FormattedPercentage::from_percentage(rate),
@@ -85,8 +118,9 @@ impl ExpenseValue {
                 }
             }
             ExpenseValueCore::RATE { value } => Self {
-                money: FormattedMoney::from_money(Money::new_rub(
+                money: FormattedMoney::from_money(Money::new(
                     value.apply_to(total_income.value),
+                    total_income.currency,
                 )),
                 rate: FormattedPercentage::from_percentage(value.clone()),
                 unit: AccountingUnit::Rate,
@@ -105,6 +139,33 @@ pub enum ExpenseKindView {
         monthly_payment: FormattedMoney,
         start_date: NaiveDate,
     },
+    Goal {
+        target: FormattedMoney,
+        deadline: NaiveDate,
+        progress: Option<GoalProgress>,
+    },
+}
+
+/// Прогресс накопления, посчитанный бэкендом по истории распределений
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct GoalProgress {
+    pub saved: FormattedMoney,
+    pub percent: FormattedPercentage,
+    pub monthly_contribution: FormattedMoney,
+    pub projected_date: Option<NaiveDate>,
+}
+
+impl From<&GoalProgressCore> for GoalProgress {
+    fn from(progress: &GoalProgressCore) -> Self {
+        Self {
+            saved: FormattedMoney::from_money(progress.saved),
+            percent: FormattedPercentage::from_percentage(progress.percent.clone()),
+            monthly_contribution: FormattedMoney::from_money(
+                progress.monthly_contribution,
+            ),
+            projected_date: progress.projected_date,
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Deserialize, Serialize)]
@@ -115,7 +176,11 @@ pub struct Expense {
 }
 
 impl Expense {
-    fn from_core(expense: &ExpenseCore, total_income: Money) -> Self {
+    fn from_core(
+        expense: &ExpenseCore,
+        total_income: Money,
+        rates: &ExchangeRates,
+    ) -> Self {
         let kind = match &expense.kind {
             CoreExpenseKind::Envelope { .. } => ExpenseKindView::Envelope,
             CoreExpenseKind::Credit(credit) => ExpenseKindView::Credit {
@@ -127,10 +192,15 @@ impl Expense {
                 monthly_payment: FormattedMoney::from_money(credit.monthly_payment),
                 start_date: credit.start_date,
             },
+            CoreExpenseKind::Goal(goal) => ExpenseKindView::Goal {
+                target: FormattedMoney::from_money(goal.target),
+                deadline: goal.deadline,
+                progress: None,
+            },
         };
         Self {
             name: expense.name.clone(),
-            value: ExpenseValue::from_core(&expense.value(), total_income),
+            value: ExpenseValue::from_core(&expense.value(), total_income, rates),
             kind,
         }
     }
@@ -151,11 +221,13 @@ impl From<&CorePlan> for Plan {
         let sources: Vec<IncomeSource> =
             plan.sources.iter().map(IncomeSource::from).collect();
 
-        let total_income = FormattedMoney::from_money(plan.total_incomes());
-        let total_expenses = FormattedMoney::from_money(plan.total_expenses());
-        let balance = FormattedMoney::from_money(plan.balance());
-
-        let income = plan.total_incomes();
+        // Без нужного курса итоги не посчитать - показываем нули в базовой валюте
+        let zero = Money::new(Decimal::ZERO, plan.currency());
+        let income = plan.total_incomes().unwrap_or(zero);
+        let total_income = FormattedMoney::from_money(income);
+        let total_expenses =
+            FormattedMoney::from_money(plan.total_expenses().unwrap_or(zero));
+        let balance = FormattedMoney::from_money(plan.balance().unwrap_or(zero));
 
         // Группируем расходы по категориям и преобразуем в ViewModel за один проход
         let mut categories: BTreeMap<CategoryKey, Vec<Expense>> = plan
@@ -166,9 +238,11 @@ impl From<&CorePlan> for Plan {
                     None => CategoryKey::NoCategory,
                     Some(name) => CategoryKey::Named(name.clone()),
                 };
-                acc.entry(key)
-                    .or_default()
-                    .push(Expense::from_core(expense, income));
+                acc.entry(key).or_default().push(Expense::from_core(
+                    expense,
+                    income,
+                    &plan.rates,
+                ));
                 acc
             });
 
@@ -186,3 +260,31 @@ impl From<&CorePlan> for Plan {
         }
     }
 }
+
+impl Plan {
+    /// Дополняет источники ожидаемыми поступлениями
+    pub fn with_expected_incomes(mut self, expected: &[ExpectedIncomeCore]) -> Self {
+        for source in &mut self.sources {
+            source.expected = expected
+                .iter()
+                .filter(|e| e.source == source.name)
+                .map(ExpectedIncome::from)
+                .collect();
+        }
+        self
+    }
+
+    /// Дополняет цели прогрессом накоплений
+    pub fn with_goals(mut self, goals: &[GoalProgressCore]) -> Self {
+        let expenses = self.categories.values_mut().flatten();
+        for expense in expenses {
+            if let ExpenseKindView::Goal { progress, .. } = &mut expense.kind {
+                *progress = goals
+                    .iter()
+                    .find(|g| g.name == expense.name)
+                    .map(GoalProgress::from);
+            }
+        }
+        self
+    }
+}
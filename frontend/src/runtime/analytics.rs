@@ -0,0 +1,34 @@
+use std::rc::Rc;
+
+use yew::html::Scope;
+
+use crate::{
+    api::ApiClient,
+    engine::{analytics, app::msg, core::Shell},
+    runtime::App,
+};
+
+pub struct AnalyticsShell {
+    pub api: Rc<ApiClient>,
+    pub link: Scope<App>,
+}
+
+impl Shell<analytics::AnalyticsModel> for AnalyticsShell {
+    fn execute(&self, cmd: analytics::Cmd) {
+        let api = self.api.clone();
+        let link = self.link.clone();
+        match cmd {
+            analytics::Cmd::Fetch { granularity } => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api
+                        .get_analytics(granularity)
+                        .await
+                        .map_err(|e| e.to_string());
+                    link.send_message(msg::Msg::Analytics(analytics::Msg::Loaded(
+                        result,
+                    )));
+                });
+            }
+        }
+    }
+}
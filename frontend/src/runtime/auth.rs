@@ -0,0 +1,77 @@
+use std::rc::Rc;
+
+use yew::html::Scope;
+
+use crate::{
+    api::{ApiClient, ApiError, session},
+    engine::{
+        app::{cmd, msg},
+        auth::{self, AuthModel},
+        core::Shell,
+    },
+    runtime::App,
+};
+
+pub struct AuthShell {
+    pub api: Rc<ApiClient>,
+    pub link: Scope<App>,
+}
+
+/// Настольное приложение работает без учётных записей,
+/// в браузере сначала проверяется сохранённый токен
+#[cfg(feature = "tauri")]
+pub fn resolve_initial() -> (AuthModel, Vec<cmd::Cmd>) {
+    (AuthModel::Disabled, vec![])
+}
+
+#[cfg(not(feature = "tauri"))]
+pub fn resolve_initial() -> (AuthModel, Vec<cmd::Cmd>) {
+    (AuthModel::Checking, vec![cmd::Cmd::Auth(auth::Cmd::Check)])
+}
+
+impl Shell<AuthModel> for AuthShell {
+    fn execute(&self, cmd: auth::Cmd) {
+        let api = self.api.clone();
+        let link = self.link.clone();
+        match cmd {
+            auth::Cmd::Check => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let phase = match api.me().await {
+                        Ok(Some(account)) => AuthModel::SignedIn {
+                            login: account.login,
+                        },
+                        Ok(None) => AuthModel::Disabled,
+                        Err(ApiError::Unauthorized) => AuthModel::signed_out(None),
+                        Err(e) => AuthModel::signed_out(Some(e.to_string())),
+                    };
+                    link.send_message(msg::Msg::Auth(auth::Msg::Resolved(phase)));
+                });
+            }
+            auth::Cmd::Login { login, password } => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = match api.login(&login, &password).await {
+                        Ok(issued) => {
+                            session::store_token(&issued.token);
+                            Ok(login)
+                        }
+                        Err(ApiError::Unauthorized) => {
+                            Err("Неверный логин или пароль".to_string())
+                        }
+                        Err(e) => Err(e.to_string()),
+                    };
+                    link.send_message(msg::Msg::Auth(auth::Msg::LoggedIn(result)));
+                });
+            }
+            auth::Cmd::Logout => {
+                // Токен забываем сразу, чтобы следующий вход не потерял новый
+                let Some(token) = session::token() else {
+                    return;
+                };
+                session::clear_token();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let _ = api.logout(&token).await;
+                });
+            }
+        }
+    }
+}
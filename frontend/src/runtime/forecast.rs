@@ -0,0 +1,33 @@
+use std::rc::Rc;
+
+use yew::html::Scope;
+
+use crate::{
+    api::ApiClient,
+    engine::{app::msg, core::Shell, forecast},
+    runtime::App,
+};
+
+pub struct ForecastShell {
+    pub api: Rc<ApiClient>,
+    pub link: Scope<App>,
+}
+
+impl Shell<forecast::ForecastModel> for ForecastShell {
+    fn execute(&self, cmd: forecast::Cmd) {
+        let api = self.api.clone();
+        let link = self.link.clone();
+        match cmd {
+            forecast::Cmd::Fetch { revision, scenario } => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result =
+                        api.simulate(&scenario).await.map_err(|e| e.to_string());
+                    link.send_message(msg::Msg::Forecast(forecast::Msg::Loaded {
+                        revision,
+                        result,
+                    }));
+                });
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::rc::Rc;
 
+use wasm_bindgen::JsCast;
+use web_sys::{Blob, HtmlAnchorElement, Url};
 use yew::html::Scope;
 
 use crate::{
@@ -25,6 +27,56 @@ impl Shell<history::HistoryModel> for HistoryShell {
                     link.send_message(msg::Msg::History(history::Msg::Loaded(result)));
                 });
             }
+            history::Cmd::Void { id, reason } => {
+                let api = self.api.clone();
+                let link = self.link.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api
+                        .void_budget(&id, reason)
+                        .await
+                        .map(Box::new)
+                        .map_err(|e| e.to_string());
+                    link.send_message(msg::Msg::History(history::Msg::Voided(result)));
+                });
+            }
+            history::Cmd::Download { format } => {
+                let api = self.api.clone();
+                let link = self.link.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = match api.history_export(&format).await {
+                        Ok(bytes) => save_file(
+                            &bytes,
+                            &format!("anna_ivanovna_history.{format}"),
+                        ),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    link.send_message(msg::Msg::History(history::Msg::Downloaded(
+                        result,
+                    )));
+                });
+            }
         }
     }
 }
+
+/// Отдаёт браузеру скачанный файл через временную blob-ссылку
+fn save_file(bytes: &[u8], file_name: &str) -> Result<(), String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = Blob::new_with_u8_array_sequence(&parts)
+        .map_err(|_| "Не удалось подготовить файл".to_string())?;
+    let href = Url::create_object_url_with_blob(&blob)
+        .map_err(|_| "Не удалось подготовить файл".to_string())?;
+    let anchor = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|doc| doc.create_element("a").ok())
+        .and_then(|el| el.dyn_into::<HtmlAnchorElement>().ok());
+    if let Some(anchor) = &anchor {
+        anchor.set_href(&href);
+        anchor.set_download(file_name);
+        anchor.click();
+    }
+    let _ = Url::revoke_object_url(&href);
+    anchor
+        .map(|_| ())
+        .ok_or_else(|| "Браузер не дал сохранить файл".to_string())
+}
@@ -11,13 +11,19 @@ use crate::{
             model::{AppModel, View},
             msg,
         },
-        core::{Model, PaginatedList, Shell},
+        auth::Msg as AuthMsg,
+        core::{Model, Shell},
     },
 };
 
+mod analytics;
+mod auth;
+mod forecast;
 mod history;
 mod onboarding;
 mod plan;
+mod report;
+mod timeline;
 mod view;
 
 pub struct App {
@@ -33,6 +39,13 @@ struct AppShell {
 impl Shell<AppModel> for AppShell {
     fn execute(&self, cmd: cmd::Cmd) {
         match cmd {
+            cmd::Cmd::Auth(auth_cmd) => {
+                let shell = auth::AuthShell {
+                    api: self.api.clone(),
+                    link: self.link.clone(),
+                };
+                shell.execute(auth_cmd);
+            }
             cmd::Cmd::Plan(plan_cmd) => {
                 let shell = plan::PlanShell {
                     api: self.api.clone(),
@@ -47,6 +60,34 @@ impl Shell<AppModel> for AppShell {
                 };
                 shell.execute(history_cmd);
             }
+            cmd::Cmd::Timeline(timeline_cmd) => {
+                let shell = timeline::TimelineShell {
+                    api: self.api.clone(),
+                    link: self.link.clone(),
+                };
+                shell.execute(timeline_cmd);
+            }
+            cmd::Cmd::Analytics(analytics_cmd) => {
+                let shell = analytics::AnalyticsShell {
+                    api: self.api.clone(),
+                    link: self.link.clone(),
+                };
+                shell.execute(analytics_cmd);
+            }
+            cmd::Cmd::Report(report_cmd) => {
+                let shell = report::ReportShell {
+                    api: self.api.clone(),
+                    link: self.link.clone(),
+                };
+                shell.execute(report_cmd);
+            }
+            cmd::Cmd::Forecast(forecast_cmd) => {
+                let shell = forecast::ForecastShell {
+                    api: self.api.clone(),
+                    link: self.link.clone(),
+                };
+                shell.execute(forecast_cmd);
+            }
             cmd::Cmd::Onboarding(onboarding_cmd) => {
                 let shell = onboarding::OnboardingShell {
                     link: self.link.clone(),
@@ -62,18 +103,32 @@ impl Component for App {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let (initial_onboarding, init_cmds) = onboarding::resolve_initial(ctx);
+        let (initial_onboarding, mut init_cmds) = onboarding::resolve_initial(ctx);
+        let (initial_auth, auth_cmds) = auth::resolve_initial();
+        init_cmds.extend(auth_cmds);
 
         let app = Self {
             model: Some(AppModel {
                 onboarding: initial_onboarding,
+                auth: initial_auth,
                 view: View::Plan,
                 plan: crate::engine::plan::model::PlanModel::Loading,
-                history: crate::engine::history::HistoryModel {
-                    data: PaginatedList::loading(),
-                },
+                history: crate::engine::history::HistoryModel::loading(),
+                timeline: crate::engine::timeline::TimelineModel::open(None).0,
+                analytics: crate::engine::analytics::AnalyticsModel::loading(),
+                report: crate::engine::report::ReportModel::new(
+                    chrono::Local::now().date_naive(),
+                ),
+                forecast: crate::engine::forecast::ForecastModel::new(
+                    chrono::Local::now().date_naive(),
+                ),
             }),
-            api: Rc::new(ApiClient::new(API_V1_BASE_URL.clone())),
+            api: Rc::new(
+                ApiClient::new(API_V1_BASE_URL.clone()).on_unauthorized(
+                    ctx.link()
+                        .callback(|_| msg::Msg::Auth(AuthMsg::SessionExpired)),
+                ),
+            ),
         };
 
         let shell = AppShell {
@@ -36,12 +36,10 @@ pub fn resolve_initial(ctx: &Context<App>) -> (OnboardingModel, Vec<cmd::Cmd>) {
     (OnboardingModel::Checking, vec![])
 }
 
+/// План загрузится, когда проверка входа подтвердит доступ
 #[cfg(not(feature = "tauri"))]
 pub fn resolve_initial(_ctx: &Context<App>) -> (OnboardingModel, Vec<cmd::Cmd>) {
-    (
-        OnboardingModel::Ready,
-        vec![cmd::Cmd::Plan(crate::engine::plan::cmd::Cmd::LoadPlan)],
-    )
+    (OnboardingModel::Ready, vec![])
 }
 
 #[cfg(feature = "tauri")]
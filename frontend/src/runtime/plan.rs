@@ -33,6 +33,22 @@ impl Shell<plan::model::PlanModel> for PlanShell {
                     )));
                 });
             }
+            plan::cmd::Cmd::LoadGoals => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api.get_goals().await;
+                    link.send_message(msg::Msg::Plan(Msg::Loading(
+                        LoadingMsg::GoalsLoaded(result),
+                    )));
+                });
+            }
+            plan::cmd::Cmd::LoadExpectedIncomes => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api.get_expected_incomes().await;
+                    link.send_message(msg::Msg::Plan(Msg::Loading(
+                        LoadingMsg::ExpectedIncomesLoaded(result),
+                    )));
+                });
+            }
             plan::cmd::Cmd::LoadTemplates => {
                 wasm_bindgen_futures::spawn_local(async move {
                     let result = api.get_collections().await.map_err(|e| e.to_string());
@@ -41,14 +57,22 @@ impl Shell<plan::model::PlanModel> for PlanShell {
                     )));
                 });
             }
-            plan::cmd::Cmd::SavePlan { id, plan } => {
+            plan::cmd::Cmd::SavePlan { id, version, plan } => {
                 wasm_bindgen_futures::spawn_local(async move {
-                    let result = api.update_plan(&id, &plan).await;
+                    let result = api.update_plan(&id, version, &plan).await;
                     link.send_message(msg::Msg::Plan(Msg::Persist(
                         PersistMsg::SaveFinished(result),
                     )));
                 });
             }
+            plan::cmd::Cmd::LoadLatestPlan => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api.get_plan().await;
+                    link.send_message(msg::Msg::Plan(Msg::Persist(
+                        PersistMsg::LatestLoaded(result),
+                    )));
+                });
+            }
             plan::cmd::Cmd::CreatePlan { plan } => {
                 wasm_bindgen_futures::spawn_local(async move {
                     let result = api.create_plan(&plan).await;
@@ -0,0 +1,30 @@
+use std::rc::Rc;
+
+use yew::html::Scope;
+
+use crate::{
+    api::ApiClient,
+    engine::{app::msg, core::Shell, report},
+    runtime::App,
+};
+
+pub struct ReportShell {
+    pub api: Rc<ApiClient>,
+    pub link: Scope<App>,
+}
+
+impl Shell<report::ReportModel> for ReportShell {
+    fn execute(&self, cmd: report::Cmd) {
+        let api = self.api.clone();
+        let link = self.link.clone();
+        match cmd {
+            report::Cmd::Fetch { month } => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result =
+                        api.get_plan_report(month).await.map_err(|e| e.to_string());
+                    link.send_message(msg::Msg::Report(report::Msg::Loaded(result)));
+                });
+            }
+        }
+    }
+}
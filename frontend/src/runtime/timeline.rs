@@ -0,0 +1,61 @@
+use std::rc::Rc;
+
+use yew::html::Scope;
+
+use crate::{
+    api::ApiClient,
+    engine::{app::msg, core::Shell, timeline},
+    runtime::App,
+};
+
+pub struct TimelineShell {
+    pub api: Rc<ApiClient>,
+    pub link: Scope<App>,
+}
+
+impl Shell<timeline::TimelineModel> for TimelineShell {
+    fn execute(&self, cmd: timeline::Cmd) {
+        let api = self.api.clone();
+        let link = self.link.clone();
+        match cmd {
+            timeline::Cmd::Fetch { plan_id, cursor } => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api
+                        .get_plan_events(&plan_id, cursor)
+                        .await
+                        .map_err(|e| e.to_string());
+                    link.send_message(msg::Msg::Timeline(timeline::Msg::Loaded(
+                        result,
+                    )));
+                });
+            }
+            timeline::Cmd::LoadDiff { plan_id, from, to } => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api
+                        .get_plan_diff(&plan_id, from, to)
+                        .await
+                        .map_err(|e| e.to_string());
+                    link.send_message(msg::Msg::Timeline(timeline::Msg::DiffLoaded {
+                        version: to,
+                        result,
+                    }));
+                });
+            }
+            timeline::Cmd::Restore {
+                plan_id,
+                current,
+                plan,
+            } => {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = api
+                        .update_plan(&plan_id, current, &plan)
+                        .await
+                        .map_err(|e| e.to_string());
+                    link.send_message(msg::Msg::Timeline(timeline::Msg::Restored(
+                        result,
+                    )));
+                });
+            }
+        }
+    }
+}
@@ -1,31 +1,42 @@
 use std::rc::Rc;
 
-use yew::{Context, Html, html};
+use yew::{Context, Html, classes, html};
 
 use crate::{
     api::ApiClient,
     engine::{
+        analytics,
         app::{
             model::{AppModel, View},
             msg,
         },
+        auth::{self, AuthModel},
         core::{DataState, PageStatus},
+        forecast,
         history,
         onboarding::{self, OnboardingModel},
         plan::{
-            model::{EditState, PlanModel, PlanValidation, SaveState},
+            model::{EditState, PlanModel, PlanValidation, SaveState, VersionConflict},
             msg::{EditMsg, LoadingMsg, PersistMsg, TemplateMsg},
         },
+        report,
+        timeline::{self, RestoreState},
     },
     presentation::{
         components::{
+            AnalyticsView,
             AppLayout,
             EditLayout,
             Error,
+            ForecastIncomes,
+            ForecastView,
             HistoryView,
             Loading,
+            LoginScreen,
+            PlanReportView,
             PlanView,
             TemplateSelector,
+            TimelineView,
             Totals,
             WelcomeScreen,
         },
@@ -59,19 +70,48 @@ pub fn render(
                 on_complete={ctx.link().callback(|_| msg::Msg::Onboarding(onboarding::Msg::CompleteSetup))}
             />
         },
-        OnboardingModel::Ready => html! {
-            <AppLayout
-                current_view={model.view.clone()}
-                on_switch_view={ctx.link().callback(msg::Msg::SwitchView)}
-                sticky_header={render_sticky_header(model)}
-            >
-                {render_content(model, api, ctx)}
-            </AppLayout>
-        },
+        OnboardingModel::Ready => render_signed_in(model, api, ctx),
+    }
+}
+
+fn render_signed_in(model: &AppModel, api: &Rc<ApiClient>, ctx: &Context<App>) -> Html {
+    let account = match &model.auth {
+        AuthModel::Checking => return html! { <Loading /> },
+        AuthModel::SignedOut {
+            login,
+            password,
+            error,
+            submitting,
+        } => {
+            return html! {
+                <LoginScreen
+                    login={login.clone()}
+                    password={password.clone()}
+                    error={error.clone()}
+                    submitting={*submitting}
+                    on_login_change={ctx.link().callback(|v| msg::Msg::Auth(auth::Msg::LoginChanged(v)))}
+                    on_password_change={ctx.link().callback(|v| msg::Msg::Auth(auth::Msg::PasswordChanged(v)))}
+                    on_submit={ctx.link().callback(|_| msg::Msg::Auth(auth::Msg::Submit))}
+                />
+            };
+        }
+        AuthModel::Disabled => None,
+        AuthModel::SignedIn { login } => Some(login.clone()),
+    };
+    html! {
+        <AppLayout
+            current_view={model.view.clone()}
+            on_switch_view={ctx.link().callback(msg::Msg::SwitchView)}
+            sticky_header={render_sticky_header(model, ctx)}
+            account={account}
+            on_logout={ctx.link().callback(|_| msg::Msg::Auth(auth::Msg::Logout))}
+        >
+            {render_content(model, api, ctx)}
+        </AppLayout>
     }
 }
 
-fn render_sticky_header(model: &AppModel) -> Html {
+fn render_sticky_header(model: &AppModel, ctx: &Context<App>) -> Html {
     match model.view {
         View::Plan => render_plan_sticky_header(model),
         View::History => html! {
@@ -79,6 +119,18 @@ fn render_sticky_header(model: &AppModel) -> Html {
                 { "История распределений" }
             </h2>
         },
+        View::Timeline => html! {
+            <h2 class="text-2xl font-bold mb-2 text-center">
+                { "Версии плана" }
+            </h2>
+        },
+        View::Analytics => html! {
+            <h2 class="text-2xl font-bold mb-2 text-center">
+                { "Аналитика распределений" }
+            </h2>
+        },
+        View::Report => render_report_header(model, ctx),
+        View::Forecast => render_forecast_header(model, ctx),
     }
 }
 
@@ -92,7 +144,7 @@ fn render_plan_sticky_header(model: &AppModel) -> Html {
             let plan = edit.core_plan.as_ref().map(Plan::from);
             (plan, Some(edit))
         }
-        PlanModel::Viewing { origin } => (Some(Plan::from(&origin.plan)), None),
+        PlanModel::Viewing { origin, .. } => (Some(Plan::from(&origin.plan)), None),
         _ => (None, None),
     };
 
@@ -132,6 +184,10 @@ fn render_content(model: &AppModel, api: &Rc<ApiClient>, ctx: &Context<App>) ->
     match model.view {
         View::Plan => render_plan_content(model, api, ctx),
         View::History => render_history_content(model, ctx),
+        View::Timeline => render_timeline_content(model, ctx),
+        View::Analytics => render_analytics_content(model, ctx),
+        View::Report => render_report_content(model, ctx),
+        View::Forecast => render_forecast_content(model, ctx),
     }
 }
 
@@ -152,8 +208,14 @@ fn render_plan_content(
             render_template_selection(templates, ctx)
         }
         PlanModel::Creating { edit } => render_plan_edit_mode(edit, true, ctx),
-        PlanModel::Viewing { origin } => {
-            let plan = Plan::from(&origin.plan);
+        PlanModel::Viewing {
+            origin,
+            goals,
+            expected_incomes,
+        } => {
+            let plan = Plan::from(&origin.plan)
+                .with_goals(goals)
+                .with_expected_incomes(expected_incomes);
             render_plan_view_mode(&plan, api, ctx)
         }
         PlanModel::Editing { edit, .. } => render_plan_edit_mode(edit, false, ctx),
@@ -226,12 +288,23 @@ fn render_plan_edit_mode(
         ctx.link()
             .callback(|_| msg::Msg::Plan(EditMsg::Cancel.into()))
     };
-    let total_income = edit.core_plan.as_ref().map(|p| p.total_incomes().value);
+    let total_income = edit
+        .core_plan
+        .as_ref()
+        .and_then(|p| p.total_incomes().ok())
+        .map(|m| m.value);
     html! {
+        <>
+        {match &edit.conflict {
+            Some(conflict) => render_version_conflict(conflict, ctx),
+            None => html! {},
+        }}
         <EditLayout
             incomes={edit.incomes.clone()}
             expenses={edit.expenses.clone()}
             total_income={total_income}
+            mode={edit.mode}
+            surplus={edit.surplus.clone()}
             disable_save={disable_save}
             on_cancel={on_cancel}
             on_save={on_save}
@@ -241,7 +314,58 @@ fn render_plan_edit_mode(
             on_expenses_change={ctx
                 .link()
                 .callback(|v| msg::Msg::Plan(EditMsg::ExpensesChanged(v).into()))}
+            on_mode_change={ctx
+                .link()
+                .callback(|v| msg::Msg::Plan(EditMsg::ModeChanged(v).into()))}
+            on_surplus_change={ctx
+                .link()
+                .callback(|v| msg::Msg::Plan(EditMsg::SurplusChanged(v).into()))}
         />
+        </>
+    }
+}
+
+fn render_version_conflict(conflict: &VersionConflict, ctx: &Context<App>) -> Html {
+    let on_reload = ctx
+        .link()
+        .callback(|_| msg::Msg::Plan(LoadingMsg::Reload.into()));
+    let on_merge = ctx
+        .link()
+        .callback(|_| msg::Msg::Plan(PersistMsg::Merge.into()));
+    html! {
+        <div class="alert alert-warning mb-4 flex flex-col items-start gap-2">
+            <span>
+                { format!(
+                    "План уже изменён в другом окне (версия {}). Ваши правки не сохранены.",
+                    conflict.current
+                ) }
+            </span>
+            {match &conflict.latest {
+                DataState::Loading => html! {
+                    <span class="loading loading-spinner loading-sm"></span>
+                },
+                DataState::Error(e) => html! {
+                    <span class="text-sm">{ format!("Не удалось загрузить свежую версию: {e}") }</span>
+                },
+                DataState::Loaded(_) => html! {
+                    <span class="text-sm">
+                        { "Можно загрузить свежую версию, отбросив правки, или перенести правки на неё и сохранить ещё раз." }
+                    </span>
+                },
+            }}
+            <div class="flex gap-2">
+                <button class="btn btn-sm" onclick={on_reload}>
+                    { "Загрузить свежую версию" }
+                </button>
+                <button
+                    class="btn btn-sm btn-primary"
+                    disabled={!matches!(conflict.latest, DataState::Loaded(_))}
+                    onclick={on_merge}
+                >
+                    { "Перенести мои правки" }
+                </button>
+            </div>
+        </div>
     }
 }
 
@@ -277,7 +401,44 @@ fn render_history_content(model: &AppModel, ctx: &Context<App>) -> Html {
 
     html! {
         <>
-            <HistoryView entries={data.items.clone()} />
+            {if let Some(error) = &model.history.void_error {
+                html! {
+                    <div class="alert alert-error mb-4">
+                        { format!("Не удалось аннулировать: {error}") }
+                    </div>
+                }
+            } else {
+                html! {}
+            }}
+            {if let Some(error) = &model.history.download_error {
+                html! {
+                    <div class="alert alert-error mb-4">
+                        { format!("Не удалось скачать историю: {error}") }
+                    </div>
+                }
+            } else {
+                html! {}
+            }}
+            <div class="flex justify-end gap-2 mb-4">
+                {for [("csv", "Скачать CSV"), ("xlsx", "Скачать XLSX")].into_iter().map(|(format, title)| {
+                    html! {
+                        <button
+                            class="btn btn-sm btn-outline"
+                            onclick={ctx.link().callback(move |_| msg::Msg::History(history::Msg::Download {
+                                format: format.to_string(),
+                            }))}
+                        >
+                            { title }
+                        </button>
+                    }
+                })}
+            </div>
+            <HistoryView
+                entries={data.items.clone()}
+                on_void={ctx.link().callback(|(id, reason)| {
+                    msg::Msg::History(history::Msg::Void { id, reason })
+                })}
+            />
             {if data.is_loading() {
                 html! {
                     <div class="text-center mt-4">
@@ -302,6 +463,185 @@ fn render_history_content(model: &AppModel, ctx: &Context<App>) -> Html {
     }
 }
 
+fn render_timeline_content(model: &AppModel, ctx: &Context<App>) -> Html {
+    let timeline = &model.timeline;
+    let data = &timeline.data;
+
+    if let PageStatus::Error(error) = &data.status {
+        return html! {
+            <Error
+                message={format!("Ошибка: {}", error)}
+                on_retry={ctx.link().callback(|_| msg::Msg::Timeline(timeline::Msg::Load))}
+            />
+        };
+    }
+
+    if data.items.is_empty() && data.is_loading() {
+        return html! { <Loading /> };
+    }
+
+    if data.items.is_empty() {
+        return html! {
+            <p class="text-center text-base-content/60 py-20">
+                {"Версий пока нет: создайте план, и его изменения появятся здесь"}
+            </p>
+        };
+    }
+
+    html! {
+        <>
+            {if let RestoreState::Failed(error) = &timeline.restore {
+                html! {
+                    <div class="alert alert-error mb-4">
+                        { format!("Не удалось восстановить версию: {error}") }
+                    </div>
+                }
+            } else {
+                html! {}
+            }}
+            <TimelineView
+                events={data.items.clone()}
+                selected={timeline.selected.clone()}
+                restore={timeline.restore.clone()}
+                on_select={ctx.link().callback(|version| {
+                    msg::Msg::Timeline(timeline::Msg::Select { version })
+                })}
+                on_restore={ctx.link().callback(|version| {
+                    msg::Msg::Timeline(timeline::Msg::Restore { version })
+                })}
+            />
+            {if data.next_cursor.is_some() && !data.is_loading() {
+                html! {
+                    <div class="text-center mt-4">
+                        <button
+                            class="btn btn-primary"
+                            onclick={ctx.link().callback(|_| msg::Msg::Timeline(timeline::Msg::Load))}
+                        >
+                            { "Загрузить еще" }
+                        </button>
+                    </div>
+                }
+            } else {
+                html! {}
+            }}
+        </>
+    }
+}
+
+fn render_analytics_content(model: &AppModel, ctx: &Context<App>) -> Html {
+    let granularity = model.analytics.granularity;
+    match &model.analytics.data {
+        DataState::Loading => html! { <Loading /> },
+        DataState::Error(error) => html! {
+            <Error
+                message={format!("Ошибка: {}", error)}
+                on_retry={ctx.link().callback(move |_| {
+                    msg::Msg::Analytics(analytics::Msg::Load(granularity))
+                })}
+            />
+        },
+        DataState::Loaded(data) if data.periods.is_empty() => html! {
+            <p class="text-center text-base-content/60 py-20">
+                {"Распределите первый доход, и здесь появится сводка"}
+            </p>
+        },
+        DataState::Loaded(data) => html! {
+            <AnalyticsView
+                analytics={data.clone()}
+                on_granularity={ctx.link().callback(|granularity| {
+                    msg::Msg::Analytics(analytics::Msg::Load(granularity))
+                })}
+            />
+        },
+    }
+}
+
+/// Заголовок с переключением месяца отчёта
+fn render_report_header(model: &AppModel, ctx: &Context<App>) -> Html {
+    let shift = |months| {
+        ctx.link()
+            .callback(move |_| msg::Msg::Report(report::Msg::Shift { months }))
+    };
+    html! {
+        <div class="flex items-center justify-center gap-4 mb-2">
+            <button class="btn btn-sm btn-ghost" onclick={shift(-1)}>{ "←" }</button>
+            <h2 class="text-2xl font-bold text-center">
+                { format!("План/факт за {}", model.report.month.format("%Y-%m")) }
+            </h2>
+            <button class="btn btn-sm btn-ghost" onclick={shift(1)}>{ "→" }</button>
+        </div>
+    }
+}
+
+fn render_report_content(model: &AppModel, ctx: &Context<App>) -> Html {
+    match &model.report.data {
+        DataState::Loading => html! { <Loading /> },
+        DataState::Error(error) => html! {
+            <Error
+                message={format!("Ошибка: {}", error)}
+                on_retry={ctx.link().callback(|_| msg::Msg::Report(report::Msg::Load))}
+            />
+        },
+        DataState::Loaded(report) => html! {
+            <PlanReportView report={report.clone()} />
+        },
+    }
+}
+
+/// Заголовок с выбором горизонта прогноза
+fn render_forecast_header(model: &AppModel, ctx: &Context<App>) -> Html {
+    html! {
+        <div class="flex items-center justify-center gap-4 mb-2">
+            <h2 class="text-2xl font-bold text-center">
+                { format!("Прогноз с {}", model.forecast.start.format("%Y-%m")) }
+            </h2>
+            <div class="join">
+                {for [6, 12, 24].into_iter().map(|months| html! {
+                    <button
+                        class={classes!("btn", "btn-sm", "join-item", (months == model.forecast.months).then_some("btn-active"))}
+                        onclick={ctx.link().callback(move |_| {
+                            msg::Msg::Forecast(forecast::Msg::SetMonths(months))
+                        })}
+                    >
+                        { format!("{months} мес.") }
+                    </button>
+                })}
+            </div>
+        </div>
+    }
+}
+
+fn render_forecast_content(model: &AppModel, ctx: &Context<App>) -> Html {
+    let forecast = &model.forecast;
+    let data = match &forecast.data {
+        DataState::Loading => html! { <Loading /> },
+        DataState::Error(error) => html! {
+            <Error
+                message={format!("Ошибка: {}", error)}
+                on_retry={ctx.link().callback(|_| msg::Msg::Forecast(forecast::Msg::Load))}
+            />
+        },
+        DataState::Loaded(data) => html! {
+            <ForecastView forecast={data.clone()} />
+        },
+    };
+    html! {
+        <>
+            <ForecastIncomes
+                sources={forecast.sources.iter().map(|s| s.name.clone()).collect::<Vec<_>>()}
+                extra={forecast.extra.clone()}
+                on_add={ctx.link().callback(|income| {
+                    msg::Msg::Forecast(forecast::Msg::AddIncome(income))
+                })}
+                on_remove={ctx.link().callback(|pos| {
+                    msg::Msg::Forecast(forecast::Msg::RemoveIncome(pos))
+                })}
+            />
+            { data }
+        </>
+    }
+}
+
 fn render_validation_bar(edit: &EditState) -> (String, Html) {
     match &edit.validation {
         PlanValidation::Valid => match edit.save_state {
@@ -121,3 +121,28 @@ fn history(#[files("stories/history/**/config.toml")] path: PathBuf) {
 fn onboarding(#[files("stories/onboarding/**/config.toml")] path: PathBuf) {
     run_story::<frontend::engine::onboarding::OnboardingModel>(&path);
 }
+
+#[rstest]
+fn auth(#[files("stories/auth/**/config.toml")] path: PathBuf) {
+    run_story::<frontend::engine::auth::AuthModel>(&path);
+}
+
+#[rstest]
+fn analytics(#[files("stories/analytics/**/config.toml")] path: PathBuf) {
+    run_story::<frontend::engine::analytics::AnalyticsModel>(&path);
+}
+
+#[rstest]
+fn report(#[files("stories/report/**/config.toml")] path: PathBuf) {
+    run_story::<frontend::engine::report::ReportModel>(&path);
+}
+
+#[rstest]
+fn forecast(#[files("stories/forecast/**/config.toml")] path: PathBuf) {
+    run_story::<frontend::engine::forecast::ForecastModel>(&path);
+}
+
+#[rstest]
+fn timeline(#[files("stories/timeline/**/config.toml")] path: PathBuf) {
+    run_story::<frontend::engine::timeline::TimelineModel>(&path);
+}
@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-use ai_app::config::{Config, Database, Error, Server, read_json, write_json};
+use ai_app::config::{Auth, Config, Database, Error, Server, read_json, write_json};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
@@ -80,11 +80,13 @@ impl TauriConfigProvider {
                 let abs = buh_home.join(&name).to_string_lossy().to_string();
                 Database::Sqlite { name: abs }
             }
+            other => other,
         };
         let config = Config {
             server: Server {
                 host: "127.0.0.1".to_string(),
                 port: 31415,
+                auth: Auth::None,
             },
             database,
         };
@@ -346,10 +346,12 @@ async fn run_backend(
     mut shutdown_rx: watch::Receiver<bool>,
     config: ai_app::config::Config,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let repo =
-        SqliteRepo::init(std::path::Path::new(config.database.connection_string()))
-            .await
-            .map_err(|e| format!("SQLite init failed: {e}"))?;
+    let ai_app::config::Database::Sqlite { name } = &config.database else {
+        return Err("Desktop app supports only SQLite database".into());
+    };
+    let repo = SqliteRepo::init(std::path::Path::new(name))
+        .await
+        .map_err(|e| format!("SQLite init failed: {e}"))?;
 
     let api = CoreApi::new(Arc::new(repo));
     let app = create_router(api);